@@ -0,0 +1,54 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) texCoords: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texCoords: vec2<f32>,
+    @location(1) @interpolate(flat) texture_index: u32,
+};
+
+struct ObjectData {
+    model: mat4x4<f32>,
+    // Indices into the bindless texture array
+    texture_indices: vec4<u32>,
+    // Model flags such as whether it receives shadows, unused here
+    flags: vec4<u32>,
+};
+
+struct Camera {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+};
+
+// Every model's data, indexed by the instance index the renderer draws each model with
+@group(0) @binding(0)
+var<storage, read> objects: array<ObjectData>;
+
+@group(0) @binding(1)
+var<uniform> camera: Camera;
+
+// Every texture in the scene, shared by all models
+@group(1) @binding(0)
+var bindless_textures: binding_array<texture_2d<f32>>;
+@group(1) @binding(1)
+var bindless_textures_sampler: sampler;
+
+@vertex
+fn vertex_main(vertex_input: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let object = objects[instance_index];
+
+    output.clip_position = camera.projection * camera.view * object.model * vec4<f32>(vertex_input.position, 1.0);
+    output.texCoords = vertex_input.texCoords;
+    output.texture_index = object.texture_indices.x;
+
+    return output;
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(bindless_textures[input.texture_index], bindless_textures_sampler, input.texCoords);
+}
@@ -0,0 +1,14 @@
+// Copies a region of the source into the target's viewport, filtered by the sampler bound with it
+
+struct Blit {
+    // Offset in xy and size in zw of the region of the source to read, from 0 to 1
+    source_rect: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> blit: Blit;
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source, source_sampler, blit.source_rect.xy + input.uv * blit.source_rect.zw);
+}
@@ -0,0 +1,21 @@
+// Bloom: adds the blurred bright parts of the frame back on top of it
+
+struct Bloom {
+    // Threshold in x, soft knee in y, intensity in z
+    params: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> bloom: Bloom;
+@group(1) @binding(1)
+var bloom_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var bloom_sampler: sampler;
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(source, source_sampler, input.uv, 0.0);
+    let glow = textureSampleLevel(bloom_texture, bloom_sampler, input.uv, 0.0).rgb;
+
+    return vec4<f32>(color.rgb + glow * bloom.params.z, color.a);
+}
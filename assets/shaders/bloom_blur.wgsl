@@ -0,0 +1,24 @@
+// Bloom blur: one direction of a separable 9 tap Gaussian blur of the prefiltered frame
+
+struct BloomBlur {
+    // Step between taps in uv, in xy
+    direction: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> blur: BloomBlur;
+
+const WEIGHTS: array<f32, 5> = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    var weights = WEIGHTS;
+    var color = textureSampleLevel(source, source_sampler, input.uv, 0.0).rgb * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = blur.direction.xy * f32(i);
+        color += textureSampleLevel(source, source_sampler, input.uv + offset, 0.0).rgb * weights[i];
+        color += textureSampleLevel(source, source_sampler, input.uv - offset, 0.0).rgb * weights[i];
+    }
+
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,29 @@
+// Bloom prefilter: keeps the parts of the frame brighter than the threshold, at half resolution
+
+struct Bloom {
+    // Threshold in x, soft knee in y, intensity in z
+    params: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> bloom: Bloom;
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    // Four taps between the source's texels, so every source texel is read once
+    let texel = 1.0 / vec2<f32>(textureDimensions(source));
+    var color = textureSampleLevel(source, source_sampler, input.uv + vec2<f32>(-texel.x, -texel.y) * 0.5, 0.0).rgb;
+    color += textureSampleLevel(source, source_sampler, input.uv + vec2<f32>(texel.x, -texel.y) * 0.5, 0.0).rgb;
+    color += textureSampleLevel(source, source_sampler, input.uv + vec2<f32>(-texel.x, texel.y) * 0.5, 0.0).rgb;
+    color += textureSampleLevel(source, source_sampler, input.uv + vec2<f32>(texel.x, texel.y) * 0.5, 0.0).rgb;
+    color *= 0.25;
+
+    // Brightness past the threshold is kept, fading in over the knee below it so there's no hard edge
+    let threshold = bloom.params.x;
+    let knee = max(bloom.params.y, 0.0001);
+    let brightness = max(color.r, max(color.g, color.b));
+    let soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    let contribution = max(soft * soft / (4.0 * knee), brightness - threshold) / max(brightness, 0.0001);
+
+    return vec4<f32>(color * contribution, 1.0);
+}
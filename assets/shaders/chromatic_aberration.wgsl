@@ -0,0 +1,20 @@
+// Chromatic aberration: splits the red and blue channels apart towards the frame's edges
+
+struct ChromaticAberration {
+    // Offset of the red and blue channels at the corners in x, as a fraction of the frame
+    params: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> aberration: ChromaticAberration;
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    let offset = (input.uv - 0.5) * 2.0 * aberration.params.x;
+
+    let red = textureSampleLevel(source, source_sampler, input.uv - offset, 0.0).r;
+    let color = textureSampleLevel(source, source_sampler, input.uv, 0.0);
+    let blue = textureSampleLevel(source, source_sampler, input.uv + offset, 0.0).b;
+
+    return vec4<f32>(red, color.g, blue, color.a);
+}
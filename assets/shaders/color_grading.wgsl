@@ -0,0 +1,38 @@
+// Colour grading: looks each pixel up in a 3D LUT, blended with the original by the intensity
+
+struct ColorGrading {
+    // Intensity in x, and the LUT's size along each axis in y
+    params: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> grading: ColorGrading;
+@group(1) @binding(1)
+var lut: texture_3d<f32>;
+@group(1) @binding(2)
+var lut_sampler: sampler;
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(source, source_sampler, input.uv, 0.0);
+
+    // LUTs are authored against display (sRGB) values, and sampled at texel centres
+    let size = grading.params.y;
+    let encoded = clamp(linear_to_srgb(color.rgb), vec3<f32>(0.0), vec3<f32>(1.0));
+    let coords = encoded * ((size - 1.0) / size) + 0.5 / size;
+    let graded = srgb_to_linear(textureSampleLevel(lut, lut_sampler, coords, 0.0).rgb);
+
+    return vec4<f32>(mix(color.rgb, graded, grading.params.x), color.a);
+}
@@ -0,0 +1,133 @@
+// Frustum and occlusion culls every indirect draw of a pipeline, appending the visible ones to
+// their group's range of the indirect buffer. Groups are the draws sharing a material, mesh and submesh
+
+struct ObjectData {
+    model: mat4x4<f32>,
+    texture_indices: vec4<u32>,
+    flags: vec4<u32>,
+};
+
+struct Cull {
+    view_projection: mat4x4<f32>,
+    // Frustum planes, with the normal in xyz pointing inwards and the distance in w
+    planes: array<vec4<f32>, 6>,
+    // The number of draws in x
+    draw_count: vec4<u32>,
+};
+
+struct CullDraw {
+    // Local space bounding sphere. A negative radius means the draw is never culled
+    sphere: vec4<f32>,
+    index_count: u32,
+    object_index: u32,
+    group: u32,
+    // Start of the group's range in the indirect buffer
+    first_draw: u32,
+};
+
+struct DrawIndexedIndirect {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> cull: Cull;
+@group(0) @binding(1)
+var<storage, read> objects: array<ObjectData>;
+@group(0) @binding(2)
+var<storage, read> draws: array<CullDraw>;
+@group(0) @binding(3)
+var<storage, read_write> indirect: array<DrawIndexedIndirect>;
+@group(0) @binding(4)
+var<storage, read_write> counts: array<atomic<u32>>;
+
+struct HiZ {
+    // Width and height of the first level, the number of levels, and whether the pyramid is in use
+    size: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> hi_z: HiZ;
+@group(1) @binding(1)
+var hi_z_pyramid: texture_2d<f32>;
+
+// Whether a world space sphere is behind the depth in the Hi-Z pyramid, tested with its screen space bounding rectangle
+fn is_occluded(center: vec3<f32>, radius: f32) -> bool {
+    if (hi_z.size.w == 0.0) {
+        return false;
+    }
+
+    var min_uv = vec2<f32>(1.0);
+    var max_uv = vec2<f32>(0.0);
+    var nearest = 1.0;
+    for (var i = 0u; i < 8u; i++) {
+        let corner = center + radius * vec3<f32>(
+            select(-1.0, 1.0, (i & 1u) != 0u),
+            select(-1.0, 1.0, (i & 2u) != 0u),
+            select(-1.0, 1.0, (i & 4u) != 0u)
+        );
+        let clip = cull.view_projection * vec4<f32>(corner, 1.0);
+        // Bounds crossing the camera plane can't be projected, so are kept
+        if (clip.w <= 0.0) {
+            return false;
+        }
+
+        let ndc = clip.xyz / clip.w;
+        let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+        min_uv = min(min_uv, uv);
+        max_uv = max(max_uv, uv);
+        nearest = min(nearest, ndc.z);
+    }
+    min_uv = clamp(min_uv, vec2<f32>(0.0), vec2<f32>(1.0));
+    max_uv = clamp(max_uv, vec2<f32>(0.0), vec2<f32>(1.0));
+
+    // The level where the rectangle covers at most 2x2 texels
+    let extent = (max_uv - min_uv) * hi_z.size.xy;
+    let level = u32(min(ceil(log2(max(max(extent.x, extent.y), 1.0))), hi_z.size.z - 1.0));
+    let level_size = max(vec2<u32>(hi_z.size.xy) >> vec2<u32>(level), vec2<u32>(1u));
+    let min_texel = min(vec2<u32>(min_uv * vec2<f32>(level_size)), level_size - 1u);
+    let max_texel = min(vec2<u32>(max_uv * vec2<f32>(level_size)), level_size - 1u);
+
+    let mip = i32(level);
+    let farthest = max(
+        max(textureLoad(hi_z_pyramid, min_texel, mip).r, textureLoad(hi_z_pyramid, vec2<u32>(max_texel.x, min_texel.y), mip).r),
+        max(textureLoad(hi_z_pyramid, vec2<u32>(min_texel.x, max_texel.y), mip).r, textureLoad(hi_z_pyramid, max_texel, mip).r)
+    );
+    return nearest > farthest;
+}
+
+fn is_visible(draw: CullDraw) -> bool {
+    if (draw.sphere.w < 0.0) {
+        return true;
+    }
+
+    let model = objects[draw.object_index].model;
+    let center = (model * vec4<f32>(draw.sphere.xyz, 1.0)).xyz;
+    let scale = max(length(model[0].xyz), max(length(model[1].xyz), length(model[2].xyz)));
+    let radius = draw.sphere.w * scale;
+
+    for (var i = 0u; i < 6u; i++) {
+        if (dot(cull.planes[i].xyz, center) + cull.planes[i].w < -radius) {
+            return false;
+        }
+    }
+    return !is_occluded(center, radius);
+}
+
+@compute @workgroup_size(64)
+fn cull_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= cull.draw_count.x) {
+        return;
+    }
+
+    let draw = draws[id.x];
+    if (!is_visible(draw)) {
+        return;
+    }
+
+    let slot = atomicAdd(&counts[draw.group], 1u);
+    indirect[draw.first_draw + slot] = DrawIndexedIndirect(draw.index_count, 1u, 0u, 0, draw.object_index);
+}
@@ -0,0 +1,34 @@
+// Stands in for shaders that failed to compile, drawing every model using them in flat magenta
+
+struct ObjectData {
+    model: mat4x4<f32>,
+    // Indices into the bindless texture array, unused here
+    texture_indices: vec4<u32>,
+    // Model flags such as whether it receives shadows, unused here
+    flags: vec4<u32>,
+};
+
+// Filled in by the renderer, see `SCENE_UNIFORM_WGSL`
+struct Scene {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+};
+
+@group(0) @binding(0)
+var<storage, read> objects: array<ObjectData>;
+
+@group(0) @binding(1)
+var<uniform> scene: Scene;
+
+@vertex
+fn vertex_main(@location(0) position: vec3<f32>, @builtin(instance_index) instance_index: u32) -> @builtin(position) vec4<f32> {
+    return scene.view_projection * objects[instance_index].model * vec4<f32>(position, 1.0);
+}
+
+@fragment
+fn fragment_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+}
@@ -0,0 +1,25 @@
+// Film grain: adds noise to the frame, changing every frame
+
+struct FilmGrain {
+    // Intensity in x, grain size in pixels in y, and the frame's seed in z
+    params: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> grain: FilmGrain;
+
+fn hash(point: vec3<f32>) -> f32 {
+    let p = fract(point * vec3<f32>(0.1031, 0.1030, 0.0973));
+    let q = p + dot(p, p.yzx + 33.33);
+    return fract((q.x + q.y) * q.z);
+}
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(source, source_sampler, input.uv, 0.0);
+
+    let pixel = floor(input.uv * vec2<f32>(textureDimensions(source)) / max(grain.params.y, 1.0));
+    let noise = hash(vec3<f32>(pixel, grain.params.z)) - 0.5;
+
+    return vec4<f32>(max(color.rgb + noise * grain.params.x, vec3<f32>(0.0)), color.a);
+}
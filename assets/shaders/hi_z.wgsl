@@ -0,0 +1,41 @@
+// Builds the Hi-Z pyramid: a mip chain of the depth buffer where each texel holds the
+// farthest depth of the texels it covers in the level above
+
+// The depth buffer when copying, or the level above when downsampling. Depth is bound as a
+// float texture rather than a depth texture, as not every backend can load from depth textures
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var destination: texture_storage_2d<r32float, write>;
+
+@compute @workgroup_size(8, 8)
+fn copy_depth(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(destination))) {
+        return;
+    }
+
+    textureStore(destination, id.xy, vec4<f32>(textureLoad(source, id.xy, 0).r, 0.0, 0.0, 1.0));
+}
+
+@compute @workgroup_size(8, 8)
+fn downsample(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if (any(id.xy >= size)) {
+        return;
+    }
+
+    // Odd sized levels have an extra row or column, which the last texel covers too
+    let source_size = textureDimensions(source);
+    let extra = select(vec2<u32>(0u), source_size % 2u, id.xy == size - 1u);
+    let start = id.xy * 2u;
+    let end = min(start + 2u + extra, source_size);
+
+    var farthest = 0.0;
+    for (var y = start.y; y < end.y; y++) {
+        for (var x = start.x; x < end.x; x++) {
+            farthest = max(farthest, textureLoad(source, vec2<u32>(x, y), 0).r);
+        }
+    }
+
+    textureStore(destination, id.xy, vec4<f32>(farthest, 0.0, 0.0, 1.0));
+}
@@ -0,0 +1,145 @@
+// Built-in "lit" shader: the base colour texture multiplied by the material colour, shaded with
+// Blinn-Phong by the scene's lights
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) texCoords: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) texCoords: vec2<f32>,
+};
+
+struct ObjectData {
+    model: mat4x4<f32>,
+    // Indices into the bindless texture array, unused here
+    texture_indices: vec4<u32>,
+    // Model flags such as whether it receives shadows, unused here
+    flags: vec4<u32>,
+};
+
+// Filled in by the renderer, see `SCENE_UNIFORM_WGSL`
+struct Scene {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+};
+
+struct Light {
+    // range is 0 for infinite
+    position_range: vec4<f32>,
+    // type is 0 for directional, 1 for point, 2 for spot
+    direction_type: vec4<f32>,
+    color_intensity: vec4<f32>,
+    spot_scale_offset: vec4<f32>,
+    // Point shadows are only sampled by the PBR shader
+    shadow: vec4<f32>,
+};
+
+struct Lights {
+    count: vec4<u32>,
+    shadow: vec4<f32>,
+    lights: array<Light, 16>,
+};
+
+// Shared by the built-in shaders, see `BasicMaterialUniform`
+struct BasicMaterial {
+    color: vec4<f32>,
+    // Specular strength in x, shininess in y
+    specular: vec4<f32>,
+    // Linear colour added on top of the lighting, masked by the texture. Above 1 it feeds bloom
+    emissive: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<storage, read> objects: array<ObjectData>;
+
+@group(0) @binding(1)
+var<uniform> scene: Scene;
+
+@group(0) @binding(2)
+var<uniform> lights: Lights;
+
+@group(1) @binding(0)
+var<uniform> material: BasicMaterial;
+@group(1) @binding(1)
+var base_color: texture_2d<f32>;
+@group(1) @binding(2)
+var base_color_sampler: sampler;
+
+// With no lights in the scene, surfaces are lit by a fixed key light so they aren't black
+const DEFAULT_LIGHT_DIRECTION: vec3<f32> = vec3<f32>(-0.4, -1.0, -0.3);
+const AMBIENT_COLOR: vec3<f32> = vec3<f32>(0.08, 0.08, 0.1);
+
+@vertex
+fn vertex_main(vertex_input: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let model = objects[instance_index].model;
+    let world_position = model * vec4<f32>(vertex_input.position, 1.0);
+    output.clip_position = scene.view_projection * world_position;
+    output.world_position = world_position.xyz;
+    output.normal = (model * vec4<f32>(vertex_input.normal, 0.0)).xyz;
+    output.texCoords = vertex_input.texCoords;
+    return output;
+}
+
+// The direction towards the light and the radiance arriving from it, following KHR_lights_punctual
+fn light_incoming(light: Light, position: vec3<f32>) -> array<vec3<f32>, 2> {
+    let light_type = u32(light.direction_type.w);
+    let radiance = light.color_intensity.rgb * light.color_intensity.w;
+    if light_type == 0u {
+        return array<vec3<f32>, 2>(-normalize(light.direction_type.xyz), radiance);
+    }
+
+    let to_light = light.position_range.xyz - position;
+    let distance_squared = max(dot(to_light, to_light), 0.0001);
+    let l = to_light * inverseSqrt(distance_squared);
+
+    var attenuation = 1.0 / distance_squared;
+    let range = light.position_range.w;
+    if range > 0.0 {
+        let ratio = distance_squared / (range * range);
+        attenuation *= clamp(1.0 - ratio * ratio, 0.0, 1.0);
+    }
+
+    if light_type == 2u {
+        let cone = clamp(dot(normalize(light.direction_type.xyz), -l) * light.spot_scale_offset.x + light.spot_scale_offset.y, 0.0, 1.0);
+        attenuation *= cone * cone;
+    }
+
+    return array<vec3<f32>, 2>(l, radiance * attenuation);
+}
+
+fn blinn_phong(albedo: vec3<f32>, n: vec3<f32>, v: vec3<f32>, l: vec3<f32>, radiance: vec3<f32>) -> vec3<f32> {
+    let h = normalize(v + l);
+    let diffuse = albedo * max(dot(n, l), 0.0);
+    let specular = material.specular.x * pow(max(dot(n, h), 0.0), max(material.specular.y, 1.0));
+    return (diffuse + vec3<f32>(specular)) * radiance;
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(base_color, base_color_sampler, input.texCoords);
+    let color = texel * material.color;
+    let n = normalize(input.normal);
+    let v = normalize(scene.camera_position - input.world_position);
+
+    var lit = AMBIENT_COLOR * color.rgb;
+    let count = min(lights.count.x, 16u);
+    if count == 0u {
+        lit += blinn_phong(color.rgb, n, v, -normalize(DEFAULT_LIGHT_DIRECTION), vec3<f32>(1.0));
+    }
+    for (var i = 0u; i < count; i++) {
+        let incoming = light_incoming(lights.lights[i], input.world_position);
+        lit += blinn_phong(color.rgb, n, v, incoming[0], incoming[1]);
+    }
+    lit += texel.rgb * material.emissive.rgb;
+
+    return vec4<f32>(lit, color.a);
+}
@@ -0,0 +1,37 @@
+// Final pass of the linear output modes: writes the linear frame to the output, encoding it explicitly
+
+struct OutputEncoding {
+    // Whether to apply the sRGB curve in x, and whether the output is an sRGB texture in y
+    params: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> encoding: OutputEncoding;
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(source, source_sampler, input.uv, 0.0);
+
+    var encoded = clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    if (encoding.params.x != 0.0) {
+        encoded = linear_to_srgb(encoded);
+    }
+    // An sRGB output encodes whatever is written to it, so it's given the value that encodes to the one wanted
+    if (encoding.params.y != 0.0) {
+        encoded = srgb_to_linear(encoded);
+    }
+
+    return vec4<f32>(encoded, color.a);
+}
@@ -0,0 +1,419 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) texCoords: vec2<f32>,
+    // Meshes with a single UV set alias this to texCoords
+    @location(4) uv1: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) texCoords: vec2<f32>,
+    @location(3) uv1: vec2<f32>,
+    @location(4) @interpolate(flat) receives_shadows: u32,
+};
+
+struct ObjectData {
+    model: mat4x4<f32>,
+    // Indices into the bindless texture array, unused here
+    texture_indices: vec4<u32>,
+    // Bit 0 of x is set when the model receives shadows, see `OBJECT_RECEIVES_SHADOWS`
+    flags: vec4<u32>,
+};
+
+struct Camera {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+};
+
+struct Light {
+    // range is 0 for infinite
+    position_range: vec4<f32>,
+    // type is 0 for directional, 1 for point, 2 for spot
+    direction_type: vec4<f32>,
+    color_intensity: vec4<f32>,
+    spot_scale_offset: vec4<f32>,
+    // First layer of the light's faces in point_shadows (negative without a shadow), and its far plane
+    shadow: vec4<f32>,
+};
+
+struct Lights {
+    count: vec4<u32>,
+    // Depth bias, slope bias, normal offset and PCF kernel size of every shadow
+    shadow: vec4<f32>,
+    lights: array<Light, 16>,
+};
+
+struct Lightmap {
+    // Region of the lightmap the model covers, as a scale in xy and offset in zw
+    scale_offset: vec4<f32>,
+    // Multiplier of the baked lighting in x, 0 when the model has no lightmap
+    intensity: vec4<f32>,
+};
+
+struct PbrMaterial {
+    base_color_factor: vec4<f32>,
+    // rgb is the emissive factor multiplied by the emissive strength
+    emissive: vec4<f32>,
+    // metallic, roughness, clearcoat, clearcoat roughness
+    metallic_roughness_clearcoat: vec4<f32>,
+    // transmission, alpha cutoff (negative when not masked), 1 when alpha blended
+    transmission_alpha: vec4<f32>,
+    // KHR_texture_transform of each texture, as a 2D affine matrix
+    base_color_transform: mat3x3<f32>,
+    metallic_roughness_transform: mat3x3<f32>,
+    emissive_transform: mat3x3<f32>,
+    clearcoat_transform: mat3x3<f32>,
+    clearcoat_roughness_transform: mat3x3<f32>,
+    transmission_transform: mat3x3<f32>,
+    occlusion_transform: mat3x3<f32>,
+    normal_transform: mat3x3<f32>,
+    // The UV set (0 or 1) each texture samples, in the same order as the transforms
+    texture_uv_sets: array<vec4<u32>, 2>,
+    // Occlusion strength in x, normal scale in y, 1 in z when occlusion is packed into metallic_roughness
+    // and 1 in w for two-channel normal maps
+    occlusion_normal: vec4<f32>,
+};
+
+// Every model's data, indexed by the instance index the renderer draws each model with
+@group(0) @binding(0)
+var<storage, read> objects: array<ObjectData>;
+
+@group(0) @binding(1)
+var<uniform> camera: Camera;
+
+@group(0) @binding(2)
+var<uniform> lights: Lights;
+
+@group(0) @binding(3)
+var<uniform> lightmap: Lightmap;
+
+// Point light shadows, six layers per light holding the distance to it over its far plane.
+// Bound by the renderer
+@group(0) @binding(4)
+var point_shadows: texture_depth_2d_array;
+@group(0) @binding(5)
+var point_shadows_sampler: sampler_comparison;
+
+@group(1) @binding(0)
+var<uniform> material: PbrMaterial;
+@group(1) @binding(1)
+var base_color: texture_2d<f32>;
+@group(1) @binding(2)
+var base_color_sampler: sampler;
+@group(1) @binding(3)
+var metallic_roughness: texture_2d<f32>;
+@group(1) @binding(4)
+var metallic_roughness_sampler: sampler;
+@group(1) @binding(5)
+var emissive: texture_2d<f32>;
+@group(1) @binding(6)
+var emissive_sampler: sampler;
+@group(1) @binding(7)
+var clearcoat: texture_2d<f32>;
+@group(1) @binding(8)
+var clearcoat_sampler: sampler;
+@group(1) @binding(9)
+var clearcoat_roughness: texture_2d<f32>;
+@group(1) @binding(10)
+var clearcoat_roughness_sampler: sampler;
+@group(1) @binding(11)
+var transmission: texture_2d<f32>;
+@group(1) @binding(12)
+var transmission_sampler: sampler;
+// Baked lighting, sampled with the second UV set
+@group(1) @binding(13)
+var lightmap_texture: texture_2d<f32>;
+@group(1) @binding(14)
+var lightmap_texture_sampler: sampler;
+// Baked ambient occlusion in the red channel
+@group(1) @binding(15)
+var occlusion: texture_2d<f32>;
+@group(1) @binding(16)
+var occlusion_sampler: sampler;
+// Tangent-space normals
+@group(1) @binding(17)
+var normal: texture_2d<f32>;
+@group(1) @binding(18)
+var normal_sampler: sampler;
+
+const PI: f32 = 3.14159265;
+
+// With no lights in the scene, surfaces are lit by a fixed key light so they aren't black
+const DEFAULT_LIGHT_DIRECTION: vec3<f32> = vec3<f32>(-0.4, -1.0, -0.3);
+const DEFAULT_LIGHT_COLOR: vec3<f32> = vec3<f32>(3.0, 3.0, 3.0);
+const AMBIENT_COLOR: vec3<f32> = vec3<f32>(0.08, 0.08, 0.1);
+
+@vertex
+fn vertex_main(vertex_input: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let transform = objects[instance_index];
+
+    let world_position = transform.model * vec4<f32>(vertex_input.position, 1.0);
+    output.clip_position = camera.projection * camera.view * world_position;
+    output.world_position = world_position.xyz;
+    output.normal = (transform.model * vec4<f32>(vertex_input.normal, 0.0)).xyz;
+    output.texCoords = vertex_input.texCoords;
+    output.uv1 = vertex_input.uv1;
+    output.receives_shadows = transform.flags.x & 1u;
+
+    return output;
+}
+
+fn transform_uv(uv_transform: mat3x3<f32>, uv: vec2<f32>) -> vec2<f32> {
+    return (uv_transform * vec3<f32>(uv, 1.0)).xy;
+}
+
+// The UV set the texture in the given slot samples
+fn select_uv(slot: u32, input: VertexOutput) -> vec2<f32> {
+    if material.texture_uv_sets[slot / 4u][slot % 4u] == 1u {
+        return input.uv1;
+    }
+    return input.texCoords;
+}
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a2 = roughness * roughness * roughness * roughness;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+fn visibility_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let a2 = roughness * roughness * roughness * roughness;
+    let ggx_v = n_dot_l * sqrt(n_dot_v * n_dot_v * (1.0 - a2) + a2);
+    let ggx_l = n_dot_v * sqrt(n_dot_l * n_dot_l * (1.0 - a2) + a2);
+    return 0.5 / max(ggx_v + ggx_l, 0.00001);
+}
+
+fn fresnel_schlick(f0: vec3<f32>, v_dot_h: f32) -> vec3<f32> {
+    return f0 + (vec3<f32>(1.0) - f0) * pow(1.0 - v_dot_h, 5.0);
+}
+
+struct Surface {
+    base_color: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    diffuse_weight: f32,
+    f0: vec3<f32>,
+};
+
+// Light reflected towards the viewer from light arriving from direction l with the given radiance
+fn shade(surface: Surface, n: vec3<f32>, v: vec3<f32>, l: vec3<f32>, radiance: vec3<f32>) -> vec3<f32> {
+    let h = normalize(v + l);
+    let n_dot_l = max(dot(n, l), 0.0);
+    let n_dot_v = max(dot(n, v), 0.0001);
+    let n_dot_h = max(dot(n, h), 0.0);
+    let v_dot_h = max(dot(v, h), 0.0);
+
+    let f = fresnel_schlick(surface.f0, v_dot_h);
+    let specular = f * distribution_ggx(n_dot_h, surface.roughness) * visibility_smith(n_dot_v, n_dot_l, surface.roughness);
+    let diffuse = (vec3<f32>(1.0) - f) * surface.diffuse_weight * surface.base_color / PI;
+
+    // Clearcoat is a second, dielectric specular layer on top that dims the layer below
+    let clearcoat_fresnel = fresnel_schlick(vec3<f32>(0.04), v_dot_h).x * surface.clearcoat;
+    let clearcoat_specular = clearcoat_fresnel * distribution_ggx(n_dot_h, surface.clearcoat_roughness)
+        * visibility_smith(n_dot_v, n_dot_l, surface.clearcoat_roughness);
+
+    return ((diffuse + specular) * (1.0 - clearcoat_fresnel) + clearcoat_specular) * radiance * n_dot_l;
+}
+
+// The direction towards the light and the radiance arriving from it, following KHR_lights_punctual
+fn light_incoming(light: Light, position: vec3<f32>) -> array<vec3<f32>, 2> {
+    let light_type = u32(light.direction_type.w);
+    let radiance = light.color_intensity.rgb * light.color_intensity.w;
+    if light_type == 0u {
+        return array<vec3<f32>, 2>(-normalize(light.direction_type.xyz), radiance);
+    }
+
+    let to_light = light.position_range.xyz - position;
+    let distance_squared = max(dot(to_light, to_light), 0.0001);
+    let l = to_light * inverseSqrt(distance_squared);
+
+    var attenuation = 1.0 / distance_squared;
+    let range = light.position_range.w;
+    if range > 0.0 {
+        let ratio = distance_squared / (range * range);
+        attenuation *= clamp(1.0 - ratio * ratio, 0.0, 1.0);
+    }
+
+    if light_type == 2u {
+        let cone = clamp(dot(normalize(light.direction_type.xyz), -l) * light.spot_scale_offset.x + light.spot_scale_offset.y, 0.0, 1.0);
+        attenuation *= cone * cone;
+    }
+
+    return array<vec3<f32>, 2>(l, radiance * attenuation);
+}
+
+// How much of a point light reaches the position, from 0 (shadowed) to 1 (lit). The cube face is
+// picked, and projected onto, the same way the renderer draws them
+fn point_shadow(light: Light, position: vec3<f32>, n: vec3<f32>) -> f32 {
+    // Surfaces facing away from the light need the most offset to stop them shadowing themselves
+    let n_dot_l = clamp(dot(n, normalize(light.position_range.xyz - position)), 0.0, 1.0);
+    let to_position = position + n * lights.shadow.z * (1.0 - n_dot_l) - light.position_range.xyz;
+    let axis = abs(to_position);
+
+    var face = 0u;
+    var forward = vec3<f32>(1.0, 0.0, 0.0);
+    var up = vec3<f32>(0.0, -1.0, 0.0);
+    if axis.x >= axis.y && axis.x >= axis.z {
+        if to_position.x < 0.0 {
+            face = 1u;
+            forward = vec3<f32>(-1.0, 0.0, 0.0);
+        }
+    } else if axis.y >= axis.z {
+        if to_position.y >= 0.0 {
+            face = 2u;
+            forward = vec3<f32>(0.0, 1.0, 0.0);
+            up = vec3<f32>(0.0, 0.0, 1.0);
+        } else {
+            face = 3u;
+            forward = vec3<f32>(0.0, -1.0, 0.0);
+            up = vec3<f32>(0.0, 0.0, -1.0);
+        }
+    } else {
+        face = 4u;
+        forward = vec3<f32>(0.0, 0.0, 1.0);
+        if to_position.z < 0.0 {
+            face = 5u;
+            forward = vec3<f32>(0.0, 0.0, -1.0);
+        }
+    }
+
+    let right = normalize(cross(forward, up));
+    let face_up = cross(right, forward);
+    let depth = dot(to_position, forward);
+    let uv = vec2<f32>(dot(to_position, right) / depth * 0.5 + 0.5, 0.5 - dot(to_position, face_up) / depth * 0.5);
+
+    // The slope bias grows with the tangent of the angle to the light, capped so grazing surfaces still get a shadow
+    let slope = min(sqrt(1.0 - n_dot_l * n_dot_l) / max(n_dot_l, 0.01), 10.0);
+    let bias = lights.shadow.x + lights.shadow.y * slope;
+
+    // Anything past the far plane is outside the shadow, so is lit
+    let reference = (length(to_position) - bias) / light.shadow.y;
+    let layer = i32(light.shadow.x) + i32(face);
+
+    // Average a kernel of filtered samples, a texel apart
+    let kernel = max(i32(lights.shadow.w), 1);
+    let texel = 1.0 / f32(textureDimensions(point_shadows).x);
+    var lit = 0.0;
+    for (var y = 0; y < kernel; y++) {
+        for (var x = 0; x < kernel; x++) {
+            let offset = (vec2<f32>(f32(x), f32(y)) - f32(kernel - 1) * 0.5) * texel;
+            lit += textureSampleCompareLevel(point_shadows, point_shadows_sampler, uv + offset, layer, reference);
+        }
+    }
+    lit /= f32(kernel * kernel);
+
+    return select(lit, 1.0, reference >= 1.0);
+}
+
+// Applies a tangent-space normal map sample. Meshes don't carry tangents, so the tangent frame is built
+// from the screen-space derivatives of the position and UV, which has to happen in uniform control flow
+fn perturb_normal(n: vec3<f32>, position: vec3<f32>, uv: vec2<f32>, normal_sample: vec3<f32>) -> vec3<f32> {
+    let dp1 = dpdx(position);
+    let dp2 = dpdy(position);
+    let duv1 = dpdx(uv);
+    let duv2 = dpdy(uv);
+
+    let dp2_perp = cross(dp2, n);
+    let dp1_perp = cross(n, dp1);
+    let t = dp2_perp * duv1.x + dp1_perp * duv2.x;
+    // glTF's V runs down the texture, while the normal's Y points up it
+    let b = -(dp2_perp * duv1.y + dp1_perp * duv2.y);
+    let scale = max(dot(t, t), dot(b, b));
+    if scale <= 0.0 {
+        return n;
+    }
+
+    var tangent_normal = (normal_sample * 2.0 - 1.0) * vec3<f32>(material.occlusion_normal.y, material.occlusion_normal.y, 1.0);
+    // Two-channel normal maps leave out Z, which is positive in tangent space. It's rebuilt from the
+    // scaled X and Y, so a scale of 0 still gives the unchanged normal
+    if material.occlusion_normal.w > 0.0 {
+        tangent_normal.z = sqrt(max(1.0 - dot(tangent_normal.xy, tangent_normal.xy), 0.0));
+    }
+    let tbn = mat3x3<f32>(t * inverseSqrt(scale), b * inverseSqrt(scale), n);
+    return normalize(tbn * tangent_normal);
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let base = material.base_color_factor * textureSample(base_color, base_color_sampler, transform_uv(material.base_color_transform, select_uv(0u, input)));
+    let alpha_cutoff = material.transmission_alpha.y;
+    if alpha_cutoff >= 0.0 && base.a < alpha_cutoff {
+        discard;
+    }
+
+    let factors = material.metallic_roughness_clearcoat;
+    let metallic_roughness_sample = textureSample(metallic_roughness, metallic_roughness_sampler, transform_uv(material.metallic_roughness_transform, select_uv(1u, input)));
+    let metallic = factors.x * metallic_roughness_sample.b;
+    let roughness = clamp(factors.y * metallic_roughness_sample.g, 0.04, 1.0);
+    let clearcoat_factor = factors.z * textureSample(clearcoat, clearcoat_sampler, transform_uv(material.clearcoat_transform, select_uv(3u, input))).r;
+    let clearcoat_roughness_factor = clamp(factors.w * textureSample(clearcoat_roughness, clearcoat_roughness_sampler, transform_uv(material.clearcoat_roughness_transform, select_uv(4u, input))).g, 0.04, 1.0);
+    let transmission_factor = material.transmission_alpha.x * textureSample(transmission, transmission_sampler, transform_uv(material.transmission_transform, select_uv(5u, input))).r;
+    let emissive_color = material.emissive.rgb * textureSample(emissive, emissive_sampler, transform_uv(material.emissive_transform, select_uv(2u, input))).rgb;
+    let occlusion_sample = select(
+        textureSample(occlusion, occlusion_sampler, transform_uv(material.occlusion_transform, select_uv(6u, input))).r,
+        metallic_roughness_sample.r,
+        material.occlusion_normal.z > 0.0
+    );
+    let ambient_occlusion = mix(1.0, occlusion_sample, material.occlusion_normal.x);
+
+    // The camera position is the inverse of the view's translation
+    let view_rotation = mat3x3<f32>(camera.view[0].xyz, camera.view[1].xyz, camera.view[2].xyz);
+    let camera_position = -(transpose(view_rotation) * camera.view[3].xyz);
+
+    let geometric_normal = normalize(input.normal);
+    let normal_uv = transform_uv(material.normal_transform, select_uv(7u, input));
+    let normal_sample = textureSample(normal, normal_sampler, normal_uv).rgb;
+    let n = perturb_normal(geometric_normal, input.world_position, normal_uv, normal_sample);
+    let v = normalize(camera_position - input.world_position);
+
+    var surface: Surface;
+    surface.base_color = base.rgb;
+    surface.metallic = metallic;
+    surface.roughness = roughness;
+    surface.clearcoat = clearcoat_factor;
+    surface.clearcoat_roughness = clearcoat_roughness_factor;
+    // Transmitted light passes through the surface rather than scattering off it
+    surface.diffuse_weight = (1.0 - metallic) * (1.0 - transmission_factor);
+    surface.f0 = mix(vec3<f32>(0.04), base.rgb, metallic);
+
+    // Baked lighting replaces the flat ambient term. It's always sampled, as texture sampling has
+    // to happen in uniform control flow
+    let lightmap_uv = input.uv1 * lightmap.scale_offset.xy + lightmap.scale_offset.zw;
+    let baked = textureSample(lightmap_texture, lightmap_texture_sampler, lightmap_uv).rgb * lightmap.intensity.x;
+    var indirect = AMBIENT_COLOR;
+    if lightmap.intensity.x > 0.0 {
+        indirect = baked;
+    }
+
+    // Occlusion only darkens the indirect light, as in glTF
+    var color = indirect * ambient_occlusion * (base.rgb * surface.diffuse_weight + surface.f0) + emissive_color;
+    let light_count = min(lights.count.x, 16u);
+    if light_count == 0u {
+        color += shade(surface, n, v, -normalize(DEFAULT_LIGHT_DIRECTION), DEFAULT_LIGHT_COLOR);
+    }
+    for (var i = 0u; i < light_count; i++) {
+        let light = lights.lights[i];
+        let incoming = light_incoming(light, input.world_position);
+        var shadow = 1.0;
+        if light.shadow.x >= 0.0 && input.receives_shadows != 0u {
+            shadow = point_shadow(light, input.world_position, geometric_normal);
+        }
+        color += shade(surface, n, v, incoming[0], incoming[1] * shadow);
+    }
+
+    // There's no copy of the scene behind the surface to refract, so transmission is
+    // approximated by blending the surface over it
+    var alpha = 1.0;
+    if material.transmission_alpha.z > 0.5 {
+        alpha = base.a;
+    }
+    alpha = alpha * (1.0 - transmission_factor);
+
+    return vec4<f32>(color, alpha);
+}
@@ -0,0 +1,66 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) corner: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) corner: vec2<f32>,
+};
+
+struct ObjectData {
+    model: mat4x4<f32>,
+    // Indices into the bindless texture array, unused here
+    texture_indices: vec4<u32>,
+    // Model flags such as whether it receives shadows, unused here
+    flags: vec4<u32>,
+};
+
+struct Camera {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+};
+
+struct PointCloud {
+    viewport: vec2<f32>,
+    size: f32,
+};
+
+// Every model's data, indexed by the instance index the renderer draws each model with
+@group(0) @binding(0)
+var<storage, read> objects: array<ObjectData>;
+
+@group(0) @binding(1)
+var<uniform> camera: Camera;
+
+@group(1) @binding(0)
+var<uniform> point_cloud: PointCloud;
+
+@vertex
+fn vertex_main(vertex_input: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let transform = objects[instance_index];
+
+    output.clip_position = camera.projection * camera.view * transform.model * vec4<f32>(vertex_input.position, 1.0);
+
+    // Push sprite corners out in screen space, so points keep the same pixel size at any distance
+    let offset = vertex_input.corner * point_cloud.size / point_cloud.viewport;
+    output.clip_position = vec4<f32>(output.clip_position.xy + offset * output.clip_position.w, output.clip_position.zw);
+
+    output.color = vertex_input.color;
+    output.corner = vertex_input.corner;
+
+    return output;
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // Round sprites. Pixel points have no corner, so are never discarded
+    if dot(input.corner, input.corner) > 1.0 {
+        discard;
+    }
+
+    return input.color;
+}
@@ -0,0 +1,87 @@
+// Draws one cube face of a point light's shadow, storing each fragment's distance to the light
+// over the far plane as its depth
+
+struct ObjectData {
+    model: mat4x4<f32>,
+    texture_indices: vec4<u32>,
+    flags: vec4<u32>,
+};
+
+struct ShadowFace {
+    view_projection: mat4x4<f32>,
+    // Light position, and the far plane distances are divided by
+    light_position_far: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+};
+
+@group(0) @binding(0)
+var<storage, read> objects: array<ObjectData>;
+
+@group(0) @binding(1)
+var<uniform> face: ShadowFace;
+
+// Each mesh layout's pipeline uses one of the entry points below, and the group 1 binding it reads
+// from the model's material, if any
+struct Skin {
+    joints: array<mat4x4<f32>, 64>,
+};
+
+struct Quantization {
+    offset: vec4<f32>,
+    scale: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> skin: Skin;
+
+@group(1) @binding(1)
+var<uniform> quantization: Quantization;
+
+fn project(position: vec3<f32>, instance_index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let world_position = objects[instance_index].model * vec4<f32>(position, 1.0);
+    output.clip_position = face.view_projection * world_position;
+    output.world_position = world_position.xyz;
+    return output;
+}
+
+@vertex
+fn vertex_main(@location(0) position: vec3<f32>, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    return project(position, instance_index);
+}
+
+// `SkinnedVertex` meshes, blended between their joints like the "skinned" shader
+@vertex
+fn vertex_skinned(
+    @location(0) position: vec3<f32>,
+    @location(8) joints: vec4<u32>,
+    @location(9) weights: vec4<f32>,
+    @builtin(instance_index) instance_index: u32
+) -> VertexOutput {
+    var skinned = vec4<f32>(position, 1.0);
+    let total_weight = dot(weights, vec4<f32>(1.0));
+    if total_weight > 0.0 {
+        let normalized = weights / total_weight;
+        let skin_matrix = skin.joints[min(joints.x, 63u)] * normalized.x
+            + skin.joints[min(joints.y, 63u)] * normalized.y
+            + skin.joints[min(joints.z, 63u)] * normalized.z
+            + skin.joints[min(joints.w, 63u)] * normalized.w;
+        skinned = skin_matrix * skinned;
+    }
+    return project(skinned.xyz, instance_index);
+}
+
+// `QuantizedVertex` meshes, with 16-bit positions mapped back to mesh space
+@vertex
+fn vertex_quantized(@location(0) position: vec4<f32>, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    return project(quantization.offset.xyz + position.xyz * quantization.scale.xyz, instance_index);
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @builtin(frag_depth) f32 {
+    return clamp(length(input.world_position - face.light_position_far.xyz) / face.light_position_far.w, 0.0, 1.0);
+}
@@ -0,0 +1,21 @@
+// Shared by every post effect and `FullscreenPass`: a single triangle covering the target.
+// The fragment shader appended to it defines `fragment_main`, taking a `PostVertexOutput`
+
+struct PostVertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 0 to 1 across the target, with (0, 0) in the top left
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vertex_main(@builtin(vertex_index) index: u32) -> PostVertexOutput {
+    var output: PostVertexOutput;
+
+    // (0, 0), (2, 0) and (0, 2) in uv, so the target is covered by the triangle's first half.
+    // It sits just in front of the far plane, so in the scene pass it only covers what no model drew over
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    output.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.99999994, 1.0);
+    output.uv = uv;
+
+    return output;
+}
@@ -0,0 +1,6 @@
+// The frame post effects read from, bound by the stack. Effects put their own bindings in group 1
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
@@ -9,26 +9,36 @@ struct VertexOutput {
     @location(0) texCoords: vec2<f32>
 };
 
-struct Transform {
+struct ObjectData {
     model: mat4x4<f32>,
+    // Indices into the bindless texture array, unused here
+    texture_indices: vec4<u32>,
+    // Model flags such as whether it receives shadows, unused here
+    flags: vec4<u32>,
 };
 
-struct Camera {
+// Filled in by the renderer, see `SCENE_UNIFORM_WGSL`
+struct Scene {
     view: mat4x4<f32>,
     projection: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
 };
 
+// Every model's data, indexed by the instance index the renderer draws each model with
 @group(0) @binding(0)
-var<uniform> transform: Transform;
+var<storage, read> objects: array<ObjectData>;
 
 @group(0) @binding(1)
-var<uniform> camera: Camera;
+var<uniform> scene: Scene;
 
 @vertex
-fn vertex_main(vertex_input: VertexInput) -> VertexOutput {
+fn vertex_main(vertex_input: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
     var output: VertexOutput;
+    let transform = objects[instance_index];
 
-    output.clip_position = camera.projection * camera.view * transform.model * vec4<f32>(vertex_input.position, 1.0);
+    output.clip_position = scene.view_projection * transform.model * vec4<f32>(vertex_input.position, 1.0);
     output.texCoords = vertex_input.texCoords;  // Pass texture coordinates to fragment shader
 
     return output;
@@ -0,0 +1,69 @@
+// Skins the `SkinnedVertex` data of a sub mesh into standard `Vertex` data, blending each vertex
+// between up to four joints like the "skinned" shader does, so any shader can draw the result
+
+// Each joint's world transform multiplied by its inverse bind matrix, see `SkinUniform`
+struct Skin {
+    joints: array<mat4x4<f32>, 64>,
+};
+
+@group(0) @binding(0)
+var<uniform> skin: Skin;
+
+// `SkinnedVertex`s, 16 words each: position, normal, texture coordinates, joints and weights.
+// Read as words, as a struct of vec3s would be padded differently
+@group(0) @binding(1)
+var<storage, read> source: array<u32>;
+
+// `Vertex`s, 8 floats each: position, normal and texture coordinates
+@group(0) @binding(2)
+var<storage, read_write> output: array<f32>;
+
+fn read_f32(index: u32) -> f32 {
+    return bitcast<f32>(source[index]);
+}
+
+@compute @workgroup_size(64)
+fn skin_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let vertex = id.x;
+    if vertex >= arrayLength(&source) / 16u {
+        return;
+    }
+
+    let base = vertex * 16u;
+    let position = vec3<f32>(read_f32(base), read_f32(base + 1u), read_f32(base + 2u));
+    let normal = vec3<f32>(read_f32(base + 3u), read_f32(base + 4u), read_f32(base + 5u));
+    let joints = vec4<u32>(source[base + 8u], source[base + 9u], source[base + 10u], source[base + 11u]);
+    let weights = vec4<f32>(read_f32(base + 12u), read_f32(base + 13u), read_f32(base + 14u), read_f32(base + 15u));
+
+    // Vertices without weights stay where they are
+    let total_weight = dot(weights, vec4<f32>(1.0));
+    var skin_matrix = mat4x4<f32>(
+        vec4<f32>(1.0, 0.0, 0.0, 0.0),
+        vec4<f32>(0.0, 1.0, 0.0, 0.0),
+        vec4<f32>(0.0, 0.0, 1.0, 0.0),
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
+    );
+    if total_weight > 0.0 {
+        let normalized = weights / total_weight;
+        skin_matrix = skin.joints[min(joints.x, 63u)] * normalized.x
+            + skin.joints[min(joints.y, 63u)] * normalized.y
+            + skin.joints[min(joints.z, 63u)] * normalized.z
+            + skin.joints[min(joints.w, 63u)] * normalized.w;
+    }
+
+    let skinned_position = (skin_matrix * vec4<f32>(position, 1.0)).xyz;
+    var skinned_normal = (skin_matrix * vec4<f32>(normal, 0.0)).xyz;
+    if dot(skinned_normal, skinned_normal) > 0.0 {
+        skinned_normal = normalize(skinned_normal);
+    }
+
+    let out = vertex * 8u;
+    output[out] = skinned_position.x;
+    output[out + 1u] = skinned_position.y;
+    output[out + 2u] = skinned_position.z;
+    output[out + 3u] = skinned_normal.x;
+    output[out + 4u] = skinned_normal.y;
+    output[out + 5u] = skinned_normal.z;
+    output[out + 6u] = read_f32(base + 6u);
+    output[out + 7u] = read_f32(base + 7u);
+}
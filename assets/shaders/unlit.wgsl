@@ -0,0 +1,66 @@
+// Built-in "unlit" shader: the base colour texture multiplied by the material colour, with no lighting
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) texCoords: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texCoords: vec2<f32>,
+};
+
+struct ObjectData {
+    model: mat4x4<f32>,
+    // Indices into the bindless texture array, unused here
+    texture_indices: vec4<u32>,
+    // Model flags such as whether it receives shadows, unused here
+    flags: vec4<u32>,
+};
+
+// Filled in by the renderer, see `SCENE_UNIFORM_WGSL`
+struct Scene {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+};
+
+// Shared by the built-in shaders, see `BasicMaterialUniform`. Unlit ignores the specular
+struct BasicMaterial {
+    color: vec4<f32>,
+    // Specular strength in x, shininess in y
+    specular: vec4<f32>,
+    // Linear colour added on top of the lighting, masked by the texture. Above 1 it feeds bloom
+    emissive: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<storage, read> objects: array<ObjectData>;
+
+@group(0) @binding(1)
+var<uniform> scene: Scene;
+
+@group(1) @binding(0)
+var<uniform> material: BasicMaterial;
+@group(1) @binding(1)
+var base_color: texture_2d<f32>;
+@group(1) @binding(2)
+var base_color_sampler: sampler;
+
+@vertex
+fn vertex_main(vertex_input: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    output.clip_position = scene.view_projection * objects[instance_index].model * vec4<f32>(vertex_input.position, 1.0);
+    output.texCoords = vertex_input.texCoords;
+    return output;
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(base_color, base_color_sampler, input.texCoords);
+    let color = texel * material.color;
+    return vec4<f32>(color.rgb + texel.rgb * material.emissive.rgb, color.a);
+}
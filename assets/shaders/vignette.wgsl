@@ -0,0 +1,21 @@
+// Vignette: darkens the frame towards its corners
+
+struct Vignette {
+    // Intensity in x, the radius darkening starts at in y, and how gradually it falls off in z
+    params: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> vignette: Vignette;
+
+@fragment
+fn fragment_main(input: PostVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(source, source_sampler, input.uv, 0.0);
+
+    // Distance from the centre, where the corners are at 1
+    let distance = length(input.uv - 0.5) * sqrt(2.0);
+    let radius = vignette.params.y;
+    let falloff = smoothstep(radius, radius + max(vignette.params.z, 0.0001), distance);
+
+    return vec4<f32>(color.rgb * (1.0 - falloff * vignette.params.x), color.a);
+}
@@ -1,5 +1,4 @@
-use log::info;
-use minirenderer::{AsBytes, Renderer, RenderFramework, ResourceHandle, Transform};
+use minirenderer::{init_default_logging, Renderer, RenderFramework, ResourceHandle, Transform};
 
 
 struct Camera {
@@ -28,41 +27,13 @@ impl Camera {
     }
 
     fn get_projection_matrix(&self) -> glam::Mat4 {
-        glam::Mat4::perspective_rh_gl(self.fov.to_radians(), self.aspect, self.near, self.far)
-    }
-}
-
-struct CameraUniform {
-    view: glam::Mat4,
-    projection: glam::Mat4,
-}
-
-impl CameraUniform {
-    fn new(camera: &Camera) -> Self {
-        Self {
-            view: camera.get_view_matrix(),
-            projection: camera.get_projection_matrix(),
-        }
-    }
-}
-
-impl AsBytes for CameraUniform {
-    fn as_bytes(&self) -> &[u8] {
-        unsafe {
-            std::slice::from_raw_parts(
-                (self as *const Self) as *const u8,
-                std::mem::size_of::<Self>(),
-            )
-        }
+        minirenderer::math::perspective(self.fov.to_radians(), self.aspect, self.near, self.far)
     }
 }
 
 pub struct RenderState {
     // Persistent Variables
 
-    // Uniforms
-    camera_handle: ResourceHandle,
-
     // Meshes
     mesh_handle: ResourceHandle,
 
@@ -79,7 +50,6 @@ pub struct RenderState {
 impl RenderState {
     pub fn new() -> Self {
         Self {
-            camera_handle: ResourceHandle::default(),
             mesh_handle: ResourceHandle::default(),
             texture_handle: ResourceHandle::default(),
             material_handle: ResourceHandle::default(),
@@ -89,6 +59,8 @@ impl RenderState {
 }
 
 fn main() {
+    init_default_logging();
+
     let renderer = Renderer::new();
 
     let state = RenderState::new();
@@ -112,15 +84,13 @@ fn load_mesh_texture_material_model(renderer: &Renderer) -> (ResourceHandle, Res
     let mut transform = Transform::new();
     let position = glam::Vec3::new(0.0, 0.0, -15.0);
     // Rotate 45 degrees around the y-axis and 45 degrees around the x-axis
-    let rotation = glam::Quat::from_euler(glam::EulerRot::YXZ, 45.0, 45.0, 0.0);
+    let rotation = glam::Quat::from_euler(glam::EulerRot::YXZ, 45f32.to_radians(), 45f32.to_radians(), 0.0);
     let scale = glam::Vec3::new(1.0, 1.0, 1.0);
     transform.set_position(position);
     transform.set_rotation(rotation);
     transform.set_scale(scale);
 
     let model_handle = resource_manager.create_model(&mesh_handle, &material_handle, transform);
-    let transform_uniform = resource_manager.get_model_transform_uniform_handle(&model_handle);
-    resource_manager.assign_uniform_to_material(&material_handle, &transform_uniform, "transform");
 
     (mesh_handle, texture_handle, material_handle, model_handle)
 }
@@ -132,13 +102,13 @@ fn initialize_renderer(state: &mut RenderState, renderer: &mut Renderer) {
     let (mesh_handle, texture_handle, material_handle, model_handle)
         = load_mesh_texture_material_model(&renderer);
 
-    // Create a camera
-    let camera = Camera::new();
-    let camera_uniform = CameraUniform::new(&camera);
-
-
     let mut resource_manager = resource_manager_handle.get();
 
+    // Set the camera. The renderer writes it into the `scene` uniform, which is bound
+    // to any shader that declares it, so there's no uniform to create or assign
+    let camera = Camera::new();
+    resource_manager.set_camera(camera.get_view_matrix(), camera.get_projection_matrix());
+
 
     // Load another mesh, but use the same material
     let mesh_handle = resource_manager.load_mesh("assets/meshes/cube.glb");
@@ -146,11 +116,6 @@ fn initialize_renderer(state: &mut RenderState, renderer: &mut Renderer) {
     second_transform.set_position(glam::Vec3::new(-2.0, 0.0, -15.0));
     let model_handle = resource_manager.create_model(&mesh_handle, &material_handle, second_transform);
 
-    // Load a uniform
-    let camera_handle = resource_manager.create_uniform_buffer(camera_uniform);
-    // Assign the uniform to the material
-    resource_manager.assign_uniform_to_material(&material_handle, &camera_handle, "camera");
-
 
     // Load a shader
     let shader_handle = resource_manager.load_shader(
@@ -173,7 +138,6 @@ fn initialize_renderer(state: &mut RenderState, renderer: &mut Renderer) {
     let pipeline_handle = resource_manager.create_pipeline(&mesh_handle, &material_handle);
 
     // Store the handles in the state
-    state.camera_handle = camera_handle;
     state.mesh_handle = mesh_handle;
     state.texture_handle = texture_handle;
     state.material_handle = material_handle;
@@ -183,15 +147,10 @@ fn initialize_renderer(state: &mut RenderState, renderer: &mut Renderer) {
 fn update_renderer(state: &mut RenderState, renderer: &mut Renderer) {
     // Rotate the model
     let resource_manager_handle = renderer.get_resource_manager();
-    let resource_manager = resource_manager_handle.get();
-
-    let mut transform = resource_manager.get_model_transform(&state.model_handle);
+    let mut resource_manager = resource_manager_handle.get();
 
-    info!("Transform Position: {:?}", transform.get_position());
-    info!("Transform Rotation: {:?}", transform.get_rotation());
-    info!("Transform Scale: {:?}", transform.get_scale());
+    let mut transform = resource_manager.get_model_transform(&state.model_handle).clone();
+    transform.rotate(glam::Quat::from_euler(glam::EulerRot::YXZ, 0.0, 0.01, 0.01));
 
-    let rotation = transform.get_rotation();
-    let rotation = glam::Quat::from_euler(glam::EulerRot::YXZ, 0.0, 0.01, 0.01) * rotation;
-    transform.set_rotation(rotation);
+    resource_manager.set_model_transform(&state.model_handle, transform);
 }
\ No newline at end of file
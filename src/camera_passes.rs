@@ -0,0 +1,134 @@
+use crate::frame_context::{CustomDrawFn, FrameContext};
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceManager;
+use crate::scene_batches::SceneBatches;
+use crate::screen_attachments;
+use crate::stats::FrameStats;
+use crate::types::camera::CameraTarget;
+use crate::types::texture::Texture;
+
+/// # Camera Passes
+///
+/// Draws the scene for every active camera added to the resource manager (see `Camera`)
+pub(crate) struct CameraPasses<'a>{
+    pub(crate) surface_target: &'a wgpu::TextureView,
+    pub(crate) surface_depth: &'a Texture,
+    // Multisampled colour target resolved into `surface_target`, when multisampling
+    pub(crate) surface_msaa: Option<&'a Texture>,
+    pub(crate) surface_size: [u32; 2],
+    pub(crate) clear_color: wgpu::Color,
+}
+
+impl CameraPasses<'_>{
+    /// # Draw
+    ///
+    /// Draws each camera in its own submission, as they each write their view into the `scene`
+    /// uniform, so everything is drawn before the default camera's submission. Returns whether any
+    /// camera drew to the surface, in which case the default camera shouldn't be drawn
+    pub(crate) fn draw(
+        &self,
+        resource_manager: &ResourceManager,
+        batches: &SceneBatches,
+        custom_draw: &mut Option<CustomDrawFn>,
+        stats: &mut FrameStats
+    ) -> bool{
+        let cameras = resource_manager.get_active_cameras();
+        if cameras.is_empty(){
+            return false;
+        }
+
+        // GPU culling was set up for the default camera's view, so other views draw everything
+        let unculled_batches;
+        let batches = if resource_manager.get_gpu_culling().is_some(){
+            unculled_batches = SceneBatches::prepare_unculled(resource_manager);
+            &unculled_batches
+        }else{
+            batches
+        };
+
+        let device = resource_manager.get_device();
+        let queue = resource_manager.get_queue();
+
+        // Targets already cleared this frame, with `None` for the surface
+        let mut cleared: Vec<Option<&ResourceHandle>> = Vec::new();
+        let mut drew_surface = false;
+
+        for camera in cameras{
+            // Models sampling the texture being drawn into are left out, e.g a mirror in its own reflection,
+            // as are the models on none of the camera's render layers
+            let own_batches = SceneBatches::prepare_for_camera(resource_manager, camera);
+            let camera_batches = own_batches.as_ref().unwrap_or(batches);
+
+            let (target_key, color, depth, msaa_color, size) = match &camera.target{
+                CameraTarget::Surface => (None, self.surface_target, self.surface_depth, self.surface_msaa, self.surface_size),
+                CameraTarget::Texture(texture_handle) => {
+                    let Some((color, depth, msaa_color)) = resource_manager.get_render_texture(texture_handle) else { continue };
+                    let size = color.get_texture_size();
+                    (Some(texture_handle), color.get_texture_view(), depth, msaa_color, [size.width, size.height])
+                }
+            };
+            drew_surface |= target_key.is_none();
+
+            let load = if cleared.contains(&target_key){
+                wgpu::LoadOp::Load
+            }else{
+                cleared.push(target_key);
+                wgpu::LoadOp::Clear(camera.clear_color.unwrap_or(self.clear_color))
+            };
+
+            resource_manager.write_scene_uniform(camera.view, camera.projection);
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor{
+                label: Some("Camera Render Encoder")
+            });
+
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                    label: Some("Camera Render Pass"),
+                    color_attachments: &[
+                        Some(screen_attachments::color_attachment(color, msaa_color, wgpu::Operations{
+                            load,
+                            store: wgpu::StoreOp::Store
+                        }))
+                    ],
+                    // Depth is always cleared, as nothing else needs an earlier camera's
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment{
+                        view: depth.get_texture_view(),
+                        depth_ops: Some(wgpu::Operations{
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: wgpu::StoreOp::Store
+                        }),
+                        stencil_ops: depth.get_format().has_stencil_aspect().then_some(wgpu::Operations{
+                            load: wgpu::LoadOp::Clear(0),
+                            store: wgpu::StoreOp::Store
+                        })
+                    }),
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+
+                // Viewports outside the target aren't allowed, so clip them to it
+                if let Some([x, y, width, height]) = camera.viewport{
+                    let (x, y) = (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0));
+                    let (width, height) = (width.clamp(0.0, 1.0 - x), height.clamp(0.0, 1.0 - y));
+                    let (target_width, target_height) = (size[0] as f32, size[1] as f32);
+                    render_pass.set_viewport(x * target_width, y * target_height, width * target_width, height * target_height, 0.0, 1.0);
+                }
+
+                camera_batches.draw(resource_manager, &mut render_pass, stats);
+
+                if let Some(custom_draw) = custom_draw.as_mut(){
+                    custom_draw(&mut FrameContext::new(resource_manager, &mut render_pass, stats));
+                }
+            }
+
+            queue.submit(std::iter::once(encoder.finish()));
+        }
+
+        // Back to the default camera, for anything drawn after
+        let (view, projection) = resource_manager.get_camera();
+        resource_manager.write_scene_uniform(view, projection);
+
+        drew_surface
+    }
+}
@@ -0,0 +1,285 @@
+use wgpu::util::{DeviceExt, DrawIndexedIndirectArgs};
+use crate::hi_z::{HiZPyramid, HI_Z_FORMAT};
+use crate::types::frustum::Frustum;
+use crate::utils::buffer::AsBytes;
+use crate::utils::handle::Handle;
+
+const CULL_SHADER: &str = include_str!("../assets/shaders/cull.wgsl");
+const WORKGROUP_SIZE: u32 = 64;
+
+/// # Cull Draw
+///
+/// One indirect draw as the culling shader reads it
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct CullDraw{
+    /// Local space bounding sphere. A negative radius means the draw is never culled
+    pub sphere: [f32; 4],
+    pub index_count: u32,
+    pub object_index: u32,
+    /// Which counter in the counts buffer the draw is appended with
+    pub group: u32,
+    /// Start of the group's range in the indirect buffer
+    pub first_draw: u32,
+}
+
+crate::impl_as_bytes!(CullDraw);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CullUniform{
+    view_projection: [[f32; 4]; 4],
+    planes: [[f32; 4]; 6],
+    draw_count: [u32; 4],
+}
+
+crate::impl_as_bytes!(CullUniform);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct HiZUniform{
+    // Width and height of the first level, the number of levels, and 1 if occlusion culling is on
+    size: [f32; 4],
+}
+
+crate::impl_as_bytes!(HiZUniform);
+
+/// # Cull Batch
+///
+/// The buffers a pipeline's draws are culled into. The indirect buffer starts zeroed, so any
+/// slot the culling pass doesn't fill is an empty draw, and the counts buffer holds the number
+/// of visible draws per group for devices that can read the draw count from a buffer
+pub(crate) struct CullBatch{
+    indirect_buffer: wgpu::Buffer,
+    counts_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    draw_count: u32,
+}
+
+impl CullBatch{
+    pub(crate) fn get_indirect_buffer(&self) -> &wgpu::Buffer{
+        &self.indirect_buffer
+    }
+
+    pub(crate) fn get_counts_buffer(&self) -> &wgpu::Buffer{
+        &self.counts_buffer
+    }
+}
+
+/// # GPU Culling
+///
+/// A compute pass that frustum culls each model's bounds against a view-projection matrix,
+/// compacting the visible draws of every group into the front of its range of the indirect buffer.
+///
+/// Given a Hi-Z pyramid of the previous frame's depth, models hidden behind it are culled too
+pub(crate) struct GpuCulling{
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    hi_z_layout: wgpu::BindGroupLayout,
+    // Bound when there's no pyramid to test against, turning occlusion culling off
+    no_hi_z_bind_group: wgpu::BindGroup,
+
+    _device: Handle<wgpu::Device>,
+}
+
+impl GpuCulling{
+    pub(crate) fn new(device: Handle<wgpu::Device>) -> Self{
+        let storage_entry = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry{
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer{
+                ty: wgpu::BufferBindingType::Storage{ read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None
+            },
+            count: None
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Culling Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                storage_entry(1, true),
+                storage_entry(2, true),
+                storage_entry(3, false),
+                storage_entry(4, false),
+            ]
+        });
+
+        let hi_z_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Culling Hi-Z Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture{
+                        sample_type: wgpu::TextureSampleType::Float{ filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                },
+            ]
+        });
+
+        let no_hi_z_texture = device.create_texture(&wgpu::TextureDescriptor{
+            label: Some("Culling Placeholder Hi-Z"),
+            size: wgpu::Extent3d{
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HI_Z_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let no_hi_z_bind_group = Self::create_hi_z_bind_group(&device, &hi_z_layout,
+            &no_hi_z_texture.create_view(&wgpu::TextureViewDescriptor::default()), HiZUniform{ size: [1.0, 1.0, 1.0, 0.0] });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Culling Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout, &hi_z_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor{
+            label: Some("Culling Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(CULL_SHADER.into())
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor{
+            label: Some("Culling Pipeline"),
+            layout: Some(&layout),
+            module: &shader_module,
+            entry_point: "cull_main",
+        });
+
+        Self{
+            pipeline,
+            bind_group_layout,
+            hi_z_layout,
+            no_hi_z_bind_group,
+
+            _device: device,
+        }
+    }
+
+    /// # Create Batch
+    ///
+    /// Uploads a pipeline's draws, with room for `indirect_draw_count` draws in the indirect buffer
+    /// and a counter for each of the `group_count` groups
+    pub(crate) fn create_batch(&self, objects: &wgpu::Buffer, draws: &[CullDraw], indirect_draw_count: u32, group_count: u32, view_projection: glam::Mat4) -> CullBatch{
+        let uniform = CullUniform{
+            view_projection: view_projection.to_cols_array_2d(),
+            planes: Frustum::from_view_projection(view_projection).to_arrays(),
+            draw_count: [draws.len() as u32, 0, 0, 0],
+        };
+
+        let uniform_buffer = self._device.create_buffer_init(&wgpu::util::BufferInitDescriptor{
+            label: Some("Culling Uniform Buffer"),
+            contents: uniform.as_bytes(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let draws_buffer = self._device.create_buffer_init(&wgpu::util::BufferInitDescriptor{
+            label: Some("Culling Draws Buffer"),
+            contents: bytemuck::cast_slice(draws),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        // New buffers are zeroed, which is what the culling pass expects
+        let indirect_buffer = self._device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("Culled Indirect Draw Buffer"),
+            size: (indirect_draw_count as usize * std::mem::size_of::<DrawIndexedIndirectArgs>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let counts_buffer = self._device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("Culled Draw Counts Buffer"),
+            size: (group_count as usize * std::mem::size_of::<u32>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self._device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some("Culling Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry{ binding: 0, resource: uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry{ binding: 1, resource: objects.as_entire_binding() },
+                wgpu::BindGroupEntry{ binding: 2, resource: draws_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry{ binding: 3, resource: indirect_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry{ binding: 4, resource: counts_buffer.as_entire_binding() },
+            ]
+        });
+
+        CullBatch{
+            indirect_buffer,
+            counts_buffer,
+            bind_group,
+            draw_count: draws.len() as u32,
+        }
+    }
+
+    fn create_hi_z_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, view: &wgpu::TextureView, uniform: HiZUniform) -> wgpu::BindGroup{
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor{
+            label: Some("Culling Hi-Z Uniform Buffer"),
+            contents: uniform.as_bytes(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some("Culling Hi-Z Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry{ binding: 0, resource: uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry{ binding: 1, resource: wgpu::BindingResource::TextureView(view) },
+            ]
+        })
+    }
+
+    /// Records the culling of each batch into a single compute pass. Occlusion culling is
+    /// skipped unless a pyramid holding the previous frame's depth is given
+    pub(crate) fn dispatch<'a>(&self, encoder: &mut wgpu::CommandEncoder, batches: impl Iterator<Item = &'a CullBatch>, hi_z: Option<&HiZPyramid>){
+        let hi_z_bind_group = hi_z.filter(|hi_z| hi_z.is_valid()).map(|hi_z| {
+            let (width, height) = hi_z.get_size();
+            let uniform = HiZUniform{ size: [width as f32, height as f32, hi_z.get_mip_level_count() as f32, 1.0] };
+            Self::create_hi_z_bind_group(&self._device, &self.hi_z_layout, hi_z.get_view(), uniform)
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
+            label: Some("Culling Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(1, hi_z_bind_group.as_ref().unwrap_or(&self.no_hi_z_bind_group), &[]);
+
+        for batch in batches{
+            compute_pass.set_bind_group(0, &batch.bind_group, &[]);
+            compute_pass.dispatch_workgroups(batch.draw_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use log::error;
+
+/// # Debug Settings
+///
+/// Per-subsystem debug verbosity. Everything is off by default, so the renderer stays
+/// silent on the hot path unless debugging is opted into.
+///
+/// Enabled subsystems log at `debug` level under their own target
+/// (e.g `minirenderer::render`), so they can be filtered further with `RUST_LOG`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DebugSettings{
+    /// Per-frame render loop logging (draw submission, transforms)
+    pub render: bool,
+    /// Material bind group generation
+    pub materials: bool,
+    /// Buffer and resource creation/destruction
+    pub resources: bool,
+    /// Shader reflection and bind group layouts
+    pub shaders: bool,
+}
+
+impl DebugSettings{
+    /// Enables every subsystem
+    pub fn all() -> Self{
+        Self{
+            render: true,
+            materials: true,
+            resources: true,
+            shaders: true,
+        }
+    }
+}
+
+/// # GPU Validation
+///
+/// Graphics API debugging the renderer is created with, see `WindowSettings::with_gpu_validation`.
+/// The default follows the build (on in debug builds) and the `WGPU_VALIDATION` and `WGPU_DEBUG`
+/// environment variables, like wgpu itself does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpuValidation{
+    /// Enables the backend's validation layers, e.g the Vulkan validation layers or the D3D12 debug layer
+    pub validation: bool,
+    /// Generates debug information in shaders and objects, so captures are easier to follow
+    pub debug: bool,
+    /// Logs uncaptured GPU errors with a backtrace of where they were reported before panicking
+    pub backtraces: bool,
+}
+
+impl Default for GpuValidation{
+    fn default() -> Self{
+        let flags = wgpu::InstanceFlags::from_build_config().with_env();
+        Self{
+            validation: flags.contains(wgpu::InstanceFlags::VALIDATION),
+            debug: flags.contains(wgpu::InstanceFlags::DEBUG),
+            backtraces: false,
+        }
+    }
+}
+
+impl GpuValidation{
+    pub fn enabled() -> Self{
+        Self{
+            validation: true,
+            debug: true,
+            backtraces: true,
+        }
+    }
+
+    /// No validation at all, for release builds that want every bit of performance
+    pub fn disabled() -> Self{
+        Self{
+            validation: false,
+            debug: false,
+            backtraces: false,
+        }
+    }
+
+    pub(crate) fn get_instance_flags(&self) -> wgpu::InstanceFlags{
+        let mut flags = wgpu::InstanceFlags::empty();
+        flags.set(wgpu::InstanceFlags::VALIDATION, self.validation);
+        flags.set(wgpu::InstanceFlags::DEBUG, self.debug);
+        flags
+    }
+
+    /// Replaces wgpu's panic on uncaptured errors with one that logs a backtrace first, if enabled
+    pub(crate) fn apply_to_device(&self, device: &wgpu::Device){
+        if !self.backtraces{
+            return;
+        }
+
+        device.on_uncaptured_error(Box::new(|e|{
+            error!("Uncaptured GPU error: {}\n{}", e, std::backtrace::Backtrace::force_capture());
+            panic!("Uncaptured GPU error: {}", e)
+        }));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Subsystem{
+    Render = 1 << 0,
+    Materials = 1 << 1,
+    Resources = 1 << 2,
+    Shaders = 1 << 3,
+}
+
+impl Subsystem{
+    pub(crate) fn target(&self) -> &'static str{
+        match self{
+            Subsystem::Render => "minirenderer::render",
+            Subsystem::Materials => "minirenderer::materials",
+            Subsystem::Resources => "minirenderer::resources",
+            Subsystem::Shaders => "minirenderer::shaders",
+        }
+    }
+}
+
+// Global, as the subsystems that log (buffers, materials, shaders) don't have access to the renderer
+static ENABLED_SUBSYSTEMS: AtomicU8 = AtomicU8::new(0);
+
+pub(crate) fn set_debug_settings(settings: DebugSettings){
+    let mut bits = 0;
+    if settings.render { bits |= Subsystem::Render as u8; }
+    if settings.materials { bits |= Subsystem::Materials as u8; }
+    if settings.resources { bits |= Subsystem::Resources as u8; }
+    if settings.shaders { bits |= Subsystem::Shaders as u8; }
+
+    ENABLED_SUBSYSTEMS.store(bits, Ordering::Relaxed);
+}
+
+pub(crate) fn get_debug_settings() -> DebugSettings{
+    DebugSettings{
+        render: is_enabled(Subsystem::Render),
+        materials: is_enabled(Subsystem::Materials),
+        resources: is_enabled(Subsystem::Resources),
+        shaders: is_enabled(Subsystem::Shaders),
+    }
+}
+
+pub(crate) fn is_enabled(subsystem: Subsystem) -> bool{
+    ENABLED_SUBSYSTEMS.load(Ordering::Relaxed) & subsystem as u8 != 0
+}
+
+/// Logs at debug level, but only when the given subsystem has debugging enabled
+macro_rules! debug_log {
+    ($subsystem:expr, $($arg:tt)+) => {
+        if $crate::debug::is_enabled($subsystem) {
+            log::debug!(target: $subsystem.target(), $($arg)+);
+        }
+    };
+}
+
+pub(crate) use debug_log;
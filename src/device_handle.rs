@@ -1,28 +1,64 @@
 use log::info;
 use crate::utils::handle::Handle;
 use crate::instance_handle::InstanceHandle;
+use crate::types::bindless;
+use crate::scene_batches;
 
 pub struct DeviceHandle{
     device: Handle<wgpu::Device>,
-    queue: Handle<wgpu::Queue>
+    queue: Handle<wgpu::Queue>,
+
+    max_anisotropy: u16
 }
 
 impl DeviceHandle{
     pub fn new(instance: &InstanceHandle) -> Self{
         let adapter = instance.get_adapter();
+
+        // Timestamp queries are used for GPU frame timings, but are optional
+        let mut required_features = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
+        let mut required_limits = wgpu::Limits::default();
+
+        // Sample counts besides 1 and 4 (e.g 2x and 8x MSAA) depend on the adapter's format features
+        required_features |= adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+
+        // As are bindless textures
+        if let Some((features, limits)) = bindless::device_requirements(&adapter){
+            info!("Bindless textures are supported");
+            required_features |= features;
+            required_limits = limits;
+        }
+
+        // And multi-draw indirect, which batches the draws of each pipeline
+        if adapter.features().contains(scene_batches::INDIRECT_FEATURES){
+            info!("Multi-draw indirect is supported");
+            required_features |= scene_batches::INDIRECT_FEATURES;
+            // Lets culled draws skip the empty slots
+            required_features |= adapter.features() & wgpu::Features::MULTI_DRAW_INDIRECT_COUNT;
+        }
+
         let (device, queue) = pollster::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor{
                 label: Some("Device"),
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default()
+                required_features,
+                required_limits
             },
             None
         )).unwrap();
         info!("Device and Queue created");
 
+        // Some downlevel backends (e.g WebGL) can't do anisotropic filtering at all
+        let max_anisotropy = if adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING){
+            16
+        } else {
+            1
+        };
+
         Self{
             device: Handle::new(device),
-            queue: Handle::new(queue)
+            queue: Handle::new(queue),
+
+            max_anisotropy
         }
     }
 
@@ -33,4 +69,8 @@ impl DeviceHandle{
     pub fn get_queue(&self) -> Handle<wgpu::Queue>{
         self.queue.clone()
     }
+
+    pub fn get_max_anisotropy(&self) -> u16{
+        self.max_anisotropy
+    }
 }
\ No newline at end of file
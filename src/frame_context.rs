@@ -0,0 +1,116 @@
+use std::ops::Range;
+use log::error;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceManager;
+use crate::stats::FrameStats;
+use crate::types::fullscreen_pass::FullscreenPass;
+
+/// A callback drawing custom geometry into the scene pass, see `Renderer::set_custom_draw`
+pub type CustomDrawFn = Box<dyn FnMut(&mut FrameContext)>;
+
+/// # Frame Context
+///
+/// Access to the scene's render pass while it's being recorded, for drawing geometry that isn't
+/// part of the model list (e.g procedural or debug geometry). Custom draws are recorded after the
+/// models, into the same colour and depth attachments.
+///
+/// Pipelines, materials and meshes are set by handle, so they have to be created up-front
+/// through the resource manager, the same as for models
+pub struct FrameContext<'a, 'p>{
+    resource_manager: &'a ResourceManager,
+    render_pass: &'p mut wgpu::RenderPass<'a>,
+    // The copy of the `objects` buffer written this frame, which materials have to bind
+    objects_version: usize,
+    stats: &'p mut FrameStats,
+}
+
+impl<'a, 'p> FrameContext<'a, 'p>{
+    pub(crate) fn new(resource_manager: &'a ResourceManager, render_pass: &'p mut wgpu::RenderPass<'a>, stats: &'p mut FrameStats) -> Self{
+        Self{
+            resource_manager,
+            render_pass,
+            objects_version: resource_manager.get_objects_buffer().get_version(),
+            stats,
+        }
+    }
+
+    /// Read access to the resources, e.g to look up a mesh's submeshes
+    pub fn get_resource_manager(&self) -> &ResourceManager{
+        self.resource_manager
+    }
+
+    /// # Set Pipeline
+    ///
+    /// Sets the pipeline the following draws use, as created by `ResourceManager::create_pipeline`
+    pub fn set_pipeline(&mut self, pipeline_handle: &ResourceHandle){
+        let pipeline = self.resource_manager.get_pipeline(pipeline_handle).unwrap_or_else(||{
+            error!("Pipeline not found: {:?}", pipeline_handle);
+            panic!("Pipeline not found: {:?}", pipeline_handle)
+        });
+
+        self.render_pass.set_pipeline(pipeline.get_render_pipeline());
+        self.stats.pipeline_switches += 1;
+    }
+
+    /// # Set Material
+    ///
+    /// Binds every bind group of a material. The material's shader has to match the current pipeline's
+    pub fn set_material(&mut self, material_handle: &ResourceHandle){
+        if self.resource_manager.get_material(material_handle).is_none(){
+            error!("Material not found: {:?}", material_handle);
+            panic!("Material not found: {:?}", material_handle);
+        }
+
+        let material = self.resource_manager.borrow_material(material_handle);
+        material.bind_material(self.render_pass, self.objects_version);
+        self.stats.bind_group_sets += material.get_bind_group_count() as u32;
+    }
+
+    /// # Draw Mesh
+    ///
+    /// Draws every submesh of a mesh over the given instances. Shaders reading the `objects`
+    /// buffer index it with the instance, so a single instance of a model's object index draws it
+    /// with the model's transform. The mesh has to be uploaded, e.g with `ResourceManager::ensure_uploaded`
+    pub fn draw_mesh(&mut self, mesh_handle: &ResourceHandle, instances: Range<u32>){
+        let (Some(mesh), Some(vertex_buffers), Some(index_buffers)) = (
+            self.resource_manager.get_mesh(mesh_handle),
+            self.resource_manager.get_mesh_vertex_buffers(mesh_handle),
+            self.resource_manager.get_mesh_index_buffers(mesh_handle),
+        ) else {
+            error!("Mesh not found or not uploaded: {:?}", mesh_handle);
+            panic!("Mesh not found or not uploaded: {:?}", mesh_handle)
+        };
+
+        for (idx, submesh) in mesh.get_sub_meshes().iter().enumerate(){
+            vertex_buffers[idx].bind_vertex_buffer(0, self.render_pass);
+            index_buffers[idx].bind_index_buffer(self.render_pass);
+            self.render_pass.draw_indexed(0..submesh.get_indices_count() as u32, 0, instances.clone());
+
+            self.stats.draw_calls += 1;
+            self.stats.instances += instances.len() as u32;
+            self.stats.triangles += submesh.get_indices_count() as u64 / 3 * instances.len() as u64;
+        }
+    }
+
+    /// # Draw Fullscreen
+    ///
+    /// Draws a fullscreen pass from `ResourceManager::create_fullscreen_pass`. Its triangle sits at the
+    /// far plane, so it only covers the pixels no model drew over, e.g for a procedural background
+    pub fn draw_fullscreen(&mut self, pass: &FullscreenPass){
+        self.set_pipeline(pass.get_pipeline());
+        self.set_material(pass.get_material());
+        self.draw(0..3, 0..1);
+    }
+
+    /// # Draw
+    ///
+    /// Draws without any vertex or index buffers bound, for shaders that generate their
+    /// geometry from the vertex and instance indices
+    pub fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>){
+        self.stats.draw_calls += 1;
+        self.stats.instances += instances.len() as u32;
+        self.stats.triangles += vertices.len() as u64 / 3 * instances.len() as u64;
+
+        self.render_pass.draw(vertices, instances);
+    }
+}
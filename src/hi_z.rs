@@ -0,0 +1,210 @@
+use crate::debug::{debug_log, Subsystem};
+use crate::utils::handle::Handle;
+
+const HI_Z_SHADER: &str = include_str!("../assets/shaders/hi_z.wgsl");
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Format of the Hi-Z pyramid
+pub(crate) const HI_Z_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+
+/// # Hi-Z Pyramid
+///
+/// A mip chain of the depth buffer, where each texel is the farthest depth of the area it covers.
+/// It's built from a frame's depth after the frame is drawn, and used by GPU culling in the
+/// next frame to reject models that were hidden behind others
+pub(crate) struct HiZPyramid{
+    texture: wgpu::Texture,
+    // The whole chain, as the culling pass reads it
+    view: wgpu::TextureView,
+    // One view per level, as the build passes read and write them
+    mip_views: Vec<wgpu::TextureView>,
+
+    width: u32,
+    height: u32,
+    // Whether the pyramid holds a drawn frame's depth. It doesn't after being created or resized
+    valid: bool,
+
+    copy_pipeline: wgpu::ComputePipeline,
+    downsample_pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+
+    _device: Handle<wgpu::Device>,
+}
+
+impl HiZPyramid{
+    pub(crate) fn new(device: Handle<wgpu::Device>, width: u32, height: u32) -> Self{
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Hi-Z Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture{
+                        sample_type: wgpu::TextureSampleType::Float{ filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture{
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: HI_Z_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2
+                    },
+                    count: None
+                },
+            ]
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Hi-Z Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor{
+            label: Some("Hi-Z Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(HI_Z_SHADER.into())
+        });
+
+        let create_pipeline = |entry_point: &str, label: &str| device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor{
+            label: Some(label),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point,
+        });
+
+        let copy_pipeline = create_pipeline("copy_depth", "Hi-Z Copy Pipeline");
+        let downsample_pipeline = create_pipeline("downsample", "Hi-Z Downsample Pipeline");
+
+        let (width, height) = (width.max(1), height.max(1));
+        let (texture, view, mip_views) = Self::create_texture(&device, width, height);
+
+        Self{
+            texture,
+            view,
+            mip_views,
+
+            width,
+            height,
+            valid: false,
+
+            copy_pipeline,
+            downsample_pipeline,
+            bind_group_layout,
+
+            _device: device,
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, width: u32, height: u32) -> (wgpu::Texture, wgpu::TextureView, Vec<wgpu::TextureView>){
+        let mip_level_count = 32 - width.max(height).leading_zeros();
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor{
+            label: Some("Hi-Z Pyramid"),
+            size: wgpu::Extent3d{
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HI_Z_FORMAT,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mip_views = (0..mip_level_count).map(|level| texture.create_view(&wgpu::TextureViewDescriptor{
+            label: Some("Hi-Z Pyramid Level"),
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })).collect();
+
+        (texture, view, mip_views)
+    }
+
+    /// Recreates the pyramid at the new size. It isn't valid again until it's next built
+    pub(crate) fn resize(&mut self, width: u32, height: u32){
+        if width == 0 || height == 0 || (width == self.width && height == self.height){
+            return;
+        }
+
+        let (texture, view, mip_views) = Self::create_texture(&self._device, width, height);
+        self.texture = texture;
+        self.view = view;
+        self.mip_views = mip_views;
+
+        self.width = width;
+        self.height = height;
+        self.valid = false;
+    }
+
+    /// # Build
+    ///
+    /// Records the passes copying the depth buffer into the first level and reducing it down the chain.
+    /// The depth buffer has to be the same size as the pyramid
+    pub(crate) fn build(&mut self, encoder: &mut wgpu::CommandEncoder, depth: &wgpu::TextureView){
+        // The first level is copied from the depth buffer, and each after is reduced from the one before
+        let bind_groups: Vec<wgpu::BindGroup> = self.mip_views.iter().enumerate().map(|(level, destination)|{
+            let source = if level == 0 { depth } else { &self.mip_views[level - 1] };
+            self._device.create_bind_group(&wgpu::BindGroupDescriptor{
+                label: Some("Hi-Z Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry{ binding: 0, resource: wgpu::BindingResource::TextureView(source) },
+                    wgpu::BindGroupEntry{ binding: 1, resource: wgpu::BindingResource::TextureView(destination) },
+                ]
+            })
+        }).collect();
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
+            label: Some("Hi-Z Pass"),
+            timestamp_writes: None,
+        });
+
+        for (level, bind_group) in bind_groups.iter().enumerate(){
+            let pipeline = if level == 0 { &self.copy_pipeline } else { &self.downsample_pipeline };
+            let (width, height) = self.get_level_size(level as u32);
+
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            compute_pass.dispatch_workgroups(width.div_ceil(WORKGROUP_SIZE), height.div_ceil(WORKGROUP_SIZE), 1);
+        }
+
+        if !self.valid{
+            debug_log!(Subsystem::Render, "Built {}x{} Hi-Z pyramid with {} levels", self.width, self.height, self.mip_views.len());
+        }
+        self.valid = true;
+    }
+
+    /// Marks the pyramid as out of date, e.g when a frame was drawn without building it
+    pub(crate) fn invalidate(&mut self){
+        self.valid = false;
+    }
+
+    pub(crate) fn is_valid(&self) -> bool{
+        self.valid
+    }
+
+    pub(crate) fn get_view(&self) -> &wgpu::TextureView{
+        &self.view
+    }
+
+    pub(crate) fn get_size(&self) -> (u32, u32){
+        (self.width, self.height)
+    }
+
+    pub(crate) fn get_mip_level_count(&self) -> u32{
+        self.texture.mip_level_count()
+    }
+
+    fn get_level_size(&self, level: u32) -> (u32, u32){
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+}
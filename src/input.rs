@@ -0,0 +1,93 @@
+use log::warn;
+use winit::event::{ElementState, Ime, KeyEvent, WindowEvent};
+
+/// # Text Input Event
+///
+/// Text typed into the window since the last frame, for implementing text fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextInputEvent{
+    /// Text typed directly, without an IME. Control characters (e.g backspace) aren't included,
+    /// they're left to key handling
+    Text(String),
+    /// Text being composed by the IME, which should be shown in place of the selection but not
+    /// inserted yet. `cursor` is the byte range of the IME's cursor within the text, if it has one
+    Preedit{ text: String, cursor: Option<(usize, usize)> },
+    /// Text the IME finished composing, to be inserted
+    Commit(String),
+    /// The IME was turned on, after `Renderer::set_ime_allowed`
+    ImeEnabled,
+    /// The IME was turned off, any preedit text should be cleared
+    ImeDisabled,
+}
+
+/// # Text Input
+///
+/// Collects text and IME input from the window's events, and gives access to the system clipboard
+pub(crate) struct TextInput{
+    events: Vec<TextInputEvent>,
+    // Created on first use, as it can fail (e.g no display server)
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl TextInput{
+    pub(crate) fn new() -> Self{
+        Self{
+            events: Vec::new(),
+            clipboard: None,
+        }
+    }
+
+    /// Records any text input in a window event
+    pub(crate) fn handle_event(&mut self, event: &WindowEvent){
+        match event{
+            WindowEvent::KeyboardInput{ event: KeyEvent{ text: Some(text), state: ElementState::Pressed, .. }, .. } => {
+                let text: String = text.chars().filter(|c| !c.is_control()).collect();
+                if !text.is_empty(){
+                    self.events.push(TextInputEvent::Text(text));
+                }
+            }
+            WindowEvent::Ime(ime) => {
+                self.events.push(match ime{
+                    Ime::Enabled => TextInputEvent::ImeEnabled,
+                    Ime::Preedit(text, cursor) => TextInputEvent::Preedit{ text: text.clone(), cursor: *cursor },
+                    Ime::Commit(text) => TextInputEvent::Commit(text.clone()),
+                    Ime::Disabled => TextInputEvent::ImeDisabled,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn get_events(&self) -> &[TextInputEvent]{
+        &self.events
+    }
+
+    /// Clears the events once the frame has seen them
+    pub(crate) fn clear(&mut self){
+        self.events.clear();
+    }
+
+    fn get_clipboard(&mut self) -> Option<&mut arboard::Clipboard>{
+        if self.clipboard.is_none(){
+            match arboard::Clipboard::new(){
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => warn!("Failed to open the clipboard: {}", e),
+            }
+        }
+
+        self.clipboard.as_mut()
+    }
+
+    pub(crate) fn get_clipboard_text(&mut self) -> Option<String>{
+        self.get_clipboard()?.get_text().ok()
+    }
+
+    pub(crate) fn set_clipboard_text(&mut self, text: &str) -> anyhow::Result<()>{
+        let Some(clipboard) = self.get_clipboard() else {
+            anyhow::bail!("The clipboard isn't available");
+        };
+        clipboard.set_text(text)?;
+
+        Ok(())
+    }
+}
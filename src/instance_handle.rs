@@ -8,13 +8,13 @@ pub struct InstanceHandle{
 }
 
 impl InstanceHandle{
-    pub fn new() -> Self{
+    pub fn new(flags: wgpu::InstanceFlags) -> Self{
         let backends = wgpu::util::backend_bits_from_env().unwrap_or_default();
         let dx12_shader_compiler = wgpu::util::dx12_shader_compiler_from_env().unwrap_or_default();
         let gles_minor_version = wgpu::util::gles_minor_version_from_env().unwrap_or_default();
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor{
             backends,
-            flags: wgpu::InstanceFlags::from_build_config().with_env(),
+            flags,
             dx12_shader_compiler,
             gles_minor_version
         });
@@ -4,13 +4,78 @@ mod instance_handle;
 mod surface_wrapper;
 mod device_handle;
 mod renderer;
+mod threaded_framework;
 mod pipeline;
 mod utils;
 mod managers;
 mod uniform;
+mod logging;
+mod debug;
+mod stats;
+mod state_dump;
+mod overlay;
+mod settings;
+mod window_settings;
+mod input;
+mod scene_batches;
+mod static_bundles;
+mod frame_context;
+mod camera_passes;
+mod scene_frame;
+mod culling;
+mod skinning;
+mod hi_z;
+mod point_shadows;
+mod screen_attachments;
+mod post;
+pub mod testing;
+pub mod math;
 
 pub use renderer::Renderer;
 pub use renderer::RenderFramework;
+pub use frame_context::{CustomDrawFn, FrameContext};
+pub use threaded_framework::{snapshot_buffer, SnapshotReader, SnapshotWriter, ThreadedRenderFramework};
+pub use logging::{get_recent_logs, init_default_logging, LogCapture, LogEntry, LOG_CONSOLE_CAPACITY};
+pub use window_settings::{CursorMode, WindowSettings};
+pub use input::TextInputEvent;
+pub use debug::{DebugSettings, GpuValidation};
+pub use stats::{CpuTimings, FrameStats, MemoryUsage};
+pub use state_dump::{AdapterState, MaterialState, PipelineState, ResourceCounts, ResourceState, StateDump, SurfaceState};
+pub use settings::{BloomSettings, ChromaticAberrationSettings, ColorGradingSettings, DepthFormat, FilmGrainSettings, OutputEncoding, RenderSettings, ShadowSettings, VignetteSettings};
+pub use post::color_grading::ColorGradingLut;
 pub use utils::buffer::AsBytes;
+pub use utils::readback::PendingReadback;
 pub use managers::resource_handle::ResourceHandle;
-pub use types::transform::Transform;
\ No newline at end of file
+pub use managers::resource_queue::ResourceQueue;
+pub use managers::resource_manager::ResourceType;
+pub use managers::resource_event::{ResourceEvent, ResourceEventKind, ResourceObserverFn};
+pub use managers::resource_info::{MaterialInfo, MeshInfo, ModelInfo, TextureInfo};
+pub use types::transform::Transform;
+pub use types::tween::Easing;
+pub use types::material::MaterialDiagnostic;
+pub use types::mesh::{Mesh, SubMesh};
+pub use types::model::{Model, DEFAULT_RENDER_LAYERS};
+pub use types::object_data::{ObjectData, OBJECTS_BINDING, OBJECT_RECEIVES_SHADOWS};
+pub use types::scene_uniform::{SceneUniform, SCENE_BINDING, SCENE_UNIFORM_WGSL};
+pub use types::globals_uniform::{GlobalsUniform, GLOBALS_BINDING, GLOBALS_UNIFORM_WGSL};
+pub use types::bounds::BoundingSphere;
+pub use types::frustum::Frustum;
+pub use types::camera::{Camera, CameraTarget, ALL_RENDER_LAYERS};
+pub use types::planar_reflection::{PlanarReflection, PLANAR_REFLECTION_WGSL};
+pub use types::bindless::{BINDLESS_SHADER, BINDLESS_TEXTURES_BINDING, MAX_BINDLESS_TEXTURES};
+pub use types::property_block::PropertyBlock;
+pub use types::dynamic_mesh::DynamicMesh;
+pub use types::point_cloud::{Point, PointCloudUniform, PointStyle, PointVertex, POINT_CLOUD_SHADER};
+pub use types::light::{Light, LightType, LightUniform, LightsUniform, DEFAULT_POINT_SHADOW_FAR, MAX_LIGHTS, MAX_POINT_SHADOWS, POINT_SHADOWS_BINDING};
+pub use types::pbr_material::{LightmapUniform, PbrMaterialUniform, TextureTransform, PBR_LIGHTMAP_SLOT, PBR_SHADER, PBR_TEXTURE_SLOTS};
+pub use types::builtin_shaders::{BasicMaterialUniform, SkinUniform, BUILTIN_SHADER_NAMES, ERROR_SHADER, LIT_SHADER, MAX_JOINTS, SKINNED_SHADER, SKIN_BINDING, UNLIT_SHADER};
+pub use types::vertex::{uv_set_location, ColoredVertex, MultiUvVertex, QuantizedVertex, SkinnedVertex, Vertex, VertexLayoutBuilder, COLOR_LOCATION, JOINTS_LOCATION, MAX_UV_SETS, WEIGHTS_LOCATION};
+pub use types::quantization::{decode_octahedral, encode_octahedral, QuantizationUniform, QUANTIZATION_BINDING, QUANTIZED_VERTEX_WGSL};
+pub use types::texture::ColorSpace;
+pub use types::texture_atlas::{AtlasRect, TextureAtlas, TextureAtlasBuilder};
+pub use types::fullscreen_pass::{FullscreenPass, FULLSCREEN_TRIANGLE_WGSL};
+
+// Re-exported so `impl_as_bytes!` works without the user depending on bytemuck directly. Deriving `Pod`
+// through it needs `#[bytemuck(crate = "minirenderer::bytemuck")]` on the type, see `impl_as_bytes!`
+#[doc(hidden)]
+pub use bytemuck;
\ No newline at end of file
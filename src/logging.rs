@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// How many of the most recent log records are kept for the log console
+pub const LOG_CONSOLE_CAPACITY: usize = 64;
+
+static RECENT_LOGS: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+/// # Log Entry
+///
+/// A log record captured for the on-screen log console
+#[derive(Debug, Clone)]
+pub struct LogEntry{
+    pub level: log::Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// # Log Capture
+///
+/// Wraps a logger, keeping the last `LOG_CONSOLE_CAPACITY` records it accepts so the log
+/// console overlay can show them. Applications installing their own logger can wrap it
+/// in this to keep the console working
+pub struct LogCapture<L: log::Log>{
+    inner: L,
+}
+
+impl<L: log::Log> LogCapture<L>{
+    pub fn new(inner: L) -> Self{
+        Self{ inner }
+    }
+}
+
+impl<L: log::Log> log::Log for LogCapture<L>{
+    fn enabled(&self, metadata: &log::Metadata) -> bool{
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record){
+        if !self.inner.enabled(record.metadata()){
+            return;
+        }
+
+        if let Ok(mut recent_logs) = RECENT_LOGS.lock(){
+            if recent_logs.len() == LOG_CONSOLE_CAPACITY{
+                recent_logs.pop_front();
+            }
+            recent_logs.push_back(LogEntry{
+                level: record.level(),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            });
+        }
+
+        self.inner.log(record);
+    }
+
+    fn flush(&self){
+        self.inner.flush();
+    }
+}
+
+/// # Get Recent Logs
+///
+/// Returns the most recent log records captured by a `LogCapture`, oldest first
+pub fn get_recent_logs() -> Vec<LogEntry>{
+    RECENT_LOGS.lock().map(|recent_logs| recent_logs.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// # Init Default Logging
+///
+/// Sets up `env_logger` with the renderer's default filters. The renderer only ever
+/// logs through the `log` facade, so this is entirely optional - applications that
+/// already install their own logger should skip it.
+///
+/// Records are also captured for the log console overlay.
+///
+/// Levels can still be overridden with `RUST_LOG`. Calling this when a logger is
+/// already set is harmless.
+pub fn init_default_logging(){
+    let logger = env_logger::builder()
+        .filter_level(log::LevelFilter::Info)
+        // We keep wgpu at Error level, as it's very noisy.
+        .filter_module("wgpu_core", log::LevelFilter::Info)
+        .filter_module("wgpu_hal", log::LevelFilter::Error)
+        .filter_module("naga", log::LevelFilter::Error)
+        .parse_default_env()
+        .build();
+
+    let max_level = logger.filter();
+    if log::set_boxed_logger(Box::new(LogCapture::new(logger))).is_ok(){
+        log::set_max_level(max_level);
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use log::warn;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceType;
+use crate::settings::ShadowSettings;
+use crate::types::light::{self, Light, LightsUniform, MAX_LIGHTS};
+
+/// # Light Manager
+///
+/// Owns the lights in the scene, and tracks when they've changed so the
+/// lights uniform is only rewritten when needed
+pub(crate) struct LightManager{
+    lights: HashMap<ResourceHandle, Light>,
+    // Keeps the upload order stable, so lights past MAX_LIGHTS are always the newest
+    order: Vec<ResourceHandle>,
+    shadow_settings: ShadowSettings,
+    dirty: bool,
+}
+
+impl LightManager{
+    pub fn new() -> Self{
+        Self{
+            lights: HashMap::new(),
+            order: Vec::new(),
+            shadow_settings: ShadowSettings::default(),
+            dirty: true,
+        }
+    }
+
+    pub fn add_light(&mut self, light: Light) -> ResourceHandle{
+        let handle = ResourceHandle::new(ResourceType::Light);
+        self.lights.insert(handle.clone(), light);
+        self.order.push(handle.clone());
+        self.dirty = true;
+
+        if self.order.len() == MAX_LIGHTS + 1{
+            warn!("More than {} lights have been added, the extra lights won't be shaded", MAX_LIGHTS);
+        }
+
+        handle
+    }
+
+    pub fn remove_light(&mut self, handle: &ResourceHandle) -> Option<Light>{
+        let light = self.lights.remove(handle)?;
+        self.order.retain(|other| other != handle);
+        self.dirty = true;
+        Some(light)
+    }
+
+    pub fn get_light(&self, handle: &ResourceHandle) -> Option<&Light>{
+        self.lights.get(handle)
+    }
+
+    /// Mutable access marks the lights as changed
+    pub fn get_light_mut(&mut self, handle: &ResourceHandle) -> Option<&mut Light>{
+        let light = self.lights.get_mut(handle)?;
+        self.dirty = true;
+        Some(light)
+    }
+
+    pub fn get_light_handles(&self) -> Vec<ResourceHandle>{
+        self.order.clone()
+    }
+
+    pub fn set_shadow_settings(&mut self, shadow_settings: ShadowSettings){
+        if shadow_settings != self.shadow_settings{
+            self.shadow_settings = shadow_settings;
+            self.dirty = true;
+        }
+    }
+
+    /// The position and shadow far plane of every light with a point shadow, in shadow map order
+    pub fn get_point_shadow_casters(&self) -> Vec<(glam::Vec3, f32)>{
+        light::point_shadow_casters(self.order.iter().map(|handle| &self.lights[handle]))
+            .map(|light| (light.get_position(), light.get_shadow_far()))
+            .collect()
+    }
+
+    /// Returns the uniform data if the lights changed since the last call
+    pub fn take_changes(&mut self) -> Option<LightsUniform>{
+        if !self.dirty{
+            return None;
+        }
+        self.dirty = false;
+
+        Some(LightsUniform::new(self.order.iter().map(|handle| &self.lights[handle])).with_shadow_settings(&self.shadow_settings))
+    }
+}
@@ -1,4 +1,12 @@
 pub mod resource_manager;
 pub mod resource_handle;
+mod resource_store;
+pub mod resource_queue;
+pub mod resource_info;
+pub mod resource_event;
 mod pipeline_manager;
-mod shader_manager;
\ No newline at end of file
+mod shader_manager;
+mod texture_streamer;
+mod texture_budget;
+pub(crate) mod sampler_cache;
+mod light_manager;
\ No newline at end of file
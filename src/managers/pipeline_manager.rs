@@ -7,24 +7,51 @@ use crate::types::mesh::MeshLayout;
 use crate::types::shader::Shader;
 use super::resource_handle::ResourceHandle;
 use super::resource_manager::{ResourceManager, ResourceType};
+use super::shader_manager::ShaderManager;
+
+/// Formats and sample count of the colour and depth targets pipelines draw into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetFormats{
+    pub color: wgpu::TextureFormat,
+    pub depth: wgpu::TextureFormat,
+    pub samples: u32,
+}
+
+// What a pipeline was created from, so it can be recreated when the target formats change
+struct PipelineSource{
+    mesh_layout: MeshLayout,
+    material_bind_groups: Vec<Handle<wgpu::BindGroupLayout>>,
+    shader_handle: ResourceHandle,
+    double_sided: bool,
+}
 
 pub struct PipelineManager{
-    pipelines: HashMap<ResourceHandle, Pipeline>
+    pipelines: HashMap<ResourceHandle, Pipeline>,
+    sources: HashMap<ResourceHandle, PipelineSource>,
 }
 
 impl PipelineManager{
     pub fn new() -> Self{
         Self{
-            pipelines: HashMap::new()
+            pipelines: HashMap::new(),
+            sources: HashMap::new(),
         }
     }
 
-    pub fn create_or_get_pipeline(&mut self, device: &wgpu::Device, mesh_layout: &MeshLayout,
-                                  material_bind_groups: Vec<Handle<wgpu::BindGroupLayout>>,
-                                  shader: &Shader,
-                                  shader_handle: ResourceHandle) -> ResourceHandle {
+    fn build_settings<'a>(mesh_layout: &MeshLayout, material_bind_groups: &'a [Handle<wgpu::BindGroupLayout>],
+                          shader: &'a Shader, shader_handle: &ResourceHandle, formats: TargetFormats,
+                          double_sided: bool) -> PipelineBuildSettings<'a> {
         let mut config = PipelineBuildSettings::new()
-            .use_depth(false);
+            .use_depth(true)
+            .set_depth_format(formats.depth)
+            .set_color_format(formats.color)
+            .set_sample_count(formats.samples)
+            .set_topology(mesh_layout.get_topology());
+
+        // Double sided models draw their back faces too
+        if double_sided{
+            config = config.set_cull_mode(None);
+        }
 
         // For each vertex buffer layout in the mesh layout, add it to the pipeline config
         for vertex_buffer_layout in mesh_layout.get_vertex_buffer_layouts().iter(){
@@ -37,10 +64,19 @@ impl PipelineManager{
         }
 
         // Add the shader to the pipeline config
-        config = config.set_shader(shader);
+        config = config.set_shader(shader, shader_handle.clone());
 
         config.calculate_hash();
+        config
+    }
 
+    pub fn create_or_get_pipeline(&mut self, device: &wgpu::Device, mesh_layout: &MeshLayout,
+                                  shader: &Shader,
+                                  shader_handle: ResourceHandle,
+                                  formats: TargetFormats,
+                                  double_sided: bool) -> ResourceHandle {
+        let material_bind_groups = shader.get_bind_group_layouts();
+        let config = Self::build_settings(mesh_layout, &material_bind_groups, shader, &shader_handle, formats, double_sided);
         let config_hash = config.get_uuid();
 
         for (handle, pipeline) in self.pipelines.iter() {
@@ -50,9 +86,27 @@ impl PipelineManager{
         }
 
         let handle = self.create_pipeline(device, config, shader_handle.clone());
+        self.sources.insert(handle.clone(), PipelineSource{
+            mesh_layout: mesh_layout.clone(),
+            material_bind_groups,
+            shader_handle,
+            double_sided,
+        });
         handle
     }
 
+    /// # Rebuild Pipelines
+    ///
+    /// Recreates every pipeline for new target formats, keeping their handles
+    pub fn rebuild_pipelines(&mut self, device: &wgpu::Device, formats: TargetFormats, shader_manager: &ShaderManager) {
+        for (handle, source) in self.sources.iter(){
+            let Some(shader) = shader_manager.get_shader(&source.shader_handle) else { continue };
+
+            let config = Self::build_settings(&source.mesh_layout, &source.material_bind_groups, shader, &source.shader_handle, formats, source.double_sided);
+            self.pipelines.insert(handle.clone(), Pipeline::new(device, config, source.shader_handle.clone()));
+        }
+    }
+
     pub fn create_pipeline(&mut self, device: &wgpu::Device, config: PipelineBuildSettings,
                             shader_handle: ResourceHandle) -> ResourceHandle {
         let handle = ResourceHandle::new(ResourceType::Pipeline);
@@ -68,6 +122,11 @@ impl PipelineManager{
     pub fn get_all_pipelines(&self) -> Vec<&Pipeline>{
         self.pipelines.values().collect()
     }
+
+    /// The mesh layout a pipeline was built for, if it was built through `create_or_get_pipeline`
+    pub(crate) fn get_mesh_layout(&self, handle: &ResourceHandle) -> Option<&MeshLayout>{
+        self.sources.get(handle).map(|source| &source.mesh_layout)
+    }
 }
 
 impl PipelineManager {
@@ -0,0 +1,34 @@
+use super::resource_handle::ResourceHandle;
+use super::resource_manager::{ResourceManager, ResourceType};
+
+/// What happened to a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceEventKind{
+    /// Made at runtime, e.g by `create_model` or `add_mesh`
+    Created,
+    /// Finished loading from a file, or from another thread through the resource queue
+    Loaded,
+    /// Replaced in-place, e.g by `update_mesh` or a streamed texture getting more mips
+    Reloaded,
+    Removed,
+}
+
+/// # Resource Event
+///
+/// Sent to resource observers at the start of the frame after it happened
+#[derive(Debug, Clone)]
+pub struct ResourceEvent{
+    pub kind: ResourceEventKind,
+    pub resource_type: ResourceType,
+    pub handle: ResourceHandle,
+}
+
+/// Called with each event an observer was registered for. It can read from the resource manager,
+/// e.g to build a physics collider from a mesh once it's loaded
+pub type ResourceObserverFn = Box<dyn FnMut(&ResourceEvent, &ResourceManager)>;
+
+pub(crate) struct ResourceObserver{
+    pub id: usize,
+    pub resource_type: Option<ResourceType>,
+    pub callback: ResourceObserverFn,
+}
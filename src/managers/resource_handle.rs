@@ -40,6 +40,10 @@ impl ResourceHandle{
     }
 }
 
+// The reference count is atomic, so handles can be shared with other threads (e.g by `ResourceQueue`)
+unsafe impl Send for ResourceHandle {}
+unsafe impl Sync for ResourceHandle {}
+
 impl Clone for ResourceHandle{
     fn clone(&self) -> Self{
         unsafe{
@@ -0,0 +1,58 @@
+use super::resource_handle::ResourceHandle;
+
+/// # Model Info
+///
+/// A read-only summary of a loaded model, returned by `ResourceManager::iter_models`
+#[derive(Debug, Clone)]
+pub struct ModelInfo{
+    pub handle: ResourceHandle,
+    pub name: Option<String>,
+    pub mesh: ResourceHandle,
+    pub material: ResourceHandle,
+    /// The model's slot in the `objects` buffer
+    pub object_index: u32,
+    pub is_static: bool,
+}
+
+/// # Material Info
+///
+/// A read-only summary of a loaded material, returned by `ResourceManager::iter_materials`
+#[derive(Debug, Clone)]
+pub struct MaterialInfo{
+    pub handle: ResourceHandle,
+    pub name: Option<String>,
+    pub shader: ResourceHandle,
+    /// Binding names of the assigned textures, sorted
+    pub textures: Vec<String>,
+    /// Binding names of the assigned uniforms, sorted
+    pub uniforms: Vec<String>,
+    pub is_instance: bool,
+}
+
+/// # Mesh Info
+///
+/// A read-only summary of a loaded mesh, returned by `ResourceManager::iter_meshes`
+#[derive(Debug, Clone)]
+pub struct MeshInfo{
+    pub handle: ResourceHandle,
+    pub name: Option<String>,
+    pub sub_mesh_count: usize,
+    pub triangle_count: usize,
+    /// Size of the vertex and index buffers, or 0 if they haven't been uploaded yet
+    pub gpu_bytes: u64,
+}
+
+/// # Texture Info
+///
+/// A read-only summary of a loaded texture, returned by `ResourceManager::iter_textures`
+#[derive(Debug, Clone)]
+pub struct TextureInfo{
+    pub handle: ResourceHandle,
+    pub name: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub mip_level_count: u32,
+    pub format: wgpu::TextureFormat,
+    /// Including every mip level
+    pub gpu_bytes: u64,
+}
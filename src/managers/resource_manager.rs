@@ -1,22 +1,59 @@
-use std::collections::HashMap;
-use std::ops::Deref;
-use log::{error, info};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::Receiver;
+use log::{error, info, warn};
 use crate::utils::handle::Handle;
 use crate::managers::shader_manager::ShaderManager;
 use crate::pipeline::Pipeline;
 use crate::Transform;
-use crate::types::material::Material;
+use crate::types::material::{Material, MaterialDiagnostic};
 use crate::types::model::Model;
-use crate::types::mesh::Mesh;
+use crate::types::bindless::{self, BindlessTextures, BINDLESS_TEXTURES_BINDING, MAX_BINDLESS_TEXTURES};
+use crate::types::object_data::{ObjectData, OBJECTS_BINDING};
+use crate::utils::shader_reflect::BindingType;
+use crate::types::mesh::{Mesh, MeshLayout, SubMesh};
+use crate::types::bounds::BoundingSphere;
+use crate::types::dynamic_mesh::DynamicMesh;
+use crate::types::point_cloud::{self, PointStyle};
+use crate::types::light::{Light, LightsUniform, POINT_SHADOWS_BINDING};
+use crate::types::scene_uniform::SceneUniform;
+use crate::types::globals_uniform::GlobalsUniform;
+use crate::types::camera::{Camera, CameraTarget};
+use crate::types::planar_reflection::{self, PlanarReflection};
+use crate::types::pbr_material::{self, GltfPbrMaterial, LightmapUniform, PBR_LIGHTMAP_SLOT, PBR_TEXTURE_SLOTS};
+use crate::types::builtin_shaders;
+use crate::types::vertex::{uv_set_location, Vertex, MAX_UV_SETS};
 use crate::types::shader::Shader;
-use crate::types::texture::Texture;
+use crate::types::texture::{ColorSpace, Texture};
+use crate::managers::texture_streamer::{self, TextureStreamer};
+use crate::managers::texture_budget::TextureBudget;
+use crate::managers::sampler_cache::SamplerCache;
+use crate::managers::light_manager::LightManager;
+use crate::types::texture_atlas::{TextureAtlas, TextureAtlasBuilder};
+use crate::types::fullscreen_pass::{FullscreenPass, FULLSCREEN_TRIANGLE_WGSL};
 use crate::types::transform::TransformUniform;
+use crate::types::tween::{Easing, TransformTween};
+use crate::stats::MemoryUsage;
+use crate::culling::GpuCulling;
+use crate::skinning::{GpuSkin, GpuSkinning};
+use crate::point_shadows::PointShadows;
+use crate::settings::{RenderSettings, ShadowSettings};
+use crate::scene_batches;
+use crate::debug::{debug_log, Subsystem};
+use crate::uniform::storage_buffer::{StorageBuffer, FRAMES_IN_FLIGHT};
 use crate::uniform::uniform_buffer::UniformBuffer;
 use crate::utils::buffer::*;
 use crate::utils::mut_handle::MutHandle;
-
-use super::pipeline_manager::PipelineManager;
+use crate::utils::readback::{self, PendingReadback};
+use crate::managers::resource_store::ResourceStore;
+use crate::static_bundles::StaticBundles;
+use crate::managers::resource_queue::{ResourceCommand, ResourceQueue};
+
+use super::pipeline_manager::{PipelineManager, TargetFormats};
+use crate::pipeline::DEFAULT_COLOR_FORMAT;
+use crate::screen_attachments::{self, DEPTH_FORMAT};
 use super::resource_handle::ResourceHandle;
+use super::resource_event::{ResourceEvent, ResourceEventKind, ResourceObserver, ResourceObserverFn};
+use super::resource_info::{MaterialInfo, MeshInfo, ModelInfo, TextureInfo};
 
 /// # Resource Type
 ///
@@ -29,137 +66,2057 @@ pub enum ResourceType{
     Material,
     Pipeline,
     Shader,
-    Model // A model is a combination of a mesh and a material, used for rendering
+    Model, // A model is a combination of a mesh and a material, used for rendering
+    Light,
+    Camera
 }
 
-/// # Resource Manager
-///
-/// Manages resources such as meshes, textures, materials, and models
-pub struct ResourceManager{
-    meshes: HashMap<ResourceHandle, Mesh>,
-    mesh_vertex_buffers: HashMap<ResourceHandle, Vec<Buffer>>,
-    mesh_index_buffers: HashMap<ResourceHandle, Vec<Buffer>>,
-    mesh_instance_buffers: HashMap<ResourceHandle, Option<Vec<Buffer>>>, // Optional instance buffers
+/// # Resource Manager
+///
+/// Manages resources such as meshes, textures, materials, and models
+pub struct ResourceManager{
+    meshes: ResourceStore<Mesh>,
+    mesh_vertex_buffers: HashMap<ResourceHandle, Vec<Buffer>>,
+    mesh_index_buffers: HashMap<ResourceHandle, Vec<Buffer>>,
+    mesh_instance_buffers: HashMap<ResourceHandle, Option<Vec<Buffer>>>, // Optional instance buffers
+    dynamic_meshes: HashMap<ResourceHandle, DynamicMesh>, // Replace the vertex/index buffers of these meshes
+    mesh_bounds: HashMap<ResourceHandle, Vec<Option<BoundingSphere>>>, // Per submesh, kept in sync with the uploaded data
+
+    textures: ResourceStore<Texture>,
+    materials: ResourceStore<Material>,
+    models: ResourceStore<Model>,
+    uniforms: HashMap<ResourceHandle, Handle<UniformBuffer>>,
+
+    // Every model's `ObjectData`, indexed by the model's object index
+    objects: StorageBuffer,
+    object_count: u32,
+    // What was last written to each slot of `objects`
+    object_data: Vec<ObjectData>,
+    // Models created or changed since the last frame, the only ones whose `ObjectData` is rebuilt
+    dirty_models: HashSet<ResourceHandle>,
+    // Slots of removed models, reused before the buffer grows
+    free_object_indices: Vec<u32>,
+    // Materials instances have been made from, kept up-to-date by `update_template_bind_groups`
+    material_templates: HashSet<ResourceHandle>,
+
+    // The global texture array, when the device supports binding arrays
+    bindless: Option<BindlessTextures>,
+
+    // Frustum culling of indirect draws, when the device supports multi-draw indirect
+    gpu_culling: Option<GpuCulling>,
+    // Skins the meshes in `gpu_skins` (keyed by the mesh they're skinned into) each frame
+    gpu_skinning: Option<GpuSkinning>,
+    gpu_skins: HashMap<ResourceHandle, GpuSkin>,
+    cull_view_projection: Option<glam::Mat4>,
+
+    shader_manager: ShaderManager,
+    // Built-in shaders by name, compiled the first time they're asked for
+    builtin_shaders: HashMap<String, ResourceHandle>,
+    pipeline_manager: PipelineManager,
+    // Formats pipelines are built for, matching the attachments the scene is drawn into
+    target_formats: TargetFormats,
+
+    texture_streamer: TextureStreamer,
+    texture_budget: TextureBudget,
+
+    // Cached draws of the static models
+    static_bundles: StaticBundles,
+    // Mesh and material pairs the double sided pipeline variant has been made for
+    double_sided_pipelines: HashSet<(ResourceHandle, ResourceHandle)>,
+
+    // Resources created on other threads, waiting to be added at the start of the next frame
+    resource_queue: ResourceQueue,
+    queued_resources: Receiver<ResourceCommand>,
+    // Textures from `load_texture_async` still showing their placeholder
+    pending_textures: HashSet<ResourceHandle>,
+
+    // Model transforms being animated, advanced by `update_tweens` each frame
+    tweens: HashMap<ResourceHandle, TransformTween>,
+
+    // Names shown by the `iter_*` queries, e.g the path a mesh or texture was loaded from
+    resource_names: HashMap<ResourceHandle, String>,
+
+    // Observers of resource events, and the events waiting to be sent to them at the start of the next frame
+    resource_observers: Vec<ResourceObserver>,
+    next_observer_id: usize,
+    resource_events: Vec<ResourceEvent>,
+
+    light_manager: LightManager,
+    // Uniform holding every light, kept up to date by `update_lights`
+    lights_uniform: ResourceHandle,
+    // Shadows of the point lights casting them, and the texture they're drawn into
+    point_shadows: PointShadows,
+    point_shadow_texture: ResourceHandle,
+
+    // Bound in place of textures a material doesn't provide, and of textures that don't exist
+    white_texture: ResourceHandle,
+    missing_texture: ResourceHandle,
+
+    // The camera, written into the `scene` uniform with the frame time by `update_scene`
+    camera_view: glam::Mat4,
+    camera_projection: glam::Mat4,
+    scene_uniform: ResourceHandle,
+    // Time written into the scene uniform this frame
+    scene_time: f32,
+    // Timing and resolution, written by `update_globals`, and the frames it's been written for
+    globals_uniform: ResourceHandle,
+    globals_frame: u32,
+
+    // Cameras drawn on top of the default one, see `Camera`
+    cameras: HashMap<ResourceHandle, Camera>,
+    // Depth attachments of the textures cameras can draw into
+    render_texture_depths: HashMap<ResourceHandle, Texture>,
+    // Multisampled colour attachments they're drawn into and resolved from, when multisampling is on
+    render_texture_msaa: HashMap<ResourceHandle, Texture>,
+    // Camera - the plane it mirrors the default camera across, updated by `update_scene`
+    reflection_planes: HashMap<ResourceHandle, glam::Vec4>,
+
+    // Anisotropic filtering level applied to newly loaded textures, and the device's limit
+    default_anisotropy: u16,
+    max_anisotropy: u16,
+    // Shared by every texture, so equal samplers are only created once
+    samplers: Handle<SamplerCache>,
+
+    _device: Handle<wgpu::Device>,
+    _queue: Handle<wgpu::Queue>
+}
+
+impl ResourceManager{
+    pub(crate) fn new(device: Handle<wgpu::Device>, queue: Handle<wgpu::Queue>) -> Self{
+        let lights_uniform = ResourceHandle::new(ResourceType::Material);
+        let (resource_queue, queued_resources) = ResourceQueue::new();
+        let mut uniforms = HashMap::new();
+        uniforms.insert(lights_uniform.clone(), Handle::new(UniformBuffer::new(device.clone(), LightsUniform::new(std::iter::empty()), "Lights Uniform")));
+        let scene_uniform = ResourceHandle::new(ResourceType::Material);
+        uniforms.insert(scene_uniform.clone(), Handle::new(UniformBuffer::new(device.clone(), SceneUniform::default(), "Scene Uniform")));
+        let globals_uniform = ResourceHandle::new(ResourceType::Material);
+        uniforms.insert(globals_uniform.clone(), Handle::new(UniformBuffer::new(device.clone(), GlobalsUniform::default(), "Globals Uniform")));
+
+        let samplers = Handle::new(SamplerCache::new(device.clone()));
+        let mut textures = ResourceStore::new();
+        let mut point_shadows = PointShadows::new(device.clone(), RenderSettings::default().shadow_resolution);
+        let point_shadow_texture = ResourceHandle::new(ResourceType::Texture);
+        textures.insert(point_shadow_texture.clone(), point_shadows.create_placeholder(&samplers));
+        let white_texture = ResourceHandle::new(ResourceType::Texture);
+        textures.insert(white_texture.clone(), Texture::create_white(&device, &queue, &samplers));
+        let missing_texture = ResourceHandle::new(ResourceType::Texture);
+        textures.insert(missing_texture.clone(), Texture::create_missing(&device, &queue, &samplers));
+
+        // Named so they're recognisable in `iter_textures` and state dumps
+        let resource_names = HashMap::from([
+            (lights_uniform.clone(), "lights".to_string()),
+            (scene_uniform.clone(), "scene".to_string()),
+            (globals_uniform.clone(), "globals".to_string()),
+            (point_shadow_texture.clone(), "point shadows".to_string()),
+            (white_texture.clone(), "white".to_string()),
+            (missing_texture.clone(), "missing".to_string()),
+        ]);
+
+        Self{
+            meshes: ResourceStore::new(),
+            mesh_vertex_buffers: HashMap::new(),
+            mesh_index_buffers: HashMap::new(),
+            mesh_instance_buffers: HashMap::new(),
+            dynamic_meshes: HashMap::new(),
+            mesh_bounds: HashMap::new(),
+
+            textures,
+            materials: ResourceStore::new(),
+            models: ResourceStore::new(),
+            uniforms,
+
+            objects: StorageBuffer::new(device.clone(), 64 * std::mem::size_of::<ObjectData>(), "Objects Storage Buffer", FRAMES_IN_FLIGHT),
+            object_count: 0,
+            object_data: Vec::new(),
+            dirty_models: HashSet::new(),
+            material_templates: HashSet::new(),
+            free_object_indices: Vec::new(),
+
+            bindless: device.features().contains(bindless::BINDLESS_FEATURES)
+                .then(|| BindlessTextures::new(&device, &queue, &samplers)),
+
+            gpu_culling: device.features().contains(scene_batches::INDIRECT_FEATURES)
+                .then(|| GpuCulling::new(device.clone())),
+            cull_view_projection: None,
+
+            gpu_skinning: GpuSkinning::is_supported(&device)
+                .then(|| GpuSkinning::new(device.clone())),
+            gpu_skins: HashMap::new(),
+
+            shader_manager: ShaderManager::new(device.clone()),
+            builtin_shaders: HashMap::new(),
+            pipeline_manager: PipelineManager::new(),
+            target_formats: TargetFormats{
+                color: DEFAULT_COLOR_FORMAT,
+                depth: DEPTH_FORMAT,
+                samples: 1,
+            },
+
+            texture_streamer: TextureStreamer::new(),
+            texture_budget: TextureBudget::new(),
+
+            static_bundles: StaticBundles::new(),
+            double_sided_pipelines: HashSet::new(),
+
+            resource_queue,
+            queued_resources,
+            pending_textures: HashSet::new(),
+
+            tweens: HashMap::new(),
+
+            resource_names,
+
+            resource_observers: Vec::new(),
+            next_observer_id: 0,
+            resource_events: Vec::new(),
+
+            light_manager: LightManager::new(),
+            lights_uniform,
+            point_shadows,
+            point_shadow_texture,
+
+            white_texture,
+            missing_texture,
+
+            camera_view: glam::Mat4::IDENTITY,
+            camera_projection: glam::Mat4::IDENTITY,
+            scene_uniform,
+            scene_time: 0.0,
+            globals_uniform,
+            globals_frame: 0,
+
+            cameras: HashMap::new(),
+            render_texture_depths: HashMap::new(),
+            render_texture_msaa: HashMap::new(),
+            reflection_planes: HashMap::new(),
+
+            default_anisotropy: 1,
+            max_anisotropy: 16,
+            samplers,
+            
+            _device: device,
+            _queue: queue
+        }
+    }
+
+    // Adds the resources other threads created through the resource queue since the last frame
+    pub(crate) fn process_queued_resources(&mut self){
+        while let Ok(command) = self.queued_resources.try_recv(){
+            match command{
+                ResourceCommand::AddMesh{ handle, mesh } => {
+                    debug_log!(Subsystem::Resources, "Adding queued mesh {:?}", handle);
+                    self.emit_resource_event(ResourceEventKind::Loaded, &handle);
+                    self.meshes.insert(handle, mesh);
+                }
+                ResourceCommand::AddTexture{ handle, image, color_space } => {
+                    debug_log!(Subsystem::Resources, "Uploading queued texture {:?}", handle);
+                    let texture = Texture::from_image(&self._device, &self._queue, &self.samplers, &image, color_space, "Texture");
+                    self.emit_resource_event(ResourceEventKind::Loaded, &handle);
+                    if self.pending_textures.remove(&handle){
+                        self.replace_texture(&handle, texture);
+                    }else{
+                        self.add_texture(handle, texture);
+                    }
+                }
+                ResourceCommand::TextureFailed{ handle, error } => {
+                    warn!("Failed to load texture {}, using the missing texture instead", error);
+                    self.pending_textures.remove(&handle);
+                    self.replace_texture(&handle, Texture::create_missing(&self._device, &self._queue, &self.samplers));
+                }
+            }
+        }
+
+        self.dispatch_resource_events();
+    }
+
+    /// # Add Resource Observer
+    ///
+    /// Registers a callback for resource lifecycle events, of one resource type or of all of them with `None`.
+    /// Events are batched and sent at the start of the next frame, before the render closure runs.
+    /// Returns an id for `remove_resource_observer`
+    pub fn add_resource_observer<F>(&mut self, resource_type: Option<ResourceType>, callback: F) -> usize
+        where F: FnMut(&ResourceEvent, &ResourceManager) + 'static{
+        let id = self.next_observer_id;
+        self.next_observer_id += 1;
+        let callback: ResourceObserverFn = Box::new(callback);
+        self.resource_observers.push(ResourceObserver{ id, resource_type, callback });
+
+        id
+    }
+
+    pub fn remove_resource_observer(&mut self, id: usize) -> bool{
+        let count = self.resource_observers.len();
+        self.resource_observers.retain(|observer| observer.id != id);
+        self.resource_observers.len() != count
+    }
+
+    // Events are only kept while something is observing them
+    fn emit_resource_event(&mut self, kind: ResourceEventKind, handle: &ResourceHandle){
+        if self.resource_observers.is_empty(){
+            return;
+        }
+
+        self.resource_events.push(ResourceEvent{ kind, resource_type: *handle.get_type(), handle: handle.clone() });
+    }
+
+    fn dispatch_resource_events(&mut self){
+        if self.resource_events.is_empty(){
+            return;
+        }
+
+        // The observers are taken out while they run, so they can be given the resource manager
+        let events = std::mem::take(&mut self.resource_events);
+        let mut observers = std::mem::take(&mut self.resource_observers);
+        for event in events.iter(){
+            for observer in observers.iter_mut(){
+                if observer.resource_type.is_none_or(|resource_type| resource_type == event.resource_type){
+                    (observer.callback)(event, self);
+                }
+            }
+        }
+        self.resource_observers = observers;
+    }
+
+    // Re-records the static render bundles if the static models changed. Runs after everything
+    // else is updated, as the bundles hold the materials' bind groups and the meshes' buffers
+    pub(crate) fn update_static_bundles(&mut self){
+        let mut static_bundles = std::mem::replace(&mut self.static_bundles, StaticBundles::new());
+        static_bundles.update(self);
+        self.static_bundles = static_bundles;
+    }
+
+    pub(crate) fn get_static_bundles(&self) -> &StaticBundles{
+        &self.static_bundles
+    }
+
+    pub(crate) fn update_model_transforms(&mut self){
+        let mut changed = self.object_data.len() != self.object_count as usize;
+        self.object_data.resize(self.object_count as usize, ObjectData::default());
+
+        let mut to_update = Vec::new();
+        for handle in std::mem::take(&mut self.dirty_models){
+            // Removed since it was changed
+            let Some(model) = self.models.borrow(&handle) else { continue };
+            let transform = model.get_transform();
+            self.object_data[model.get_object_index() as usize] = ObjectData::new(transform, model.get_texture_indices())
+                .with_flags(model.get_object_flags());
+            changed = true;
+
+            if let Some(transform_uniform_handle) = model.get_transform_uniform_handle(){
+                to_update.push((transform_uniform_handle, TransformUniform::new(transform)));
+            }
+        }
+
+        // The copy written last still holds the latest data, so there's nothing to write until something changes
+        if !changed{
+            return;
+        }
+
+        // A bigger buffer means new bind groups for every material reading it
+        if self.objects.write(&self._queue, &self.object_data){
+            debug_log!(Subsystem::Resources, "Grew the objects buffer to {} bytes", self.objects.get_capacity());
+            for material in self.materials.values_mut(){
+                if material.get_shader_bindings().is_some_and(|bindings| bindings.contains_key(OBJECTS_BINDING)){
+                    material.mark_needs_regen();
+                }
+            }
+        }
+
+        for (handle, data) in to_update{
+            if let Err(e) = self.update_uniform_buffer(&handle, data){
+                error!("Failed to update model transform: {}", e);
+            }
+        }
+    }
+    
+    /// Runs every per-frame update, before the frame is drawn. `delta` is the seconds animations advance by,
+    /// `time` the seconds since the renderer started and `surface_size` the size of the frame being drawn
+    pub(crate) fn update_frame(&mut self, delta: f32, time: f32, surface_size: [u32; 2]){
+        self.update_tweens(delta);
+        self.update_scene(time);
+        self.update_globals(delta, surface_size);
+        self.update_model_transforms();
+        self.update_model_properties();
+        self.update_model_pipelines();
+        self.upload_pending_meshes();
+        self.update_texture_streaming();
+        self.update_texture_budget();
+        self.update_lights();
+        self.update_uniforms();
+        self.update_gpu_skinning();
+        self.update_template_bind_groups();
+        self.update_static_bundles();
+    }
+
+    // Regenerates the bind groups of the materials instances are made from, if they changed. Instances share their
+    // template's bind groups and only read them, so templates are brought up-to-date here, before anything draws.
+    // Goes through `get_material` like `StaticBundles::update`, rather than taking the template out of the store
+    pub(crate) fn update_template_bind_groups(&self){
+        for template_handle in self.material_templates.iter(){
+            let Some(mut template) = self.get_material(template_handle) else { continue };
+            if template.needs_bind_groups(){
+                template.generate_bind_groups(self);
+            }
+        }
+    }
+
+    // Makes the double sided variant of the pipeline for any double sided models without one
+    pub(crate) fn update_model_pipelines(&mut self){
+        let missing: Vec<(ResourceHandle, ResourceHandle)> = self.models.values()
+            .filter(|model| model.is_double_sided())
+            .map(|model| (model.get_mesh().clone(), model.get_material().clone()))
+            .filter(|pair| !self.double_sided_pipelines.contains(pair))
+            .collect();
+
+        for (mesh_handle, material_handle) in missing{
+            if self.double_sided_pipelines.contains(&(mesh_handle.clone(), material_handle.clone())){
+                continue;
+            }
+            self.create_pipeline_variant(&mesh_handle, &material_handle, true);
+            debug_log!(Subsystem::Resources, "Created a double sided pipeline for {:?}", material_handle);
+            self.double_sided_pipelines.insert((mesh_handle, material_handle));
+        }
+    }
+
+    // Writes each model's properties over a copy of the material uniforms they override.
+    // The copies are bound through an instance of the material, so everything else stays shared
+    pub(crate) fn update_model_properties(&mut self){
+        let model_handles: Vec<ResourceHandle> = self.models.get_handles().into_iter()
+            .filter(|handle| self.models.borrow(handle).is_some_and(|model| !model.get_properties().is_empty() || !model.get_property_uniforms().is_empty()))
+            .collect();
+
+        for model_handle in model_handles{
+            let mut model = self.models.get(&model_handle).unwrap();
+            let material = self.materials.get(model.get_material()).unwrap();
+            let template = material.get_template().and_then(|template| self.materials.get(template));
+            let shader = match self.shader_manager.get_shader(&material.get_shader()){
+                Some(shader) => shader,
+                None => continue
+            };
+
+            let mut overridden = Vec::new();
+            for (name, binding) in material.get_shader_bindings().unwrap().iter(){
+                if !matches!(binding.get_binding_type(), BindingType::Uniform){
+                    continue;
+                }
+                let Some(layout) = shader.get_uniform_layout(name) else { continue };
+                // Keep writing uniforms that had a property removed, so they go back to the material's values
+                if !model.get_properties().overrides(layout) && !model.get_property_uniforms().contains_key(name){
+                    continue;
+                }
+
+                let base_handle = material.get_uniform(name)
+                    .or_else(|| template.as_ref().and_then(|template| template.get_uniform(name)));
+                let Some(base) = base_handle.and_then(|handle| self.uniforms.get(handle)) else { continue };
+
+                let mut data = base.get_data().as_bytes().to_vec();
+                model.get_properties().apply(layout, &mut data);
+                overridden.push((name.clone(), data));
+            }
+
+            for (name, data) in overridden{
+                match model.get_property_uniforms().get(&name).cloned(){
+                    Some(uniform_handle) => {
+                        let unchanged = self.uniforms.get(&uniform_handle)
+                            .is_some_and(|uniform| uniform.get_data().as_bytes() == data.as_slice());
+                        if !unchanged{
+                            if let Err(e) = self.update_uniform_buffer(&uniform_handle, data){
+                                error!("Failed to update model property: {}", e);
+                            }
+                        }
+                    }
+                    None => {
+                        let uniform_handle = self.create_uniform_buffer(data);
+                        model.add_property_uniform(&name, uniform_handle);
+                    }
+                }
+            }
+
+            if model.get_property_material().is_none() && !model.get_property_uniforms().is_empty(){
+                // Instances can't be nested, so an instanced material's overrides are copied across
+                let template_handle = material.get_template().cloned().unwrap_or_else(|| model.get_material().clone());
+                let instance_handle = self.create_material_instance(&template_handle);
+                let mut instance = self.materials.get(&instance_handle).unwrap();
+                if material.is_instance(){
+                    for (name, texture_handle) in material.get_textures().iter(){
+                        instance.add_texture(name, texture_handle.clone());
+                    }
+                    for (name, uniform_handle) in material.get_uniforms().iter(){
+                        instance.add_uniform(name, uniform_handle.clone());
+                    }
+                }
+                for (name, uniform_handle) in model.get_property_uniforms().iter(){
+                    instance.add_uniform(name, uniform_handle.clone());
+                }
+
+                debug_log!(Subsystem::Materials, "Created property material for model, overriding {} uniforms", model.get_property_uniforms().len());
+                model.set_property_material(instance_handle);
+            }
+        }
+    }
+
+    pub(crate) fn update_texture_streaming(&mut self){
+        for change in self.texture_streamer.update(){
+            let (mips, color_space) = self.texture_streamer.get_mips(&change.handle, change.resident_mip).unwrap();
+            let texture = Texture::from_mips(&self._device, &self._queue, &self.samplers, mips, color_space, "Streamed Texture");
+            self.replace_texture(&change.handle, texture);
+            self.emit_resource_event(ResourceEventKind::Reloaded, &change.handle);
+        }
+    }
+
+    // Swaps a texture in-place, keeping its anisotropy, so every handle to it sees the new one
+    fn replace_texture(&mut self, texture_handle: &ResourceHandle, mut texture: Texture){
+        let existing = self.textures.get_mut(texture_handle).unwrap();
+        if existing.get_anisotropy() > 1{
+            texture.set_anisotropy(&self.samplers, existing.get_anisotropy());
+        }
+        *existing = texture;
+
+        self.mark_texture_users_for_regen(texture_handle);
+    }
+
+    // Regenerates the bind groups of every material sampling a texture that was replaced in-place,
+    // directly or through the bindless array
+    fn mark_texture_users_for_regen(&mut self, texture_handle: &ResourceHandle){
+        let in_bindless = self.bindless.as_ref().is_some_and(|bindless| bindless.get_index(texture_handle).is_some());
+        for material in self.materials.values_mut(){
+            let uses_bindless = in_bindless && material.get_shader_bindings().is_some_and(|bindings| bindings.contains_key(BINDLESS_TEXTURES_BINDING));
+            if material.uses_texture(texture_handle) || uses_bindless{
+                material.mark_needs_regen();
+            }
+        }
+    }
+
+    pub(crate) fn update_lights(&mut self){
+        if let Some(lights) = self.light_manager.take_changes(){
+            let handle = self.lights_uniform.clone();
+            if let Err(e) = self.update_uniform_buffer(&handle, lights){
+                error!("Failed to update lights: {}", e);
+            }
+        }
+
+        // The lights and models can move without the lights changing, so the shadows are updated every frame
+        let casters = self.light_manager.get_point_shadow_casters();
+        if !casters.is_empty(){
+            for model in self.models.values(){
+                if let Some(mesh) = self.meshes.borrow(model.get_mesh()){
+                    self.point_shadows.prepare_layout(mesh.get_layout());
+                }
+            }
+        }
+
+        let current = self.textures.borrow(&self.point_shadow_texture).unwrap();
+        if let Some(texture) = self.point_shadows.set_casters(&self._queue, &self.samplers, current, casters){
+            *self.textures.get_mut(&self.point_shadow_texture).unwrap() = texture;
+
+            for material in self.materials.values_mut(){
+                if material.get_shader_bindings().is_some_and(|bindings| bindings.contains_key(POINT_SHADOWS_BINDING)){
+                    material.mark_needs_regen();
+                }
+            }
+        }
+    }
+
+    /// Draws the point light shadows, in a submission of their own
+    pub(crate) fn render_point_shadows(&self){
+        self.point_shadows.draw(self);
+    }
+
+    /// Sets the width and height of each face of the point light shadows, used from the next frame
+    pub(crate) fn set_shadow_resolution(&mut self, resolution: u32){
+        self.point_shadows.set_resolution(resolution);
+    }
+
+    /// Sets how shadows are biased and filtered, uploaded with the lights
+    pub(crate) fn set_shadow_settings(&mut self, shadow_settings: ShadowSettings){
+        self.light_manager.set_shadow_settings(shadow_settings);
+    }
+
+    pub(crate) fn get_white_texture_ref(&self) -> &ResourceHandle{
+        &self.white_texture
+    }
+
+    pub(crate) fn get_missing_texture_ref(&self) -> &ResourceHandle{
+        &self.missing_texture
+    }
+
+    pub(crate) fn get_point_shadow_texture_ref(&self) -> &ResourceHandle{
+        &self.point_shadow_texture
+    }
+
+    /// Writes the camera and frame time into the `scene` uniform
+    pub(crate) fn update_scene(&mut self, time: f32){
+        self.scene_time = time;
+
+        for (camera_handle, plane) in self.reflection_planes.iter(){
+            if let Some(camera) = self.cameras.get_mut(camera_handle){
+                (camera.view, camera.projection) = planar_reflection::reflect_camera(self.camera_view, self.camera_projection, *plane);
+            }
+        }
+
+        let handle = self.scene_uniform.clone();
+        if let Err(e) = self.update_uniform_buffer(&handle, SceneUniform::new(self.camera_view, self.camera_projection, time)){
+            error!("Failed to update the scene uniform: {}", e);
+        }
+    }
+
+    /// Writes the frame's timing and resolution into the `globals` uniform. Called after `update_scene`,
+    /// whose time and camera it uses
+    pub(crate) fn update_globals(&mut self, delta: f32, resolution: [u32; 2]){
+        let camera_position = self.camera_view.inverse().w_axis.truncate();
+        let globals = GlobalsUniform::new(camera_position, self.scene_time, delta, resolution, self.globals_frame);
+        self.globals_frame = self.globals_frame.wrapping_add(1);
+
+        let handle = self.globals_uniform.clone();
+        if let Err(e) = self.update_uniform_buffer(&handle, globals){
+            error!("Failed to update the globals uniform: {}", e);
+        }
+    }
+
+    /// Writes a camera into the `scene` uniform straight away, for the next submission to draw with.
+    /// Each camera's pass is submitted separately, so they each see their own
+    pub(crate) fn write_scene_uniform(&self, view: glam::Mat4, projection: glam::Mat4){
+        let uniform = self.uniforms.get(&self.scene_uniform).unwrap();
+        self._queue.write_buffer(uniform.get_buffer(), 0, SceneUniform::new(view, projection, self.scene_time).as_bytes());
+    }
+
+    /// Writes any uniform buffers whose data changed since they were last written.
+    /// Materials bind the buffers directly, so this is all they need to see the new data
+    pub(crate) fn update_uniforms(&mut self){
+        for uniform in self.uniforms.values_mut(){
+            uniform.update(&self._queue);
+        }
+    }
+
+    /// # Load Mesh
+    ///
+    /// Loads a mesh from a file and returns a handle to it
+    pub fn load_mesh(&mut self, path: &str) -> ResourceHandle{
+        let handle = self.load_mesh_deferred(path);
+        self.ensure_uploaded(&handle);
+
+        handle
+    }
+
+    /// # Load Mesh Deferred
+    ///
+    /// Loads a mesh from a file into CPU memory only. The vertex and index buffers are created
+    /// the first time a model using the mesh is drawn, or when `ensure_uploaded` is called,
+    /// so loading many meshes up-front doesn't stall the queue
+    pub fn load_mesh_deferred(&mut self, path: &str) -> ResourceHandle{
+        let mesh = Mesh::load(path);
+
+        let handle = ResourceHandle::new(ResourceType::Mesh);
+
+        self.meshes.insert(handle.clone(), mesh);
+        self.resource_names.insert(handle.clone(), path.to_string());
+        self.emit_resource_event(ResourceEventKind::Loaded, &handle);
+
+        handle
+    }
+
+    /// # Load Point Cloud
+    ///
+    /// Loads a point cloud from a `.ply` or `.xyz` file and returns a mesh handle to it.
+    /// Draw it with a point cloud shader, such as `POINT_CLOUD_SHADER`
+    pub fn load_point_cloud(&mut self, path: &str, style: PointStyle) -> ResourceHandle{
+        let points = point_cloud::load_points(path).unwrap_or_else(|e| {
+            error!("Failed to load point cloud {}: {}", path, e);
+            panic!("Failed to load point cloud {}: {}", path, e)
+        });
+        info!("Loaded point cloud with {} points from {}", points.len(), path);
+
+        self.add_mesh(Mesh::from_points(&points, style))
+    }
+
+    /// # Load Mesh Simplified
+    ///
+    /// Loads a mesh and simplifies it to roughly `ratio` (0 to 1) of its triangles before uploading
+    pub fn load_mesh_simplified(&mut self, path: &str, ratio: f32) -> ResourceHandle{
+        let handle = self.load_mesh_deferred(path);
+        let mesh = self.meshes.get_mut(&handle).unwrap();
+        *mesh = mesh.simplify(ratio);
+        self.ensure_uploaded(&handle);
+
+        handle
+    }
+
+    /// # Load Mesh Optimized
+    ///
+    /// Loads a mesh and reorders its triangles and vertices for the GPU's vertex cache and less
+    /// overdraw before uploading (see `Mesh::optimize`). Worth it for large meshes, at some load time
+    pub fn load_mesh_optimized(&mut self, path: &str) -> ResourceHandle{
+        let handle = self.load_mesh_deferred(path);
+        let mesh = self.meshes.get_mut(&handle).unwrap();
+        *mesh = mesh.optimize();
+        self.ensure_uploaded(&handle);
+
+        handle
+    }
+
+    /// # Load Mesh Quantized
+    ///
+    /// Loads a mesh with its vertices quantized (see `Mesh::quantize`), halving its vertex bandwidth. Every importer's
+    /// output can be quantized, including multi-UV glTF and coloured PLY meshes. Returns the mesh and a uniform
+    /// holding its `QuantizationUniform`, to assign to its material as `QUANTIZATION_BINDING`
+    pub fn load_mesh_quantized(&mut self, path: &str) -> (ResourceHandle, ResourceHandle){
+        let mesh = Mesh::load(path);
+        let (mesh, quantization) = mesh.quantize().unwrap_or_else(|| {
+            error!("Mesh {} uses a vertex layout that can't be quantized", path);
+            panic!("Mesh {} uses a vertex layout that can't be quantized", path)
+        });
+
+        let handle = self.add_mesh(mesh);
+        self.resource_names.insert(handle.clone(), path.to_string());
+        self.ensure_uploaded(&handle);
+
+        (handle, self.create_uniform_buffer(quantization))
+    }
+
+    /// # Add Mesh
+    ///
+    /// Adds a mesh built at runtime (e.g by `Mesh::simplify`) and returns a handle to it.
+    /// Its buffers are created the first time it's drawn
+    pub fn add_mesh(&mut self, mesh: Mesh) -> ResourceHandle{
+        let handle = ResourceHandle::new(ResourceType::Mesh);
+        self.meshes.insert(handle.clone(), mesh);
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
+
+        handle
+    }
+
+    /// # Merge Models
+    ///
+    /// Bakes the world transforms of static models into their vertices, and merges
+    /// the models that draw the same way into a single mesh and model, turning many draws into one.
+    /// Models are merged when they share a material, mesh layout and per-model settings
+    /// (shadows, double sided, render layers, properties), which the merged model keeps.
+    ///
+    /// The source models are removed, and the merged models are returned. The merged models
+    /// have an identity transform. Models whose mesh can't be merged (see `Mesh::is_mergeable`)
+    /// or that have a lightmap region are left as they are
+    pub fn merge_models(&mut self, model_handles: &[ResourceHandle]) -> Vec<ResourceHandle>{
+        // Group the models that can be drawn as one, keeping the order they were given in
+        let mut groups: Vec<Vec<ResourceHandle>> = Vec::new();
+        for model_handle in model_handles{
+            let model = match self.models.borrow(model_handle){
+                Some(model) => model,
+                None => {
+                    error!("Model not found: {:?}", model_handle);
+                    continue;
+                }
+            };
+            let mesh = self.meshes.borrow(model.get_mesh()).unwrap();
+            if !mesh.is_mergeable() || model.get_lightmap_uniform_handle().is_some(){
+                warn!("Model {:?} can't be merged, as it has a custom vertex layout, strip topology or lightmap. Leaving it as it is", model_handle);
+                continue;
+            }
+
+            let group = groups.iter_mut().find(|group| {
+                let other = self.models.borrow(&group[0]).unwrap();
+                model.draws_like(other) && self.meshes.borrow(other.get_mesh()).unwrap().get_layout() == mesh.get_layout()
+            });
+            match group{
+                Some(group) => group.push(model_handle.clone()),
+                None => groups.push(vec![model_handle.clone()]),
+            }
+        }
+
+        let mut merged_models = Vec::new();
+        for group in groups{
+            let merged_mesh = {
+                let parts: Vec<(&Mesh, glam::Mat4)> = group.iter()
+                    .map(|model_handle| {
+                        let model = self.models.borrow(model_handle).unwrap();
+                        (self.meshes.borrow(model.get_mesh()).unwrap(), model.get_transform().get_matrix())
+                    })
+                    .collect();
+
+                Mesh::merge(&parts)
+            };
+            info!("Merged {} models into one mesh with {} triangles", group.len(), merged_mesh.get_triangle_count());
+
+            let mesh_handle = self.add_mesh(merged_mesh);
+            let material_handle = self.models.borrow(&group[0]).unwrap().get_material().clone();
+            let merged_model = self.create_model(&mesh_handle, &material_handle, Transform::new());
+            let source = self.models.get(&group[0]).unwrap();
+            source.copy_draw_settings(self.models.get_mut(&merged_model).unwrap());
+
+            for model_handle in group.iter(){
+                self.remove_model(model_handle, true);
+            }
+            merged_models.push(merged_model);
+        }
+
+        merged_models
+    }
+
+    /// # Generate Mesh LODs
+    ///
+    /// Creates a simplified copy of a mesh for each ratio (e.g `[0.5, 0.25, 0.1]`),
+    /// returning their handles in the same order
+    pub fn generate_mesh_lods(&mut self, mesh_handle: &ResourceHandle, ratios: &[f32]) -> Vec<ResourceHandle>{
+        let lods = match self.meshes.borrow(mesh_handle){
+            Some(mesh) => mesh.generate_lods(ratios),
+            None => {
+                error!("Mesh not found: {:?}", mesh_handle);
+                return Vec::new();
+            }
+        };
+
+        lods.into_iter().map(|lod| self.add_mesh(lod)).collect()
+    }
+
+    /// # Ensure Uploaded
+    ///
+    /// Creates the GPU buffers for a mesh if they don't exist yet
+    pub fn ensure_uploaded(&mut self, mesh_handle: &ResourceHandle){
+        if self.is_mesh_uploaded(mesh_handle){
+            return;
+        }
+
+        let mesh = match self.meshes.borrow(mesh_handle){
+            Some(mesh) => mesh,
+            None => {
+                error!("Mesh not found: {:?}", mesh_handle);
+                return;
+            }
+        };
+        debug_log!(Subsystem::Resources, "Uploading mesh {:?}", mesh_handle);
+
+        // We need to create a buffer for each submesh
+        let mut vertex_buffers = Vec::new();
+        let mut index_buffers = Vec::new();
+
+        for sub_mesh in mesh.get_sub_meshes(){
+            let indices = sub_mesh.get_indices();
+
+            // GPU skinned meshes are written by a compute pass
+            let vertex_buffer_type = if self.gpu_skins.contains_key(mesh_handle){ BufferType::StorageVertex }else{ BufferType::Vertex };
+            let vertex_buffer = Buffer::create_buffer_from_bytes(&self._device,
+                                                                 sub_mesh.get_vertex_bytes(), vertex_buffer_type);
+            let index_buffer = Buffer::create_buffer_from_type(&self._device,
+                                                               &indices.as_slice(), BufferType::Index);
+
+            vertex_buffers.push(vertex_buffer);
+            index_buffers.push(index_buffer);
+        }
+
+        self.mesh_bounds.insert(mesh_handle.clone(), mesh.compute_bounds());
+        self.mesh_vertex_buffers.insert(mesh_handle.clone(), vertex_buffers);
+        self.mesh_index_buffers.insert(mesh_handle.clone(), index_buffers);
+    }
+
+    /// # Update Mesh
+    ///
+    /// Re-uploads a mesh's CPU-side data after it was changed through `get_mesh_mut`.
+    /// Buffers are written in place when their sizes are unchanged, and recreated otherwise
+    pub fn update_mesh(&mut self, mesh_handle: &ResourceHandle){
+        let mesh = match self.meshes.borrow(mesh_handle){
+            Some(mesh) => mesh,
+            None => {
+                error!("Mesh not found: {:?}", mesh_handle);
+                return;
+            }
+        };
+
+        self.mesh_bounds.insert(mesh_handle.clone(), mesh.compute_bounds());
+
+        if let Some(dynamic_mesh) = self.dynamic_meshes.get_mut(mesh_handle){
+            let sub_mesh = &mesh.get_sub_meshes()[0];
+            dynamic_mesh.write(&self._device, &self._queue, sub_mesh.get_vertices(), sub_mesh.get_indices());
+            return;
+        }
+
+        // Not uploaded yet, so it'll pick up the new data when it is
+        let (vertex_buffers, index_buffers) = match (self.mesh_vertex_buffers.get(mesh_handle), self.mesh_index_buffers.get(mesh_handle)){
+            (Some(vertex_buffers), Some(index_buffers)) => (vertex_buffers, index_buffers),
+            _ => return,
+        };
+
+        let sub_meshes = mesh.get_sub_meshes();
+        let same_layout = sub_meshes.len() == vertex_buffers.len() && sub_meshes.iter().enumerate().all(|(idx, sub_mesh)|{
+            sub_mesh.get_vertex_bytes().len() == vertex_buffers[idx].get_size()
+                && sub_mesh.get_indices().as_bytes().len() == index_buffers[idx].get_size()
+        });
+
+        if same_layout{
+            for (idx, sub_mesh) in sub_meshes.iter().enumerate(){
+                vertex_buffers[idx].update(&self._queue, sub_mesh.get_vertex_bytes());
+                index_buffers[idx].update(&self._queue, sub_mesh.get_indices().as_bytes());
+            }
+        } else {
+            debug_log!(Subsystem::Resources, "Mesh {:?} changed size, recreating its buffers", mesh_handle);
+            self.mesh_vertex_buffers.remove(mesh_handle);
+            self.mesh_index_buffers.remove(mesh_handle);
+            self.ensure_uploaded(mesh_handle);
+        }
+
+        self.emit_resource_event(ResourceEventKind::Reloaded, mesh_handle);
+    }
+
+    pub fn is_mesh_uploaded(&self, mesh_handle: &ResourceHandle) -> bool{
+        self.mesh_vertex_buffers.contains_key(mesh_handle) || self.dynamic_meshes.contains_key(mesh_handle)
+    }
+
+    /// # Create Dynamic Mesh
+    ///
+    /// Creates an empty mesh meant to be rewritten every frame with `update_dynamic_mesh`,
+    /// with room for the given number of vertices and indices (it grows if needed).
+    /// The handle is used like any other mesh handle
+    pub fn create_dynamic_mesh(&mut self, vertex_capacity: usize, index_capacity: usize) -> ResourceHandle{
+        let handle = self.add_mesh(Mesh::new(vec![SubMesh::new(Vec::new(), Vec::new())]));
+        self.dynamic_meshes.insert(handle.clone(), DynamicMesh::new(&self._device, vertex_capacity, index_capacity));
+
+        handle
+    }
+
+    /// # Update Dynamic Mesh
+    ///
+    /// Replaces the contents of a dynamic mesh. This is cheap enough to call every frame
+    pub fn update_dynamic_mesh(&mut self, mesh_handle: &ResourceHandle, vertices: &[Vertex], indices: &[u32]){
+        let dynamic_mesh = match self.dynamic_meshes.get_mut(mesh_handle){
+            Some(dynamic_mesh) => dynamic_mesh,
+            None => {
+                error!("Dynamic mesh not found: {:?}", mesh_handle);
+                return;
+            }
+        };
+        dynamic_mesh.write(&self._device, &self._queue, vertices, indices);
+
+        // Keep the CPU copy in sync, it's what the draw count comes from
+        let mesh = self.meshes.get_mut(mesh_handle).unwrap();
+        *mesh = Mesh::new(vec![SubMesh::new(vertices.to_vec(), indices.to_vec())]);
+        self.mesh_bounds.insert(mesh_handle.clone(), mesh.compute_bounds());
+    }
+
+    pub fn get_dynamic_mesh(&self, mesh_handle: &ResourceHandle) -> Option<&DynamicMesh>{
+        self.dynamic_meshes.get(mesh_handle)
+    }
+
+    // Uploads any deferred meshes that are about to be drawn
+    pub(crate) fn upload_pending_meshes(&mut self){
+        let pending: Vec<ResourceHandle> = self.models.values()
+            .map(|model| model.get_mesh().clone())
+            .filter(|mesh_handle| !self.is_mesh_uploaded(mesh_handle))
+            .collect();
+
+        for mesh_handle in pending{
+            self.ensure_uploaded(&mesh_handle);
+        }
+    }
+
+    /// # Load Texture
+    ///
+    /// Loads a texture from a file and returns a handle to it
+    pub fn load_texture(&mut self, path: &str) -> ResourceHandle{
+        self.load_texture_with_color_space(path, ColorSpace::Srgb)
+    }
+
+    /// # Load Texture With Color Space
+    ///
+    /// Loads a texture from a file and returns a handle to it. Use `ColorSpace::Linear`
+    /// for data textures such as normal, roughness and metalness maps, so they aren't
+    /// gamma-decoded when sampled. `ColorSpace::from_gltf_slot` picks the right one for glTF slots
+    pub fn load_texture_with_color_space(&mut self, path: &str, color_space: ColorSpace) -> ResourceHandle{
+        let texture = Texture::load_from_file_with_color_space(&self._device, &self._queue, &self.samplers, path, color_space);
+        let handle = ResourceHandle::new(ResourceType::Texture);
+
+        self.add_texture(handle.clone(), texture);
+        self.resource_names.insert(handle.clone(), path.to_string());
+        self.texture_budget.add_source(&handle, path, color_space);
+        self.emit_resource_event(ResourceEventKind::Loaded, &handle);
+
+        handle
+    }
+
+    // Stores a texture, with the default anisotropic filtering applied
+    fn add_texture(&mut self, handle: ResourceHandle, mut texture: Texture){
+        if self.default_anisotropy > 1{
+            texture.set_anisotropy(&self.samplers, self.default_anisotropy);
+        }
+
+        self.textures.insert(handle, texture);
+    }
+
+    /// # Load Texture Async
+    ///
+    /// Returns a texture handle straight away, holding a 1x1 white placeholder while the file is
+    /// decoded on another thread. Once it's done, the texture is swapped in at the start of a frame
+    /// and materials using it pick it up. `is_resource_ready` reports when that's happened
+    pub fn load_texture_async(&mut self, path: &str, color_space: ColorSpace) -> ResourceHandle{
+        let handle = ResourceHandle::new(ResourceType::Texture);
+        let placeholder = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+        let placeholder = Texture::from_image(&self._device, &self._queue, &self.samplers, &placeholder, color_space, "Placeholder Texture");
+        self.add_texture(handle.clone(), placeholder);
+        self.resource_names.insert(handle.clone(), path.to_string());
+        self.texture_budget.add_source(&handle, path, color_space);
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
+        self.decode_texture_async(&handle, path, color_space);
+
+        handle
+    }
+
+    // Decodes a texture on another thread, swapping it in over the current one once it's done
+    fn decode_texture_async(&mut self, handle: &ResourceHandle, path: &str, color_space: ColorSpace){
+        self.pending_textures.insert(handle.clone());
+
+        let resource_queue = self.resource_queue.clone();
+        let path = path.to_string();
+        let texture_handle = handle.clone();
+        std::thread::spawn(move || resource_queue.decode_texture_into(texture_handle, &path, color_space));
+    }
+
+    /// # Get Resource Queue
+    ///
+    /// Gets a queue that other threads can create meshes and textures through,
+    /// without waiting on the resource manager
+    pub fn get_resource_queue(&self) -> ResourceQueue{
+        self.resource_queue.clone()
+    }
+
+    /// # Is Resource Ready
+    ///
+    /// Whether a mesh or texture created through the resource queue, or a texture from
+    /// `load_texture_async`, has been added yet. Every other resource is created straight away,
+    /// so is always ready
+    pub fn is_resource_ready(&self, handle: &ResourceHandle) -> bool{
+        match handle.get_type(){
+            ResourceType::Mesh => self.meshes.contains(handle),
+            ResourceType::Texture => self.textures.contains(handle) && !self.pending_textures.contains(handle)
+                && !self.texture_budget.is_evicted(handle),
+            _ => true
+        }
+    }
+
+    /// # Are Resources Ready
+    ///
+    /// Whether every one of the handles is ready, see `is_resource_ready`. Handy for ending a loading phase
+    pub fn are_resources_ready(&self, handles: &[ResourceHandle]) -> bool{
+        handles.iter().all(|handle| self.is_resource_ready(handle))
+    }
+
+    /// # Load Texture Streamed
+    ///
+    /// Loads a texture for streaming. Only the low-resolution mips are uploaded at first,
+    /// and higher ones are streamed in over the following frames once the texture
+    /// is requested with `request_texture_resolution` or `request_texture_for_distance`
+    pub fn load_texture_streamed(&mut self, path: &str, color_space: ColorSpace) -> ResourceHandle{
+        info!("Loading streamed texture from file: {:?}", path);
+        let image = image::open(path).unwrap_or_else(|e| {
+            error!("Failed to load texture {}: {}", path, e);
+            panic!("Failed to load texture {}: {}", path, e)
+        }).to_rgba8();
+        let mips = Texture::generate_mip_chain(&image);
+
+        let handle = ResourceHandle::new(ResourceType::Texture);
+        let base_mip = self.texture_streamer.add(handle.clone(), mips, color_space);
+        self.resource_names.insert(handle.clone(), path.to_string());
+        self.emit_resource_event(ResourceEventKind::Loaded, &handle);
+
+        let (mips, _) = self.texture_streamer.get_mips(&handle, base_mip).unwrap();
+        let mut texture = Texture::from_mips(&self._device, &self._queue, &self.samplers, mips, color_space, "Streamed Texture");
+        if self.default_anisotropy > 1{
+            texture.set_anisotropy(&self.samplers, self.default_anisotropy);
+        }
+        self.textures.insert(handle.clone(), texture);
+
+        handle
+    }
+
+    /// # Request Texture Resolution
+    ///
+    /// Tells the streamer a streamed texture covers roughly `screen_size` pixels on screen
+    /// this frame, so the matching mips get uploaded. Call every frame the texture is visible;
+    /// textures which stop being requested are evicted back to their low mips after a while
+    pub fn request_texture_resolution(&mut self, texture_handle: &ResourceHandle, screen_size: f32){
+        self.texture_streamer.request(texture_handle, screen_size);
+    }
+
+    /// # Request Texture For Distance
+    ///
+    /// Like `request_texture_resolution`, but estimates the screen coverage from an object
+    /// `world_size` units tall, `distance` units from a camera with the given vertical fov (radians)
+    pub fn request_texture_for_distance(&mut self, texture_handle: &ResourceHandle, world_size: f32, distance: f32, fov_y: f32, viewport_height: f32){
+        let screen_size = texture_streamer::screen_size_at_distance(world_size, distance, fov_y, viewport_height);
+        self.texture_streamer.request(texture_handle, screen_size);
+    }
+
+    /// # Set Texture Streaming Budget
+    ///
+    /// Sets how much GPU memory streamed textures may use, in bytes. Defaults to 256MB
+    pub fn set_texture_streaming_budget(&mut self, budget_bytes: u64){
+        self.texture_streamer.set_budget(budget_bytes);
+    }
+
+    pub fn get_texture_streaming_budget(&self) -> u64{
+        self.texture_streamer.get_budget()
+    }
+
+    /// Bytes currently used by the resident mips of streamed textures
+    pub fn get_texture_streaming_usage(&self) -> u64{
+        self.texture_streamer.get_resident_bytes()
+    }
+
+    /// Sets how many mip levels may be uploaded per frame, to limit upload stalls
+    pub fn set_texture_streaming_uploads_per_frame(&mut self, uploads_per_frame: usize){
+        self.texture_streamer.set_uploads_per_frame(uploads_per_frame);
+    }
+
+    pub fn is_texture_streamed(&self, texture_handle: &ResourceHandle) -> bool{
+        self.texture_streamer.contains(texture_handle)
+    }
+
+    /// # Set Texture Budget
+    ///
+    /// Caps the GPU memory all textures together may use, in bytes, or removes the cap with None (the default).
+    /// While over it, the least recently used textures not drawn this frame are evicted: streamed textures drop
+    /// to their low mips, and textures loaded from files swap to a 1x1 placeholder until a model uses them again,
+    /// when they're reloaded in the background. Other textures, and pinned ones, are never evicted
+    pub fn set_texture_budget(&mut self, budget_bytes: Option<u64>){
+        self.texture_budget.set_budget(budget_bytes);
+    }
+
+    pub fn get_texture_budget(&self) -> Option<u64>{
+        self.texture_budget.get_budget()
+    }
+
+    /// # Pin Texture
+    ///
+    /// Keeps a texture fully resident whatever the texture budget, e.g for UI or other critical assets
+    pub fn pin_texture(&mut self, texture_handle: &ResourceHandle){
+        self.texture_budget.set_pinned(texture_handle, true);
+    }
+
+    pub fn unpin_texture(&mut self, texture_handle: &ResourceHandle){
+        self.texture_budget.set_pinned(texture_handle, false);
+    }
+
+    pub fn is_texture_pinned(&self, texture_handle: &ResourceHandle) -> bool{
+        self.texture_budget.is_pinned(texture_handle)
+    }
+
+    /// Whether a texture was evicted to its placeholder by the texture budget
+    pub fn is_texture_evicted(&self, texture_handle: &ResourceHandle) -> bool{
+        self.texture_budget.is_evicted(texture_handle)
+    }
+
+    // The textures sampled by the materials of every model, and the streamed textures still being requested
+    fn get_textures_in_use(&self) -> HashSet<ResourceHandle>{
+        let mut used = HashSet::new();
+        for model in self.models.values(){
+            let material_handle = model.get_property_material().unwrap_or(model.get_material());
+            if let Some(material) = self.materials.borrow(material_handle){
+                used.extend(material.get_textures().values().cloned());
+            }
+        }
+        used.extend(self.textures.get_handles().into_iter().filter(|handle| self.texture_streamer.is_requested(handle)));
+        used
+    }
+
+    // Reloads textures evicted by the budget once they're used again, then evicts the least
+    // recently used textures while all of them together are over the budget
+    pub(crate) fn update_texture_budget(&mut self){
+        let used = self.get_textures_in_use();
+        for handle in used.iter(){
+            if self.texture_budget.is_evicted(handle){
+                let (path, color_space) = self.texture_budget.get_source(handle).cloned().unwrap();
+                debug_log!(Subsystem::Resources, "Reloading evicted texture {}", path);
+                self.texture_budget.set_evicted(handle, false);
+                self.decode_texture_async(handle, &path, color_space);
+            }
+        }
+        self.texture_budget.begin_frame(used.into_iter());
+
+        let Some(budget) = self.texture_budget.get_budget() else { return };
+        let mut total: u64 = self.textures.values().map(|texture| texture.get_memory_size()).sum();
+        if total <= budget{
+            return;
+        }
+
+        // Textures in the bindless array are bound by every material using it, so are left alone
+        let evictable: Vec<ResourceHandle> = self.textures.get_handles().into_iter()
+            .filter(|handle| self.texture_streamer.contains(handle)
+                || (self.texture_budget.get_source(handle).is_some() && !self.texture_budget.is_evicted(handle) && !self.pending_textures.contains(handle)))
+            .filter(|handle| self.bindless.as_ref().is_none_or(|bindless| bindless.get_index(handle).is_none()))
+            .collect();
+
+        for handle in self.texture_budget.get_eviction_candidates(evictable.into_iter()){
+            if total <= budget{
+                break;
+            }
+
+            let texture = if self.texture_streamer.contains(&handle){
+                let Some(change) = self.texture_streamer.evict(&handle) else { continue };
+                let (mips, color_space) = self.texture_streamer.get_mips(&handle, change.resident_mip).unwrap();
+                Texture::from_mips(&self._device, &self._queue, &self.samplers, mips, color_space, "Streamed Texture")
+            }else{
+                let color_space = self.texture_budget.get_source(&handle).unwrap().1;
+                let placeholder = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+                self.texture_budget.set_evicted(&handle, true);
+                Texture::from_image(&self._device, &self._queue, &self.samplers, &placeholder, color_space, "Evicted Texture")
+            };
+
+            total = total - self.textures.borrow(&handle).unwrap().get_memory_size() + texture.get_memory_size();
+            debug_log!(Subsystem::Resources, "Evicted texture {:?} to stay under the texture budget, {} bytes in use", handle, total);
+            self.replace_texture(&handle, texture);
+            self.emit_resource_event(ResourceEventKind::Reloaded, &handle);
+        }
+    }
+
+    /// # Create Texture Atlas
+    ///
+    /// Packs the builder's images into a single texture and returns the atlas, which holds
+    /// the texture handle and each image's UV rect
+    pub fn create_texture_atlas(&mut self, builder: &TextureAtlasBuilder, color_space: ColorSpace) -> anyhow::Result<TextureAtlas>{
+        let (image, rects) = builder.build()?;
+        info!("Created {}x{} texture atlas with {} images", image.width(), image.height(), rects.len());
+
+        let mut texture = Texture::from_image(&self._device, &self._queue, &self.samplers, &image, color_space, "Texture Atlas");
+        if self.default_anisotropy > 1{
+            texture.set_anisotropy(&self.samplers, self.default_anisotropy);
+        }
+        let handle = ResourceHandle::new(ResourceType::Texture);
+        self.textures.insert(handle.clone(), texture);
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
+
+        Ok(TextureAtlas::new(handle, rects, image.width()))
+    }
+
+    /// Whether the device supports skinning meshes in a compute pass, see `create_gpu_skinned_mesh`
+    pub fn is_gpu_skinning_supported(&self) -> bool{
+        self.gpu_skinning.is_some()
+    }
+
+    /// # Create GPU Skinned Mesh
+    ///
+    /// Creates a mesh that a compute pass skins from a `SkinnedVertex` mesh every frame, following the joints
+    /// in a `SkinUniform` uniform buffer (the same one `SKINNED_SHADER` would use). The result has the standard
+    /// `Vertex` layout, so any shader can draw it and it casts shadows, without a skinning variant of each.
+    /// Culling uses the bind pose's bounds. Without compute support (see `is_gpu_skinning_supported`) the mesh
+    /// stays in its bind pose
+    pub fn create_gpu_skinned_mesh(&mut self, skinned_mesh_handle: &ResourceHandle, skin_uniform_handle: &ResourceHandle) -> ResourceHandle{
+        let skinned_mesh = self.meshes.borrow(skinned_mesh_handle).unwrap_or_else(|| {
+            error!("Mesh not found: {:?}", skinned_mesh_handle);
+            panic!("Mesh not found: {:?}", skinned_mesh_handle)
+        });
+        let bind_pose = skinned_mesh.to_bind_pose().unwrap_or_else(|| {
+            error!("Mesh {:?} doesn't use the SkinnedVertex layout, so can't be GPU skinned", skinned_mesh_handle);
+            panic!("Mesh {:?} doesn't use the SkinnedVertex layout, so can't be GPU skinned", skinned_mesh_handle)
+        });
+        if !self.uniforms.contains_key(skin_uniform_handle){
+            error!("Skin uniform buffer not found: {:?}", skin_uniform_handle);
+            panic!("Skin uniform buffer not found: {:?}", skin_uniform_handle);
+        }
+
+        let skin = GpuSkin::new(&self._device, skinned_mesh, skin_uniform_handle.clone());
+        let handle = self.add_mesh(bind_pose);
+        if self.gpu_skinning.is_some(){
+            self.gpu_skins.insert(handle.clone(), skin);
+        }else{
+            warn!("Compute shaders aren't supported, so GPU skinned mesh {:?} stays in its bind pose", handle);
+        }
+        self.ensure_uploaded(&handle);
+
+        handle
+    }
+
+    // Skins every GPU skinned mesh for this frame, after the skin uniforms have been uploaded
+    pub(crate) fn update_gpu_skinning(&self){
+        let Some(gpu_skinning) = self.gpu_skinning.as_ref() else { return };
+        if self.gpu_skins.is_empty(){
+            return;
+        }
+
+        let mut encoder = self._device.create_command_encoder(&wgpu::CommandEncoderDescriptor{
+            label: Some("Skinning Encoder")
+        });
+
+        let uniforms: HashMap<&ResourceHandle, &Handle<UniformBuffer>> = self.gpu_skins.values()
+            .filter_map(|skin| self.uniforms.get(skin.get_skin_uniform()).map(|uniform| (skin.get_skin_uniform(), uniform)))
+            .collect();
+        let sub_meshes = self.gpu_skins.iter()
+            .filter_map(|(mesh_handle, skin)| Some((uniforms.get(skin.get_skin_uniform())?.get_buffer(), skin, self.mesh_vertex_buffers.get(mesh_handle)?)))
+            .flat_map(|(uniform, skin, outputs)| skin.get_source_buffers().iter().zip(outputs.iter()).map(move |(source, output)| (uniform, source, output)));
+        gpu_skinning.dispatch(&mut encoder, sub_meshes);
+
+        self._queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    pub fn is_gpu_culling_supported(&self) -> bool{
+        self.gpu_culling.is_some()
+    }
+
+    /// # Set Cull View Projection
+    ///
+    /// Frustum culls models against a camera's view-projection matrix on the GPU before they're drawn,
+    /// using the bounds of their meshes. `None` turns culling off. This does nothing on devices without
+    /// multi-draw indirect, see `is_gpu_culling_supported`
+    pub fn set_cull_view_projection(&mut self, view_projection: Option<glam::Mat4>){
+        self.cull_view_projection = view_projection;
+    }
+
+    pub fn get_cull_view_projection(&self) -> Option<glam::Mat4>{
+        self.cull_view_projection
+    }
+
+    // The culling pass and the matrix to cull against, if culling is on
+    pub(crate) fn get_gpu_culling(&self) -> Option<(&GpuCulling, glam::Mat4)>{
+        self.gpu_culling.as_ref().zip(self.cull_view_projection)
+    }
+
+    /// The bounds of each submesh of a mesh, once it's been uploaded. Submeshes without
+    /// positions the bounds can be computed from are None
+    pub fn get_mesh_bounds(&self, mesh_handle: &ResourceHandle) -> Option<&Vec<Option<BoundingSphere>>>{
+        self.mesh_bounds.get(mesh_handle)
+    }
+
+    /// Whether the device supports bindless textures, so shaders such as `BINDLESS_SHADER` can be used
+    pub fn is_bindless_supported(&self) -> bool{
+        self.bindless.is_some()
+    }
+
+    /// # Get Bindless Texture Index
+    ///
+    /// Adds a texture to the global texture array if it isn't in it already, and returns its index.
+    /// Returns None if bindless textures aren't supported or the array is full
+    pub fn get_bindless_texture_index(&mut self, texture_handle: &ResourceHandle) -> Option<u32>{
+        if !self.textures.contains(texture_handle){
+            error!("Texture not found");
+            return None;
+        }
+
+        let bindless = self.bindless.as_mut()?;
+        let (index, added) = match bindless.register(texture_handle){
+            Some(registered) => registered,
+            None => {
+                warn!("Bindless texture array is full ({} textures)", MAX_BINDLESS_TEXTURES);
+                return None;
+            }
+        };
+
+        // Materials binding the array need to pick up the new texture
+        if added{
+            for material in self.materials.values_mut(){
+                if material.get_shader_bindings().is_some_and(|bindings| bindings.contains_key(BINDLESS_TEXTURES_BINDING)){
+                    material.mark_needs_regen();
+                }
+            }
+        }
+
+        Some(index)
+    }
+
+    /// # Set Model Bindless Textures
+    ///
+    /// Sets the textures (up to 4) a model samples from the global texture array. Their indices are
+    /// written to the model's `ObjectData::texture_indices`, so models with different textures can share
+    /// one material. Returns false if bindless textures aren't supported
+    pub fn set_model_bindless_textures(&mut self, model_handle: &ResourceHandle, texture_handles: &[ResourceHandle]) -> bool{
+        if texture_handles.len() > 4{
+            warn!("Models can only reference 4 bindless textures, ignoring the rest");
+        }
+
+        let mut texture_indices = [0; 4];
+        for (slot, texture_handle) in texture_indices.iter_mut().zip(texture_handles){
+            match self.get_bindless_texture_index(texture_handle){
+                Some(index) => *slot = index,
+                None => return false
+            }
+        }
+
+        match self.models.get_mut(model_handle){
+            Some(model) => {
+                model.set_texture_indices(texture_indices);
+                self.dirty_models.insert(model_handle.clone());
+            },
+            None => {
+                error!("Model not found");
+                return false;
+            }
+        }
+        true
+    }
+
+    pub(crate) fn get_bindless_textures(&self) -> Option<&BindlessTextures>{
+        self.bindless.as_ref()
+    }
+
+    pub(crate) fn set_max_anisotropy(&mut self, max_anisotropy: u16){
+        self.max_anisotropy = max_anisotropy.max(1);
+        self.default_anisotropy = self.default_anisotropy.min(self.max_anisotropy);
+    }
+
+    pub fn get_default_anisotropy(&self) -> u16{
+        self.default_anisotropy
+    }
+
+    /// # Set Default Anisotropy
+    ///
+    /// Sets the anisotropic filtering level (1 to 16, clamped to what the device supports)
+    /// used for every texture, including ones that are already loaded.
+    /// Higher levels keep textures sharp when viewed at grazing angles
+    pub fn set_default_anisotropy(&mut self, anisotropy: u16){
+        self.default_anisotropy = anisotropy.clamp(1, self.max_anisotropy);
+
+        // The shadow map keeps its comparison sampler
+        let handles: Vec<ResourceHandle> = self.textures.get_handles().into_iter()
+            .filter(|handle| *handle != self.point_shadow_texture)
+            .collect();
+        for handle in handles{
+            self.set_texture_anisotropy(&handle, self.default_anisotropy);
+        }
+    }
+
+    /// # Set Texture Anisotropy
+    ///
+    /// Overrides the anisotropic filtering level of a single texture.
+    /// Materials using the texture will rebuild their bind groups
+    pub fn set_texture_anisotropy(&mut self, texture_handle: &ResourceHandle, anisotropy: u16){
+        let anisotropy = anisotropy.clamp(1, self.max_anisotropy);
+
+        let texture = match self.textures.get_mut(texture_handle){
+            Some(texture) => texture,
+            None => {
+                error!("Texture not found: {:?}", texture_handle);
+                return;
+            }
+        };
+        if texture.get_anisotropy() == anisotropy{
+            return;
+        }
+        texture.set_anisotropy(&self.samplers, anisotropy);
+
+        for material in self.materials.values_mut(){
+            if material.uses_texture(texture_handle){
+                material.mark_needs_regen();
+            }
+        }
+    }
+
+    /// # Create Material
+    ///
+    /// Creates a new material and returns a handle to it
+    pub fn create_material(&mut self) -> ResourceHandle{
+        let material = Material::new(self._device.clone(), self._queue.clone());
+        let handle = ResourceHandle::new(ResourceType::Material);
+
+        self.materials.insert(handle.clone(), material);
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
+
+        handle
+    }
+
+    /// # Load glTF Materials
+    ///
+    /// Creates a material for each material in a glTF file, in the file's order, using `PBR_SHADER`.
+    /// Metallic-roughness, `KHR_materials_clearcoat`, `KHR_materials_transmission`,
+    /// `KHR_materials_emissive_strength`, `KHR_texture_transform`, occlusion and normal textures are honoured, and slots
+    /// without a texture sample a white placeholder so the factors are used alone. Models created with
+    /// a `doubleSided` material are double sided.
+    /// The lightmap starts off, see `set_model_lightmap`
+    pub fn load_gltf_materials(&mut self, path: &str) -> Vec<ResourceHandle>{
+        let (document, _, images) = gltf::import(path).unwrap_or_else(|e| {
+            error!("Failed to load gltf file {}: {}", path, e);
+            panic!("Failed to load gltf file {}: {}", path, e)
+        });
+
+        let shader_handle = self.get_builtin_shader("pbr");
+        let placeholder_handle = self.white_texture.clone();
+
+        // Images shared by several materials are only uploaded once per colour space
+        let mut image_textures: HashMap<(usize, ColorSpace), ResourceHandle> = HashMap::new();
+        let mut material_handles = Vec::new();
+        let lightmap_handle = self.create_uniform_buffer(LightmapUniform::default());
+
+        for material in document.materials(){
+            let gltf_material = GltfPbrMaterial::from_gltf(&document, &material);
+            debug_log!(Subsystem::Resources, "glTF material {:?} samples {} textures", gltf_material.name, gltf_material.textures.len());
+
+            let material_handle = self.create_material();
+            if let Some(name) = &gltf_material.name{
+                self.resource_names.insert(material_handle.clone(), name.clone());
+            }
+            self.assign_shader_to_material(&material_handle, &shader_handle);
+            self.set_material_double_sided(&material_handle, gltf_material.double_sided);
+            let uniform_handle = self.create_uniform_buffer(gltf_material.uniform);
+            self.assign_uniform_to_material(&material_handle, &uniform_handle, "material");
+            let lights_handle = self.lights_uniform.clone();
+            self.assign_uniform_to_material(&material_handle, &lights_handle, "lights");
+            self.assign_uniform_to_material(&material_handle, &lightmap_handle, "lightmap");
+            self.assign_texture_to_material(&material_handle, &placeholder_handle, PBR_LIGHTMAP_SLOT);
+
+            for slot in PBR_TEXTURE_SLOTS{
+                let texture = gltf_material.textures.iter().find(|(name, _, _)| *name == slot);
+                let texture_handle = match texture{
+                    Some(&(_, image_index, color_space)) => match image_textures.get(&(image_index, color_space)){
+                        Some(handle) => handle.clone(),
+                        None => {
+                            let image = pbr_material::gltf_image_to_rgba(&images[image_index]).unwrap_or_else(|e| {
+                                error!("Failed to read glTF image {}: {}", image_index, e);
+                                panic!("Failed to read glTF image {}: {}", image_index, e)
+                            });
+                            let mut texture = Texture::from_image(&self._device, &self._queue, &self.samplers, &image, color_space, "glTF Texture");
+                            if self.default_anisotropy > 1{
+                                texture.set_anisotropy(&self.samplers, self.default_anisotropy);
+                            }
+                            let handle = ResourceHandle::new(ResourceType::Texture);
+                            self.textures.insert(handle.clone(), texture);
+                            image_textures.insert((image_index, color_space), handle.clone());
+                            handle
+                        }
+                    },
+                    None => placeholder_handle.clone(),
+                };
+                self.assign_texture_to_material(&material_handle, &texture_handle, slot);
+            }
+
+            material_handles.push(material_handle);
+        }
+
+        info!("Loaded {} materials from {}", material_handles.len(), path);
+        material_handles
+    }
+
+    /// # Load USD Materials
+    ///
+    /// Creates a material for each `UsdPreviewSurface` material in a `.usda` file, or a `.usdz` package
+    /// with a `.usda` root layer, in the layer's order, using `PBR_SHADER`. Diffuse, emissive, metallic,
+    /// roughness, occlusion, normal and opacity inputs are honoured, either as values or `UsdUVTexture`
+    /// textures, and slots without a texture sample a white placeholder so the factors are used alone.
+    /// The lightmap starts off, see `set_model_lightmap`
+    #[cfg(feature = "usd")]
+    pub fn load_usd_materials(&mut self, path: &str) -> Vec<ResourceHandle>{
+        let usd_materials = crate::utils::usd::load_usd_materials(path).unwrap_or_else(|e| {
+            error!("Failed to load usd file {}: {}", path, e);
+            panic!("Failed to load usd file {}: {}", path, e)
+        });
+
+        let shader_handle = self.get_builtin_shader("pbr");
+        let placeholder_handle = self.white_texture.clone();
+        let mut material_handles = Vec::new();
+        let lightmap_handle = self.create_uniform_buffer(LightmapUniform::default());
+
+        for usd_material in usd_materials{
+            debug_log!(Subsystem::Resources, "USD material `{}` samples {} textures", usd_material.name, usd_material.textures.len());
+
+            let material_handle = self.create_material();
+            self.resource_names.insert(material_handle.clone(), usd_material.name.clone());
+            self.assign_shader_to_material(&material_handle, &shader_handle);
+            let uniform_handle = self.create_uniform_buffer(usd_material.uniform);
+            self.assign_uniform_to_material(&material_handle, &uniform_handle, "material");
+            let lights_handle = self.lights_uniform.clone();
+            self.assign_uniform_to_material(&material_handle, &lights_handle, "lights");
+            self.assign_uniform_to_material(&material_handle, &lightmap_handle, "lightmap");
+            self.assign_texture_to_material(&material_handle, &placeholder_handle, PBR_LIGHTMAP_SLOT);
+
+            for slot in PBR_TEXTURE_SLOTS{
+                let texture_handle = match usd_material.textures.iter().find(|(name, _)| *name == slot){
+                    Some((_, image)) => {
+                        let mut texture = Texture::from_image(&self._device, &self._queue, &self.samplers, image, ColorSpace::from_gltf_slot(slot), "USD Texture");
+                        if self.default_anisotropy > 1{
+                            texture.set_anisotropy(&self.samplers, self.default_anisotropy);
+                        }
+                        let handle = ResourceHandle::new(ResourceType::Texture);
+                        self.textures.insert(handle.clone(), texture);
+                        handle
+                    }
+                    None => placeholder_handle.clone(),
+                };
+                self.assign_texture_to_material(&material_handle, &texture_handle, slot);
+            }
+
+            material_handles.push(material_handle);
+        }
+
+        info!("Loaded {} materials from {}", material_handles.len(), path);
+        material_handles
+    }
+
+    /// # Add Light
+    ///
+    /// Adds a light to the scene and returns a handle to it. Lights are shaded by any material
+    /// with the `lights` uniform from `get_lights_uniform_handle` assigned, such as `PBR_SHADER` materials
+    pub fn add_light(&mut self, light: Light) -> ResourceHandle{
+        let handle = self.light_manager.add_light(light);
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
+        handle
+    }
+
+    pub fn remove_light(&mut self, light_handle: &ResourceHandle) -> Option<Light>{
+        let light = self.light_manager.remove_light(light_handle);
+        if light.is_some(){
+            self.emit_resource_event(ResourceEventKind::Removed, light_handle);
+        }
+        light
+    }
+
+    pub fn get_light(&self, light_handle: &ResourceHandle) -> Option<&Light>{
+        self.light_manager.get_light(light_handle)
+    }
+
+    /// Changes made through the returned light are uploaded before the next frame
+    pub fn get_light_mut(&mut self, light_handle: &ResourceHandle) -> Option<&mut Light>{
+        self.light_manager.get_light_mut(light_handle)
+    }
+
+    /// Every light, in the order they were added
+    pub fn get_light_handles(&self) -> Vec<ResourceHandle>{
+        self.light_manager.get_light_handles()
+    }
+
+    /// # Get Lights Uniform Handle
+    ///
+    /// The `LightsUniform` holding every light, to assign to materials as `lights`
+    pub fn get_lights_uniform_handle(&self) -> ResourceHandle{
+        self.lights_uniform.clone()
+    }
+
+    /// # Set Camera
+    ///
+    /// Sets the view and projection matrices written into the `scene` uniform, which is bound
+    /// to every material whose shader declares it (see `SCENE_UNIFORM_WGSL`)
+    pub fn set_camera(&mut self, view: glam::Mat4, projection: glam::Mat4){
+        self.camera_view = view;
+        self.camera_projection = projection;
+    }
+
+    /// The camera's view and projection matrices
+    pub fn get_camera(&self) -> (glam::Mat4, glam::Mat4){
+        (self.camera_view, self.camera_projection)
+    }
+
+    /// # Get Scene Uniform Handle
+    ///
+    /// The `SceneUniform` the renderer keeps up to date. It's bound automatically,
+    /// so this is only needed to read it or to assign it under a different name
+    pub fn get_scene_uniform_handle(&self) -> ResourceHandle{
+        self.scene_uniform.clone()
+    }
+
+    /// # Get Globals Uniform Handle
+    ///
+    /// The `GlobalsUniform` the renderer keeps up to date. Like the scene uniform, it's bound
+    /// automatically to any shader declaring `globals` (see `GLOBALS_UNIFORM_WGSL`)
+    pub fn get_globals_uniform_handle(&self) -> ResourceHandle{
+        self.globals_uniform.clone()
+    }
+
+    /// # Add Camera
+    ///
+    /// Adds a camera drawn every frame while it's active, and returns a handle to it
+    pub fn add_camera(&mut self, camera: Camera) -> ResourceHandle{
+        if let CameraTarget::Texture(texture_handle) = &camera.target{
+            if !self.render_texture_depths.contains_key(texture_handle){
+                error!("Camera target {:?} isn't a render texture, see `create_render_texture`", texture_handle);
+                panic!("Camera target {:?} isn't a render texture", texture_handle);
+            }
+        }
+
+        let handle = ResourceHandle::new(ResourceType::Camera);
+        self.cameras.insert(handle.clone(), camera);
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
+        handle
+    }
+
+    pub fn remove_camera(&mut self, camera_handle: &ResourceHandle) -> Option<Camera>{
+        self.reflection_planes.remove(camera_handle);
+        let camera = self.cameras.remove(camera_handle);
+        if camera.is_some(){
+            self.emit_resource_event(ResourceEventKind::Removed, camera_handle);
+        }
+        camera
+    }
+
+    /// # Create Planar Reflection
+    ///
+    /// Adds a camera mirroring the default camera across the plane through `point` facing `normal`,
+    /// drawing into a new render texture of the given size. It's drawn before the other cameras, and
+    /// removed with `remove_camera`
+    pub fn create_planar_reflection(&mut self, point: glam::Vec3, normal: glam::Vec3, width: u32, height: u32) -> PlanarReflection{
+        let texture_handle = self.create_render_texture(width, height);
+        let camera = Camera::new(self.camera_view, self.camera_projection, CameraTarget::Texture(texture_handle.clone()))
+            .with_priority(i32::MIN);
+        let camera_handle = self.add_camera(camera);
+
+        self.reflection_planes.insert(camera_handle.clone(), planar_reflection::plane_from_point_normal(point, normal));
+        PlanarReflection::new(camera_handle, texture_handle)
+    }
 
-    textures: HashMap<ResourceHandle, Handle<Texture>>,
-    materials: HashMap<ResourceHandle, Handle<Material>>,
-    models: HashMap<ResourceHandle, Handle<Model>>,
-    uniforms: HashMap<ResourceHandle, Handle<UniformBuffer>>,
+    /// Moves a planar reflection's mirror plane
+    pub fn set_planar_reflection_plane(&mut self, reflection: &PlanarReflection, point: glam::Vec3, normal: glam::Vec3){
+        if let Some(plane) = self.reflection_planes.get_mut(reflection.get_camera()){
+            *plane = planar_reflection::plane_from_point_normal(point, normal);
+        }
+    }
 
-    shader_manager: ShaderManager,
-    pipeline_manager: PipelineManager,
+    /// Changes take effect the next frame
+    pub fn get_camera_mut(&mut self, camera_handle: &ResourceHandle) -> Option<&mut Camera>{
+        self.cameras.get_mut(camera_handle)
+    }
 
-    _device: Handle<wgpu::Device>,
-    _queue: Handle<wgpu::Queue>
-}
+    pub fn get_camera_handles(&self) -> Vec<ResourceHandle>{
+        self.cameras.keys().cloned().collect()
+    }
 
-impl ResourceManager{
-    pub(crate) fn new(device: Handle<wgpu::Device>, queue: Handle<wgpu::Queue>) -> Self{
-        Self{
-            meshes: HashMap::new(),
-            mesh_vertex_buffers: HashMap::new(),
-            mesh_index_buffers: HashMap::new(),
-            mesh_instance_buffers: HashMap::new(),
+    /// The active cameras, in the order they're drawn
+    pub(crate) fn get_active_cameras(&self) -> Vec<&Camera>{
+        let mut cameras: Vec<&Camera> = self.cameras.values().filter(|camera| camera.active).collect();
+        cameras.sort_by_key(|camera| camera.priority);
+        cameras
+    }
 
-            textures: HashMap::new(),
-            materials: HashMap::new(),
-            models: HashMap::new(),
-            uniforms: HashMap::new(),
+    /// # Create Render Texture
+    ///
+    /// Creates a texture cameras can draw into (see `CameraTarget::Texture`), which materials can
+    /// sample like any other texture. Cameras drawing into it leave out the models whose material samples it,
+    /// and can draw a chosen set of models with a layer mask (see `Model::set_render_layers`)
+    pub fn create_render_texture(&mut self, width: u32, height: u32) -> ResourceHandle{
+        let handle = ResourceHandle::new(ResourceType::Texture);
+        let (color, depth, msaa_color) = self.create_render_texture_attachments(width, height);
 
-            shader_manager: ShaderManager::new(device.clone()),
-            pipeline_manager: PipelineManager::new(),
-            
-            _device: device,
-            _queue: queue
+        self.textures.insert(handle.clone(), color);
+        self.render_texture_depths.insert(handle.clone(), depth);
+        if let Some(msaa_color) = msaa_color{
+            self.render_texture_msaa.insert(handle.clone(), msaa_color);
         }
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
+        handle
     }
 
-    pub(crate) fn update_model_transforms(&mut self){
-        let mut to_update = Vec::new();
-        for model in self.models.values().cloned(){
-            let transform = model.get_transform();
-            let transform_uniform_handle = model.get_transform_uniform_handle();
-            let transform_uniform = TransformUniform::new(&transform.clone());
-            
-            to_update.push((transform_uniform_handle, transform_uniform));
-        }
-        
-        for (handle, data) in to_update{
-            self.update_uniform_buffer(&handle, data);
+    // The scene pipelines draw into them, so they have to use the current target formats. When multisampling,
+    // the depth is multisampled too, and there's a multisampled colour attachment to resolve into the texture
+    fn create_render_texture_attachments(&self, width: u32, height: u32) -> (Texture, Texture, Option<Texture>){
+        let TargetFormats{ color, depth, samples } = self.target_formats;
+        let color_texture = Texture::create_screen_texture(&self._device, &self.samplers, width, height, color, "Render Texture");
+
+        if samples > 1{
+            (
+                color_texture,
+                Texture::create_multisampled_texture(&self._device, &self.samplers, width, height, depth, samples, "Render Texture Depth"),
+                Some(Texture::create_multisampled_texture(&self._device, &self.samplers, width, height, color, samples, "Render Texture Multisampled Color")),
+            )
+        }else{
+            (
+                color_texture,
+                Texture::create_screen_texture(&self._device, &self.samplers, width, height, depth, "Render Texture Depth"),
+                None,
+            )
         }
     }
-    
-    pub(crate) fn update_materials(&mut self){
-        for mut material in self.materials.values().cloned(){
-            material.update(self);
-        }
+
+    /// # Create Fullscreen Pass
+    ///
+    /// Compiles a fragment shader to draw over a full-screen triangle, see `FullscreenPass`. The source
+    /// defines `fragment_main`, taking the `PostVertexOutput` of `FULLSCREEN_TRIANGLE_WGSL`
+    /// (which is prepended to it), and may bind textures and uniforms in any group
+    pub fn create_fullscreen_pass(&mut self, fragment_source: &str) -> FullscreenPass{
+        let shader_handle = self.load_shader(&format!("{}\n{}", FULLSCREEN_TRIANGLE_WGSL, fragment_source));
+        let material_handle = self.create_material();
+        self.assign_shader_to_material(&material_handle, &shader_handle);
+
+        // No vertex buffers, and both sides drawn so the triangle's winding doesn't matter
+        let shader = self.shader_manager.get_shader(&shader_handle).unwrap();
+        let mesh_layout = MeshLayout::new(Vec::new(), wgpu::IndexFormat::Uint32);
+        let pipeline_handle = self.pipeline_manager.create_or_get_pipeline(&self._device, &mesh_layout, shader, shader_handle, self.target_formats, true);
+
+        FullscreenPass::new(material_handle, pipeline_handle)
     }
 
-    /// # Load Mesh
+    /// # Draw Fullscreen Pass
     ///
-    /// Loads a mesh from a file and returns a handle to it
-    pub fn load_mesh(&mut self, path: &str) -> ResourceHandle{
-        // Check if the path is an obj or fbx
-        let mesh = if path.ends_with(".obj"){
-            Mesh::load_obj(path)
-        }else if path.ends_with(".gltf") || path.ends_with(".glb"){
-            Mesh::load_gltf(path)
-        }else{
-            error!("Unsupported mesh format");
-            panic!("Unsupported mesh format")
+    /// Draws a fullscreen pass into a render texture from `create_render_texture`, straight away.
+    /// The pass' output is blended over what's in the texture, so it can be drawn into more than once
+    pub fn draw_fullscreen_pass(&self, pass: &FullscreenPass, target_handle: &ResourceHandle){
+        let Some((color, depth, msaa_color)) = self.get_render_texture(target_handle) else {
+            error!("Fullscreen passes can only draw into render textures, {:?} isn't one", target_handle);
+            return;
         };
+        let (Some(pipeline), Some(mut material)) = (self.get_pipeline(pass.get_pipeline()), self.get_material(pass.get_material())) else {
+            error!("Fullscreen pass' pipeline or material not found");
+            return;
+        };
+        material.generate_bind_groups(self);
+
+        let mut encoder = self._device.create_command_encoder(&wgpu::CommandEncoderDescriptor{
+            label: Some("Fullscreen Pass Encoder")
+        });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: Some("Fullscreen Pass"),
+                // When multisampling, the multisampled attachment still holds what was last resolved into the texture
+                color_attachments: &[
+                    Some(screen_attachments::color_attachment(color.get_texture_view(), msaa_color, wgpu::Operations{
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store
+                    }))
+                ],
+                // The pipeline shares the scene's depth setup, so it needs a depth attachment, but nothing reads it
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment{
+                    view: depth.get_texture_view(),
+                    depth_ops: Some(wgpu::Operations{
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard
+                    }),
+                    stencil_ops: depth.get_format().has_stencil_aspect().then_some(wgpu::Operations{
+                        load: wgpu::LoadOp::Clear(0),
+                        store: wgpu::StoreOp::Discard
+                    })
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(pipeline.get_render_pipeline());
+            material.bind_material(&mut render_pass, self.get_objects_buffer().get_version());
+            render_pass.draw(0..3, 0..1);
+        }
 
-        let handle = ResourceHandle::new(ResourceType::Mesh);
+        self._queue.submit(std::iter::once(encoder.finish()));
+    }
 
-        // We need to create a buffer for each submesh
-        let mut vertex_buffers = Vec::new();
-        let mut index_buffers = Vec::new();
+    /// The colour and depth attachments of a render texture, and the multisampled colour attachment it's resolved from when multisampling
+    pub(crate) fn get_render_texture(&self, handle: &ResourceHandle) -> Option<(&Texture, &Texture, Option<&Texture>)>{
+        Some((self.textures.borrow(handle)?, self.render_texture_depths.get(handle)?, self.render_texture_msaa.get(handle)))
+    }
 
-        for sub_mesh in mesh.get_sub_meshes(){
-            let vertices = sub_mesh.get_vertices();
-            let indices = sub_mesh.get_indices();
+    pub(crate) fn get_scene_uniform_ref(&self) -> &ResourceHandle{
+        &self.scene_uniform
+    }
 
-            let vertex_buffer = Buffer::create_buffer_from_type(&self._device,
-                                                                vertices, BufferType::Vertex);
-            let index_buffer = Buffer::create_buffer_from_type(&self._device,
-                                                               &indices.as_slice(), BufferType::Index);
+    pub(crate) fn get_globals_uniform_ref(&self) -> &ResourceHandle{
+        &self.globals_uniform
+    }
 
-            vertex_buffers.push(vertex_buffer);
-            index_buffers.push(index_buffer);
+    /// # Load glTF Lights
+    ///
+    /// Adds every `KHR_lights_punctual` light placed in a glTF file's scenes, with the
+    /// transform of the node it's attached to. Returns the handles of the added lights
+    pub fn load_gltf_lights(&mut self, path: &str) -> Vec<ResourceHandle>{
+        let (document, _, _) = gltf::import(path).unwrap_or_else(|e| {
+            error!("Failed to load gltf file {}: {}", path, e);
+            panic!("Failed to load gltf file {}: {}", path, e)
+        });
+
+        let mut handles = Vec::new();
+        for scene in document.scenes(){
+            for node in scene.nodes(){
+                self.add_gltf_node_lights(&node, glam::Mat4::IDENTITY, &mut handles);
+            }
         }
 
-        self.meshes.insert(handle.clone(), mesh);
+        info!("Loaded {} lights from {}", handles.len(), path);
+        handles
+    }
 
-        self.mesh_vertex_buffers.insert(handle.clone(), vertex_buffers);
-        self.mesh_index_buffers.insert(handle.clone(), index_buffers);
+    fn add_gltf_node_lights(&mut self, node: &gltf::Node, parent: glam::Mat4, handles: &mut Vec<ResourceHandle>){
+        let world = parent * glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+
+        if let Some(gltf_light) = node.light(){
+            let color = glam::Vec3::from(gltf_light.color());
+            let intensity = gltf_light.intensity();
+            let mut light = match gltf_light.kind(){
+                gltf::khr_lights_punctual::Kind::Directional => Light::directional(color, intensity),
+                gltf::khr_lights_punctual::Kind::Point => Light::point(color, intensity),
+                gltf::khr_lights_punctual::Kind::Spot{ inner_cone_angle, outer_cone_angle } =>
+                    Light::spot(color, intensity, inner_cone_angle, outer_cone_angle),
+            };
+            if let Some(range) = gltf_light.range(){
+                light = light.with_range(range);
+            }
+
+            // Lights ignore scale, only where they are and which way they point matters
+            let (_, rotation, position) = world.to_scale_rotation_translation();
+            let mut transform = Transform::new();
+            transform.set_position(position);
+            transform.set_rotation(rotation);
+
+            debug_log!(Subsystem::Resources, "glTF light {:?} {:?} at {:?}", gltf_light.name(), light.get_light_type(), position);
+            handles.push(self.add_light(light.with_transform(transform)));
+        }
 
-        handle
+        for child in node.children(){
+            self.add_gltf_node_lights(&child, world, handles);
+        }
     }
 
-    /// # Load Texture
+    /// # Create Material Instance
     ///
-    /// Loads a texture from a file and returns a handle to it
-    pub fn load_texture(&mut self, path: &str) -> ResourceHandle{
-        let texture = Texture::load_from_file(&self._device, &self._queue, path);
-        let handle = ResourceHandle::new(ResourceType::Texture);
+    /// Creates a lightweight instance of an existing (template) material and returns a handle to it
+    ///
+    /// The instance shares the template's shader, pipeline and bind group layouts. Textures and
+    /// uniforms assigned to the instance override the template's, and anything not overridden is
+    /// read from the template. Bind groups with no overrides are shared rather than duplicated,
+    /// so many similar materials can be created cheaply.
+    ///
+    /// The template must have a shader assigned before instances are created
+    pub fn create_material_instance(&mut self, template_handle: &ResourceHandle) -> ResourceHandle{
+        let template = self.materials.borrow(template_handle).unwrap_or_else(||{
+            error!("Template material not found");
+            panic!("Template material not found")
+        });
+
+        if template.is_instance(){
+            error!("Cannot create an instance of a material instance");
+            panic!("Cannot create an instance of a material instance");
+        }
+
+        if template.get_shader_bindings().is_none(){
+            error!("Template material has no shader assigned");
+            panic!("Template material has no shader assigned");
+        }
+
+        let material = Material::new_instance(template_handle.clone(), template);
+        let handle = ResourceHandle::new(ResourceType::Material);
+        self.material_templates.insert(template_handle.clone());
 
-        self.textures.insert(handle.clone(), Handle::new(texture));
+        self.materials.insert(handle.clone(), material);
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
 
         handle
     }
 
-    /// # Create Material
+    /// # Clone Material
     ///
-    /// Creates a new material and returns a handle to it
-    pub fn create_material(&mut self) -> ResourceHandle{
-        let material = Material::new(self._device.clone(), self._queue.clone());
+    /// Creates a new material with the same shader, texture and uniform assignments as an existing one,
+    /// so variants can be made from it by changing just what differs. The uniform buffers are shared
+    /// rather than copied, so to give the clone e.g a different tint, assign it a uniform of its own
+    pub fn clone_material(&mut self, material_handle: &ResourceHandle) -> ResourceHandle{
+        let material = self.materials.borrow(material_handle).unwrap_or_else(||{
+            error!("Material to clone not found");
+            panic!("Material to clone not found")
+        }).duplicate();
         let handle = ResourceHandle::new(ResourceType::Material);
 
-        self.materials.insert(handle.clone(), Handle::new(material));
+        self.materials.insert(handle.clone(), material);
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
 
         handle
     }
 
+    /// # Get Memory Usage
+    ///
+    /// Returns the approximate GPU memory used by each type of resource
+    pub fn get_memory_usage(&self) -> MemoryUsage{
+        let buffer_bytes = |buffers: &HashMap<ResourceHandle, Vec<Buffer>>| -> u64{
+            buffers.values().flatten().map(|buffer| buffer.get_size() as u64).sum()
+        };
+
+        MemoryUsage{
+            mesh_bytes: buffer_bytes(&self.mesh_vertex_buffers) + buffer_bytes(&self.mesh_index_buffers)
+                + self.dynamic_meshes.values().map(|mesh| mesh.get_memory_size()).sum::<u64>()
+                + self.gpu_skins.values().map(|skin| skin.get_memory_size()).sum::<u64>(),
+            texture_bytes: self.textures.values().map(|texture| texture.get_memory_size()).sum(),
+            uniform_bytes: self.uniforms.values().map(|uniform| uniform.get_size() as u64).sum(),
+        }
+    }
+
+    /// # Set Resource Name
+    ///
+    /// Names a resource for the `iter_*` queries, e.g for an in-app resource browser.
+    /// Meshes and textures loaded from files are named after their path, and glTF materials after their name
+    pub fn set_resource_name(&mut self, handle: &ResourceHandle, name: &str){
+        self.resource_names.insert(handle.clone(), name.to_string());
+    }
+
+    pub fn get_resource_name(&self, handle: &ResourceHandle) -> Option<&str>{
+        self.resource_names.get(handle).map(|name| name.as_str())
+    }
+
+    /// # Iter Models
+    ///
+    /// Read-only summaries of every loaded model
+    pub fn iter_models(&self) -> impl Iterator<Item = ModelInfo> + '_{
+        self.models.get_handles().into_iter().filter_map(move |handle| {
+            let model = self.models.borrow(&handle)?;
+            Some(ModelInfo{
+                name: self.resource_names.get(&handle).cloned(),
+                mesh: model.get_mesh().clone(),
+                material: model.get_material().clone(),
+                object_index: model.get_object_index(),
+                is_static: model.is_static(),
+                handle,
+            })
+        })
+    }
+
+    /// # Iter Materials
+    ///
+    /// Read-only summaries of every loaded material, including material instances
+    pub fn iter_materials(&self) -> impl Iterator<Item = MaterialInfo> + '_{
+        self.materials.get_handles().into_iter().filter_map(move |handle| {
+            let material = self.materials.borrow(&handle)?;
+            let sorted_names = |names: Vec<&String>| -> Vec<String>{
+                let mut names: Vec<String> = names.into_iter().cloned().collect();
+                names.sort();
+                names
+            };
+            Some(MaterialInfo{
+                name: self.resource_names.get(&handle).cloned(),
+                shader: material.get_shader(),
+                textures: sorted_names(material.get_textures().keys().collect()),
+                uniforms: sorted_names(material.get_uniforms().keys().collect()),
+                is_instance: material.is_instance(),
+                handle,
+            })
+        })
+    }
+
+    /// # Iter Meshes
+    ///
+    /// Read-only summaries of every loaded mesh
+    pub fn iter_meshes(&self) -> impl Iterator<Item = MeshInfo> + '_{
+        self.meshes.get_handles().into_iter().filter_map(move |handle| {
+            let mesh = self.meshes.borrow(&handle)?;
+            let gpu_bytes = [&self.mesh_vertex_buffers, &self.mesh_index_buffers].iter()
+                .filter_map(|buffers| buffers.get(&handle))
+                .flatten()
+                .map(|buffer| buffer.get_size() as u64)
+                .sum();
+            Some(MeshInfo{
+                name: self.resource_names.get(&handle).cloned(),
+                sub_mesh_count: mesh.get_sub_meshes().len(),
+                triangle_count: mesh.get_triangle_count(),
+                gpu_bytes,
+                handle,
+            })
+        })
+    }
+
+    /// # Iter Textures
+    ///
+    /// Read-only summaries of every loaded texture
+    pub fn iter_textures(&self) -> impl Iterator<Item = TextureInfo> + '_{
+        self.textures.get_handles().into_iter().filter_map(move |handle| {
+            let texture = self.textures.borrow(&handle)?;
+            let size = texture.get_texture_size();
+            Some(TextureInfo{
+                name: self.resource_names.get(&handle).cloned(),
+                width: size.width,
+                height: size.height,
+                mip_level_count: texture.get_mip_level_count(),
+                format: texture.get_format(),
+                gpu_bytes: texture.get_memory_size(),
+                handle,
+            })
+        })
+    }
+
+    /// # Validate Material
+    ///
+    /// Cross-checks a material's textures and uniforms against its shader's reflected bindings,
+    /// and returns a list of missing, extra and mistyped bindings. An empty list means the
+    /// material is ready to render
+    pub fn validate_material(&self, material_handle: &ResourceHandle) -> Vec<MaterialDiagnostic>{
+        let material = self.materials.borrow(material_handle).unwrap_or_else(||{
+            error!("Material not found");
+            panic!("Material not found")
+        });
+
+        material.validate(self)
+    }
+
     /// # Assign Texture to Material
     ///
     /// Assigns a texture to a material
@@ -168,11 +2125,47 @@ impl ResourceManager{
     /// The sampler is assumed to be called <strong>`texture_name`</strong>_sampler,
     /// where `texture_name` is the name of the texture
     pub fn assign_texture_to_material(&mut self, material_handle: &ResourceHandle, texture_handle: &ResourceHandle, name: &str){
+        // Catch the common mistake of loading a data texture (e.g a normal map) as colour
+        if let Some(texture) = self.textures.borrow(texture_handle){
+            let expected = ColorSpace::from_gltf_slot(name);
+            if expected == ColorSpace::Linear && texture.get_color_space() != expected{
+                warn!("Texture assigned to `{}` was loaded as {:?}, but the slot expects {:?} data", name, texture.get_color_space(), expected);
+            }
+        }
+
         let material = self.materials.get_mut(material_handle).unwrap();
 
         material.add_texture(name, texture_handle.clone());
     }
 
+    /// # Swap Material Texture
+    ///
+    /// Replaces a texture a material is drawn with at runtime, e.g for skins or live texture switching.
+    /// Unlike `assign_texture_to_material`, only the bind group holding the texture is rebuilt, straight away
+    pub fn swap_material_texture(&mut self, material_handle: &ResourceHandle, name: &str, texture_handle: &ResourceHandle){
+        if self.textures.borrow(texture_handle).is_none(){
+            warn!("Swapping in a texture that doesn't exist for `{}`, the missing texture will be drawn instead", name);
+        }
+
+        let Some(mut material) = self.get_material(material_handle) else {
+            warn!("Tried to swap a texture on a material that doesn't exist: {:?}", material_handle);
+            return;
+        };
+
+        material.swap_texture(name, texture_handle.clone(), self);
+    }
+
+    /// # Set Material Dynamic Offsets
+    ///
+    /// Sets the byte offsets a material's buffers with dynamic offsets are bound at in a group, see
+    /// `Material::set_dynamic_offsets`. Their layout has to be set with `load_shader_with_layouts`
+    pub fn set_material_dynamic_offsets(&mut self, material_handle: &ResourceHandle, group: u32, offsets: Vec<u32>){
+        match self.materials.get_mut(material_handle){
+            Some(material) => material.set_dynamic_offsets(group, offsets),
+            None => warn!("Tried to set dynamic offsets on a material that doesn't exist: {:?}", material_handle),
+        }
+    }
+
     /// # Assign Shader to Material
     ///
     /// Assigns a shader to a material
@@ -184,23 +2177,100 @@ impl ResourceManager{
         }else{
             error!("Shader bindings not found");
         }
+
+        // Check any uniforms already assigned match what the shader expects
+        let uniforms = material.get_uniforms().clone();
+        for (name, uniform_handle) in uniforms.iter(){
+            self.validate_uniform_layout(shader_handle, name, uniform_handle);
+        }
     }
 
 
+    /// # Set Material Double Sided
+    ///
+    /// Sets whether models created with the material from now on are double sided.
+    /// Existing models keep their setting, see `Model::set_double_sided`
+    pub fn set_material_double_sided(&mut self, material_handle: &ResourceHandle, double_sided: bool){
+        match self.materials.get_mut(material_handle){
+            Some(material) => material.set_double_sided(double_sided),
+            None => warn!("Tried to set a material that doesn't exist as double sided: {:?}", material_handle),
+        }
+    }
+
     /// # Assign Uniform to Material
     ///
-    /// Assigns a uniform buffer to a material
+    /// Assigns a uniform buffer to a material. A warning is logged if the uniform's size doesn't match the
+    /// struct the shader declares for it. Member offsets aren't checked, so a struct of the right size with
+    /// its members in a different order still binds
     pub fn assign_uniform_to_material(&mut self, material_handle: &ResourceHandle, uniform_handle: &ResourceHandle, name: &str){
         let material = self.materials.get_mut(material_handle).unwrap();
 
         material.add_uniform(name, uniform_handle.clone());
+
+        if material.get_shader_bindings().is_some(){
+            let shader_handle = material.get_shader();
+            self.validate_uniform_layout(&shader_handle, name, uniform_handle);
+        }
+    }
+
+    // Compares the uniform data against the struct the shader declares for the binding, so a badly
+    // padded struct is reported up-front instead of rendering garbage
+    fn validate_uniform_layout(&self, shader_handle: &ResourceHandle, name: &str, uniform_handle: &ResourceHandle){
+        let shader = match self.shader_manager.get_shader(shader_handle){
+            Some(shader) => shader,
+            None => return
+        };
+
+        let uniform = match self.uniforms.get(uniform_handle){
+            Some(uniform) => uniform,
+            None => return
+        };
+
+        // The material binds a zeroed fallback in its place, see `Material::generate_bind_groups`
+        if let Err(e) = shader.validate_uniform_layout(name, uniform){
+            warn!("{}", e);
+        }
     }
 
     /// # Load Shader
     ///
     /// Loads a shader from a file and returns a handle to it
     pub fn load_shader(&mut self, path: &str) -> ResourceHandle{
-        self.shader_manager.create_shader(path)
+        let handle = self.shader_manager.create_shader(path);
+        self.emit_resource_event(ResourceEventKind::Loaded, &handle);
+        handle
+    }
+
+    /// # Load Shader With Layouts
+    ///
+    /// Same as `load_shader`, with explicit bind group layouts for the groups reflection can't get right,
+    /// e.g uniforms bound with dynamic offsets (see `set_material_dynamic_offsets`) or non-filtering samplers.
+    /// Each replaces the reflected layout of its group, and has to list every binding the shader declares in it
+    pub fn load_shader_with_layouts(&mut self, path: &str, layouts: &[(u32, wgpu::BindGroupLayoutDescriptor)]) -> ResourceHandle{
+        let handle = self.shader_manager.create_shader_with_layouts(path, layouts);
+        self.emit_resource_event(ResourceEventKind::Loaded, &handle);
+        handle
+    }
+
+    /// # Get Builtin Shader
+    ///
+    /// Returns a handle to one of the shaders bundled with the renderer, by name: `"unlit"`, `"lit"`,
+    /// `"pbr"` or `"skinned"` (see `BUILTIN_SHADER_NAMES`). Each is compiled once and shared.
+    /// Unknown names get `ERROR_SHADER`
+    pub fn get_builtin_shader(&mut self, name: &str) -> ResourceHandle{
+        if let Some(handle) = self.builtin_shaders.get(name){
+            return handle.clone();
+        }
+
+        let source = builtin_shaders::builtin_shader_source(name).unwrap_or_else(|| {
+            warn!("Unknown built-in shader `{}`, expected one of {:?}. Using the error shader instead", name, builtin_shaders::BUILTIN_SHADER_NAMES);
+            builtin_shaders::ERROR_SHADER
+        });
+
+        let handle = self.load_shader(source);
+        self.builtin_shaders.insert(name.to_string(), handle.clone());
+        self.resource_names.insert(handle.clone(), name.to_string());
+        handle
     }
 
     /// # Create Model
@@ -209,34 +2279,114 @@ impl ResourceManager{
     pub fn create_model(&mut self, mesh_handle: &ResourceHandle, material_handle: &ResourceHandle, transform: Transform) -> ResourceHandle{
         let handle = ResourceHandle::new(ResourceType::Model);
 
-        // Create the uniform buffer for the model transform
-        let transform_handle = self.create_uniform_buffer::<TransformUniform>(transform.clone().into());
-
-        let model = Model::new(mesh_handle.clone(), material_handle.clone(), transform.clone(), transform_handle.clone());
+        let object_index = self.free_object_indices.pop().unwrap_or_else(|| {
+            self.object_count += 1;
+            self.object_count - 1
+        });
+        let mut model = Model::new(mesh_handle.clone(), material_handle.clone(), transform.clone(), object_index);
+        model.set_double_sided(self.materials.borrow(material_handle).is_some_and(|material| material.is_double_sided()));
 
-        self.models.insert(handle.clone(), Handle::new(model));
+        self.models.insert(handle.clone(), model);
+        // Written to its slot, which may still hold a removed model's data, at the start of the next frame
+        self.dirty_models.insert(handle.clone());
+        self.emit_resource_event(ResourceEventKind::Created, &handle);
 
         handle
     }
 
+    /// # Remove Model
+    ///
+    /// Removes a model from the scene, along with its animation and property overrides, and frees
+    /// its slot in the `objects` buffer for the next model. With `remove_uniforms`, its transform and
+    /// lightmap uniforms are dropped too; keep them if a material still binds them.
+    /// Returns false if the model doesn't exist
+    pub fn remove_model(&mut self, handle: &ResourceHandle, remove_uniforms: bool) -> bool{
+        let model = match self.models.remove(handle){
+            Some(model) => model,
+            None => {
+                warn!("Tried to remove a model that doesn't exist: {:?}", handle);
+                return false;
+            }
+        };
+
+        self.tweens.remove(handle);
+        self.resource_names.remove(handle);
+        if let Some(property_material) = model.get_property_material(){
+            self.materials.remove(property_material);
+        }
+        for uniform_handle in model.get_property_uniforms().values(){
+            self.uniforms.remove(uniform_handle);
+        }
+        if remove_uniforms{
+            for uniform_handle in [model.get_transform_uniform_handle(), model.get_lightmap_uniform_handle()].into_iter().flatten(){
+                self.uniforms.remove(&uniform_handle);
+            }
+        }
+
+        self.free_object_indices.push(model.get_object_index());
+        self.emit_resource_event(ResourceEventKind::Removed, handle);
+        true
+    }
+
+    /// # Clear Scene
+    ///
+    /// Removes every model and the uniforms they own, e.g between levels. Meshes, materials,
+    /// textures and lights are kept, so they can be reused by the next scene
+    pub fn clear_scene(&mut self){
+        let handles = self.models.get_handles();
+        info!("Clearing {} models from the scene", handles.len());
+        for handle in handles.iter(){
+            self.remove_model(handle, true);
+        }
+
+        // Nothing is left to reuse slots from, so start the objects buffer over
+        self.object_count = 0;
+        self.object_data.clear();
+        self.dirty_models.clear();
+        self.free_object_indices.clear();
+    }
+
     /// # Create Pipeline
     ///
-    /// Creates a new pipeline and returns a handle to it
+    /// Creates a new pipeline and returns a handle to it. The double sided variant
+    /// double sided models are drawn with is made when the first one is drawn
     pub fn create_pipeline(&mut self, mesh_handle: &ResourceHandle, material_handle: &ResourceHandle) -> ResourceHandle{
-        let mesh = self.meshes.get(mesh_handle).unwrap();
-        let material = self.materials.get(material_handle).unwrap();
+        self.create_pipeline_variant(mesh_handle, material_handle, false)
+    }
+
+    fn create_pipeline_variant(&mut self, mesh_handle: &ResourceHandle, material_handle: &ResourceHandle, double_sided: bool) -> ResourceHandle{
+        let mesh = self.meshes.borrow(mesh_handle).unwrap();
+        let material = self.materials.borrow(material_handle).unwrap();
         let shader = self.shader_manager.get_shader(&material.get_shader()).unwrap_or_else(
             || panic!("Shader not found")
         );
 
-        let bind_group_layouts = shader.get_bind_group_layouts();
+        // Every location the vertex shader reads has to come from the mesh, e.g a shader sampling
+        // `uv1` needs a mesh with a second UV set
+        let attributes: Vec<u32> = mesh.get_layout().get_vertex_buffer_layouts().iter()
+            .flat_map(|layout| layout.attributes.iter().map(|attribute| attribute.shader_location))
+            .collect();
+        // Extra UV sets only come from meshes imported with them, so say where they come from
+        for set in 1..MAX_UV_SETS as u32{
+            if shader.uses_uv_set(set) && !attributes.contains(&uv_set_location(set)){
+                error!("The shader samples UV set {}, but the mesh has no `TEXCOORD_{}`. Load a mesh with that many UV sets, e.g from glTF", set, set);
+                panic!("The shader samples UV set {}, but the mesh has no `TEXCOORD_{}`", set, set);
+            }
+        }
+        for input in shader.get_vertex_inputs(){
+            if !attributes.contains(&input.location){
+                error!("Vertex input `{}` at location {} isn't provided by the mesh's vertex layout", input.name, input.location);
+                panic!("Vertex input `{}` at location {} isn't provided by the mesh's vertex layout", input.name, input.location);
+            }
+        }
 
         let pipeline_handle = self.pipeline_manager.create_or_get_pipeline(
             &self._device,
             mesh.get_layout(),
-            bind_group_layouts,
             shader,
-            material.get_shader().clone()
+            material.get_shader().clone(),
+            self.target_formats,
+            double_sided
         );
 
         pipeline_handle
@@ -259,44 +2409,169 @@ impl ResourceManager{
     /// # Update Uniform Buffer
     ///
     /// Updates the data in a uniform buffer
-    pub fn update_uniform_buffer<T: AsBytes + 'static>(&mut self, handle: &ResourceHandle, data: T){
-        let buffer = self.uniforms.get_mut(handle).unwrap();
+    ///
+    /// The data must be the same size as the data the buffer was created with,
+    /// otherwise an error is returned and the buffer is left untouched
+    pub fn update_uniform_buffer<T: AsBytes + 'static>(&mut self, handle: &ResourceHandle, data: T) -> anyhow::Result<()>{
+        let buffer = self.uniforms.get_mut(handle)
+            .ok_or_else(|| anyhow::anyhow!("Uniform buffer not found: {:?}", handle))?;
 
-        buffer.set_data(data);
+        buffer.set_data(data)?;
 
         buffer.update(&self._queue);
+
+        Ok(())
+    }
+
+    /// # Read Buffer
+    ///
+    /// Starts copying a buffer back from the GPU, once the work submitted so far has finished. Takes a
+    /// uniform buffer, or a mesh to read the vertex buffers of each sub mesh one after the other,
+    /// e.g to check the output of GPU skinning. The data arrives a frame or two later, see `PendingReadback`
+    pub fn read_buffer(&self, handle: &ResourceHandle) -> anyhow::Result<PendingReadback<Vec<u8>>>{
+        if let Some(uniform) = self.uniforms.get(handle){
+            return Ok(readback::read_buffers(&self._device, &self._queue, &[uniform.get_buffer()]));
+        }
+
+        let vertex_buffers = self.get_mesh_vertex_buffers(handle)
+            .ok_or_else(|| anyhow::anyhow!("No uniform buffer or uploaded mesh found: {:?}", handle))?;
+        let buffers: Vec<&wgpu::Buffer> = vertex_buffers.iter().map(|buffer| &buffer.buffer).collect();
+        Ok(readback::read_buffers(&self._device, &self._queue, &buffers))
+    }
+
+    /// Same as `read_buffer`, but waits for the data, e.g in tests
+    pub fn read_buffer_blocking(&self, handle: &ResourceHandle) -> anyhow::Result<Vec<u8>>{
+        self.read_buffer(handle)?.wait(&self._device)
+    }
+
+    /// # Read Texture
+    ///
+    /// Starts copying a texture or render texture back from the GPU as an RGBA image, once the work
+    /// submitted so far has finished. Only the full size mip level is read, and sRGB textures stay sRGB
+    /// encoded. The image arrives a frame or two later, see `PendingReadback`
+    pub fn read_texture(&self, handle: &ResourceHandle) -> anyhow::Result<PendingReadback<image::RgbaImage>>{
+        let texture = self.textures.borrow(handle)
+            .ok_or_else(|| anyhow::anyhow!("Texture not found: {:?}", handle))?;
+
+        readback::read_texture(&self._device, &self._queue, texture.get_raw_texture())
+    }
+
+    /// Same as `read_texture`, but waits for the image, e.g in tests
+    pub fn read_texture_blocking(&self, handle: &ResourceHandle) -> anyhow::Result<image::RgbaImage>{
+        self.read_texture(handle)?.wait(&self._device)
     }
 }
 
 impl ResourceManager{
     // Getters are pub(crate) because we don't want the user to access the internal resources directly
 
-    pub(crate) fn get_mesh(&self, handle: &ResourceHandle) -> Option<&Mesh>{
-        self.meshes.get(handle)
+    /// # Get Mesh
+    ///
+    /// CPU-side copy of a mesh's data
+    pub fn get_mesh(&self, handle: &ResourceHandle) -> Option<&Mesh>{
+        self.meshes.borrow(handle)
+    }
+
+    /// # Get Mesh Mut
+    ///
+    /// Mutable access to a mesh's CPU-side data, for deformation, editing or procedural changes.
+    /// Changes aren't visible until `update_mesh` is called
+    pub fn get_mesh_mut(&mut self, handle: &ResourceHandle) -> Option<&mut Mesh>{
+        self.meshes.get_mut(handle)
     }
 
     pub(crate) fn get_mesh_vertex_buffers(&self, handle: &ResourceHandle) -> Option<&Vec<Buffer>>{
-        self.mesh_vertex_buffers.get(handle)
+        match self.dynamic_meshes.get(handle){
+            Some(dynamic_mesh) => Some(dynamic_mesh.get_vertex_buffers()),
+            None => self.mesh_vertex_buffers.get(handle),
+        }
     }
 
     pub(crate) fn get_mesh_index_buffers(&self, handle: &ResourceHandle) -> Option<&Vec<Buffer>>{
-        self.mesh_index_buffers.get(handle)
+        match self.dynamic_meshes.get(handle){
+            Some(dynamic_mesh) => Some(dynamic_mesh.get_index_buffers()),
+            None => self.mesh_index_buffers.get(handle),
+        }
     }
 
     pub(crate) fn get_texture(&self, handle: &ResourceHandle) -> Option<Handle<Texture>>{
-        self.textures.get(handle).cloned()
+        self.textures.get(handle)
     }
 
     pub(crate) fn borrow_texture(&self, handle: &ResourceHandle) -> &Texture{
-        &self.textures.get(handle).unwrap()
+        self.textures.borrow(handle).unwrap()
     }
 
     pub(crate) fn get_material(&self, handle: &ResourceHandle) -> Option<Handle<Material>>{
-        self.materials.get(handle).cloned()
+        self.materials.get(handle)
     }
 
     pub(crate) fn borrow_material(&self, handle: &ResourceHandle) -> &Material{
-        &self.materials.get(handle).unwrap()
+        self.materials.borrow(handle).unwrap()
+    }
+
+    /// # Set Depth Format
+    ///
+    /// Sets the depth format pipelines are built for, rebuilding any existing pipelines when it changes.
+    /// Has to match the depth attachment they're drawn with
+    pub(crate) fn set_depth_format(&mut self, format: wgpu::TextureFormat){
+        self.set_target_formats(TargetFormats{ depth: format, ..self.target_formats });
+    }
+
+    pub fn get_depth_format(&self) -> wgpu::TextureFormat{
+        self.target_formats.depth
+    }
+
+    /// # Set Color Format
+    ///
+    /// Sets the colour format pipelines are built for, rebuilding any existing pipelines when it changes.
+    /// Has to match the target the scene is drawn into
+    pub(crate) fn set_color_format(&mut self, format: wgpu::TextureFormat){
+        self.set_target_formats(TargetFormats{ color: format, ..self.target_formats });
+    }
+
+    pub fn get_color_format(&self) -> wgpu::TextureFormat{
+        self.target_formats.color
+    }
+
+    /// # Set Sample Count
+    ///
+    /// Sets the samples per pixel pipelines are built for, rebuilding any existing pipelines when it changes.
+    /// Has to match the attachments they're drawn with, so render textures are recreated with it too
+    pub(crate) fn set_sample_count(&mut self, samples: u32){
+        self.set_target_formats(TargetFormats{ samples, ..self.target_formats });
+    }
+
+    pub fn get_sample_count(&self) -> u32{
+        self.target_formats.samples
+    }
+
+    fn set_target_formats(&mut self, formats: TargetFormats){
+        if self.target_formats == formats{
+            return;
+        }
+
+        self.target_formats = formats;
+        self.pipeline_manager.rebuild_pipelines(&self._device, formats, &self.shader_manager);
+
+        // Render textures are drawn with the pipelines, so have to match them
+        let render_textures: Vec<ResourceHandle> = self.render_texture_depths.keys().cloned().collect();
+        for handle in render_textures{
+            let size = self.textures.borrow(&handle).unwrap().get_texture_size();
+            let (color, depth, msaa_color) = self.create_render_texture_attachments(size.width, size.height);
+            *self.textures.get_mut(&handle).unwrap() = color;
+            self.render_texture_depths.insert(handle.clone(), depth);
+            match msaa_color{
+                Some(msaa_color) => self.render_texture_msaa.insert(handle.clone(), msaa_color),
+                None => self.render_texture_msaa.remove(&handle),
+            };
+
+            for material in self.materials.values_mut(){
+                if material.uses_texture(&handle){
+                    material.mark_needs_regen();
+                }
+            }
+        }
     }
 
     pub(crate) fn get_shader(&self, handle: &ResourceHandle) -> Option<&Shader>{
@@ -307,6 +2582,10 @@ impl ResourceManager{
         self.pipeline_manager.get_pipeline(handle)
     }
 
+    pub(crate) fn get_pipeline_mesh_layout(&self, handle: &ResourceHandle) -> Option<&MeshLayout>{
+        self.pipeline_manager.get_mesh_layout(handle)
+    }
+
     pub(crate) fn get_uniform_buffer(&self, handle: &ResourceHandle) -> Option<Handle<UniformBuffer>>{
         self.uniforms.get(handle).cloned()
     }
@@ -324,15 +2603,15 @@ impl ResourceManager{
     }
 
     pub(crate) fn get_all_textures(&self) -> Vec<Handle<Texture>>{
-        self.textures.values().cloned().collect()
+        self.textures.get_all()
     }
 
     pub(crate) fn get_all_materials(&self) -> Vec<Handle<Material>>{
-        self.materials.values().cloned().collect()
+        self.materials.get_all()
     }
 
     pub(crate) fn get_all_models(&self) -> Vec<Handle<Model>>{
-        self.models.values().cloned().collect()
+        self.models.get_all()
     }
 
     pub(crate) fn get_all_pipelines(&self) -> Vec<&Pipeline>{
@@ -343,19 +2622,23 @@ impl ResourceManager{
 // Getters for all handles
 impl ResourceManager{
     pub(crate) fn get_all_mesh_handles(&self) -> Vec<ResourceHandle>{
-        self.meshes.keys().cloned().collect()
+        self.meshes.get_handles()
     }
 
     pub(crate) fn get_all_texture_handles(&self) -> Vec<ResourceHandle>{
-        self.textures.keys().cloned().collect()
+        self.textures.get_handles()
     }
 
     pub(crate) fn get_all_material_handles(&self) -> Vec<ResourceHandle>{
-        self.materials.keys().cloned().collect()
+        self.materials.get_handles()
+    }
+
+    pub(crate) fn get_all_uniform_handles(&self) -> Vec<ResourceHandle>{
+        self.uniforms.keys().cloned().collect()
     }
 
     pub(crate) fn get_all_model_handles(&self) -> Vec<ResourceHandle>{
-        self.models.keys().cloned().collect()
+        self.models.get_handles()
     }
 
     pub(crate) fn get_all_shader_handles(&self) -> Vec<ResourceHandle>{
@@ -370,19 +2653,150 @@ impl ResourceManager{
 /* Model functions */
 impl ResourceManager{
     pub(crate) fn get_model(&self, handle: &ResourceHandle) -> Option<Handle<Model>>{
-        self.models.get(handle).cloned()
+        self.models.get(handle)
     }
 
     pub(crate) fn borrow_model(&self, handle: &ResourceHandle) -> &Model{
-        &self.models.get(handle).unwrap()
+        self.models.borrow(handle).unwrap()
+    }
+
+    pub fn get_model_transform(&self, handle: &ResourceHandle) -> &Transform{
+        self.models.borrow(handle).unwrap().get_transform()
+    }
+
+    /// # Set Model Transform
+    ///
+    /// Replaces a model's transform, stopping any animation it had. Only the objects data and
+    /// transform uniforms of models whose transform was set are rewritten at the start of the next frame
+    pub fn set_model_transform(&mut self, handle: &ResourceHandle, transform: Transform){
+        let Some(model) = self.models.get_mut(handle) else {
+            error!("Model not found: {:?}", handle);
+            return;
+        };
+
+        model.set_transform(transform);
+        self.tweens.remove(handle);
+        self.dirty_models.insert(handle.clone());
+    }
+
+    /// # Set Transforms
+    ///
+    /// Sets the transforms of many models at once, e.g from a physics step. See `set_model_transform`
+    pub fn set_transforms(&mut self, transforms: &[(ResourceHandle, Transform)]){
+        for (handle, transform) in transforms{
+            self.set_model_transform(handle, transform.clone());
+        }
+    }
+
+    /// # Animate Transform
+    ///
+    /// Moves a model from its current transform to the target over `duration` seconds,
+    /// advanced by the renderer each frame. Replaces any animation the model already had.
+    /// Setting its transform through `get_model_mut` while it's animating is overwritten on the next frame,
+    /// use `stop_animation` or `set_model_transform` instead
+    pub fn animate_transform(&mut self, handle: &ResourceHandle, target: Transform, duration: f32, easing: Easing){
+        let Some(model) = self.models.borrow(handle) else {
+            error!("Model not found: {:?}", handle);
+            panic!("Model not found: {:?}", handle);
+        };
+
+        let start = model.get_transform().clone();
+        self.tweens.insert(handle.clone(), TransformTween::new(start, target, duration, easing));
+    }
+
+    /// Stops a model's animation where it is, if it has one
+    pub fn stop_animation(&mut self, handle: &ResourceHandle){
+        self.tweens.remove(handle);
+    }
+
+    pub fn is_animating(&self, handle: &ResourceHandle) -> bool{
+        self.tweens.contains_key(handle)
+    }
+
+    /// Advances every animation by `delta` seconds, removing the ones that finished
+    pub(crate) fn update_tweens(&mut self, delta: f32){
+        let models = &mut self.models;
+        let dirty_models = &mut self.dirty_models;
+        self.tweens.retain(|handle, tween|{
+            let Some(model) = models.get_mut(handle) else { return false };
+            model.set_transform(tween.advance(delta));
+            dirty_models.insert(handle.clone());
+
+            !tween.is_finished()
+        });
+    }
+
+    /// # Get Model Mut
+    ///
+    /// Gets a model to change, e.g to set per-model material properties with `Model::set_property`.
+    /// Its objects data is rewritten at the start of the next frame
+    pub fn get_model_mut(&mut self, handle: &ResourceHandle) -> Option<&mut Model>{
+        let model = self.models.get_mut(handle)?;
+        self.dirty_models.insert(handle.clone());
+        Some(model)
+    }
+
+    /// # Get Model Transform Uniform Handle
+    ///
+    /// Gets a uniform holding the model's transform, for shaders with a `transform` uniform
+    /// rather than reading the `objects` storage buffer. It's created on first use
+    pub fn get_model_transform_uniform_handle(&mut self, handle: &ResourceHandle) -> ResourceHandle{
+        let mut model = self.models.get(handle).unwrap();
+        if let Some(transform_uniform_handle) = model.get_transform_uniform_handle(){
+            return transform_uniform_handle;
+        }
+
+        let transform_uniform_handle = self.create_uniform_buffer(TransformUniform::new(model.get_transform()));
+        model.set_transform_uniform_handle(transform_uniform_handle.clone());
+        transform_uniform_handle
+    }
+
+    pub(crate) fn get_device(&self) -> &wgpu::Device{
+        &self._device
     }
 
-    pub fn get_model_transform(&self, handle: &ResourceHandle) -> Handle<Transform>{
-        self.models.get(handle).unwrap().get_transform()
+    pub(crate) fn get_queue(&self) -> &wgpu::Queue{
+        &self._queue
+    }
+
+    pub(crate) fn get_sampler_cache(&self) -> Handle<SamplerCache>{
+        self.samplers.clone()
+    }
+
+    pub(crate) fn get_objects_buffer(&self) -> &StorageBuffer{
+        &self.objects
+    }
+
+    /// # Set Model Lightmap
+    ///
+    /// Sets the region of a lightmap a model samples, and how strongly. Returns the model's
+    /// `LightmapUniform`, to assign as the `lightmap` uniform of its `PBR_SHADER` material
+    /// alongside a texture in the `PBR_LIGHTMAP_SLOT`
+    pub fn set_model_lightmap(&mut self, handle: &ResourceHandle, scale: glam::Vec2, offset: glam::Vec2, intensity: f32) -> ResourceHandle{
+        let uniform = LightmapUniform::new(scale, offset, intensity);
+        let existing = self.models.borrow(handle).unwrap_or_else(|| {
+            error!("Model not found");
+            panic!("Model not found")
+        }).get_lightmap_uniform_handle();
+
+        match existing{
+            Some(uniform_handle) => {
+                self.update_uniform_buffer(&uniform_handle, uniform).unwrap_or_else(|e| {
+                    error!("Failed to update lightmap uniform: {}", e);
+                    panic!("Failed to update lightmap uniform: {}", e)
+                });
+                uniform_handle
+            }
+            None => {
+                let uniform_handle = self.create_uniform_buffer(uniform);
+                self.models.get_mut(handle).unwrap().set_lightmap_uniform_handle(uniform_handle.clone());
+                uniform_handle
+            }
+        }
     }
 
-    pub fn get_model_transform_uniform_handle(&self, handle: &ResourceHandle) -> ResourceHandle{
-        self.models.get(handle).unwrap().get_transform_uniform_handle()
+    pub fn get_model_lightmap_uniform_handle(&self, handle: &ResourceHandle) -> Option<ResourceHandle>{
+        self.models.borrow(handle).unwrap().get_lightmap_uniform_handle()
     }
 }
 
@@ -0,0 +1,84 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use log::info;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceType;
+use crate::types::mesh::Mesh;
+use crate::types::texture::ColorSpace;
+
+pub(crate) enum ResourceCommand{
+    AddMesh{ handle: ResourceHandle, mesh: Mesh },
+    AddTexture{ handle: ResourceHandle, image: image::RgbaImage, color_space: ColorSpace },
+    // A texture loaded with `ResourceManager::load_texture_async` that couldn't be decoded
+    TextureFailed{ handle: ResourceHandle, error: String },
+}
+
+/// # Resource Queue
+///
+/// Lets worker threads create resources without holding the resource manager. Files are
+/// loaded and decoded on the calling thread, and the finished resources are sent over a
+/// lock-free channel which the resource manager drains at the start of each frame.
+///
+/// The handles are returned straight away, but the resources only exist once they've been
+/// drained, which `ResourceManager::is_resource_ready` reports
+#[derive(Clone)]
+pub struct ResourceQueue{
+    sender: Sender<ResourceCommand>,
+}
+
+impl ResourceQueue{
+    pub(crate) fn new() -> (Self, Receiver<ResourceCommand>){
+        let (sender, receiver) = channel();
+        (Self{ sender }, receiver)
+    }
+
+    fn send(&self, command: ResourceCommand){
+        // The receiver only goes away with the resource manager, after which nothing is drawn anyway
+        let _ = self.sender.send(command);
+    }
+
+    /// # Add Mesh
+    ///
+    /// Queues a mesh built on this thread. Its buffers are created the first time it's drawn
+    pub fn add_mesh(&self, mesh: Mesh) -> ResourceHandle{
+        let handle = ResourceHandle::new(ResourceType::Mesh);
+        self.send(ResourceCommand::AddMesh{ handle: handle.clone(), mesh });
+
+        handle
+    }
+
+    /// # Load Mesh
+    ///
+    /// Loads a mesh from a file on this thread and queues it
+    pub fn load_mesh(&self, path: &str) -> ResourceHandle{
+        self.add_mesh(Mesh::load(path))
+    }
+
+    /// # Add Texture
+    ///
+    /// Queues an image decoded on this thread, to be uploaded as a texture
+    pub fn add_texture(&self, image: image::RgbaImage, color_space: ColorSpace) -> ResourceHandle{
+        let handle = ResourceHandle::new(ResourceType::Texture);
+        self.send(ResourceCommand::AddTexture{ handle: handle.clone(), image, color_space });
+
+        handle
+    }
+
+    // Decodes a texture into a handle that already holds a placeholder, replacing it once drained
+    pub(crate) fn decode_texture_into(&self, handle: ResourceHandle, path: &str, color_space: ColorSpace){
+        info!("Decoding texture from file: {:?}", path);
+        match image::open(path){
+            Ok(image) => self.send(ResourceCommand::AddTexture{ handle, image: image.to_rgba8(), color_space }),
+            Err(e) => self.send(ResourceCommand::TextureFailed{ handle, error: format!("{}: {}", path, e) }),
+        }
+    }
+
+    /// # Load Texture
+    ///
+    /// Decodes a texture from a file on this thread and queues it for upload
+    pub fn load_texture(&self, path: &str, color_space: ColorSpace) -> anyhow::Result<ResourceHandle>{
+        info!("Decoding texture from file: {:?}", path);
+        let image = image::open(path)?.to_rgba8();
+
+        Ok(self.add_texture(image, color_space))
+    }
+}
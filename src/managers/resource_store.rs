@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use log::error;
+use crate::utils::handle::Handle;
+use crate::managers::resource_handle::ResourceHandle;
+
+/// # Resource Store
+///
+/// Storage for one type of resource, e.g meshes or models. Each resource is kept in a `Handle`,
+/// so a copy taken with `get` stays valid after the resource is removed
+pub(crate) struct ResourceStore<T>{
+    resources: HashMap<ResourceHandle, Handle<T>>,
+}
+
+impl<T> ResourceStore<T>{
+    pub(crate) fn new() -> Self{
+        Self{
+            resources: HashMap::new(),
+        }
+    }
+
+    /// Adds a resource under a new handle. Handles can't be reused, as the resource they
+    /// held may still be shared
+    pub(crate) fn insert(&mut self, handle: ResourceHandle, resource: T){
+        if self.resources.contains_key(&handle){
+            error!("Resource {:?} is already stored", handle);
+            panic!("Resource {:?} is already stored", handle);
+        }
+        self.resources.insert(handle, Handle::new(resource));
+    }
+
+    pub(crate) fn remove(&mut self, handle: &ResourceHandle) -> Option<Handle<T>>{
+        self.resources.remove(handle)
+    }
+
+    pub(crate) fn contains(&self, handle: &ResourceHandle) -> bool{
+        self.resources.contains_key(handle)
+    }
+
+    /// Gets a shared handle to the resource, which stays valid even if it's removed from the store
+    pub(crate) fn get(&self, handle: &ResourceHandle) -> Option<Handle<T>>{
+        self.resources.get(handle).cloned()
+    }
+
+    pub(crate) fn borrow(&self, handle: &ResourceHandle) -> Option<&T>{
+        self.resources.get(handle).map(|resource| &**resource)
+    }
+
+    pub(crate) fn get_mut(&mut self, handle: &ResourceHandle) -> Option<&mut T>{
+        self.resources.get_mut(handle).map(|resource| &mut **resource)
+    }
+
+    pub(crate) fn get_handles(&self) -> Vec<ResourceHandle>{
+        self.resources.keys().cloned().collect()
+    }
+
+    pub(crate) fn get_all(&self) -> Vec<Handle<T>>{
+        self.resources.values().cloned().collect()
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &T>{
+        self.resources.values().map(|resource| &**resource)
+    }
+
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut T>{
+        self.resources.values_mut().map(|resource| &mut **resource)
+    }
+}
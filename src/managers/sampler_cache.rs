@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::debug::{debug_log, Subsystem};
+use crate::utils::handle::Handle;
+
+/// # Sampler Key
+///
+/// Everything a texture's sampler can differ by. Textures with equal keys share a sampler
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerKey{
+    pub address_mode: wgpu::AddressMode,
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    pub anisotropy: u16,
+    pub compare: Option<wgpu::CompareFunction>,
+}
+
+impl SamplerKey{
+    /// Clamped, trilinear filtering with the given anisotropy level. Anisotropic
+    /// filtering requires all filter modes to be linear, which these are
+    pub fn linear(anisotropy: u16) -> Self{
+        Self{
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            anisotropy,
+            compare: None,
+        }
+    }
+
+    /// Clamped, unfiltered sampling, for bindings declared as non-filtering
+    pub fn nearest() -> Self{
+        Self{
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Self::linear(1)
+        }
+    }
+
+    /// For sampling depth textures with a comparison, e.g shadow maps
+    pub fn comparison() -> Self{
+        Self{
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Self::linear(1)
+        }
+    }
+}
+
+/// # Sampler Cache
+///
+/// Creates each distinct sampler once and hands out shared handles to it, so hundreds of
+/// textures don't create hundreds of identical `wgpu::Sampler`s. Samplers live as long as the cache
+pub struct SamplerCache{
+    samplers: Mutex<HashMap<SamplerKey, Handle<wgpu::Sampler>>>,
+
+    _device: Handle<wgpu::Device>,
+}
+
+impl SamplerCache{
+    pub fn new(device: Handle<wgpu::Device>) -> Self{
+        Self{
+            samplers: Mutex::new(HashMap::new()),
+
+            _device: device,
+        }
+    }
+
+    pub fn get(&self, key: SamplerKey) -> Handle<wgpu::Sampler>{
+        let mut samplers = self.samplers.lock().unwrap();
+        samplers.entry(key).or_insert_with(||{
+            debug_log!(Subsystem::Resources, "Creating sampler: {:?}", key);
+            Handle::new(self._device.create_sampler(&wgpu::SamplerDescriptor{
+                label: Some("Texture Sampler"),
+                address_mode_u: key.address_mode,
+                address_mode_v: key.address_mode,
+                address_mode_w: key.address_mode,
+                mag_filter: key.mag_filter,
+                min_filter: key.min_filter,
+                mipmap_filter: key.mipmap_filter,
+                anisotropy_clamp: key.anisotropy,
+                compare: key.compare,
+                ..Default::default()
+            }))
+        }).clone()
+    }
+}
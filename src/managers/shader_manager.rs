@@ -1,7 +1,9 @@
 use std::collections::HashMap;
+use log::warn;
 use crate::managers::resource_handle::ResourceHandle;
 use crate::managers::resource_manager::ResourceType;
 use crate::types::shader::Shader;
+use crate::types::builtin_shaders::ERROR_SHADER;
 use crate::utils::handle::Handle;
 use crate::utils::shader_reflect::Binding;
 
@@ -19,19 +21,46 @@ impl ShaderManager{
         }
     }
 
+    /// Shaders that fail to parse or validate are replaced by `ERROR_SHADER`, with a warning
     pub fn create_shader(&mut self, source: &str) -> ResourceHandle{
+        self.create_shader_with_layouts(source, &[])
+    }
+
+    /// Same as `create_shader`, replacing the reflected layouts of the given groups, see `Shader::set_bind_group_layout`.
+    /// The error shader keeps its own layouts
+    pub fn create_shader_with_layouts(&mut self, source: &str, layouts: &[(u32, wgpu::BindGroupLayoutDescriptor)]) -> ResourceHandle{
         let handle = ResourceHandle::new(
             ResourceType::Shader
         );
+
+        let (source, layouts) = match Self::check_source(source){
+            Ok(()) => (source, layouts),
+            Err(e) => {
+                warn!("Shader failed to compile, using the error shader instead: {}", e);
+                (ERROR_SHADER, &[][..])
+            }
+        };
         
         let mut shader = Shader::new(self._device.clone(), source);
 
         shader.generate_bindings();
+        for (group, descriptor) in layouts.iter(){
+            shader.set_bind_group_layout(*group, descriptor);
+        }
         
         self.shaders.insert(handle.clone(), shader);
         handle
     }
 
+    // Parses and validates the WGSL with naga up-front, as wgpu treats an invalid module as a fatal error
+    fn check_source(source: &str) -> Result<(), String>{
+        let module = naga::front::wgsl::parse_str(source).map_err(|e| e.emit_to_string(source))?;
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+            .validate(&module)
+            .map_err(|e| e.emit_to_string(source))?;
+        Ok(())
+    }
+
     pub fn get_shader(&self, handle: &ResourceHandle) -> Option<&Shader>{
         self.shaders.get(handle)
     }
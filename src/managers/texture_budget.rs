@@ -0,0 +1,98 @@
+use std::collections::{HashMap, HashSet};
+use crate::managers::resource_handle::ResourceHandle;
+use crate::types::texture::ColorSpace;
+
+/// # Texture Budget
+///
+/// Tracks when each texture was last used, so textures can be evicted least recently used
+/// first when all of them together go over a VRAM budget. Textures loaded from files are
+/// evicted to a placeholder and reloaded once they're used again, and streamed textures
+/// drop back to their low mips. Pinned textures are never evicted
+pub(crate) struct TextureBudget{
+    budget_bytes: Option<u64>,
+    pinned: HashSet<ResourceHandle>,
+
+    // Where file textures came from, so they can be reloaded after being evicted
+    sources: HashMap<ResourceHandle, (String, ColorSpace)>,
+    evicted: HashSet<ResourceHandle>,
+
+    last_used: HashMap<ResourceHandle, u64>,
+    frame: u64,
+}
+
+impl TextureBudget{
+    pub fn new() -> Self{
+        Self{
+            budget_bytes: None,
+            pinned: HashSet::new(),
+
+            sources: HashMap::new(),
+            evicted: HashSet::new(),
+
+            last_used: HashMap::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn set_budget(&mut self, budget_bytes: Option<u64>){
+        self.budget_bytes = budget_bytes;
+    }
+
+    pub fn get_budget(&self) -> Option<u64>{
+        self.budget_bytes
+    }
+
+    pub fn set_pinned(&mut self, handle: &ResourceHandle, pinned: bool){
+        if pinned{
+            self.pinned.insert(handle.clone());
+        }else{
+            self.pinned.remove(handle);
+        }
+    }
+
+    pub fn is_pinned(&self, handle: &ResourceHandle) -> bool{
+        self.pinned.contains(handle)
+    }
+
+    pub fn add_source(&mut self, handle: &ResourceHandle, path: &str, color_space: ColorSpace){
+        self.sources.insert(handle.clone(), (path.to_string(), color_space));
+    }
+
+    pub fn get_source(&self, handle: &ResourceHandle) -> Option<&(String, ColorSpace)>{
+        self.sources.get(handle)
+    }
+
+    pub fn is_evicted(&self, handle: &ResourceHandle) -> bool{
+        self.evicted.contains(handle)
+    }
+
+    pub fn set_evicted(&mut self, handle: &ResourceHandle, evicted: bool){
+        if evicted{
+            self.evicted.insert(handle.clone());
+        }else{
+            self.evicted.remove(handle);
+        }
+    }
+
+    /// Starts a new frame, marking `used` as used in it
+    pub fn begin_frame(&mut self, used: impl Iterator<Item = ResourceHandle>){
+        self.frame += 1;
+        for handle in used{
+            self.last_used.insert(handle, self.frame);
+        }
+    }
+
+    pub fn is_used_this_frame(&self, handle: &ResourceHandle) -> bool{
+        self.last_used.get(handle) == Some(&self.frame)
+    }
+
+    /// The textures that may be evicted, least recently used first. Pinned textures and
+    /// anything used this frame are left out
+    pub fn get_eviction_candidates(&self, handles: impl Iterator<Item = ResourceHandle>) -> Vec<ResourceHandle>{
+        let mut candidates: Vec<ResourceHandle> = handles
+            .filter(|handle| !self.pinned.contains(handle) && !self.is_used_this_frame(handle))
+            .collect();
+        candidates.sort_by_key(|handle| self.last_used.get(handle).copied().unwrap_or(0));
+        candidates
+    }
+}
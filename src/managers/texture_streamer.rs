@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use image::RgbaImage;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::types::texture::ColorSpace;
+
+// Mips at or below this size are always resident, so there's always something to sample
+const RESIDENT_BASE_SIZE: u32 = 64;
+// Textures that haven't been requested for this many frames fall back to their base mip
+const REQUEST_TIMEOUT_FRAMES: u64 = 120;
+
+struct StreamedTexture{
+    // Full CPU-side mip chain, largest first
+    mips: Vec<RgbaImage>,
+    color_space: ColorSpace,
+
+    // Finest mip currently on the GPU
+    resident_mip: usize,
+    // Finest mip needed, from the most recent requests
+    requested_mip: usize,
+    // Coarsest mip we stream from, which is never evicted
+    base_mip: usize,
+
+    last_request_frame: u64,
+}
+
+impl StreamedTexture{
+    fn get_bytes_from(&self, mip: usize) -> u64{
+        self.mips[mip..].iter()
+            .map(|mip| mip.as_raw().len() as u64)
+            .sum()
+    }
+}
+
+/// # Residency Change
+///
+/// A streamed texture whose resident mips changed, and needs recreating from `resident_mip`
+pub(crate) struct ResidencyChange{
+    pub handle: ResourceHandle,
+    pub resident_mip: usize,
+}
+
+/// # Texture Streamer
+///
+/// Keeps the CPU-side mip chains of streamed textures, and decides which mips
+/// should be on the GPU based on how large each texture appears on screen.
+///
+/// Higher mips are uploaded a few at a time each frame, and textures that are no longer
+/// needed drop back to their low mips, keeping the total under a VRAM budget
+pub(crate) struct TextureStreamer{
+    textures: HashMap<ResourceHandle, StreamedTexture>,
+
+    budget_bytes: u64,
+    uploads_per_frame: usize,
+
+    frame: u64,
+}
+
+impl TextureStreamer{
+    pub fn new() -> Self{
+        Self{
+            textures: HashMap::new(),
+
+            budget_bytes: 256 * 1024 * 1024,
+            uploads_per_frame: 2,
+
+            frame: 0,
+        }
+    }
+
+    /// Starts streaming a texture, returning the mip that should be resident initially
+    pub fn add(&mut self, handle: ResourceHandle, mips: Vec<RgbaImage>, color_space: ColorSpace) -> usize{
+        let base_mip = mips.iter()
+            .position(|mip| mip.width().max(mip.height()) <= RESIDENT_BASE_SIZE)
+            .unwrap_or(mips.len() - 1);
+
+        self.textures.insert(handle, StreamedTexture{
+            mips,
+            color_space,
+
+            resident_mip: base_mip,
+            requested_mip: base_mip,
+            base_mip,
+
+            last_request_frame: 0,
+        });
+
+        base_mip
+    }
+
+    pub fn contains(&self, handle: &ResourceHandle) -> bool{
+        self.textures.contains_key(handle)
+    }
+
+    pub fn get_mips(&self, handle: &ResourceHandle, from: usize) -> Option<(&[RgbaImage], ColorSpace)>{
+        self.textures.get(handle).map(|texture| (&texture.mips[from..], texture.color_space))
+    }
+
+    /// # Request
+    ///
+    /// Requests enough detail for the texture to cover `screen_size` pixels.
+    /// Requests made during the same frame keep the most detailed one
+    pub fn request(&mut self, handle: &ResourceHandle, screen_size: f32){
+        let frame = self.frame;
+        let texture = match self.textures.get_mut(handle){
+            Some(texture) => texture,
+            None => return,
+        };
+
+        let full_size = texture.mips[0].width().max(texture.mips[0].height()) as f32;
+        let mip = (full_size / screen_size.max(1.0)).log2().floor().max(0.0) as usize;
+        let mip = mip.min(texture.base_mip);
+
+        if texture.last_request_frame == frame{
+            texture.requested_mip = texture.requested_mip.min(mip);
+        } else {
+            texture.requested_mip = mip;
+            texture.last_request_frame = frame;
+        }
+    }
+
+    pub fn set_budget(&mut self, budget_bytes: u64){
+        self.budget_bytes = budget_bytes;
+    }
+
+    pub fn get_budget(&self) -> u64{
+        self.budget_bytes
+    }
+
+    pub fn set_uploads_per_frame(&mut self, uploads_per_frame: usize){
+        self.uploads_per_frame = uploads_per_frame.max(1);
+    }
+
+    /// Bytes used by all the streamed textures' resident mips
+    pub fn get_resident_bytes(&self) -> u64{
+        self.textures.values()
+            .map(|texture| texture.get_bytes_from(texture.resident_mip))
+            .sum()
+    }
+
+    /// Whether the texture was requested recently enough to want more than its base mip
+    pub fn is_requested(&self, handle: &ResourceHandle) -> bool{
+        self.textures.get(handle).is_some_and(|texture| Self::get_target_mip(texture, self.frame) < texture.base_mip)
+    }
+
+    /// Drops a texture back to its base mip, returning the change if it had more than that resident
+    pub fn evict(&mut self, handle: &ResourceHandle) -> Option<ResidencyChange>{
+        let texture = self.textures.get_mut(handle)?;
+        if texture.resident_mip >= texture.base_mip{
+            return None;
+        }
+
+        texture.resident_mip = texture.base_mip;
+        texture.requested_mip = texture.base_mip;
+        Some(ResidencyChange{
+            handle: handle.clone(),
+            resident_mip: texture.base_mip,
+        })
+    }
+
+    /// # Update
+    ///
+    /// Advances a frame, returning the textures whose residency changed.
+    /// Unneeded mips are evicted straight away, while missing mips are added
+    /// one level at a time, most starved texture first, as long as they fit the budget
+    pub fn update(&mut self) -> Vec<ResidencyChange>{
+        self.frame += 1;
+
+        let mut changes = Vec::new();
+        let mut resident_bytes = self.get_resident_bytes();
+
+        // Evict first, to make room for anything that needs more detail
+        for (handle, texture) in self.textures.iter_mut(){
+            let target = Self::get_target_mip(texture, self.frame);
+            if texture.resident_mip < target{
+                resident_bytes -= texture.get_bytes_from(texture.resident_mip) - texture.get_bytes_from(target);
+                texture.resident_mip = target;
+
+                changes.push(ResidencyChange{
+                    handle: handle.clone(),
+                    resident_mip: target,
+                });
+            }
+        }
+
+        let mut starved: Vec<(&ResourceHandle, &mut StreamedTexture)> = self.textures.iter_mut()
+            .filter(|(_, texture)| texture.resident_mip > Self::get_target_mip(texture, self.frame))
+            .collect();
+        starved.sort_by_key(|(_, texture)| std::cmp::Reverse(texture.resident_mip - texture.requested_mip));
+
+        for (handle, texture) in starved.into_iter().take(self.uploads_per_frame){
+            let next_mip = texture.resident_mip - 1;
+            let extra_bytes = texture.mips[next_mip].as_raw().len() as u64;
+            if resident_bytes + extra_bytes > self.budget_bytes{
+                continue;
+            }
+
+            resident_bytes += extra_bytes;
+            texture.resident_mip = next_mip;
+
+            // Replace any eviction of the same texture made above
+            changes.retain(|change| &change.handle != handle);
+            changes.push(ResidencyChange{
+                handle: handle.clone(),
+                resident_mip: next_mip,
+            });
+        }
+
+        changes
+    }
+
+    fn get_target_mip(texture: &StreamedTexture, frame: u64) -> usize{
+        if frame.saturating_sub(texture.last_request_frame) > REQUEST_TIMEOUT_FRAMES{
+            texture.base_mip
+        } else {
+            texture.requested_mip
+        }
+    }
+}
+
+/// # Screen Size At Distance
+///
+/// Approximate height in pixels of an object `world_size` units tall, `distance` units away
+/// from a perspective camera
+pub(crate) fn screen_size_at_distance(world_size: f32, distance: f32, fov_y: f32, viewport_height: f32) -> f32{
+    let visible_height = 2.0 * distance.max(f32::EPSILON) * (fov_y * 0.5).tan();
+    world_size / visible_height * viewport_height
+}
@@ -0,0 +1,114 @@
+//! # Math
+//!
+//! Projection helpers that follow wgpu's clip space conventions: right-handed view
+//! space looking down -Z, and clip space depth in the `0..1` range (not OpenGL's `-1..1`).
+//!
+//! Reverse-Z projections map the near plane to depth 1 and the far plane to depth 0,
+//! which spreads floating point depth precision far more evenly. They must be paired with
+//! a `Greater` depth comparison and a depth clear value of 0, see [`DepthConvention`].
+
+/// # Depth Convention
+///
+/// How a projection maps view depth to clip space depth, and so which depth
+/// comparison and clear value the render pass and pipeline need to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DepthConvention{
+    /// Near plane at depth 0, far plane at depth 1
+    Standard,
+    /// Near plane at depth 1, far plane at depth 0
+    ReverseZ,
+}
+
+impl DepthConvention{
+    pub fn depth_compare(&self) -> wgpu::CompareFunction{
+        match self{
+            DepthConvention::Standard => wgpu::CompareFunction::Less,
+            DepthConvention::ReverseZ => wgpu::CompareFunction::Greater,
+        }
+    }
+
+    pub fn depth_clear_value(&self) -> f32{
+        match self{
+            DepthConvention::Standard => 1.0,
+            DepthConvention::ReverseZ => 0.0,
+        }
+    }
+}
+
+/// # Projection
+///
+/// A perspective projection. A `far` of `None` gives an infinite far plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projection{
+    /// Vertical field of view, in radians
+    pub fov_y: f32,
+    /// Width divided by height
+    pub aspect: f32,
+    pub near: f32,
+    pub far: Option<f32>,
+    pub depth_convention: DepthConvention,
+}
+
+impl Projection{
+    /// A standard perspective projection with a finite far plane
+    pub fn new(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self{
+        Self{
+            fov_y,
+            aspect,
+            near,
+            far: Some(far),
+            depth_convention: DepthConvention::Standard,
+        }
+    }
+
+    /// Uses an infinite far plane
+    pub fn with_infinite_far(mut self) -> Self{
+        self.far = None;
+        self
+    }
+
+    /// Uses reverse-Z depth
+    pub fn with_reverse_z(mut self) -> Self{
+        self.depth_convention = DepthConvention::ReverseZ;
+        self
+    }
+
+    pub fn set_aspect(&mut self, aspect: f32){
+        self.aspect = aspect;
+    }
+
+    pub fn get_matrix(&self) -> glam::Mat4{
+        match (self.far, self.depth_convention){
+            (Some(far), DepthConvention::Standard) => perspective(self.fov_y, self.aspect, self.near, far),
+            (Some(far), DepthConvention::ReverseZ) => perspective_reverse_z(self.fov_y, self.aspect, self.near, far),
+            (None, DepthConvention::Standard) => perspective_infinite(self.fov_y, self.aspect, self.near),
+            (None, DepthConvention::ReverseZ) => perspective_infinite_reverse_z(self.fov_y, self.aspect, self.near),
+        }
+    }
+}
+
+/// Standard perspective projection, mapping `near..far` to depth `0..1`
+pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> glam::Mat4{
+    glam::Mat4::perspective_rh(fov_y, aspect, near, far)
+}
+
+/// Reverse-Z perspective projection, mapping `near..far` to depth `1..0`
+pub fn perspective_reverse_z(fov_y: f32, aspect: f32, near: f32, far: f32) -> glam::Mat4{
+    // Swapping the planes reverses the depth range
+    glam::Mat4::perspective_rh(fov_y, aspect, far, near)
+}
+
+/// Perspective projection with an infinite far plane, mapping `near..infinity` to depth `0..1`
+pub fn perspective_infinite(fov_y: f32, aspect: f32, near: f32) -> glam::Mat4{
+    glam::Mat4::perspective_infinite_rh(fov_y, aspect, near)
+}
+
+/// Reverse-Z perspective projection with an infinite far plane, mapping `near..infinity` to depth `1..0`
+pub fn perspective_infinite_reverse_z(fov_y: f32, aspect: f32, near: f32) -> glam::Mat4{
+    glam::Mat4::perspective_infinite_reverse_rh(fov_y, aspect, near)
+}
+
+/// Orthographic projection, mapping `near..far` to depth `0..1`
+pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> glam::Mat4{
+    glam::Mat4::orthographic_rh(left, right, bottom, top, near, far)
+}
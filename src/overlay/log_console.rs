@@ -0,0 +1,36 @@
+use crate::logging::get_recent_logs;
+use crate::overlay::text_overlay::OverlayPanel;
+
+// Lines shown at once, and the longest message shown before it's cut off
+const MAX_LINES: usize = 16;
+const MAX_LINE_LENGTH: usize = 120;
+
+/// # Log Console
+///
+/// Builds an overlay panel with the most recent log records, newest at the bottom
+pub(crate) struct LogConsole;
+
+impl LogConsole{
+    /// Builds the panel anchored to the bottom left of a target of the given size
+    pub(crate) fn build_panel(target_size: [u32; 2]) -> OverlayPanel{
+        let logs = get_recent_logs();
+
+        let mut lines = vec!["Log".to_string(), String::new()];
+        if logs.is_empty(){
+            lines.push("Nothing logged yet. Logs are only captured by `init_default_logging` or a `LogCapture`".to_string());
+        }
+        for entry in logs.iter().skip(logs.len().saturating_sub(MAX_LINES)){
+            let mut line = format!("{:<5} {}: {}", entry.level, entry.target, entry.message.replace('\n', " "));
+            if line.len() > MAX_LINE_LENGTH{
+                let end = (0..=MAX_LINE_LENGTH - 3).rev().find(|&end| line.is_char_boundary(end)).unwrap_or(0);
+                line.truncate(end);
+                line.push_str("...");
+            }
+            lines.push(line);
+        }
+
+        let mut panel = OverlayPanel::new([10.0, 10.0], lines);
+        panel.position[1] = (target_size[1] as f32 - panel.get_size()[1] - 10.0).max(10.0);
+        panel
+    }
+}
@@ -0,0 +1,4 @@
+pub mod text_overlay;
+pub mod resource_inspector;
+pub mod stats_overlay;
+pub mod log_console;
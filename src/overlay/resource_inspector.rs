@@ -0,0 +1,61 @@
+use crate::managers::resource_manager::ResourceManager;
+use crate::overlay::text_overlay::OverlayPanel;
+
+/// # Resource Inspector
+///
+/// Builds an overlay panel listing the resources currently loaded in the
+/// resource manager, their memory usage, and any misconfigured materials
+pub(crate) struct ResourceInspector;
+
+impl ResourceInspector{
+    pub(crate) fn build_panel(resource_manager: &ResourceManager, position: [f32; 2]) -> OverlayPanel{
+        let memory = resource_manager.get_memory_usage();
+
+        let mut lines = vec![
+            "Resource Inspector".to_string(),
+            String::new(),
+            format!("Meshes:    {:>5}  {:>10}", resource_manager.get_all_mesh_handles().len(), format_bytes(memory.mesh_bytes)),
+            format!("Textures:  {:>5}  {:>10}", resource_manager.get_all_texture_handles().len(), format_bytes(memory.texture_bytes)),
+            format!("Uniforms:  {:>5}  {:>10}", resource_manager.get_all_uniform_handles().len(), format_bytes(memory.uniform_bytes)),
+            format!("Materials: {:>5}", resource_manager.get_all_material_handles().len()),
+            format!("Models:    {:>5}", resource_manager.get_all_model_handles().len()),
+            format!("Shaders:   {:>5}", resource_manager.get_all_shader_handles().len()),
+            format!("Pipelines: {:>5}", resource_manager.get_all_pipeline_handles().len()),
+            format!("Total GPU memory: {}", format_bytes(memory.total_bytes())),
+        ];
+
+        // Flag any materials that won't render correctly
+        let mut problems = Vec::new();
+        for material_handle in resource_manager.get_all_material_handles(){
+            let errors = resource_manager.validate_material(&material_handle).into_iter()
+                .filter(|diagnostic| diagnostic.is_error())
+                .count();
+
+            if errors > 0{
+                problems.push(format!("  Material {:016x}: {} errors", material_handle.get_uuid(), errors));
+            }
+        }
+
+        if !problems.is_empty(){
+            lines.push(String::new());
+            lines.push("Misconfigured materials:".to_string());
+            lines.extend(problems);
+        }
+
+        OverlayPanel::new(position, lines)
+    }
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String{
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+
+    let bytes = bytes as f64;
+    if bytes >= MIB{
+        format!("{:.1} MiB", bytes / MIB)
+    }else if bytes >= KIB{
+        format!("{:.1} KiB", bytes / KIB)
+    }else{
+        format!("{} B", bytes)
+    }
+}
@@ -0,0 +1,34 @@
+use crate::overlay::resource_inspector::format_bytes;
+use crate::overlay::text_overlay::OverlayPanel;
+use crate::stats::{FrameStats, MemoryUsage};
+
+/// # Stats Overlay
+///
+/// Builds a small HUD panel with the frame rate, CPU time per stage, GPU time, draw counts and GPU memory
+pub(crate) struct StatsOverlay;
+
+impl StatsOverlay{
+    pub(crate) fn build_panel(stats: &FrameStats, memory: &MemoryUsage, position: [f32; 2]) -> OverlayPanel{
+        let gpu_time = match stats.gpu_time_ms{
+            Some(gpu_time_ms) => format!("{:.2} ms", gpu_time_ms),
+            None => "n/a".to_string(),
+        };
+
+        let lines = vec![
+            format!("FPS:       {:>8.1}", stats.fps),
+            format!("Frame:     {:>5.2} ms", stats.frame_time_ms),
+            format!("CPU:       {:>5.2} ms", stats.cpu_time_ms),
+            format!("  Update:  {:>5.2} ms", stats.cpu_timings.bind_group_update_ms),
+            format!("  Extract: {:>5.2} ms", stats.cpu_timings.extract_ms),
+            format!("  Encode:  {:>5.2} ms", stats.cpu_timings.encode_ms),
+            format!("  Submit:  {:>5.2} ms", stats.cpu_timings.submit_ms),
+            format!("  Present: {:>5.2} ms", stats.cpu_timings.present_ms),
+            format!("GPU:       {:>8}", gpu_time),
+            format!("Draws:     {:>8}", stats.draw_calls),
+            format!("Triangles: {:>8}", stats.triangles),
+            format!("VRAM:      {:>8}", format_bytes(memory.total_bytes())),
+        ];
+
+        OverlayPanel::new(position, lines)
+    }
+}
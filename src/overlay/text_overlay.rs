@@ -0,0 +1,386 @@
+use wgpu::util::DeviceExt;
+use crate::utils::handle::Handle;
+
+const GLYPH_SIZE: u32 = 8;
+const GLYPH_COUNT: u32 = 128;
+// Glyph 127 (DEL) is unused by the font, so it's filled in solid and used for panel backgrounds
+const SOLID_GLYPH: u32 = 127;
+
+const SHADER: &str = r#"
+struct Screen {
+    size: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> screen: Screen;
+@group(0) @binding(1)
+var glyphs: texture_2d<f32>;
+@group(0) @binding(2)
+var glyphs_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vertex_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    // Pixel coordinates (top-left origin) to clip space
+    let ndc = vec2<f32>(
+        input.position.x / screen.size.x * 2.0 - 1.0,
+        1.0 - input.position.y / screen.size.y * 2.0
+    );
+
+    output.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    output.uv = input.uv;
+    output.color = input.color;
+
+    return output;
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(glyphs, glyphs_sampler, input.uv).r;
+    return vec4<f32>(input.color.rgb, input.color.a * coverage);
+}
+"#;
+
+/// # Overlay Panel
+///
+/// A block of text drawn on top of the frame, with a background behind it
+#[derive(Debug, Clone)]
+pub(crate) struct OverlayPanel{
+    /// Top-left corner of the panel, in pixels
+    pub position: [f32; 2],
+    pub lines: Vec<String>,
+    pub text_color: [f32; 4],
+    pub background: [f32; 4],
+    /// Glyphs are 8x8 pixels, multiplied by this scale
+    pub scale: f32,
+}
+
+impl OverlayPanel{
+    pub(crate) fn new(position: [f32; 2], lines: Vec<String>) -> Self{
+        Self{
+            position,
+            lines,
+            text_color: [1.0, 1.0, 1.0, 1.0],
+            background: [0.0, 0.0, 0.0, 0.6],
+            scale: 2.0,
+        }
+    }
+
+    /// Size of the panel in pixels, including padding
+    pub(crate) fn get_size(&self) -> [f32; 2]{
+        let glyph = GLYPH_SIZE as f32 * self.scale;
+        let columns = self.lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+
+        [
+            columns as f32 * glyph + Self::padding(self.scale) * 2.0,
+            self.lines.len() as f32 * glyph + Self::padding(self.scale) * 2.0,
+        ]
+    }
+
+    fn padding(scale: f32) -> f32{
+        4.0 * scale
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct OverlayVertex{
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+impl OverlayVertex{
+    fn desc() -> wgpu::VertexBufferLayout<'static>{
+        const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
+
+        wgpu::VertexBufferLayout{
+            array_stride: std::mem::size_of::<OverlayVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// # Text Overlay
+///
+/// Draws panels of text over the frame using a built-in 8x8 bitmap font.
+/// Used by the renderer's debug overlays, so it doesn't depend on any user resources
+pub(crate) struct TextOverlay{
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    screen_buffer: wgpu::Buffer,
+
+    vertex_buffer: wgpu::Buffer,
+    vertex_capacity: usize,
+
+    _device: Handle<wgpu::Device>,
+    _queue: Handle<wgpu::Queue>
+}
+
+impl TextOverlay{
+    pub(crate) fn new(device: Handle<wgpu::Device>, queue: Handle<wgpu::Queue>, format: wgpu::TextureFormat) -> Self{
+        let atlas = Self::create_glyph_atlas(&device, &queue);
+        let atlas_view = atlas.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor{
+            label: Some("Overlay Glyph Sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let screen_buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("Overlay Screen Buffer"),
+            size: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Overlay Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture{
+                        sample_type: wgpu::TextureSampleType::Float{ filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None
+                },
+            ]
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some("Overlay Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry{ binding: 0, resource: screen_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry{ binding: 1, resource: wgpu::BindingResource::TextureView(&atlas_view) },
+                wgpu::BindGroupEntry{ binding: 2, resource: wgpu::BindingResource::Sampler(&sampler) },
+            ]
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Overlay Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor{
+            label: Some("Overlay Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into())
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor{
+            label: Some("Overlay Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState{
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[OverlayVertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState{
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState{
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let vertex_capacity = 1024;
+        let vertex_buffer = Self::create_vertex_buffer(&device, vertex_capacity);
+
+        Self{
+            pipeline,
+            bind_group,
+            screen_buffer,
+
+            vertex_buffer,
+            vertex_capacity,
+
+            _device: device,
+            _queue: queue
+        }
+    }
+
+    // Packs the 128 ASCII glyphs into a single row R8 texture
+    fn create_glyph_atlas(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture{
+        let width = GLYPH_SIZE * GLYPH_COUNT;
+        let mut pixels = vec![0u8; (width * GLYPH_SIZE) as usize];
+
+        for (index, glyph) in font8x8::legacy::BASIC_LEGACY.iter().enumerate(){
+            for (row, bits) in glyph.iter().enumerate(){
+                for column in 0..GLYPH_SIZE as usize{
+                    let solid = index as u32 == SOLID_GLYPH;
+                    // Bit 0 is the left-most pixel
+                    if solid || bits & (1 << column) != 0{
+                        pixels[row * width as usize + index * GLYPH_SIZE as usize + column] = 255;
+                    }
+                }
+            }
+        }
+
+        device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor{
+                label: Some("Overlay Glyph Atlas"),
+                size: wgpu::Extent3d{ width, height: GLYPH_SIZE, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &pixels
+        )
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer{
+        device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("Overlay Vertex Buffer"),
+            size: (capacity * std::mem::size_of::<OverlayVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn push_quad(vertices: &mut Vec<OverlayVertex>, min: [f32; 2], max: [f32; 2], glyph: u32, color: [f32; 4]){
+        // Sample the centre of the solid glyph, so filtering never reaches the edges
+        let (uv_min, uv_max) = if glyph == SOLID_GLYPH{
+            let centre = [(glyph as f32 + 0.5) / GLYPH_COUNT as f32, 0.5];
+            (centre, centre)
+        }else{
+            ([glyph as f32 / GLYPH_COUNT as f32, 0.0], [(glyph + 1) as f32 / GLYPH_COUNT as f32, 1.0])
+        };
+
+        let corners = [
+            ([min[0], min[1]], [uv_min[0], uv_min[1]]),
+            ([max[0], min[1]], [uv_max[0], uv_min[1]]),
+            ([max[0], max[1]], [uv_max[0], uv_max[1]]),
+            ([min[0], max[1]], [uv_min[0], uv_max[1]]),
+        ];
+
+        for index in [0, 2, 1, 0, 3, 2]{
+            let (position, uv) = corners[index];
+            vertices.push(OverlayVertex{ position, uv, color });
+        }
+    }
+
+    fn build_vertices(panels: &[OverlayPanel]) -> Vec<OverlayVertex>{
+        let mut vertices = Vec::new();
+
+        for panel in panels.iter(){
+            let size = panel.get_size();
+            let [x, y] = panel.position;
+
+            Self::push_quad(&mut vertices, [x, y], [x + size[0], y + size[1]], SOLID_GLYPH, panel.background);
+
+            let glyph_size = GLYPH_SIZE as f32 * panel.scale;
+            let padding = OverlayPanel::padding(panel.scale);
+
+            for (row, line) in panel.lines.iter().enumerate(){
+                for (column, character) in line.chars().enumerate(){
+                    // Anything outside of printable ASCII is drawn as '?'
+                    let glyph = match character as u32{
+                        code @ 0x21..=0x7E => code,
+                        0x20 => continue,
+                        _ => '?' as u32
+                    };
+
+                    let min = [
+                        x + padding + column as f32 * glyph_size,
+                        y + padding + row as f32 * glyph_size,
+                    ];
+                    let max = [min[0] + glyph_size, min[1] + glyph_size];
+
+                    Self::push_quad(&mut vertices, min, max, glyph, panel.text_color);
+                }
+            }
+        }
+
+        vertices
+    }
+
+    /// # Draw
+    ///
+    /// Draws the panels on top of the given view, keeping its existing contents
+    pub(crate) fn draw(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView,
+                       screen_size: [u32; 2], panels: &[OverlayPanel]){
+        let vertices = Self::build_vertices(panels);
+        if vertices.is_empty(){
+            return;
+        }
+
+        if vertices.len() > self.vertex_capacity{
+            self.vertex_capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(&self._device, self.vertex_capacity);
+        }
+
+        self._queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        self._queue.write_buffer(&self.screen_buffer, 0, bytemuck::cast_slice(&[
+            screen_size[0] as f32, screen_size[1] as f32, 0.0, 0.0
+        ]));
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+            label: Some("Overlay Render Pass"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment{
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations{
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store
+                    }
+                })
+            ],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..vertices.len() as u32, 0..1);
+    }
+}
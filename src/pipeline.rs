@@ -1,21 +1,37 @@
 use std::ops::Deref;
 use log::error;
 use crate::managers::resource_handle::ResourceHandle;
+use crate::types::model::Model;
 use crate::types::renderable::Renderable;
 use crate::types::shader::Shader;
 use crate::utils::handle::Handle;
 
+/// Format pipelines draw colour in by default, matching the sRGB swapchain
+pub(crate) const DEFAULT_COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
 pub struct Pipeline{
     uuid: u64,
     pipeline: wgpu::RenderPipeline,
-    shader: ResourceHandle  // This tells us which shader is used by this pipeline
+    shader: ResourceHandle, // This tells us which shader is used by this pipeline
                             // so we can figure out which materials can use this pipeline
+    // Whether back faces are drawn, so only double sided models are drawn with it
+    double_sided: bool,
 }
 
 impl Pipeline {
     pub(crate) fn get_shader(&self) -> ResourceHandle {
         self.shader.clone()
     }
+
+    pub(crate) fn is_double_sided(&self) -> bool {
+        self.double_sided
+    }
+
+    /// Whether a model using one of this pipeline's materials is drawn with it, rather
+    /// than with the pipeline's single or double sided variant
+    pub(crate) fn draws_model(&self, model: &Model) -> bool {
+        model.is_double_sided() == self.double_sided
+    }
 }
 
 pub struct PipelineBuildSettings<'a>{
@@ -23,7 +39,19 @@ pub struct PipelineBuildSettings<'a>{
     pub vertex_descriptors: Vec<wgpu::VertexBufferLayout<'static>>,
     pub bind_groups: Vec<&'a wgpu::BindGroupLayout>,
     pub shader: Option<&'a Shader>,
+    // The handle of `shader`, which materials are matched to pipelines by
+    pub shader_handle: Option<ResourceHandle>,
     pub use_depth: bool,
+    pub depth_format: wgpu::TextureFormat,
+    pub color_format: wgpu::TextureFormat,
+    // Samples per pixel of the targets the pipeline draws into, more than 1 when multisampling
+    pub sample_count: u32,
+    pub topology: wgpu::PrimitiveTopology,
+    pub blend: Option<wgpu::BlendState>,
+    pub depth_write: bool,
+    pub depth_compare: wgpu::CompareFunction,
+    // Only applies to triangles, as points and lines have no facing
+    pub cull_mode: Option<wgpu::Face>,
 }
 
 
@@ -47,13 +75,13 @@ impl Pipeline{
             panic!("No shader provided for pipeline creation.");
         });
 
-        let pipeline = Self::create_pipeline(device, layout, shader,
-                                             settings.vertex_descriptors, settings.use_depth);
+        let pipeline = Self::create_pipeline(device, layout, shader, &settings);
 
         Self{
             uuid,
             pipeline,
-            shader: shader_handle
+            shader: shader_handle,
+            double_sided: settings.cull_mode.is_none(),
         }
     }
     
@@ -68,56 +96,55 @@ impl Pipeline{
 
     fn create_pipeline(device: &wgpu::Device, layout: wgpu::PipelineLayout,
                        shader: &Shader,
-                        vertex_buffer_layouts: Vec<wgpu::VertexBufferLayout>,
-                        use_depth: bool) -> wgpu::RenderPipeline {
+                       settings: &PipelineBuildSettings) -> wgpu::RenderPipeline {
 
         let depth_stencil = if cfg!(target_arch = "wasm32") {
             None
         } else {
-            if use_depth {
-                Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                })
-            }else{
-                None
-            }
+            settings.use_depth.then(|| wgpu::DepthStencilState {
+                format: settings.depth_format,
+                depth_write_enabled: settings.depth_write,
+                depth_compare: settings.depth_compare,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            })
         };
 
-        let shader_module = shader.compile(&device);
+        let shader_module = shader.get_module();
 
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&layout),
             vertex: wgpu::VertexState {
-                module: &shader_module,
+                module: shader_module,
                 entry_point: "vertex_main",
-                buffers: &vertex_buffer_layouts,
+                buffers: &settings.vertex_descriptors,
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
+                module: shader_module,
                 entry_point: "fragment_main",
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    format: settings.color_format,
+                    blend: settings.blend,
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
+                topology: settings.topology,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
+                // Points and lines have no facing, so can't be culled
+                cull_mode: match settings.topology {
+                    wgpu::PrimitiveTopology::TriangleList | wgpu::PrimitiveTopology::TriangleStrip => settings.cull_mode,
+                    _ => None,
+                },
                 unclipped_depth: false,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 conservative: false,
             },
             depth_stencil,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: settings.sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -128,6 +155,10 @@ impl Pipeline{
     pub fn get_uuid(&self) -> u64{
         self.uuid
     }
+
+    pub(crate) fn get_render_pipeline(&self) -> &wgpu::RenderPipeline{
+        &self.pipeline
+    }
 }
 
 impl<'a> PipelineBuildSettings<'a>{
@@ -137,7 +168,16 @@ impl<'a> PipelineBuildSettings<'a>{
             vertex_descriptors: Vec::new(),
             bind_groups: Vec::new(),
             shader: None,
+            shader_handle: None,
             use_depth: false,
+            depth_format: wgpu::TextureFormat::Depth32Float,
+            color_format: DEFAULT_COLOR_FORMAT,
+            sample_count: 1,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            depth_write: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            cull_mode: Some(wgpu::Face::Back),
         }
     }
 
@@ -151,8 +191,9 @@ impl<'a> PipelineBuildSettings<'a>{
         self
     }
 
-    pub fn set_shader(mut self, shader: &'a Shader) -> Self{
+    pub fn set_shader(mut self, shader: &'a Shader, shader_handle: ResourceHandle) -> Self{
         self.shader = Some(shader);
+        self.shader_handle = Some(shader_handle);
         self
     }
 
@@ -161,15 +202,58 @@ impl<'a> PipelineBuildSettings<'a>{
         self
     }
 
+    /// The format of the depth buffer the pipeline draws with, when it uses depth
+    pub fn set_depth_format(mut self, depth_format: wgpu::TextureFormat) -> Self{
+        self.depth_format = depth_format;
+        self
+    }
+
+    /// The format of the colour target the pipeline draws into
+    pub fn set_color_format(mut self, color_format: wgpu::TextureFormat) -> Self{
+        self.color_format = color_format;
+        self
+    }
+
+    /// The number of samples per pixel of the targets the pipeline draws into, e.g 4 for 4x MSAA
+    pub fn set_sample_count(mut self, sample_count: u32) -> Self{
+        self.sample_count = sample_count;
+        self
+    }
+
+    pub fn set_topology(mut self, topology: wgpu::PrimitiveTopology) -> Self{
+        self.topology = topology;
+        self
+    }
+
+    /// The faces the pipeline doesn't draw, `None` for double sided pipelines
+    pub fn set_cull_mode(mut self, cull_mode: Option<wgpu::Face>) -> Self{
+        self.cull_mode = cull_mode;
+        self
+    }
+
     pub fn calculate_hash(&mut self){
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
+        // Everything the pipeline is built from, so only identical pipelines are shared
         let mut hasher = DefaultHasher::new();
         // Hash the vertex descriptors
         for descriptor in &self.vertex_descriptors{
             descriptor.hash(&mut hasher);
         }
+        self.shader_handle.hash(&mut hasher);
+        for bind_group in &self.bind_groups{
+            bind_group.global_id().hash(&mut hasher);
+        }
+        self.topology.hash(&mut hasher);
+        self.use_depth.hash(&mut hasher);
+        self.depth_format.hash(&mut hasher);
+        self.color_format.hash(&mut hasher);
+        self.sample_count.hash(&mut hasher);
+        self.blend.hash(&mut hasher);
+        self.depth_write.hash(&mut hasher);
+        self.depth_compare.hash(&mut hasher);
+        self.cull_mode.hash(&mut hasher);
         self.uuid = hasher.finish();
     }
 
@@ -183,3 +267,85 @@ impl<'a> Renderable<'a> for Pipeline{
         render_pass.set_pipeline(&self.pipeline);
     }
 }
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::managers::resource_manager::ResourceType;
+    use crate::testing::HeadlessRenderer;
+
+    fn create_layout(device: &wgpu::Device, visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayout{
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Test Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry{
+                binding: 0,
+                visibility,
+                ty: wgpu::BindingType::Buffer{
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    fn hash_of(mut settings: PipelineBuildSettings) -> u64{
+        settings.calculate_hash();
+        settings.get_uuid()
+    }
+
+    #[test]
+    fn hash_changes_with_each_setting(){
+        // Bind group layouts need a device, so the test fails rather than passing without checking anything
+        let renderer = HeadlessRenderer::new().expect("The pipeline hash tests need a GPU adapter");
+        let resource_manager = renderer.get_resource_manager();
+        let resource_manager = resource_manager.get();
+        let layout = create_layout(resource_manager.get_device(), wgpu::ShaderStages::VERTEX);
+        let other_layout = create_layout(resource_manager.get_device(), wgpu::ShaderStages::FRAGMENT);
+
+        let shader_handle = ResourceHandle::new(ResourceType::Shader);
+        let settings = ||{
+            let mut settings = PipelineBuildSettings::new().add_bind_group(&layout).use_depth(true);
+            settings.shader_handle = Some(shader_handle.clone());
+            settings
+        };
+        let hash = hash_of(settings());
+
+        let changed: Vec<(&str, PipelineBuildSettings)> = vec![
+            ("shader handle", PipelineBuildSettings{ shader_handle: Some(ResourceHandle::new(ResourceType::Shader)), ..settings() }),
+            ("bind group layouts", PipelineBuildSettings{ bind_groups: vec![&other_layout], ..settings() }),
+            ("color format", settings().set_color_format(wgpu::TextureFormat::Rgba8Unorm)),
+            ("depth format", settings().set_depth_format(wgpu::TextureFormat::Depth24Plus)),
+            ("sample count", settings().set_sample_count(4)),
+            ("blend", PipelineBuildSettings{ blend: None, ..settings() }),
+            ("depth write", PipelineBuildSettings{ depth_write: false, ..settings() }),
+            ("depth compare", PipelineBuildSettings{ depth_compare: wgpu::CompareFunction::LessEqual, ..settings() }),
+            ("cull mode", settings().set_cull_mode(None)),
+        ];
+        for (name, changed_settings) in changed{
+            assert_ne!(hash_of(changed_settings), hash, "Changing the {} didn't change the hash", name);
+        }
+    }
+
+    #[test]
+    fn hash_matches_for_identical_settings(){
+        let renderer = HeadlessRenderer::new().expect("The pipeline hash tests need a GPU adapter");
+        let resource_manager = renderer.get_resource_manager();
+        let resource_manager = resource_manager.get();
+        let layout = create_layout(resource_manager.get_device(), wgpu::ShaderStages::VERTEX);
+
+        let shader_handle = ResourceHandle::new(ResourceType::Shader);
+        let settings = ||{
+            let mut settings = PipelineBuildSettings::new()
+                .add_bind_group(&layout)
+                .use_depth(true)
+                .set_sample_count(4)
+                .set_cull_mode(None);
+            settings.shader_handle = Some(shader_handle.clone());
+            settings
+        };
+
+        assert_eq!(hash_of(settings()), hash_of(settings()));
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use glam::{Mat4, Vec3};
+use crate::debug::{debug_log, Subsystem};
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceManager;
+use crate::managers::sampler_cache::SamplerCache;
+use crate::types::builtin_shaders::SKIN_BINDING;
+use crate::types::mesh::MeshLayout;
+use crate::types::model::Model;
+use crate::types::quantization::QUANTIZATION_BINDING;
+use crate::types::texture::Texture;
+use crate::types::vertex::{JOINTS_LOCATION, WEIGHTS_LOCATION};
+use crate::utils::handle::Handle;
+
+const POINT_SHADOW_SHADER: &str = include_str!("../assets/shaders/point_shadow.wgsl");
+
+/// Format of the point shadow map
+pub(crate) const POINT_SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// Near plane of each face. Anything closer to the light than this doesn't cast a shadow
+const POINT_SHADOW_NEAR: f32 = 0.05;
+// Every face's uniform lives in one buffer, at offsets aligned for dynamic binding
+const FACE_UNIFORM_STRIDE: u64 = 256;
+
+/// The direction each cube face looks in and its up vector, in layer order. The lit shader
+/// picks the face by the largest axis of the direction to the light, and projects onto it the same way
+const CUBE_FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::NEG_Y),
+    (Vec3::NEG_X, Vec3::NEG_Y),
+    (Vec3::Y, Vec3::Z),
+    (Vec3::NEG_Y, Vec3::NEG_Z),
+    (Vec3::Z, Vec3::NEG_Y),
+    (Vec3::NEG_Z, Vec3::NEG_Y),
+];
+
+// How a mesh layout's positions are read, each with its own vertex entry point
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ShadowVariant{
+    // `Float32x3` positions, e.g `Vertex` and instanced meshes
+    Static,
+    // `Float32x3` positions blended by the material's skin uniform, e.g `SkinnedVertex`
+    Skinned,
+    // `Unorm16x4` positions scaled by the material's quantization uniform, e.g `QuantizedVertex`
+    Quantized,
+}
+
+impl ShadowVariant{
+    fn from_layout(buffer: &wgpu::VertexBufferLayout) -> Option<Self>{
+        let has = |location: u32, format: wgpu::VertexFormat| buffer.attributes.iter()
+            .any(|attribute| attribute.shader_location == location && attribute.format == format);
+
+        if has(0, wgpu::VertexFormat::Unorm16x4){
+            Some(Self::Quantized)
+        } else if !has(0, wgpu::VertexFormat::Float32x3){
+            None
+        } else if has(JOINTS_LOCATION, wgpu::VertexFormat::Uint32x4) && has(WEIGHTS_LOCATION, wgpu::VertexFormat::Float32x4){
+            Some(Self::Skinned)
+        } else{
+            Some(Self::Static)
+        }
+    }
+
+    fn get_entry_point(&self) -> &'static str{
+        match self{
+            Self::Static => "vertex_main",
+            Self::Skinned => "vertex_skinned",
+            Self::Quantized => "vertex_quantized",
+        }
+    }
+
+    // Locations the entry point reads from the first vertex buffer
+    fn get_locations(&self) -> &'static [u32]{
+        match self{
+            Self::Static | Self::Quantized => &[0],
+            Self::Skinned => &[0, JOINTS_LOCATION, WEIGHTS_LOCATION],
+        }
+    }
+
+    // The material uniform bound at group 1, and its binding in the shader
+    fn get_uniform_binding(&self) -> Option<(&'static str, u32)>{
+        match self{
+            Self::Static => None,
+            Self::Skinned => Some((SKIN_BINDING, 0)),
+            Self::Quantized => Some((QUANTIZATION_BINDING, 1)),
+        }
+    }
+}
+
+// A depth-only pipeline for one mesh layout
+struct ShadowPipeline{
+    variant: ShadowVariant,
+    pipeline: wgpu::RenderPipeline,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowFaceUniform{
+    view_projection: [[f32; 4]; 4],
+    light_position_far: [f32; 4],
+}
+
+/// # Point Shadows
+///
+/// Draws the shadows of point lights into a depth texture array, six layers (one cube face)
+/// per light. Rather than depth, each texel holds the distance to the light over the light's
+/// far plane, so lit shaders can compare it with their own distance to the light
+pub(crate) struct PointShadows{
+    resolution: u32,
+    // Lights the shadow map has layers for. It starts as a 1x1 placeholder with none
+    capacity: usize,
+    // Position and far plane of each light drawn this frame
+    casters: Vec<(Vec3, f32)>,
+    layer_views: Vec<wgpu::TextureView>,
+    face_buffer: wgpu::Buffer,
+
+    bind_group_layout: wgpu::BindGroupLayout,
+    // The material uniform each variant reads at group 1
+    skin_layout: wgpu::BindGroupLayout,
+    quantization_layout: wgpu::BindGroupLayout,
+    shader_module: wgpu::ShaderModule,
+    // Hash of a mesh layout - pipeline reading positions from it, like the main pass' pipelines
+    pipelines: HashMap<u64, ShadowPipeline>,
+    // Uniform handle - the group 1 bind group of the skinned and quantized casters reading it, and the
+    // buffer it was made from, so it's only rebuilt when the uniform's buffer is replaced
+    uniform_bind_groups: Mutex<HashMap<ResourceHandle, (wgpu::Id<wgpu::Buffer>, wgpu::BindGroup)>>,
+
+    _device: Handle<wgpu::Device>,
+}
+
+impl PointShadows{
+    pub(crate) fn new(device: Handle<wgpu::Device>, resolution: u32) -> Self{
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Point Shadow Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Storage{ read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<ShadowFaceUniform>() as u64)
+                    },
+                    count: None
+                },
+            ]
+        });
+
+        let uniform_layout = |label: &str, binding: u32| device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some(label),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+            ]
+        });
+        let skin_layout = uniform_layout("Point Shadow Skin Bind Group Layout", 0);
+        let quantization_layout = uniform_layout("Point Shadow Quantization Bind Group Layout", 1);
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor{
+            label: Some("Point Shadow Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(POINT_SHADOW_SHADER.into())
+        });
+
+        let face_buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("Point Shadow Face Buffer"),
+            size: FACE_UNIFORM_STRIDE * 6 * crate::types::light::MAX_POINT_SHADOWS as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self{
+            resolution: resolution.max(1),
+            capacity: 0,
+            casters: Vec::new(),
+            layer_views: Vec::new(),
+            face_buffer,
+
+            bind_group_layout,
+            skin_layout,
+            quantization_layout,
+            shader_module,
+            pipelines: HashMap::new(),
+            uniform_bind_groups: Mutex::new(HashMap::new()),
+
+            _device: device,
+        }
+    }
+
+    /// The texture to start with, before any light casts a shadow
+    pub(crate) fn create_placeholder(&mut self, samplers: &SamplerCache) -> Texture{
+        let texture = Texture::create_shadow_map(&self._device, samplers, 1, Self::layer_count(0), "Point Shadow Map");
+        self.layer_views = Vec::new();
+        self.capacity = 0;
+        texture
+    }
+
+    // Layers in a shadow map for the given number of lights. GL guesses the view dimension from the
+    // layer count, and takes multiples of six to be cubemaps, so there's always one spare layer
+    fn layer_count(lights: usize) -> u32{
+        lights.max(1) as u32 * 6 + 1
+    }
+
+    pub(crate) fn set_resolution(&mut self, resolution: u32){
+        self.resolution = resolution.max(1);
+    }
+
+    /// # Set Casters
+    ///
+    /// Sets the lights to draw shadows for, and writes their faces' view-projections.
+    /// Returns a new shadow map when the current one is too small or the wrong resolution,
+    /// which the caller has to swap in for the old one
+    pub(crate) fn set_casters(&mut self, queue: &wgpu::Queue, samplers: &SamplerCache, current: &Texture, casters: Vec<(Vec3, f32)>) -> Option<Texture>{
+        let texture = (!casters.is_empty() && (casters.len() > self.capacity || current.get_texture_size().width != self.resolution)).then(||{
+            debug_log!(Subsystem::Render, "Creating a {}px point shadow map for {} lights", self.resolution, casters.len());
+            let texture = Texture::create_shadow_map(&self._device, samplers, self.resolution, Self::layer_count(casters.len()), "Point Shadow Map");
+            self.layer_views = (0..casters.len() as u32 * 6).map(|layer| texture.create_layer_view(layer)).collect();
+            self.capacity = casters.len();
+            texture
+        });
+
+        let mut data = vec![0u8; (FACE_UNIFORM_STRIDE as usize) * 6 * casters.len()];
+        for (light, (position, far)) in casters.iter().enumerate(){
+            let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, POINT_SHADOW_NEAR, *far);
+            for (face, (forward, up)) in CUBE_FACES.iter().enumerate(){
+                let view = Mat4::look_at_rh(*position, *position + *forward, *up);
+                let uniform = ShadowFaceUniform{
+                    view_projection: (projection * view).to_cols_array_2d(),
+                    light_position_far: [position.x, position.y, position.z, *far],
+                };
+
+                let offset = (light * 6 + face) * FACE_UNIFORM_STRIDE as usize;
+                data[offset..offset + std::mem::size_of::<ShadowFaceUniform>()].copy_from_slice(bytemuck::bytes_of(&uniform));
+            }
+        }
+        if !data.is_empty(){
+            queue.write_buffer(&self.face_buffer, 0, &data);
+        }
+
+        self.casters = casters;
+        texture
+    }
+
+    /// # Prepare Layout
+    ///
+    /// Creates the depth-only pipeline for a mesh layout, if it doesn't exist yet. Static, skinned and
+    /// quantized positions in the first vertex buffer are supported, drawn as triangle lists or strips.
+    /// Other layouts, e.g points and lines, don't cast shadows
+    pub(crate) fn prepare_layout(&mut self, layout: &MeshLayout){
+        let key = Self::layout_key(layout);
+        if self.pipelines.contains_key(&key){
+            return;
+        }
+        let Some(variant) = Self::layout_variant(layout) else { return };
+
+        // Only the attributes the variant reads, so anything else in the layout is skipped over
+        let buffer = &layout.get_vertex_buffer_layouts()[0];
+        let attributes: Vec<wgpu::VertexAttribute> = buffer.attributes.iter()
+            .filter(|attribute| variant.get_locations().contains(&attribute.shader_location))
+            .copied()
+            .collect();
+
+        let mut bind_group_layouts = vec![&self.bind_group_layout];
+        match variant{
+            ShadowVariant::Static => {}
+            ShadowVariant::Skinned => bind_group_layouts.push(&self.skin_layout),
+            ShadowVariant::Quantized => bind_group_layouts.push(&self.quantization_layout),
+        }
+        let pipeline_layout = self._device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Point Shadow Pipeline Layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        debug_log!(Subsystem::Render, "Creating a {:?} point shadow pipeline", variant);
+        let pipeline = self._device.create_render_pipeline(&wgpu::RenderPipelineDescriptor{
+            label: Some("Point Shadow Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState{
+                module: &self.shader_module,
+                entry_point: variant.get_entry_point(),
+                buffers: &[wgpu::VertexBufferLayout{
+                    array_stride: buffer.array_stride,
+                    step_mode: buffer.step_mode,
+                    attributes: &attributes,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState{
+                module: &self.shader_module,
+                entry_point: "fragment_main",
+                targets: &[],
+            }),
+            // Both sides are drawn, so open meshes still cast shadows
+            primitive: wgpu::PrimitiveState{
+                topology: layout.get_topology(),
+                strip_index_format: Self::strip_index_format(layout),
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState{
+                format: POINT_SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        self.pipelines.insert(key, ShadowPipeline{ variant, pipeline });
+    }
+
+    // Hashes the layout the same way `PipelineBuildSettings` does, so each layout gets its own pipeline
+    fn layout_key(layout: &MeshLayout) -> u64{
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        for descriptor in layout.get_vertex_buffer_layouts(){
+            descriptor.hash(&mut hasher);
+        }
+        layout.get_topology().hash(&mut hasher);
+        Self::strip_index_format(layout).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn layout_variant(layout: &MeshLayout) -> Option<ShadowVariant>{
+        if !matches!(layout.get_topology(), wgpu::PrimitiveTopology::TriangleList | wgpu::PrimitiveTopology::TriangleStrip){
+            return None;
+        }
+        ShadowVariant::from_layout(layout.get_vertex_buffer_layouts().first()?)
+    }
+
+    fn strip_index_format(layout: &MeshLayout) -> Option<wgpu::IndexFormat>{
+        (layout.get_topology() == wgpu::PrimitiveTopology::TriangleStrip).then_some(layout.index_format)
+    }
+
+    // The group 1 uniform of a skinned or quantized model, from its material or the material's template
+    fn find_uniform<'a>(resource_manager: &'a ResourceManager, model: &Model, name: &str) -> Option<&'a ResourceHandle>{
+        let material = resource_manager.borrow_material(model.get_draw_material());
+        material.get_uniform(name)
+            .or_else(|| material.get_template().and_then(|template| resource_manager.borrow_material(template).get_uniform(name)))
+    }
+
+    /// # Draw
+    ///
+    /// Draws every model into each caster's six faces. Submitted on its own, so it has to
+    /// happen before anything sampling the shadow map is. Skinned and quantized models without
+    /// their material's skin or quantization uniform are skipped
+    pub(crate) fn draw(&self, resource_manager: &ResourceManager){
+        if self.casters.is_empty(){
+            return;
+        }
+
+        let device = resource_manager.get_device();
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some("Point Shadow Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry{
+                    binding: 0,
+                    resource: resource_manager.get_objects_buffer().get_buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry{
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding{
+                        buffer: &self.face_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(std::mem::size_of::<ShadowFaceUniform>() as u64),
+                    }),
+                },
+            ],
+        });
+
+        let models = resource_manager.get_all_models();
+
+        // Each caster's pipeline, and its group 1 bind group for skinned and quantized layouts
+        let mut uniform_bind_groups = self.uniform_bind_groups.lock().unwrap();
+        let mut used_uniforms = HashSet::new();
+        let mut draws = Vec::new();
+        for model in models.iter().filter(|model| model.casts_shadows()){
+            let Some(mesh) = resource_manager.get_mesh(model.get_mesh()) else { continue };
+            let Some(shadow_pipeline) = self.pipelines.get(&Self::layout_key(mesh.get_layout())) else { continue };
+
+            let uniform = match shadow_pipeline.variant.get_uniform_binding(){
+                None => None,
+                Some((name, binding)) => {
+                    let Some(handle) = Self::find_uniform(resource_manager, model, name) else { continue };
+                    let Some(uniform) = resource_manager.get_uniform_buffer(handle) else { continue };
+                    let buffer_id = uniform.get_buffer().global_id();
+                    if uniform_bind_groups.get(handle).is_none_or(|(id, _)| *id != buffer_id){
+                        let layout = match shadow_pipeline.variant{
+                            ShadowVariant::Skinned => &self.skin_layout,
+                            _ => &self.quantization_layout,
+                        };
+                        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor{
+                            label: Some("Point Shadow Uniform Bind Group"),
+                            layout,
+                            entries: &[
+                                wgpu::BindGroupEntry{ binding, resource: uniform.get_buffer().as_entire_binding() },
+                            ],
+                        });
+                        uniform_bind_groups.insert(handle.clone(), (buffer_id, bind_group));
+                    }
+                    used_uniforms.insert(handle.clone());
+                    Some(handle.clone())
+                }
+            };
+            draws.push((model, mesh, &shadow_pipeline.pipeline, uniform));
+        }
+        // Drop the bind groups of uniforms no caster reads anymore
+        uniform_bind_groups.retain(|handle, _| used_uniforms.contains(handle));
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor{
+            label: Some("Point Shadow Encoder")
+        });
+
+        for layer in 0..self.casters.len() * 6{
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: Some("Point Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment{
+                    view: &self.layer_views[layer],
+                    depth_ops: Some(wgpu::Operations{
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store
+                    }),
+                    stencil_ops: None
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_bind_group(0, &bind_group, &[(layer as u64 * FACE_UNIFORM_STRIDE) as u32]);
+
+            for (model, mesh, pipeline, uniform) in draws.iter(){
+                let (Some(vertex_buffers), Some(index_buffers)) = (
+                    resource_manager.get_mesh_vertex_buffers(model.get_mesh()),
+                    resource_manager.get_mesh_index_buffers(model.get_mesh())
+                ) else { continue };
+
+                render_pass.set_pipeline(pipeline);
+                if let Some((_, bind_group)) = uniform.as_ref().and_then(|handle| uniform_bind_groups.get(handle)){
+                    render_pass.set_bind_group(1, bind_group, &[]);
+                }
+                for (idx, submesh) in mesh.get_sub_meshes().iter().enumerate(){
+                    vertex_buffers[idx].bind_vertex_buffer(0, &mut render_pass);
+                    index_buffers[idx].bind_index_buffer(&mut render_pass);
+                    submesh.render_object(&mut render_pass, model.get_object_index());
+                }
+            }
+        }
+
+        resource_manager.get_queue().submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use log::warn;
+use wgpu::util::DeviceExt;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceManager;
+use crate::post::post_stack::create_effect_pipeline;
+use crate::types::camera::CameraTarget;
+use crate::utils::handle::Handle;
+
+const BLIT_SHADER: &str = include_str!("../../assets/shaders/blit.wgsl");
+
+/// The whole of a texture, as a blit region
+pub(crate) const FULL_RECT: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+// A blit waiting for the end of the frame
+struct BlitRequest{
+    source: ResourceHandle,
+    // Offset and size in the source, from 0 to 1
+    source_rect: [f32; 4],
+    target: CameraTarget,
+    // Offset and size in the target, from 0 to 1, like a camera's viewport
+    target_rect: [f32; 4],
+    filter: wgpu::FilterMode,
+}
+
+/// # Blitter
+///
+/// Draws textures into render textures or the surface, scaled and filtered, e.g to downsample a target
+/// or show an intermediate one on screen. Blits are queued, and drawn in order once the frame's
+/// scene and post effects are
+pub(crate) struct Blitter{
+    source_layout: wgpu::BindGroupLayout,
+    rect_layout: wgpu::BindGroupLayout,
+    // Target format - pipeline drawing into it
+    pipelines: HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+    nearest_sampler: wgpu::Sampler,
+    linear_sampler: wgpu::Sampler,
+    pending: Vec<BlitRequest>,
+
+    _device: Handle<wgpu::Device>,
+}
+
+impl Blitter{
+    pub(crate) fn new(device: Handle<wgpu::Device>) -> Self{
+        let source_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Blit Source Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture{
+                        sample_type: wgpu::TextureSampleType::Float{ filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None
+                },
+            ]
+        });
+
+        let rect_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Blit Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+            ]
+        });
+
+        let create_sampler = |label: &str, filter: wgpu::FilterMode| device.create_sampler(&wgpu::SamplerDescriptor{
+            label: Some(label),
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        });
+        let nearest_sampler = create_sampler("Blit Nearest Sampler", wgpu::FilterMode::Nearest);
+        let linear_sampler = create_sampler("Blit Linear Sampler", wgpu::FilterMode::Linear);
+
+        Self{
+            source_layout,
+            rect_layout,
+            pipelines: HashMap::new(),
+            nearest_sampler,
+            linear_sampler,
+            pending: Vec::new(),
+
+            _device: device,
+        }
+    }
+
+    /// Queues a blit of a region of the source into a region of the target, both from 0 to 1
+    pub(crate) fn queue(&mut self, source: &ResourceHandle, source_rect: [f32; 4], target: CameraTarget, target_rect: [f32; 4], filter: wgpu::FilterMode){
+        if target == CameraTarget::Texture(source.clone()){
+            warn!("Can't blit a texture into itself, skipping");
+            return;
+        }
+
+        self.pending.push(BlitRequest{
+            source: source.clone(),
+            source_rect,
+            target,
+            target_rect,
+            filter,
+        });
+    }
+
+    /// # Flush
+    ///
+    /// Records every queued blit into the encoder, in the order they were queued. The surface is
+    /// the frame's output. Blits whose source or target no longer exists are skipped
+    pub(crate) fn flush(&mut self, encoder: &mut wgpu::CommandEncoder, resource_manager: &ResourceManager,
+                        surface: &wgpu::TextureView, surface_format: wgpu::TextureFormat, surface_size: [u32; 2]){
+        for request in std::mem::take(&mut self.pending){
+            let Some(source) = resource_manager.get_texture(&request.source) else {
+                warn!("Blit source {:?} not found, skipping", request.source);
+                continue;
+            };
+            let (target, format, size) = match &request.target{
+                CameraTarget::Surface => (surface, surface_format, surface_size),
+                CameraTarget::Texture(handle) => match resource_manager.get_render_texture(handle){
+                    Some((color, _, _)) => {
+                        let extent = color.get_texture_size();
+                        (color.get_texture_view(), color.get_format(), [extent.width, extent.height])
+                    },
+                    None => {
+                        warn!("Blit target {:?} isn't a render texture, skipping", handle);
+                        continue;
+                    }
+                }
+            };
+
+            let sampler = match request.filter{
+                wgpu::FilterMode::Nearest => &self.nearest_sampler,
+                wgpu::FilterMode::Linear => &self.linear_sampler,
+            };
+            let source_bind_group = self._device.create_bind_group(&wgpu::BindGroupDescriptor{
+                label: Some("Blit Source Bind Group"),
+                layout: &self.source_layout,
+                entries: &[
+                    wgpu::BindGroupEntry{ binding: 0, resource: wgpu::BindingResource::TextureView(source.get_texture_view()) },
+                    wgpu::BindGroupEntry{ binding: 1, resource: wgpu::BindingResource::Sampler(sampler) },
+                ]
+            });
+            let rect_buffer = self._device.create_buffer_init(&wgpu::util::BufferInitDescriptor{
+                label: Some("Blit Uniform Buffer"),
+                contents: bytemuck::cast_slice(&request.source_rect),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let rect_bind_group = self._device.create_bind_group(&wgpu::BindGroupDescriptor{
+                label: Some("Blit Bind Group"),
+                layout: &self.rect_layout,
+                entries: &[
+                    wgpu::BindGroupEntry{ binding: 0, resource: rect_buffer.as_entire_binding() },
+                ]
+            });
+
+            // Viewports outside the target aren't allowed, so clip them to it
+            let [x, y, width, height] = request.target_rect;
+            let (x, y) = (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0));
+            let (width, height) = (width.clamp(0.0, 1.0 - x), height.clamp(0.0, 1.0 - y));
+            if width <= 0.0 || height <= 0.0{
+                continue;
+            }
+
+            let device = &self._device;
+            let source_layout = &self.source_layout;
+            let rect_layout = &self.rect_layout;
+            let pipeline = self.pipelines.entry(format).or_insert_with(||
+                create_effect_pipeline(device, "Blit Pipeline", BLIT_SHADER, source_layout, rect_layout, format)
+            );
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                label: Some("Blit Pass"),
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment{
+                        view: target,
+                        resolve_target: None,
+                        ops: wgpu::Operations{
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store
+                        }
+                    })
+                ],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            let (target_width, target_height) = (size[0] as f32, size[1] as f32);
+            render_pass.set_viewport(x * target_width, y * target_height, width * target_width, height * target_height, 0.0, 1.0);
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &source_bind_group, &[]);
+            render_pass.set_bind_group(1, &rect_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::post::post_stack::{create_effect_pipeline, PostEffect, LINEAR_FORMAT};
+use crate::post::transient_targets::{TransientTarget, TransientTargets};
+use crate::settings::RenderSettings;
+use crate::utils::handle::Handle;
+
+const BLOOM_PREFILTER_SHADER: &str = include_str!("../../assets/shaders/bloom_prefilter.wgsl");
+const BLOOM_BLUR_SHADER: &str = include_str!("../../assets/shaders/bloom_blur.wgsl");
+const BLOOM_SHADER: &str = include_str!("../../assets/shaders/bloom.wgsl");
+
+/// # Bloom
+///
+/// Makes the parts of the frame brighter than a threshold glow, e.g emissive materials. They're
+/// picked out into a half resolution float target, blurred, and added back on top of the frame.
+/// Values are clamped to 1 when drawing in the output's format, so bloom works best with a
+/// linear `OutputEncoding`
+pub(crate) struct Bloom{
+    prefilter_pipeline: wgpu::RenderPipeline,
+    blur_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    composite_layout: wgpu::BindGroupLayout,
+
+    // Threshold, knee and intensity, read by the prefilter and composite passes
+    params_buffer: wgpu::Buffer,
+    params_bind_group: wgpu::BindGroup,
+    // Direction of each blur pass, horizontal then vertical
+    blur_buffers: [wgpu::Buffer; 2],
+    blur_bind_groups: [wgpu::BindGroup; 2],
+
+    // Reads the blurred target, and the texture it was made for. The targets are acquired from the
+    // stack's transient targets each frame, so it's only remade when they hand out a different one
+    composite_bind_group: Option<(wgpu::Id<wgpu::Texture>, wgpu::BindGroup)>,
+    sampler: wgpu::Sampler,
+    width: u32,
+    height: u32,
+
+    _device: Handle<wgpu::Device>,
+}
+
+impl Bloom{
+    pub(crate) const NAME: &'static str = "bloom";
+
+    pub(crate) fn new(device: Handle<wgpu::Device>, source_layout: &wgpu::BindGroupLayout, format: wgpu::TextureFormat,
+                      width: u32, height: u32) -> Self{
+        let uniform_entry = wgpu::BindGroupLayoutEntry{
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer{
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None
+            },
+            count: None
+        };
+
+        let params_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Bloom Bind Group Layout"),
+            entries: &[uniform_entry]
+        });
+
+        let composite_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Bloom Composite Bind Group Layout"),
+            entries: &[
+                uniform_entry,
+                wgpu::BindGroupLayoutEntry{
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture{
+                        sample_type: wgpu::TextureSampleType::Float{ filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None
+                },
+            ]
+        });
+
+        let prefilter_pipeline = create_effect_pipeline(&device, "Bloom Prefilter Pipeline", BLOOM_PREFILTER_SHADER,
+                                                        source_layout, &params_layout, LINEAR_FORMAT);
+        let blur_pipeline = create_effect_pipeline(&device, "Bloom Blur Pipeline", BLOOM_BLUR_SHADER,
+                                                   source_layout, &params_layout, LINEAR_FORMAT);
+        let composite_pipeline = create_effect_pipeline(&device, "Bloom Composite Pipeline", BLOOM_SHADER,
+                                                        source_layout, &composite_layout, format);
+
+        let create_uniform = |label: &str|{
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor{
+                label: Some(label),
+                size: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor{
+                label: Some(label),
+                layout: &params_layout,
+                entries: &[
+                    wgpu::BindGroupEntry{ binding: 0, resource: buffer.as_entire_binding() },
+                ]
+            });
+            (buffer, bind_group)
+        };
+        let (params_buffer, params_bind_group) = create_uniform("Bloom Uniform Buffer");
+        let (horizontal_buffer, horizontal_bind_group) = create_uniform("Bloom Horizontal Blur Buffer");
+        let (vertical_buffer, vertical_bind_group) = create_uniform("Bloom Vertical Blur Buffer");
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor{
+            label: Some("Bloom Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let (width, height) = Self::get_target_size(width, height);
+
+        Self{
+            prefilter_pipeline,
+            blur_pipeline,
+            composite_pipeline,
+            composite_layout,
+
+            params_buffer,
+            params_bind_group,
+            blur_buffers: [horizontal_buffer, vertical_buffer],
+            blur_bind_groups: [horizontal_bind_group, vertical_bind_group],
+
+            composite_bind_group: None,
+            sampler,
+            width,
+            height,
+
+            _device: device,
+        }
+    }
+
+    // Half the frame's size, as the blur hides the lost detail
+    fn get_target_size(width: u32, height: u32) -> (u32, u32){
+        ((width / 2).max(1), (height / 2).max(1))
+    }
+
+    fn create_composite_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, params_buffer: &wgpu::Buffer,
+                                   sampler: &wgpu::Sampler, bloom: &TransientTarget) -> wgpu::BindGroup{
+        device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some("Bloom Composite Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry{ binding: 0, resource: params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry{ binding: 1, resource: wgpu::BindingResource::TextureView(bloom.get_texture_view()) },
+                wgpu::BindGroupEntry{ binding: 2, resource: wgpu::BindingResource::Sampler(sampler) },
+            ]
+        })
+    }
+
+    /// Acquires targets for the frame's new size from the next frame
+    pub(crate) fn resize(&mut self, width: u32, height: u32){
+        (self.width, self.height) = Self::get_target_size(width, height);
+    }
+
+    // A full-screen pass into one of the targets
+    fn draw_pass(&self, encoder: &mut wgpu::CommandEncoder, label: &str, pipeline: &wgpu::RenderPipeline,
+                 source: &wgpu::BindGroup, params: &wgpu::BindGroup, target: &TransientTarget){
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+            label: Some(label),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment{
+                    view: target.get_texture_view(),
+                    resolve_target: None,
+                    ops: wgpu::Operations{
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store
+                    }
+                })
+            ],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, source, &[]);
+        render_pass.set_bind_group(1, params, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+impl PostEffect for Bloom{
+    fn get_name(&self) -> &'static str{
+        Self::NAME
+    }
+
+    fn update(&self, queue: &wgpu::Queue, settings: &RenderSettings, _frame: u32){
+        let bloom = &settings.bloom;
+        let params = [bloom.threshold.max(0.0), bloom.knee.max(0.0), bloom.intensity.max(0.0), 0.0];
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&params));
+
+        // The radius spreads the taps apart, in texels of the targets
+        let radius = bloom.radius.max(0.0);
+        let directions = [
+            [radius / self.width as f32, 0.0, 0.0, 0.0],
+            [0.0, radius / self.height as f32, 0.0, 0.0],
+        ];
+        for (buffer, direction) in self.blur_buffers.iter().zip(directions.iter()){
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(direction));
+        }
+    }
+
+    fn prepare(&mut self, encoder: &mut wgpu::CommandEncoder, targets: &mut TransientTargets, source: &wgpu::BindGroup){
+        // The prefilter draws into the first, then it's blurred into the second and back
+        let bloom = targets.acquire(self.width, self.height, LINEAR_FORMAT, "Bloom Target");
+        let blur = targets.acquire(self.width, self.height, LINEAR_FORMAT, "Bloom Target");
+        self.draw_pass(encoder, "Bloom Prefilter", &self.prefilter_pipeline, source, &self.params_bind_group, &bloom);
+        self.draw_pass(encoder, "Bloom Horizontal Blur", &self.blur_pipeline, bloom.get_source_bind_group(), &self.blur_bind_groups[0], &blur);
+        self.draw_pass(encoder, "Bloom Vertical Blur", &self.blur_pipeline, blur.get_source_bind_group(), &self.blur_bind_groups[1], &bloom);
+
+        // The composite is recorded before anything else acquires a target, see `PostStack::apply`
+        targets.release(&blur);
+        targets.release(&bloom);
+
+        let id = bloom.get_texture_id();
+        if self.composite_bind_group.as_ref().is_none_or(|(composite_id, _)| *composite_id != id){
+            let bind_group = Self::create_composite_bind_group(&self._device, &self.composite_layout, &self.params_buffer, &self.sampler, &bloom);
+            self.composite_bind_group = Some((id, bind_group));
+        }
+    }
+
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>){
+        let Some((_, composite_bind_group)) = self.composite_bind_group.as_ref() else { return };
+        render_pass.set_pipeline(&self.composite_pipeline);
+        render_pass.set_bind_group(1, composite_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
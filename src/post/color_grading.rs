@@ -0,0 +1,290 @@
+use std::path::Path;
+use anyhow::{anyhow, bail};
+use crate::post::post_stack::{create_effect_pipeline, PostEffect};
+use crate::settings::RenderSettings;
+use crate::utils::handle::Handle;
+
+const COLOR_GRADING_SHADER: &str = include_str!("../../assets/shaders/color_grading.wgsl");
+
+/// # Color Grading LUT
+///
+/// A 3D lookup table mapping display (sRGB) colours to graded ones, with red varying fastest,
+/// then green, then blue
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorGradingLut{
+    size: u32,
+    data: Vec<[u8; 4]>,
+}
+
+impl ColorGradingLut{
+    /// Size of the identity LUT used before one is loaded
+    pub const DEFAULT_SIZE: u32 = 16;
+
+    /// A LUT mapping every colour to itself
+    pub fn identity(size: u32) -> Self{
+        let size = size.max(2);
+        let max = (size - 1) as f32;
+        let data = (0..size * size * size).map(|index|{
+            let (r, g, b) = (index % size, index / size % size, index / (size * size));
+            [r, g, b].map(|channel| (channel as f32 / max * 255.0).round() as u8)
+        }).map(|[r, g, b]| [r, g, b, 255]).collect();
+
+        Self{ size, data }
+    }
+
+    /// # Load
+    ///
+    /// Loads a LUT from a `.cube` file, or from an image in any other format (see `from_image`)
+    pub fn load<T: AsRef<Path>>(path: T) -> anyhow::Result<Self>{
+        let path = path.as_ref();
+        let is_cube = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("cube"));
+
+        if is_cube{
+            Self::from_cube(&std::fs::read_to_string(path)?)
+        }else{
+            Self::from_image(&image::open(path)?.to_rgba8())
+        }
+    }
+
+    /// # From Cube
+    ///
+    /// Parses an Adobe/Resolve `.cube` 3D LUT. 1D LUTs aren't supported
+    pub fn from_cube(source: &str) -> anyhow::Result<Self>{
+        let mut size = None;
+        let mut domain_min = [0.0f32; 3];
+        let mut domain_max = [1.0f32; 3];
+        let mut values: Vec<[f32; 3]> = Vec::new();
+
+        let parse_triple = |words: &[&str]| -> anyhow::Result<[f32; 3]>{
+            match words{
+                [r, g, b] => Ok([r.parse()?, g.parse()?, b.parse()?]),
+                _ => bail!("Expected 3 values, found {}", words.len()),
+            }
+        };
+
+        for line in source.lines(){
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#'){
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[0]{
+                "TITLE" => {},
+                "LUT_3D_SIZE" => size = Some(words.get(1).ok_or_else(|| anyhow!("Missing LUT_3D_SIZE value"))?.parse::<u32>()?),
+                "LUT_1D_SIZE" => bail!("1D LUTs aren't supported"),
+                "DOMAIN_MIN" => domain_min = parse_triple(&words[1..])?,
+                "DOMAIN_MAX" => domain_max = parse_triple(&words[1..])?,
+                _ => values.push(parse_triple(&words)?),
+            }
+        }
+
+        let size = size.ok_or_else(|| anyhow!("Missing LUT_3D_SIZE"))?;
+        if size < 2 || values.len() != (size * size * size) as usize{
+            bail!("Expected {} entries for a LUT of size {}, found {}", size * size * size, size, values.len());
+        }
+
+        let data = values.iter().map(|value|{
+            let [r, g, b] = [0, 1, 2].map(|channel|{
+                let normalised = (value[channel] - domain_min[channel]) / (domain_max[channel] - domain_min[channel]);
+                (normalised.clamp(0.0, 1.0) * 255.0).round() as u8
+            });
+            [r, g, b, 255]
+        }).collect();
+
+        Ok(Self{ size, data })
+    }
+
+    /// # From Image
+    ///
+    /// Reads a LUT laid out as a horizontal strip of square slices, e.g 256x16 for a size of 16.
+    /// Red increases along each slice, green down it, and blue from one slice to the next
+    pub fn from_image(image: &image::RgbaImage) -> anyhow::Result<Self>{
+        let size = image.height();
+        if size < 2 || image.width() != size * size{
+            bail!("Expected a {}x{} strip of slices, found {}x{}", size * size, size, image.width(), image.height());
+        }
+
+        let data = (0..size * size * size).map(|index|{
+            let (r, g, b) = (index % size, index / size % size, index / (size * size));
+            let pixel = image.get_pixel(b * size + r, g).0;
+            [pixel[0], pixel[1], pixel[2], 255]
+        }).collect();
+
+        Ok(Self{ size, data })
+    }
+
+    pub fn get_size(&self) -> u32{
+        self.size
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ColorGradingUniform{
+    // Intensity in x, and the LUT's size in y
+    params: [f32; 4],
+}
+
+/// # Color Grading
+///
+/// Post effect looking the frame's colours up in a 3D LUT, blended with the originals by
+/// `ColorGradingSettings::intensity`. Enabled by the `color_grading` post effect
+pub(crate) struct ColorGrading{
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    lut_sampler: wgpu::Sampler,
+    // Kept so the effect can be recreated with the same LUT
+    lut: ColorGradingLut,
+
+    _device: Handle<wgpu::Device>,
+    _queue: Handle<wgpu::Queue>
+}
+
+impl ColorGrading{
+    pub(crate) const NAME: &'static str = "color_grading";
+
+    pub(crate) fn new(device: Handle<wgpu::Device>, queue: Handle<wgpu::Queue>, source_layout: &wgpu::BindGroupLayout, format: wgpu::TextureFormat) -> Self{
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Color Grading Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture{
+                        sample_type: wgpu::TextureSampleType::Float{ filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None
+                },
+            ]
+        });
+
+        let pipeline = create_effect_pipeline(&device, "Color Grading Pipeline", COLOR_GRADING_SHADER,
+                                              source_layout, &bind_group_layout, format);
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("Color Grading Uniform Buffer"),
+            size: std::mem::size_of::<ColorGradingUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let lut_sampler = device.create_sampler(&wgpu::SamplerDescriptor{
+            label: Some("Color Grading LUT Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let lut = ColorGradingLut::identity(ColorGradingLut::DEFAULT_SIZE);
+        let bind_group = Self::create_bind_group(&device, &queue, &bind_group_layout, &uniform_buffer, &lut_sampler, &lut);
+
+        Self{
+            pipeline,
+            bind_group_layout,
+            bind_group,
+            uniform_buffer,
+            lut_sampler,
+            lut,
+
+            _device: device,
+            _queue: queue
+        }
+    }
+
+    fn create_bind_group(device: &wgpu::Device, queue: &wgpu::Queue, layout: &wgpu::BindGroupLayout, uniform_buffer: &wgpu::Buffer,
+                         sampler: &wgpu::Sampler, lut: &ColorGradingLut) -> wgpu::BindGroup{
+        let size = wgpu::Extent3d{
+            width: lut.size,
+            height: lut.size,
+            depth_or_array_layers: lut.size,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor{
+            label: Some("Color Grading LUT"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture{
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&lut.data),
+            wgpu::ImageDataLayout{
+                offset: 0,
+                bytes_per_row: Some(lut.size * 4),
+                rows_per_image: Some(lut.size),
+            },
+            size
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some("Color Grading Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry{ binding: 0, resource: uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry{ binding: 1, resource: wgpu::BindingResource::TextureView(&view) },
+                wgpu::BindGroupEntry{ binding: 2, resource: wgpu::BindingResource::Sampler(sampler) },
+            ]
+        })
+    }
+
+    /// Replaces the LUT, uploading it to a new 3D texture
+    pub(crate) fn set_lut(&mut self, lut: &ColorGradingLut){
+        self.bind_group = Self::create_bind_group(&self._device, &self._queue, &self.bind_group_layout, &self.uniform_buffer, &self.lut_sampler, lut);
+        self.lut = lut.clone();
+    }
+
+    pub(crate) fn get_lut(&self) -> &ColorGradingLut{
+        &self.lut
+    }
+}
+
+impl PostEffect for ColorGrading{
+    fn get_name(&self) -> &'static str{
+        Self::NAME
+    }
+
+    fn update(&self, queue: &wgpu::Queue, settings: &RenderSettings, _frame: u32){
+        let uniform = ColorGradingUniform{
+            params: [settings.color_grading.intensity.clamp(0.0, 1.0), self.lut.size as f32, 0.0, 0.0],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>){
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
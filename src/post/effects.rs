@@ -0,0 +1,120 @@
+use crate::post::post_stack::{create_effect_pipeline, PostEffect};
+use crate::settings::{OutputEncoding, RenderSettings};
+use crate::utils::handle::Handle;
+
+const VIGNETTE_SHADER: &str = include_str!("../../assets/shaders/vignette.wgsl");
+const FILM_GRAIN_SHADER: &str = include_str!("../../assets/shaders/film_grain.wgsl");
+const CHROMATIC_ABERRATION_SHADER: &str = include_str!("../../assets/shaders/chromatic_aberration.wgsl");
+const OUTPUT_ENCODING_SHADER: &str = include_str!("../../assets/shaders/output_encoding.wgsl");
+
+/// Builds an effect's parameters from the settings and the frame number
+type ParamsFn = Box<dyn Fn(&RenderSettings, u32) -> [f32; 4]>;
+
+/// # Uniform Effect
+///
+/// A post effect whose only binding is a uniform of four parameters, taken from the settings each frame
+pub(crate) struct UniformEffect{
+    name: &'static str,
+    params: ParamsFn,
+
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+}
+
+impl UniformEffect{
+    pub(crate) fn new(device: &wgpu::Device, name: &'static str, fragment_source: &str, params: ParamsFn,
+                      source_layout: &wgpu::BindGroupLayout, format: wgpu::TextureFormat) -> Self{
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some(name),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer{
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+            ]
+        });
+
+        let pipeline = create_effect_pipeline(device, name, fragment_source, source_layout, &bind_group_layout, format);
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some(name),
+            size: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some(name),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry{ binding: 0, resource: uniform_buffer.as_entire_binding() },
+            ]
+        });
+
+        Self{
+            name,
+            params,
+
+            pipeline,
+            bind_group,
+            uniform_buffer,
+        }
+    }
+
+    /// Darkens the frame towards its corners. Enabled by the `vignette` post effect
+    pub(crate) fn vignette(device: &Handle<wgpu::Device>, source_layout: &wgpu::BindGroupLayout, format: wgpu::TextureFormat) -> Self{
+        Self::new(device, "vignette", VIGNETTE_SHADER, Box::new(|settings, _|{
+            let vignette = &settings.vignette;
+            [vignette.intensity.clamp(0.0, 1.0), vignette.radius, vignette.smoothness, 0.0]
+        }), source_layout, format)
+    }
+
+    /// Adds noise changing every frame. Enabled by the `film_grain` post effect
+    pub(crate) fn film_grain(device: &Handle<wgpu::Device>, source_layout: &wgpu::BindGroupLayout, format: wgpu::TextureFormat) -> Self{
+        Self::new(device, "film_grain", FILM_GRAIN_SHADER, Box::new(|settings, frame|{
+            let grain = &settings.film_grain;
+            // The seed wraps well before floats lose precision
+            [grain.intensity, grain.size, (frame % 1024) as f32, 0.0]
+        }), source_layout, format)
+    }
+
+    /// Splits the red and blue channels apart towards the edges. Enabled by the `chromatic_aberration` post effect
+    pub(crate) fn chromatic_aberration(device: &Handle<wgpu::Device>, source_layout: &wgpu::BindGroupLayout, format: wgpu::TextureFormat) -> Self{
+        Self::new(device, "chromatic_aberration", CHROMATIC_ABERRATION_SHADER, Box::new(|settings, _|{
+            [settings.chromatic_aberration.intensity, 0.0, 0.0, 0.0]
+        }), source_layout, format)
+    }
+
+    /// Writes the linear frame to the output, encoding it as `RenderSettings::output_encoding` says.
+    /// Always the last pass when drawing to a linear target
+    pub(crate) fn output_encoding(device: &Handle<wgpu::Device>, source_layout: &wgpu::BindGroupLayout, output_format: wgpu::TextureFormat) -> Self{
+        let srgb_output = if output_format.is_srgb() { 1.0 } else { 0.0 };
+        Self::new(device, "output_encoding", OUTPUT_ENCODING_SHADER, Box::new(move |settings, _|{
+            let apply_curve = if settings.output_encoding == OutputEncoding::ShaderSrgb { 1.0 } else { 0.0 };
+            [apply_curve, srgb_output, 0.0, 0.0]
+        }), source_layout, output_format)
+    }
+}
+
+impl PostEffect for UniformEffect{
+    fn get_name(&self) -> &'static str{
+        self.name
+    }
+
+    fn update(&self, queue: &wgpu::Queue, settings: &RenderSettings, frame: u32){
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&(self.params)(settings, frame)));
+    }
+
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>){
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
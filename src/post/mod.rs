@@ -0,0 +1,6 @@
+pub mod post_stack;
+pub mod color_grading;
+pub mod effects;
+pub mod bloom;
+pub mod blit;
+pub mod transient_targets;
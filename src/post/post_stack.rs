@@ -0,0 +1,297 @@
+use log::error;
+use crate::managers::sampler_cache::SamplerCache;
+use crate::post::bloom::Bloom;
+use crate::post::color_grading::{ColorGrading, ColorGradingLut};
+use crate::post::effects::UniformEffect;
+use crate::post::transient_targets::{TransientTarget, TransientTargets};
+use crate::settings::RenderSettings;
+use crate::types::fullscreen_pass::FULLSCREEN_TRIANGLE_WGSL;
+use crate::utils::handle::Handle;
+
+const SOURCE_SHADER: &str = include_str!("../../assets/shaders/post_source.wgsl");
+
+/// Format of the targets when the scene is drawn in linear, see `OutputEncoding`
+pub(crate) const LINEAR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// # Post Effect
+///
+/// A full-screen pass of the post-processing stack. It reads the frame drawn so far from group 0
+/// (bound by the stack), and draws the result into the next target
+pub(crate) trait PostEffect{
+    /// Name the effect is enabled by in `RenderSettings::post_effects`
+    fn get_name(&self) -> &'static str;
+
+    /// Writes the effect's parameters from the settings, before it's drawn. The frame number
+    /// increases each time the stack is applied, for effects that animate
+    fn update(&self, queue: &wgpu::Queue, settings: &RenderSettings, frame: u32);
+
+    /// Records any passes the effect needs before it's drawn, e.g into targets acquired from `targets`.
+    /// The source is the frame drawn so far, bound as group 0
+    fn prepare(&mut self, _encoder: &mut wgpu::CommandEncoder, _targets: &mut TransientTargets, _source: &wgpu::BindGroup){}
+
+    /// Sets the effect's pipeline and bindings, and draws the full-screen triangle
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>);
+}
+
+/// # Create Effect Pipeline
+///
+/// Creates a post effect's pipeline from its fragment shader, which is appended to the shared
+/// full-screen vertex shader. The effect's own bindings go in group 1
+pub(crate) fn create_effect_pipeline(device: &wgpu::Device, label: &str, fragment_source: &str,
+                                     source_layout: &wgpu::BindGroupLayout, effect_layout: &wgpu::BindGroupLayout,
+                                     format: wgpu::TextureFormat) -> wgpu::RenderPipeline{
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+        label: Some(label),
+        bind_group_layouts: &[source_layout, effect_layout],
+        push_constant_ranges: &[],
+    });
+
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor{
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(format!("{}\n{}\n{}", FULLSCREEN_TRIANGLE_WGSL, SOURCE_SHADER, fragment_source).into())
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor{
+        label: Some(label),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState{
+            module: &shader_module,
+            entry_point: "vertex_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState{
+            module: &shader_module,
+            entry_point: "fragment_main",
+            targets: &[Some(wgpu::ColorTargetState{
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+/// # Post Effects
+///
+/// Every effect of the stack, built for the format of the targets they draw into
+struct PostEffects{
+    bloom: Bloom,
+    chromatic_aberration: UniformEffect,
+    color_grading: ColorGrading,
+    vignette: UniformEffect,
+    film_grain: UniformEffect,
+    // Last pass when the scene is drawn into a linear target, built for the output's format
+    output_encoding: UniformEffect,
+}
+
+impl PostEffects{
+    fn new(device: &Handle<wgpu::Device>, queue: &Handle<wgpu::Queue>, source_layout: &wgpu::BindGroupLayout,
+           format: wgpu::TextureFormat, output_format: wgpu::TextureFormat, (width, height): (u32, u32)) -> Self{
+        Self{
+            bloom: Bloom::new(device.clone(), source_layout, format, width, height),
+            chromatic_aberration: UniformEffect::chromatic_aberration(device, source_layout, format),
+            color_grading: ColorGrading::new(device.clone(), queue.clone(), source_layout, format),
+            vignette: UniformEffect::vignette(device, source_layout, format),
+            film_grain: UniformEffect::film_grain(device, source_layout, format),
+            output_encoding: UniformEffect::output_encoding(device, source_layout, output_format),
+        }
+    }
+
+    // In the order they're applied: bloom while the frame is still linear, lens effects, then grading,
+    // then what's on top of the graded image
+    fn get_effects(&self) -> [&dyn PostEffect; 5]{
+        [&self.bloom, &self.chromatic_aberration, &self.color_grading, &self.vignette, &self.film_grain]
+    }
+
+    // The effects enabled in the settings, in order, then the output encoding pass if the frame is drawn in linear
+    fn get_enabled_mut(&mut self, settings: &RenderSettings, encode_output: bool) -> Vec<&mut dyn PostEffect>{
+        let mut effects: Vec<&mut dyn PostEffect> = [
+            &mut self.bloom as &mut dyn PostEffect, &mut self.chromatic_aberration, &mut self.color_grading, &mut self.vignette, &mut self.film_grain
+        ].into_iter()
+            .filter(|effect| settings.is_post_effect_enabled(effect.get_name()))
+            .collect();
+        if encode_output{
+            effects.push(&mut self.output_encoding);
+        }
+        effects
+    }
+}
+
+/// # Post Stack
+///
+/// Full-screen effects applied to the frame after the scene is drawn. While any effect is enabled,
+/// the scene is drawn into an intermediate target instead of the output, and each effect reads
+/// the previous one's result, the last writing to the output.
+///
+/// With a linear `OutputEncoding` the stack is always used: the scene and effects draw into float
+/// targets, and a final pass encodes the result into the output
+pub(crate) struct PostStack{
+    // The scene target and the effects' intermediate targets, shared by passes that don't overlap
+    targets: TransientTargets,
+    // Acquired by `begin_frame` for the scene to be drawn into, and read by the first effect
+    scene_target: Option<Handle<TransientTarget>>,
+
+    effects: PostEffects,
+    // Number of frames the stack has been applied to
+    frame: u32,
+
+    // Format of the targets, either the output's or `LINEAR_FORMAT`
+    format: wgpu::TextureFormat,
+    output_format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+
+    _device: Handle<wgpu::Device>,
+    _queue: Handle<wgpu::Queue>
+}
+
+impl PostStack{
+    pub(crate) fn new(device: Handle<wgpu::Device>, queue: Handle<wgpu::Queue>, samplers: Handle<SamplerCache>, output_format: wgpu::TextureFormat, width: u32, height: u32) -> Self{
+        let (width, height) = (width.max(1), height.max(1));
+
+        let targets = TransientTargets::new(device.clone(), samplers);
+        let effects = PostEffects::new(&device, &queue, targets.get_source_layout(), output_format, output_format, (width, height));
+
+        Self{
+            targets,
+            scene_target: None,
+
+            effects,
+            frame: 0,
+
+            format: output_format,
+            output_format,
+            width,
+            height,
+
+            _device: device,
+            _queue: queue
+        }
+    }
+
+    /// Draws at the new size from the next frame, when the targets of the old size are dropped. Zero sizes are ignored
+    pub(crate) fn resize(&mut self, width: u32, height: u32){
+        if width == 0 || height == 0 || (width == self.width && height == self.height){
+            return;
+        }
+
+        self.width = width;
+        self.height = height;
+        self.effects.bloom.resize(width, height);
+    }
+
+    /// Picks up changes to the settings that need more than a uniform write, e.g a new LUT file
+    /// or switching between drawing into the output's format and a linear one
+    pub(crate) fn apply_settings(&mut self, previous: &RenderSettings, settings: &RenderSettings){
+        let format = if settings.output_encoding.is_linear() { LINEAR_FORMAT } else { self.output_format };
+        if format != self.format{
+            self.format = format;
+
+            // The effects' pipelines are built for the target format, so are recreated keeping the LUT
+            let lut = self.effects.color_grading.get_lut().clone();
+            self.effects = PostEffects::new(&self._device, &self._queue, self.targets.get_source_layout(), format, self.output_format, (self.width, self.height));
+            self.effects.color_grading.set_lut(&lut);
+        }
+
+        if settings.color_grading.lut != previous.color_grading.lut{
+            let lut = match settings.color_grading.lut.as_ref(){
+                Some(path) => ColorGradingLut::load(path),
+                None => Ok(ColorGradingLut::identity(ColorGradingLut::DEFAULT_SIZE)),
+            };
+
+            match lut{
+                Ok(lut) => self.effects.color_grading.set_lut(&lut),
+                Err(e) => error!("Failed to load colour grading LUT {:?}: {}", settings.color_grading.lut, e),
+            }
+        }
+    }
+
+    pub(crate) fn set_color_grading_lut(&mut self, lut: &ColorGradingLut){
+        self.effects.color_grading.set_lut(lut);
+    }
+
+    /// Whether the scene has to be drawn into the target `begin_frame` returns rather than the output
+    pub(crate) fn is_active(&self, settings: &RenderSettings) -> bool{
+        settings.output_encoding.is_linear()
+            || self.effects.get_effects().iter().any(|effect| settings.is_post_effect_enabled(effect.get_name()))
+    }
+
+    /// # Begin Frame
+    ///
+    /// Returns the target the scene has to be drawn into rather than the output, or `None` if the stack
+    /// isn't active, in which case its targets are dropped until it is again
+    pub(crate) fn begin_frame(&mut self, settings: &RenderSettings) -> Option<Handle<TransientTarget>>{
+        // Left over if the last frame wasn't applied
+        if let Some(scene_target) = self.scene_target.take(){
+            self.targets.release(&scene_target);
+        }
+
+        if !self.is_active(settings){
+            self.scene_target = None;
+            self.targets.clear();
+            return None;
+        }
+
+        let scene_target = self.targets.acquire(self.width, self.height, self.format, "Post Scene Target");
+        self.scene_target = Some(scene_target.clone());
+        Some(scene_target)
+    }
+
+    /// Format of the target the scene is drawn into while the stack is active
+    pub(crate) fn get_scene_format(&self) -> wgpu::TextureFormat{
+        self.format
+    }
+
+    /// # Apply
+    ///
+    /// Records a pass for each enabled effect, reading the scene from the target `begin_frame` returned and
+    /// writing the final result to the output. Each effect draws into a target acquired for it and the one
+    /// it read is released, so the targets ping-pong without being tied to a pair. Does nothing if the stack isn't active
+    pub(crate) fn apply(&mut self, encoder: &mut wgpu::CommandEncoder, settings: &RenderSettings, output: &wgpu::TextureView){
+        let Some(mut source) = self.scene_target.take() else { return };
+        self.frame = self.frame.wrapping_add(1);
+
+        let mut effects = self.effects.get_enabled_mut(settings, self.format != self.output_format);
+        let effect_count = effects.len();
+        for (index, effect) in effects.iter_mut().enumerate(){
+            effect.update(&self._queue, settings, self.frame);
+
+            // Acquired before the effect prepares, so the targets it releases aren't handed out for its own pass
+            let target = (index + 1 < effect_count).then(|| self.targets.acquire(self.width, self.height, self.format, "Post Target"));
+            effect.prepare(encoder, &mut self.targets, source.get_source_bind_group());
+
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor{
+                    label: Some(effect.get_name()),
+                    color_attachments: &[
+                        Some(wgpu::RenderPassColorAttachment{
+                            view: target.as_ref().map_or(output, |target| target.get_texture_view()),
+                            resolve_target: None,
+                            ops: wgpu::Operations{
+                                load: wgpu::LoadOp::Load,
+                                store: wgpu::StoreOp::Store
+                            }
+                        })
+                    ],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+
+                render_pass.set_bind_group(0, source.get_source_bind_group(), &[]);
+                effect.draw(&mut render_pass);
+            }
+
+            self.targets.release(&source);
+            if let Some(target) = target{
+                source = target;
+            }
+        }
+
+        self.targets.end_frame();
+    }
+}
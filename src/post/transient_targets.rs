@@ -0,0 +1,163 @@
+use crate::managers::sampler_cache::SamplerCache;
+use crate::types::texture::Texture;
+use crate::utils::handle::Handle;
+
+/// # Transient Target
+///
+/// A target the post effects draw into for part of a frame, and the bind group reading it as a pass's source
+pub(crate) struct TransientTarget{
+    texture: Texture,
+    source_bind_group: wgpu::BindGroup,
+}
+
+impl TransientTarget{
+    pub(crate) fn get_texture_view(&self) -> &wgpu::TextureView{
+        self.texture.get_texture_view()
+    }
+
+    /// Tells targets apart, e.g to know when a bind group reading one has to be remade
+    pub(crate) fn get_texture_id(&self) -> wgpu::Id<wgpu::Texture>{
+        self.texture.get_raw_texture().global_id()
+    }
+
+    /// The target bound as group 0 of a post pass, see `PostEffect`
+    pub(crate) fn get_source_bind_group(&self) -> &wgpu::BindGroup{
+        &self.source_bind_group
+    }
+}
+
+struct TransientEntry{
+    target: Handle<TransientTarget>,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    // Acquired and not yet released, so it can't be handed out again
+    in_use: bool,
+    // Acquired at some point since the last `end_frame`
+    used: bool,
+}
+
+/// # Transient Targets
+///
+/// The intermediate targets of the post stack, e.g its ping-pong targets and the bloom targets. Passes
+/// acquire a target for as long as they read or write it and release it after, and a released target is
+/// handed to the next pass asking for the same size and format, so targets whose use doesn't overlap
+/// share the same memory. Targets are only made when a pass first asks for them, and dropped after a
+/// frame that didn't use them, e.g once the effects needing them are turned off or the frame was resized
+pub(crate) struct TransientTargets{
+    entries: Vec<TransientEntry>,
+    source_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+
+    samplers: Handle<SamplerCache>,
+    _device: Handle<wgpu::Device>,
+}
+
+impl TransientTargets{
+    pub(crate) fn new(device: Handle<wgpu::Device>, samplers: Handle<SamplerCache>) -> Self{
+        let source_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Post Source Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry{
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture{
+                        sample_type: wgpu::TextureSampleType::Float{ filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry{
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None
+                },
+            ]
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor{
+            label: Some("Post Source Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self{
+            entries: Vec::new(),
+            source_layout,
+            sampler,
+
+            samplers,
+            _device: device,
+        }
+    }
+
+    /// Layout of the group 0 every post pass reads its source from
+    pub(crate) fn get_source_layout(&self) -> &wgpu::BindGroupLayout{
+        &self.source_layout
+    }
+
+    /// # Acquire
+    ///
+    /// Returns a target of the size and format that no other pass is using, making one if there isn't.
+    /// Its contents are whatever the last pass using it left, so the first pass drawing into it should clear it
+    pub(crate) fn acquire(&mut self, width: u32, height: u32, format: wgpu::TextureFormat, label: &str) -> Handle<TransientTarget>{
+        let free = self.entries.iter_mut()
+            .find(|entry| !entry.in_use && entry.width == width && entry.height == height && entry.format == format);
+        if let Some(entry) = free{
+            entry.in_use = true;
+            entry.used = true;
+            return entry.target.clone();
+        }
+
+        let texture = Texture::create_screen_texture(&self._device, &self.samplers, width, height, format, label);
+        let source_bind_group = self._device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some("Post Source Bind Group"),
+            layout: &self.source_layout,
+            entries: &[
+                wgpu::BindGroupEntry{ binding: 0, resource: wgpu::BindingResource::TextureView(texture.get_texture_view()) },
+                wgpu::BindGroupEntry{ binding: 1, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+            ]
+        });
+
+        let target = Handle::new(TransientTarget{
+            texture,
+            source_bind_group,
+        });
+        self.entries.push(TransientEntry{
+            target: target.clone(),
+            width,
+            height,
+            format,
+            in_use: true,
+            used: true,
+        });
+
+        target
+    }
+
+    /// Lets passes recorded after this one reuse the target. Passes already recorded still read and
+    /// write it in order, so it can be released as soon as the last pass using it is recorded
+    pub(crate) fn release(&mut self, target: &Handle<TransientTarget>){
+        let id = target.get_texture_id();
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.target.get_texture_id() == id){
+            entry.in_use = false;
+        }
+    }
+
+    /// Releases every target, and drops those the frame didn't use
+    pub(crate) fn end_frame(&mut self){
+        self.entries.retain(|entry| entry.used);
+        for entry in self.entries.iter_mut(){
+            entry.in_use = false;
+            entry.used = false;
+        }
+    }
+
+    /// Drops every target, e.g while no effect is enabled
+    pub(crate) fn clear(&mut self){
+        self.entries.clear();
+    }
+}
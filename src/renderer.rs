@@ -1,27 +1,61 @@
-use std::collections::HashMap;
+use std::time::Instant;
 use log::{error, info};
-use wgpu::StoreOp;
-use winit::event::{Event, WindowEvent};
+use winit::event::{ElementState, Event, KeyEvent, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
 use crate::device_handle::DeviceHandle;
 use crate::utils::{handle::Handle, mut_handle::MutHandle};
 use crate::instance_handle::InstanceHandle;
 use crate::surface_wrapper::SurfaceWrapper;
+use crate::stats::{CpuTimings, FrameStats, GpuTimer, ScopeTimer, StatsHistory};
+use crate::state_dump::StateDump;
+use crate::overlay::text_overlay::{OverlayPanel, TextOverlay};
+use crate::overlay::resource_inspector::ResourceInspector;
+use crate::overlay::stats_overlay::StatsOverlay;
+use crate::overlay::log_console::LogConsole;
+use crate::settings::{RenderSettings, SettingsWatcher};
+use crate::scene_batches::SceneBatches;
+use crate::frame_context::CustomDrawFn;
+use crate::scene_frame::SceneFrame;
+use crate::screen_attachments::ScreenAttachments;
+use crate::post::color_grading::ColorGradingLut;
+use crate::post::post_stack::PostStack;
+use crate::post::blit::{Blitter, FULL_RECT};
+use crate::types::camera::CameraTarget;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::pipeline::DEFAULT_COLOR_FORMAT;
+use crate::types::texture::Texture;
+use crate::input::{TextInput, TextInputEvent};
+use crate::window_settings::{apply_cursor_mode, load_window_icon, CursorMode, WindowSettings};
 
-use winit::window::{Window, WindowBuilder};
+use winit::window::{CursorIcon, Window, WindowBuilder};
 use winit::event_loop::{ControlFlow, EventLoop};
-use crate::managers::resource_handle::ResourceHandle;
 use crate::managers::resource_manager::ResourceManager;
-use crate::types::model::Model;
-use crate::types::renderable::Renderable;
+use crate::debug::{self, DebugSettings};
 
 
 pub struct RenderFramework<T>{
     state: T, // Persistent state
     init: fn(&mut T, &mut Renderer) -> (),
     update: fn(&mut T, &mut Renderer) -> (),
+    loading: Option<LoadingPhase<T>>,
     renderer: Renderer
 }
 
+// Runs before `init`, drawing a loading scene while assets load
+struct LoadingPhase<T>{
+    start: fn(&mut T, &mut Renderer) -> (),
+    update: fn(&mut T, &mut Renderer) -> bool,
+}
+
+// The state of a framework with a loading phase, which swaps from the loading update to `init` and `update`
+struct StagedState<T>{
+    state: T,
+    init: fn(&mut T, &mut Renderer) -> (),
+    update: fn(&mut T, &mut Renderer) -> (),
+    loading_update: fn(&mut T, &mut Renderer) -> bool,
+    loaded: bool,
+}
+
 impl<T> RenderFramework<T>{
     pub fn new(
         state: T,
@@ -33,14 +67,53 @@ impl<T> RenderFramework<T>{
             state,
             init,
             update,
+            loading: None,
             renderer
         }
     }
 
+    /// # With Loading Phase
+    ///
+    /// Shows a loading scene instead of a blank window while assets load. `start` runs first, to set up
+    /// a lightweight loading scene and kick off async loads (e.g `load_texture_async` or a `ResourceQueue`).
+    /// `update` then runs every frame, drawing the loading scene, until it returns true, e.g once
+    /// `are_resources_ready` does. `init` runs after that, followed by the usual update each frame
+    pub fn with_loading_phase(mut self, start: fn(&mut T, &mut Renderer) -> (), update: fn(&mut T, &mut Renderer) -> bool) -> Self{
+        self.loading = Some(LoadingPhase{ start, update });
+        self
+    }
+
     pub fn run(mut self){
-        (self.init)(&mut self.state, &mut self.renderer);
-        self.renderer.run(self.state, self.update);
+        let Some(loading) = self.loading.take() else {
+            (self.init)(&mut self.state, &mut self.renderer);
+            self.renderer.run(self.state, self.update);
+            return;
+        };
+
+        (loading.start)(&mut self.state, &mut self.renderer);
+        let staged = StagedState{
+            state: self.state,
+            init: self.init,
+            update: self.update,
+            loading_update: loading.update,
+            loaded: false,
+        };
+        self.renderer.run(staged, update_staged::<T>);
+    }
+}
+
+fn update_staged<T>(staged: &mut StagedState<T>, renderer: &mut Renderer){
+    if !staged.loaded{
+        if !(staged.loading_update)(&mut staged.state, renderer){
+            return;
+        }
+
+        info!("Loading finished, initialising the scene");
+        staged.loaded = true;
+        (staged.init)(&mut staged.state, renderer);
     }
+
+    (staged.update)(&mut staged.state, renderer);
 }
 
 
@@ -54,19 +127,56 @@ pub struct Renderer{
     event_loop: Option<EventLoop<()>>,
 
     resource_manager: MutHandle<ResourceManager>,
+
+    // Frame statistics
+    stats: FrameStats,
+    stats_history: StatsHistory,
+    gpu_timer: Option<GpuTimer>,
+    // Time spent updating resources before this frame, measured outside of render
+    bind_group_update_ms: f32,
+    last_frame_start: Option<Instant>,
+    // When the renderer was created, for the time in the scene uniform
+    start_time: Instant,
+
+    // Debug overlays
+    overlay: TextOverlay,
+    show_debug_overlay: bool,
+    show_resource_inspector: bool,
+    show_log_console: bool,
+    // Key toggling the log console, if any
+    log_console_key: Option<KeyCode>,
+
+    text_input: TextInput,
+
+    // Runtime-tweakable settings, optionally reloaded from a file
+    settings: RenderSettings,
+    settings_watcher: Option<SettingsWatcher>,
+
+    // Screen-sized render targets, resized with the surface
+    screen_attachments: ScreenAttachments,
+    // Post effects applied between the scene and the overlays
+    post_stack: PostStack,
+    // Blits drawn after the post effects, see `blit`
+    blitter: Blitter,
+    // User drawing into the scene pass after the models
+    custom_draw: Option<CustomDrawFn>,
+
+    // Submission index of the most recently submitted frame
+    last_submission: Option<wgpu::SubmissionIndex>,
+    // Whether to capture the next frame with an attached graphics debugger
+    capture_next_frame: bool,
 }
 
 impl Renderer{
     pub fn new() -> Self{
-        env_logger::builder()
-            .filter_level(log::LevelFilter::Info)
-            // We keep wgpu at Error level, as it's very noisy.
-            .filter_module("wgpu_core", log::LevelFilter::Info)
-            .filter_module("wgpu_hal", log::LevelFilter::Error)
-            .filter_module("naga", log::LevelFilter::Error)
-            .parse_default_env()
-            .init();
+        Self::with_window_settings(WindowSettings::default())
+    }
 
+    /// # With Window Settings
+    ///
+    /// Creates the renderer with a window set up from the given settings.
+    /// An icon that fails to load is skipped with an error, rather than failing the whole renderer
+    pub fn with_window_settings(window_settings: WindowSettings) -> Self{
         let event_loop = EventLoop::new().unwrap_or_else(
             |e| {
                 error!("Failed to create event loop: {}", e);
@@ -75,8 +185,11 @@ impl Renderer{
         );
 
         let window = WindowBuilder::new()
-            .with_title("Renderer")
-            .with_inner_size(winit::dpi::PhysicalSize::new(1600, 1200))
+            .with_title(window_settings.title.as_str())
+            .with_inner_size(winit::dpi::PhysicalSize::new(window_settings.width, window_settings.height))
+            .with_window_icon(window_settings.icon.as_deref().and_then(|path|{
+                load_window_icon(path).map_err(|e| error!("Failed to load window icon {:?}: {}", path, e)).ok()
+            }))
             .build(&event_loop).unwrap_or_else(
                 |e| {
                     error!("Failed to create window: {}", e);
@@ -84,8 +197,12 @@ impl Renderer{
                 }
             );
 
-        let instance_handler = InstanceHandle::new();
+        window.set_cursor_icon(window_settings.cursor_icon);
+        apply_cursor_mode(&window, window_settings.cursor_mode);
+
+        let instance_handler = InstanceHandle::new(window_settings.gpu_validation.get_instance_flags());
         let device_handle = DeviceHandle::new(&instance_handler);
+        window_settings.gpu_validation.apply_to_device(&device_handle.get_device());
 
         // Set window to be borrowed for the lifetime of the surface
         let window = Handle::new(window);
@@ -101,10 +218,35 @@ impl Renderer{
         let surface_wrapper = SurfaceWrapper::new(surface, &instance_handler, &device_handle, &window);
 
 
-        let resource_manager = MutHandle::new(ResourceManager::new(
+        let mut resource_manager = ResourceManager::new(
             device_handle.get_device(),
             device_handle.get_queue(),
-        ));
+        );
+        resource_manager.set_max_anisotropy(device_handle.get_max_anisotropy());
+        let resource_manager = MutHandle::new(resource_manager);
+
+        let extent = surface_wrapper.get_surface_extent();
+        let samplers = resource_manager.get().get_sampler_cache();
+        let screen_attachments = ScreenAttachments::new(device_handle.get_device(), samplers.clone(), extent.width, extent.height);
+
+        let gpu_timer = GpuTimer::new(&device_handle.get_device(), &device_handle.get_queue());
+
+        let post_stack = PostStack::new(
+            device_handle.get_device(),
+            device_handle.get_queue(),
+            samplers,
+            surface_wrapper.get_configuration().get().format,
+            extent.width,
+            extent.height
+        );
+
+        let blitter = Blitter::new(device_handle.get_device());
+
+        let overlay = TextOverlay::new(
+            device_handle.get_device(),
+            device_handle.get_queue(),
+            surface_wrapper.get_configuration().get().format
+        );
 
         Self{
             instance_handler,
@@ -114,69 +256,70 @@ impl Renderer{
             window,
             event_loop: Some(event_loop),
 
-            resource_manager
-        }
-    }
+            resource_manager,
 
-    pub(crate) fn render(&mut self){
-
-        let rm = self.resource_manager.get();
+            stats: FrameStats::default(),
+            stats_history: StatsHistory::new(StatsHistory::DEFAULT_CAPACITY),
+            bind_group_update_ms: 0.0,
+            gpu_timer,
+            last_frame_start: None,
+            start_time: Instant::now(),
 
-        let models = rm.get_all_models();
+            overlay,
+            show_debug_overlay: false,
+            show_resource_inspector: false,
+            show_log_console: false,
+            log_console_key: Some(KeyCode::Backquote),
 
-        // Prepare the render. We want to create a collection per pipeline, made up
-        // of all the materials that use that pipeline. We then want to render all the
-        // meshes that use that material.
-        //
-        // We can check which meshes use which materials by checking the models
-        //
-        // The materials can be checked by checking the material's shader against the pipeline's shader
+            text_input: TextInput::new(),
 
-        // Pipeline - List of materials that use the pipeline
-        let mut pipeline_materials: HashMap<ResourceHandle, Vec<ResourceHandle>> = HashMap::new();
-        // Material, and the meshes that want to use that material
-        let mut material_meshes: HashMap<ResourceHandle, Vec<Handle<Model>>> = HashMap::new();
+            settings: RenderSettings::default(),
+            settings_watcher: None,
 
-        let pipeline_handles = rm.get_all_pipeline_handles();
-        let material_handles = rm.get_all_material_handles();
+            screen_attachments,
+            post_stack,
+            blitter,
+            custom_draw: None,
 
-        // Generate bind groups for all the materials
-        for material_handle in material_handles.iter(){
-            let mut material = rm.get_material(material_handle).unwrap();
-            material.generate_bind_groups(&rm);
+            last_submission: None,
+            capture_next_frame: false,
         }
+    }
 
+    pub(crate) fn render(&mut self){
+        let frame_start = Instant::now();
 
-        // Populate the pipeline_materials hashmap, and link the materials to the pipelines
-        for pipeline_handle in pipeline_handles.iter(){
-            let pipeline = rm.get_pipeline(pipeline_handle).unwrap();
-            let shader = pipeline.get_shader();
-            for material_handle in material_handles.iter(){
-                let material = rm.get_material(material_handle).unwrap();
-
-                if material.get_shader() == shader{
-                    let materials = pipeline_materials.entry(pipeline_handle.clone()).or_insert_with(Vec::new);
-                    materials.push(material_handle.clone());
-                }
-            }
+        let mut stats = FrameStats::default();
+        if let Some(last_frame_start) = self.last_frame_start{
+            stats.frame_time_ms = frame_start.duration_since(last_frame_start).as_secs_f32() * 1000.0;
+            stats.fps = if stats.frame_time_ms > 0.0 { 1000.0 / stats.frame_time_ms } else { 0.0 };
         }
 
-        // Now we've linked the materials to the pipelines, we can link the meshes to the materials
-        // We don't care about the pipeline at this point, as we can get it from the material
-        for model in models.iter(){
-            let materials = material_meshes.entry(model.get_material().clone()).or_insert_with(Vec::new);
-            materials.push(model.clone());
+        // Run the callbacks for any GPU work that has finished since the last frame
+        self.device_handle.get_device().poll(wgpu::Maintain::Poll);
+
+        // GPU timings are read back a frame or two late, so keep the last result until a new one arrives
+        stats.gpu_time_ms = match self.gpu_timer.as_mut(){
+            Some(gpu_timer) => gpu_timer.try_read(&self.device_handle.get_device()).or(self.stats.gpu_time_ms),
+            None => None
+        };
+
+        let capturing = std::mem::take(&mut self.capture_next_frame);
+        if capturing{
+            info!("Capturing frame");
+            self.device_handle.get_device().start_capture();
         }
 
-        // Now we have a set of materials linked to pipelines, and a set of materials linked to meshes
-        // This means we can link a pipeline, find all the materials that use that pipeline, and then find
-        // all the meshes that use those materials
-        //
-        // This gives us great flexibility in rendering, as we can render all the meshes that use a certain
-        // pipeline, and then render all the meshes that use a different pipeline, without having to worry about
-        // the order of the meshes in the render loop
+        let mut timings = CpuTimings{
+            bind_group_update_ms: self.bind_group_update_ms,
+            ..Default::default()
+        };
+        let mut scope_timer = ScopeTimer::start();
 
+        let rm = self.resource_manager.get();
 
+        let batches = SceneBatches::prepare(&rm);
+        timings.extract_ms = scope_timer.lap();
 
         // Get the current frame from the surface
         let frame = self.surface_wrapper.get_surface().get_current_texture()
@@ -185,6 +328,7 @@ impl Renderer{
                 panic!("Failed to get current frame: {}", e)
             }
         );
+        timings.present_ms = scope_timer.lap();
 
         let output = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -194,63 +338,65 @@ impl Renderer{
             }
         );
 
-        {
-            let mut render_pass = encoder.begin_render_pass(
-                &wgpu::RenderPassDescriptor{
-                    label: Some("Render Pass"),
-                    color_attachments: &[
-                        Some(wgpu::RenderPassColorAttachment{
-                            view: &output,
-                            resolve_target: None,
-                            ops: wgpu::Operations{
-                                load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
-                                store: StoreOp::Store
-                            }
-                        })
-                    ],
-                    depth_stencil_attachment: None,
-                    timestamp_writes: None,
-                    occlusion_query_set: None,
-                }
-            );
-
-            // Using the resource_manager, let's get to rendering
-            for (pipeline_handle, materials) in pipeline_materials.iter(){
-                let pipeline = rm.get_pipeline(pipeline_handle).unwrap();
-                pipeline.render(&mut render_pass);
-
-                for material_handle in materials.iter(){
-                    let material = rm.borrow_material(material_handle);
-
-                    for model in material_meshes.get(material_handle).unwrap_or(&Vec::new()).iter(){
-                        let mesh = rm.get_mesh(model.get_mesh()).unwrap();
+        let extent = self.surface_wrapper.get_surface_extent();
+        let surface_format = self.surface_wrapper.get_configuration().get().format;
+        let mut scene_frame = SceneFrame{
+            output: &output,
+            output_format: surface_format,
+            size: [extent.width, extent.height],
+            attachments: &mut self.screen_attachments,
+            post_stack: &mut self.post_stack,
+            blitter: &mut self.blitter,
+            custom_draw: &mut self.custom_draw,
+            settings: &self.settings,
+        };
+        scene_frame.encode(&rm, &batches, &mut encoder, self.gpu_timer.as_ref().and_then(|gpu_timer| gpu_timer.timestamp_writes()), &mut stats);
+
+        // Draw the debug overlays on top of the scene
+        let mut panels: Vec<OverlayPanel> = Vec::new();
+        if self.show_debug_overlay{
+            // This frame isn't finished yet, so show the last one's stats
+            panels.push(StatsOverlay::build_panel(&self.stats, &rm.get_memory_usage(), [10.0, 10.0]));
+        }
+        if self.show_resource_inspector{
+            let top = panels.last().map_or(10.0, |panel| panel.position[1] + panel.get_size()[1] + 10.0);
+            panels.push(ResourceInspector::build_panel(&rm, [10.0, top]));
+        }
 
-                        let vertex_buffers = rm.get_mesh_vertex_buffers(model.get_mesh()).unwrap();
-                        let index_buffers = rm.get_mesh_index_buffers(model.get_mesh()).unwrap();
+        if self.show_log_console{
+            panels.push(LogConsole::build_panel([extent.width, extent.height]));
+        }
 
-                        let mut temp_update_material = rm.get_material(material_handle).unwrap();
-                        temp_update_material.set_uniform("transform", model.get_transform_uniform_handle(), &rm);
+        if !panels.is_empty(){
+            self.overlay.draw(&mut encoder, &output, [extent.width, extent.height], &panels);
+        }
 
-                        info!("Setting transform!");
-                        let transform = model.get_transform();
-                        info!("Transform: {:?}", transform.get_position());
+        if let Some(gpu_timer) = self.gpu_timer.as_ref(){
+            gpu_timer.resolve(&mut encoder);
+        }
 
-                        material.bind_material(&mut render_pass);
+        let command_buffer = encoder.finish();
+        timings.encode_ms = scope_timer.lap();
 
+        self.last_submission = Some(self.device_handle.get_queue().submit(std::iter::once(command_buffer)));
 
-                        for (idx, submesh) in mesh.get_sub_meshes().iter().enumerate(){
-                            vertex_buffers[idx].bind_vertex_buffer(0, &mut render_pass);
-                            index_buffers[idx].bind_index_buffer(&mut render_pass);
-                            submesh.render(&mut render_pass);
-                        }
-                    }
-                }
-            }
+        if capturing{
+            self.device_handle.get_device().stop_capture();
         }
 
-        self.device_handle.get_queue().submit(std::iter::once(encoder.finish()));
+        if let Some(gpu_timer) = self.gpu_timer.as_mut(){
+            gpu_timer.begin_readback();
+        }
+        timings.submit_ms = scope_timer.lap();
 
         frame.present();
+        timings.present_ms += scope_timer.lap();
+
+        stats.cpu_time_ms = frame_start.elapsed().as_secs_f32() * 1000.0 + timings.bind_group_update_ms;
+        stats.cpu_timings = timings;
+        self.stats = stats;
+        self.stats_history.push(stats);
+        self.last_frame_start = Some(frame_start);
     }
 
     pub fn run<T>(mut self, mut render_state: T, render_func: fn(&mut T, &mut Renderer) -> ()){
@@ -269,27 +415,55 @@ impl Renderer{
                     window_id
                 } => {
                     if window_id == self.window.id(){
+                        self.text_input.handle_event(&event);
+
                         match event{
                             WindowEvent::CloseRequested => {
                                 target.exit();
                             }
+                            WindowEvent::KeyboardInput{ event: KeyEvent{ physical_key: PhysicalKey::Code(key), state: ElementState::Pressed, repeat: false, .. }, .. }
+                                if Some(key) == self.log_console_key => {
+                                self.show_log_console = !self.show_log_console;
+                            }
                             WindowEvent::Resized(new_size) => {
+                                // A minimised window reports a zero size, which can't be rendered to
+                                if new_size.width == 0 || new_size.height == 0{
+                                    return;
+                                }
+
                                 self.surface_wrapper.resize_surface(
                                     &self.device_handle.get_device(),
                                     new_size
                                 );
+                                self.screen_attachments.resize(new_size.width, new_size.height);
+                                self.post_stack.resize(new_size.width, new_size.height);
                                 self.window.request_redraw();
                             }
                             WindowEvent::RedrawRequested => {
+                                // Pick up any changes to the settings file before the frame
+                                if let Some(settings) = self.settings_watcher.as_mut().and_then(|watcher| watcher.poll()){
+                                    self.apply_render_settings(settings);
+                                }
+
+                                // Add anything other threads finished loading, so the render closure can use it
+                                self.resource_manager.get().process_queued_resources();
+
                                 // Run the render closure
                                 render_func(&mut render_state, &mut self);
+                                self.text_input.clear();
 
                                 // Update resources here, as they may have changed
                                 // We need a closure so we drop the mutable borrow of the resource manager
                                 {
-                                    let mut rm = self.resource_manager.get();
-                                    rm.update_model_transforms();
-                                    rm.update_materials();
+                                    let mut scope_timer = ScopeTimer::start();
+
+                                    // Animations advance by the time since the last frame started
+                                    let delta = self.last_frame_start.map(|start| start.elapsed().as_secs_f32()).unwrap_or(0.0);
+
+                                    let extent = self.surface_wrapper.get_surface_extent();
+                                    self.resource_manager.get().update_frame(delta, self.start_time.elapsed().as_secs_f32(), [extent.width, extent.height]);
+
+                                    self.bind_group_update_ms = scope_timer.lap();
                                 }
 
 
@@ -304,6 +478,349 @@ impl Renderer{
         }).expect("TODO: panic message");
     }
 
+    /// # Stats
+    ///
+    /// Returns the statistics for the most recently rendered frame
+    pub fn stats(&self) -> FrameStats{
+        self.stats
+    }
+
+    /// # Stats History
+    ///
+    /// Returns the statistics for the last few rendered frames, oldest first. Averaging
+    /// `cpu_timings` over these with `CpuTimings::average` shows which stage of the frame is slow
+    pub fn get_stats_history(&self) -> impl Iterator<Item = &FrameStats> + '_{
+        self.stats_history.iter()
+    }
+
+    /// Sets how many frames of stats are kept, 120 by default
+    pub fn set_stats_history_length(&mut self, frames: usize){
+        self.stats_history.set_capacity(frames);
+    }
+
+    pub fn get_stats_history_length(&self) -> usize{
+        self.stats_history.get_capacity()
+    }
+
+    /// # Dump State
+    ///
+    /// Snapshots the adapter, surface, settings and resources, e.g to attach to a bug report
+    pub fn dump_state(&self) -> StateDump{
+        StateDump::capture(
+            &self.instance_handler.get_adapter(),
+            Some(&self.surface_wrapper.get_configuration().get()),
+            &self.settings,
+            &self.resource_manager.get()
+        )
+    }
+
+    /// # Trigger GPU Capture
+    ///
+    /// Captures the next frame in an attached graphics debugger, e.g when the app was launched from RenderDoc.
+    /// Does nothing if no debugger is attached. Turn on `GpuValidation::debug` so captures include labels
+    pub fn trigger_gpu_capture(&mut self){
+        self.capture_next_frame = true;
+    }
+
+    /// # Wait Idle
+    ///
+    /// Blocks until the GPU has finished all submitted work. Useful before reading back
+    /// buffers, destroying resources, or capturing a frame deterministically
+    pub fn wait_idle(&self){
+        self.device_handle.get_device().poll(wgpu::Maintain::Wait);
+    }
+
+    /// # Get Last Submission
+    ///
+    /// Returns the submission index of the most recently rendered frame, or `None`
+    /// if no frame has been rendered yet. Pass it to `wait_for_submission` to wait
+    /// for that frame specifically
+    pub fn get_last_submission(&self) -> Option<wgpu::SubmissionIndex>{
+        self.last_submission.clone()
+    }
+
+    /// # Wait For Submission
+    ///
+    /// Blocks until the GPU has finished the given submission (and everything before it)
+    pub fn wait_for_submission(&self, submission: wgpu::SubmissionIndex){
+        self.device_handle.get_device().poll(wgpu::Maintain::WaitForSubmissionIndex(submission));
+    }
+
+    /// # On Submitted Work Done
+    ///
+    /// Registers a callback that runs once the GPU finishes all work submitted so far.
+    /// The callback is run while the device is polled, which happens every frame
+    /// (or immediately on `wait_idle`)
+    pub fn on_submitted_work_done<F: FnOnce() + Send + 'static>(&self, callback: F){
+        self.device_handle.get_queue().on_submitted_work_done(callback);
+    }
+
+    /// # Get Depth Texture
+    ///
+    /// Returns the renderer's screen-sized depth texture. The handle stays valid across
+    /// resizes, but the underlying texture (and its view) is recreated, so views shouldn't be cached.
+    /// It's replaced by a multisampled one, which can't be sampled, when multisampling is turned on
+    pub fn get_depth_texture(&self) -> Handle<Texture>{
+        self.screen_attachments.get_depth()
+    }
+
+    /// # Add Screen Attachment
+    ///
+    /// Adds a named screen-sized colour target (e.g normals) that the renderer keeps
+    /// the same size as the surface
+    pub fn add_screen_attachment(&mut self, name: &str, format: wgpu::TextureFormat) -> Handle<Texture>{
+        self.screen_attachments.add_color_attachment(name, format)
+    }
+
+    pub fn get_screen_attachment(&self, name: &str) -> Option<Handle<Texture>>{
+        self.screen_attachments.get_color_attachment(name)
+    }
+
+    /// # Load Render Settings
+    ///
+    /// Loads render settings from a TOML file, and watches the file so any changes
+    /// made while the renderer is running are applied on the next frame
+    pub fn load_render_settings<T: AsRef<std::path::Path>>(&mut self, path: T) -> anyhow::Result<()>{
+        self.apply_render_settings(RenderSettings::load_from_file(path.as_ref())?);
+        self.settings_watcher = Some(SettingsWatcher::new(path));
+
+        Ok(())
+    }
+
+    pub fn get_render_settings(&self) -> &RenderSettings{
+        &self.settings
+    }
+
+    /// # Set Render Settings
+    ///
+    /// Replaces the current render settings. If a settings file is being watched,
+    /// it will still override these the next time it changes
+    pub fn set_render_settings(&mut self, settings: RenderSettings){
+        self.apply_render_settings(settings);
+    }
+
+    fn apply_render_settings(&mut self, settings: RenderSettings){
+        // Only touch the textures if the level actually changed, so per-texture overrides survive reloads
+        if settings.anisotropy != self.settings.anisotropy{
+            self.resource_manager.get().set_default_anisotropy(settings.anisotropy);
+        }
+        if settings.shadow_resolution != self.settings.shadow_resolution{
+            self.resource_manager.get().set_shadow_resolution(settings.shadow_resolution);
+        }
+        if settings.shadows != self.settings.shadows{
+            self.resource_manager.get().set_shadow_settings(settings.shadows.clone());
+        }
+
+        // The depth buffer and every pipeline drawing into it have to agree on the format
+        if settings.depth_format != self.settings.depth_format{
+            let format = settings.depth_format.resolve(&self.instance_handler.get_adapter());
+            self.screen_attachments.set_depth_format(format);
+            self.resource_manager.get().set_depth_format(format);
+        }
+
+        self.post_stack.apply_settings(&self.settings, &settings);
+        // The linear output modes draw the scene into the stack's float target instead of the swapchain
+        if settings.output_encoding != self.settings.output_encoding{
+            let format = if settings.output_encoding.is_linear() { self.post_stack.get_scene_format() } else { DEFAULT_COLOR_FORMAT };
+            self.resource_manager.get().set_color_format(format);
+        }
+
+        // Multisampling has to be supported by both target formats, so is resolved again when either changes
+        if settings.msaa_samples != self.settings.msaa_samples || settings.depth_format != self.settings.depth_format
+            || settings.output_encoding != self.settings.output_encoding{
+            let mut rm = self.resource_manager.get();
+            let color_format = rm.get_color_format();
+            let samples = settings.resolve_msaa_samples(&self.instance_handler.get_adapter(), &[color_format, rm.get_depth_format()]);
+            self.screen_attachments.set_multisampling(samples, color_format);
+            rm.set_sample_count(samples);
+        }
+
+        self.settings = settings;
+    }
+
+    /// # Set MSAA Samples
+    ///
+    /// Sets the samples per pixel the scene is drawn with, e.g 4 for 4x multisampling, or 1 to turn it off.
+    /// Counts the adapter doesn't support fall back to the highest one below them that it does
+    pub fn set_msaa_samples(&mut self, samples: u32){
+        let mut settings = self.settings.clone();
+        settings.msaa_samples = samples;
+        self.apply_render_settings(settings);
+    }
+
+    /// # Set Default Anisotropy
+    ///
+    /// Sets the anisotropic filtering level for all textures, clamped to what the device supports
+    pub fn set_default_anisotropy(&mut self, anisotropy: u16){
+        let mut settings = self.settings.clone();
+        settings.anisotropy = anisotropy;
+        self.apply_render_settings(settings);
+    }
+
+    /// # Set Custom Draw
+    ///
+    /// Sets a callback that draws into the scene pass every frame, after the models,
+    /// through a `FrameContext`. Pass `None` to remove it
+    pub fn set_custom_draw(&mut self, custom_draw: Option<CustomDrawFn>){
+        self.custom_draw = custom_draw;
+    }
+
+    /// # Blit
+    ///
+    /// Draws a texture over the whole of a render texture or the surface, scaled with the given filter,
+    /// e.g to downsample a target or show an intermediate one on screen. Blits are drawn in the order
+    /// they're requested, once this frame's scene and post effects are, and under the debug overlays
+    pub fn blit(&mut self, source: &ResourceHandle, target: CameraTarget, filter: wgpu::FilterMode){
+        self.blitter.queue(source, FULL_RECT, target, FULL_RECT, filter);
+    }
+
+    /// # Blit Region
+    ///
+    /// Same as `blit`, but copies a region of the source into a region of the target. Each is an offset
+    /// and size from 0 to 1, like a camera's viewport, so `[0.5, 0.0, 0.5, 0.5]` is the top right quarter.
+    /// Equal sized regions with a `Nearest` filter copy the texels exactly
+    pub fn blit_region(&mut self, source: &ResourceHandle, source_rect: [f32; 4], target: CameraTarget, target_rect: [f32; 4], filter: wgpu::FilterMode){
+        self.blitter.queue(source, source_rect, target, target_rect, filter);
+    }
+
+    /// # Set Color Grading LUT
+    ///
+    /// Sets the LUT used by the `color_grading` post effect. Replaced if the settings' LUT file changes
+    pub fn set_color_grading_lut(&mut self, lut: &ColorGradingLut){
+        self.post_stack.set_color_grading_lut(lut);
+    }
+
+    /// # Show Debug Overlay
+    ///
+    /// Shows or hides a small HUD with the frame rate, CPU and GPU times, draw calls,
+    /// triangles and GPU memory. Nothing is built or drawn for it while it's hidden
+    pub fn show_debug_overlay(&mut self, visible: bool){
+        self.show_debug_overlay = visible;
+    }
+
+    pub fn is_debug_overlay_visible(&self) -> bool{
+        self.show_debug_overlay
+    }
+
+    /// # Set Resource Inspector Visible
+    ///
+    /// Shows or hides the built-in resource inspector overlay, which lists loaded
+    /// resources, their GPU memory usage, and any misconfigured materials
+    pub fn set_resource_inspector_visible(&mut self, visible: bool){
+        self.show_resource_inspector = visible;
+    }
+
+    pub fn is_resource_inspector_visible(&self) -> bool{
+        self.show_resource_inspector
+    }
+
+    /// # Set Window Icon
+    ///
+    /// Sets the window icon from an image file, or clears it with `None`
+    pub fn set_window_icon(&mut self, path: Option<&str>) -> anyhow::Result<()>{
+        let icon = path.map(load_window_icon).transpose()?;
+        self.window.set_window_icon(icon);
+
+        Ok(())
+    }
+
+    /// Sets which system cursor is shown over the window
+    pub fn set_cursor_icon(&mut self, cursor_icon: CursorIcon){
+        self.window.set_cursor_icon(cursor_icon);
+    }
+
+    /// # Set Cursor Mode
+    ///
+    /// Shows, hides or locks the cursor, e.g `CursorMode::Locked` for FPS style controls
+    pub fn set_cursor_mode(&mut self, cursor_mode: CursorMode){
+        apply_cursor_mode(&self.window, cursor_mode);
+    }
+
+    /// The window the renderer draws to, for anything not wrapped by the renderer
+    pub fn get_window(&self) -> &Window{
+        &self.window
+    }
+
+    /// # Get Text Input
+    ///
+    /// Text typed into the window since the last frame, including IME composition,
+    /// in the order it was typed
+    pub fn get_text_input(&self) -> &[TextInputEvent]{
+        self.text_input.get_events()
+    }
+
+    /// # Set IME Allowed
+    ///
+    /// Turns IME input on or off, e.g while a text field has focus. It's off by default,
+    /// in which case only `TextInputEvent::Text` is received
+    pub fn set_ime_allowed(&mut self, allowed: bool){
+        self.window.set_ime_allowed(allowed);
+    }
+
+    /// Tells the IME where the text being edited is, in physical pixels, so its candidate window can be placed next to it
+    pub fn set_ime_cursor_area(&mut self, position: [u32; 2], size: [u32; 2]){
+        self.window.set_ime_cursor_area(
+            winit::dpi::PhysicalPosition::new(position[0], position[1]),
+            winit::dpi::PhysicalSize::new(size[0], size[1])
+        );
+    }
+
+    /// Gets the text on the system clipboard, if there is any
+    pub fn get_clipboard_text(&mut self) -> Option<String>{
+        self.text_input.get_clipboard_text()
+    }
+
+    /// Puts text on the system clipboard
+    pub fn set_clipboard_text(&mut self, text: &str) -> anyhow::Result<()>{
+        self.text_input.set_clipboard_text(text)
+    }
+
+    /// # Set Surface Usage
+    ///
+    /// Requests extra usages for the window's textures, e.g `COPY_SRC` for capturing frames or
+    /// `STORAGE_BINDING` for compute post effects writing to them. `RENDER_ATTACHMENT` is always set,
+    /// and usages the adapter doesn't support are skipped. Returns the usages the surface now has
+    pub fn set_surface_usage(&mut self, usage: wgpu::TextureUsages) -> wgpu::TextureUsages{
+        self.surface_wrapper.set_usage(&self.device_handle.get_device(), usage)
+    }
+
+    pub fn get_surface_usage(&self) -> wgpu::TextureUsages{
+        self.surface_wrapper.get_configuration().get().usage
+    }
+
+    /// Usages `set_surface_usage` can request on this adapter
+    pub fn get_supported_surface_usage(&self) -> wgpu::TextureUsages{
+        self.surface_wrapper.get_supported_usages()
+    }
+
+    /// # Set Log Console Visible
+    ///
+    /// Shows or hides the log console overlay, listing the most recent log records.
+    /// It's toggled with the backquote key by default, see `set_log_console_key`
+    pub fn set_log_console_visible(&mut self, visible: bool){
+        self.show_log_console = visible;
+    }
+
+    pub fn is_log_console_visible(&self) -> bool{
+        self.show_log_console
+    }
+
+    /// Sets the key toggling the log console, or `None` to only toggle it from code
+    pub fn set_log_console_key(&mut self, key: Option<KeyCode>){
+        self.log_console_key = key;
+    }
+
+    /// # Set Debug Settings
+    ///
+    /// Enables or disables debug logging per subsystem. Everything is off by default
+    pub fn set_debug_settings(&mut self, settings: DebugSettings){
+        debug::set_debug_settings(settings);
+    }
+
+    pub fn get_debug_settings(&self) -> DebugSettings{
+        debug::get_debug_settings()
+    }
+
     pub fn get_resource_manager(&self) -> MutHandle<ResourceManager>{
         self.resource_manager.clone()
     }
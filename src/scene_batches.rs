@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use wgpu::util::{DeviceExt, DrawIndexedIndirectArgs};
+use crate::culling::{CullBatch, CullDraw, GpuCulling};
+use crate::debug::{debug_log, Subsystem};
+use crate::hi_z::HiZPyramid;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceManager;
+use crate::static_bundles::StaticBundles;
+use crate::stats::FrameStats;
+use crate::types::camera::{Camera, CameraTarget, ALL_RENDER_LAYERS};
+use crate::types::model::Model;
+use crate::types::renderable::Renderable;
+use crate::utils::handle::Handle;
+
+// Pipeline and material - the indirect draws of each of the material's meshes. A material can be
+// drawn by a pipeline's single and double sided variants, each with its own models
+type IndirectDraws = HashMap<(ResourceHandle, ResourceHandle), Vec<IndirectDraw>>;
+
+/// # Scene Batches
+///
+/// The models in the resource manager, grouped by pipeline and then by material,
+/// so the scene can be drawn with as few state changes as possible
+pub(crate) struct SceneBatches{
+    // Pipeline - List of materials that use the pipeline
+    pipeline_materials: HashMap<ResourceHandle, Vec<ResourceHandle>>,
+    // Material, and the meshes that want to use that material
+    material_meshes: HashMap<ResourceHandle, Vec<Handle<Model>>>,
+
+    // With multi-draw indirect, every draw of a pipeline lives in one argument buffer
+    indirect_buffers: HashMap<ResourceHandle, IndirectBuffer>,
+    indirect_draws: IndirectDraws,
+    // Whether culled draws can read their count from the GPU, rather than drawing the empty slots too
+    indirect_count: bool,
+    // Whether the static models are drawn from their bundles, or batched with everything else
+    use_static_bundles: bool,
+}
+
+enum IndirectBuffer{
+    // Arguments written up front
+    Static(wgpu::Buffer),
+    // Arguments written by the GPU culling pass
+    Culled(CullBatch),
+}
+
+impl IndirectBuffer{
+    fn get_buffer(&self) -> &wgpu::Buffer{
+        match self{
+            IndirectBuffer::Static(buffer) => buffer,
+            IndirectBuffer::Culled(batch) => batch.get_indirect_buffer(),
+        }
+    }
+}
+
+// The draws of one submesh for every model sharing a material and mesh, as a range of the
+// pipeline's argument buffer
+struct IndirectDraw{
+    mesh: ResourceHandle,
+    sub_mesh: usize,
+    // Index of the visible draw counter, when culled
+    group: u32,
+    // Offset into the argument buffer, in draws
+    first_draw: u32,
+    draw_count: u32,
+    triangles: u64,
+}
+
+/// The device features needed to batch draws with multi-draw indirect. Each draw reads
+/// its model's `ObjectData` through the first instance, so that has to be supported too
+pub(crate) const INDIRECT_FEATURES: wgpu::Features = wgpu::Features::MULTI_DRAW_INDIRECT
+    .union(wgpu::Features::INDIRECT_FIRST_INSTANCE);
+
+impl SceneBatches{
+    /// # Prepare
+    ///
+    /// Generates any outstanding material bind groups, and groups the models for drawing
+    pub(crate) fn prepare(resource_manager: &ResourceManager) -> Self{
+        Self::prepare_with(resource_manager, true, None, ALL_RENDER_LAYERS)
+    }
+
+    /// Same as `prepare`, but never GPU culled, for views other than the one culling was set up for
+    pub(crate) fn prepare_unculled(resource_manager: &ResourceManager) -> Self{
+        Self::prepare_with(resource_manager, false, None, ALL_RENDER_LAYERS)
+    }
+
+    /// # Prepare For Camera
+    ///
+    /// Same as `prepare_unculled`, but only with the models the camera draws: those on its render layers,
+    /// leaving out the models whose material samples the texture it draws into. The static bundles may
+    /// hold models it doesn't draw, so the static models are batched with everything else instead.
+    /// Returns `None` for cameras drawing every model to the surface, which can share the unculled batches
+    pub(crate) fn prepare_for_camera(resource_manager: &ResourceManager, camera: &Camera) -> Option<Self>{
+        match &camera.target{
+            CameraTarget::Texture(texture_handle) => Some(Self::prepare_with(resource_manager, false, Some(texture_handle), camera.layer_mask)),
+            CameraTarget::Surface if camera.layer_mask != ALL_RENDER_LAYERS => Some(Self::prepare_with(resource_manager, false, None, camera.layer_mask)),
+            CameraTarget::Surface => None,
+        }
+    }
+
+    fn prepare_with(resource_manager: &ResourceManager, culling: bool, excluded_texture: Option<&ResourceHandle>, layer_mask: u32) -> Self{
+        let models = resource_manager.get_all_models();
+
+        // Prepare the render. We want to create a collection per pipeline, made up
+        // of all the materials that use that pipeline. We then want to render all the
+        // meshes that use that material.
+        //
+        // We can check which meshes use which materials by checking the models
+        //
+        // The materials can be checked by checking the material's shader against the pipeline's shader
+
+        // Pipeline - List of materials that use the pipeline
+        let mut pipeline_materials: HashMap<ResourceHandle, Vec<ResourceHandle>> = HashMap::new();
+        // Material, and the meshes that want to use that material
+        let mut material_meshes: HashMap<ResourceHandle, Vec<Handle<Model>>> = HashMap::new();
+
+        let pipeline_handles = resource_manager.get_all_pipeline_handles();
+        let material_handles = resource_manager.get_all_material_handles();
+
+        // Generate bind groups for all the materials
+        for material_handle in material_handles.iter(){
+            let mut material = resource_manager.get_material(material_handle).unwrap();
+            material.generate_bind_groups(resource_manager);
+        }
+
+
+        // Populate the pipeline_materials hashmap, and link the materials to the pipelines
+        for pipeline_handle in pipeline_handles.iter(){
+            let pipeline = resource_manager.get_pipeline(pipeline_handle).unwrap();
+            let shader = pipeline.get_shader();
+            for material_handle in material_handles.iter(){
+                let material = resource_manager.get_material(material_handle).unwrap();
+
+                if material.get_shader() == shader{
+                    let materials = pipeline_materials.entry(pipeline_handle.clone()).or_default();
+                    materials.push(material_handle.clone());
+                }
+            }
+        }
+
+        // Now we've linked the materials to the pipelines, we can link the meshes to the materials
+        // We don't care about the pipeline at this point, as we can get it from the material
+        // Static models are drawn from the cached render bundles instead, unless some models are left out
+        let use_static_bundles = excluded_texture.is_none() && layer_mask == ALL_RENDER_LAYERS;
+        let included = |model: &Handle<Model>| model.get_render_layers() & layer_mask != 0 && match excluded_texture{
+            Some(texture_handle) => !Self::samples_texture(resource_manager, model.get_draw_material(), texture_handle),
+            None => !use_static_bundles || !StaticBundles::is_bundled(resource_manager, model),
+        };
+        for model in models.iter().filter(|model| included(model)){
+            let materials = material_meshes.entry(model.get_draw_material().clone()).or_default();
+            materials.push(model.clone());
+        }
+
+        // Now we have a set of materials linked to pipelines, and a set of materials linked to meshes
+        // This means we can link a pipeline, find all the materials that use that pipeline, and then find
+        // all the meshes that use those materials
+        //
+        // This gives us great flexibility in rendering, as we can render all the meshes that use a certain
+        // pipeline, and then render all the meshes that use a different pipeline, without having to worry about
+        // the order of the meshes in the render loop
+
+        let (indirect_buffers, indirect_draws) = if resource_manager.get_device().features().contains(INDIRECT_FEATURES){
+            let gpu_culling = resource_manager.get_gpu_culling().filter(|_| culling);
+            Self::build_indirect_draws(resource_manager, &pipeline_materials, &material_meshes, gpu_culling)
+        }else{
+            (HashMap::new(), HashMap::new())
+        };
+
+        Self{
+            pipeline_materials,
+            material_meshes,
+
+            indirect_buffers,
+            indirect_draws,
+            indirect_count: resource_manager.get_device().features().contains(wgpu::Features::MULTI_DRAW_INDIRECT_COUNT),
+            use_static_bundles,
+        }
+    }
+
+    // Whether a material, or the template it inherits textures from, samples the texture
+    fn samples_texture(resource_manager: &ResourceManager, material_handle: &ResourceHandle, texture_handle: &ResourceHandle) -> bool{
+        let material = resource_manager.borrow_material(material_handle);
+        material.uses_texture(texture_handle)
+            || material.get_template().is_some_and(|template| resource_manager.borrow_material(template).uses_texture(texture_handle))
+    }
+
+    // Writes an argument buffer per pipeline, with the draws of each material grouped by mesh and submesh
+    // so each group is issued with a single multi-draw. Materials still using a `transform` uniform
+    // need it updated between draws, so they're drawn one at a time instead.
+    //
+    // With GPU culling on, the arguments are left for the culling pass to write instead
+    fn build_indirect_draws(
+        resource_manager: &ResourceManager,
+        pipeline_materials: &HashMap<ResourceHandle, Vec<ResourceHandle>>,
+        material_meshes: &HashMap<ResourceHandle, Vec<Handle<Model>>>,
+        gpu_culling: Option<(&GpuCulling, glam::Mat4)>
+    ) -> (HashMap<ResourceHandle, IndirectBuffer>, IndirectDraws){
+        let mut indirect_buffers = HashMap::new();
+        let mut indirect_draws = HashMap::new();
+
+        for (pipeline_handle, materials) in pipeline_materials.iter(){
+            let pipeline = resource_manager.get_pipeline(pipeline_handle).unwrap();
+            let mut args: Vec<u8> = Vec::new();
+            let mut cull_draws: Vec<CullDraw> = Vec::new();
+            let mut draw_count = 0;
+            let mut group_count = 0;
+
+            for material_handle in materials.iter(){
+                let material = resource_manager.borrow_material(material_handle);
+                if material.get_shader_bindings().is_some_and(|bindings| bindings.contains_key("transform")){
+                    continue;
+                }
+
+                // Mesh - object index of every model drawing it, keeping the models' order
+                let mut mesh_objects: Vec<(ResourceHandle, Vec<u32>)> = Vec::new();
+                for model in material_meshes.get(material_handle).unwrap_or(&Vec::new()).iter().filter(|model| pipeline.draws_model(model)){
+                    match mesh_objects.iter_mut().find(|(mesh, _)| mesh == model.get_mesh()){
+                        Some((_, objects)) => objects.push(model.get_object_index()),
+                        None => mesh_objects.push((model.get_mesh().clone(), vec![model.get_object_index()])),
+                    }
+                }
+
+                let mut draws = Vec::new();
+                for (mesh_handle, objects) in mesh_objects{
+                    let mesh = resource_manager.get_mesh(&mesh_handle).unwrap();
+                    let bounds = resource_manager.get_mesh_bounds(&mesh_handle);
+
+                    for (sub_mesh, submesh) in mesh.get_sub_meshes().iter().enumerate(){
+                        let index_count = submesh.get_indices_count() as u32;
+                        // Submeshes without bounds are always drawn
+                        let sphere = bounds.and_then(|bounds| bounds.get(sub_mesh).copied().flatten())
+                            .map(|bounds| bounds.to_array())
+                            .unwrap_or([0.0, 0.0, 0.0, -1.0]);
+
+                        for object_index in objects.iter(){
+                            if gpu_culling.is_some(){
+                                cull_draws.push(CullDraw{
+                                    sphere,
+                                    index_count,
+                                    object_index: *object_index,
+                                    group: group_count,
+                                    first_draw: draw_count,
+                                });
+                            }else{
+                                args.extend_from_slice(DrawIndexedIndirectArgs{
+                                    index_count,
+                                    instance_count: 1,
+                                    first_index: 0,
+                                    base_vertex: 0,
+                                    first_instance: *object_index,
+                                }.as_bytes());
+                            }
+                        }
+
+                        draws.push(IndirectDraw{
+                            mesh: mesh_handle.clone(),
+                            sub_mesh,
+                            group: group_count,
+                            first_draw: draw_count,
+                            draw_count: objects.len() as u32,
+                            triangles: index_count as u64 / 3 * objects.len() as u64,
+                        });
+                        draw_count += objects.len() as u32;
+                        group_count += 1;
+                    }
+                }
+                indirect_draws.insert((pipeline_handle.clone(), material_handle.clone()), draws);
+            }
+
+            if draw_count == 0{
+                continue;
+            }
+
+            let indirect_buffer = match gpu_culling{
+                Some((gpu_culling, view_projection)) => IndirectBuffer::Culled(gpu_culling.create_batch(
+                    resource_manager.get_objects_buffer().get_buffer(), &cull_draws, draw_count, group_count, view_projection
+                )),
+                None => IndirectBuffer::Static(resource_manager.get_device().create_buffer_init(&wgpu::util::BufferInitDescriptor{
+                    label: Some("Indirect Draw Buffer"),
+                    contents: &args,
+                    usage: wgpu::BufferUsages::INDIRECT,
+                })),
+            };
+            indirect_buffers.insert(pipeline_handle.clone(), indirect_buffer);
+        }
+
+        debug_log!(Subsystem::Render, "Built indirect draws for {} pipelines", indirect_buffers.len());
+        (indirect_buffers, indirect_draws)
+    }
+
+    /// # Cull
+    ///
+    /// Records the GPU culling pass, which has to run before the draws are recorded.
+    /// Models hidden in the previous frame's Hi-Z pyramid are culled too, if one is given.
+    /// Does nothing when culling is off
+    pub(crate) fn cull(&self, resource_manager: &ResourceManager, encoder: &mut wgpu::CommandEncoder, hi_z: Option<&HiZPyramid>){
+        let Some((gpu_culling, _)) = resource_manager.get_gpu_culling() else { return };
+
+        let mut batches = self.indirect_buffers.values().filter_map(|indirect_buffer| match indirect_buffer{
+            IndirectBuffer::Culled(batch) => Some(batch),
+            IndirectBuffer::Static(_) => None,
+        }).peekable();
+
+        if batches.peek().is_some(){
+            gpu_culling.dispatch(encoder, batches, hi_z);
+        }
+    }
+
+    /// # Draw
+    ///
+    /// Records draw calls for every batch into the render pass, after replaying the static models' bundles
+    pub(crate) fn draw<'a>(&'a self, resource_manager: &'a ResourceManager, render_pass: &mut wgpu::RenderPass<'a>, stats: &mut FrameStats){
+        // Materials read the copy of the objects buffer written this frame
+        let objects_version = resource_manager.get_objects_buffer().get_version();
+
+        if self.use_static_bundles{
+            resource_manager.get_static_bundles().draw(render_pass, objects_version, stats);
+        }
+
+        // Using the resource_manager, let's get to rendering
+        for (pipeline_handle, materials) in self.pipeline_materials.iter(){
+            let pipeline = resource_manager.get_pipeline(pipeline_handle).unwrap();
+            pipeline.render(render_pass);
+            stats.pipeline_switches += 1;
+
+            for material_handle in materials.iter(){
+                let material = resource_manager.borrow_material(material_handle);
+
+                let indirect_key = (pipeline_handle.clone(), material_handle.clone());
+                if let (Some(draws), Some(indirect_buffer)) = (self.indirect_draws.get(&indirect_key), self.indirect_buffers.get(pipeline_handle)){
+                    if draws.is_empty(){
+                        continue;
+                    }
+                    material.bind_material(render_pass, objects_version);
+                    stats.bind_group_sets += material.get_bind_group_count() as u32;
+
+                    let draw_size = std::mem::size_of::<DrawIndexedIndirectArgs>() as u64;
+                    for draw in draws.iter(){
+                        let vertex_buffers = resource_manager.get_mesh_vertex_buffers(&draw.mesh).unwrap();
+                        let index_buffers = resource_manager.get_mesh_index_buffers(&draw.mesh).unwrap();
+                        vertex_buffers[draw.sub_mesh].bind_vertex_buffer(0, render_pass);
+                        index_buffers[draw.sub_mesh].bind_index_buffer(render_pass);
+                        let offset = draw.first_draw as u64 * draw_size;
+                        match indirect_buffer{
+                            IndirectBuffer::Culled(batch) if self.indirect_count => {
+                                let count_offset = draw.group as u64 * std::mem::size_of::<u32>() as u64;
+                                render_pass.multi_draw_indexed_indirect_count(batch.get_indirect_buffer(), offset, batch.get_counts_buffer(), count_offset, draw.draw_count);
+                            },
+                            _ => render_pass.multi_draw_indexed_indirect(indirect_buffer.get_buffer(), offset, draw.draw_count),
+                        }
+
+                        stats.draw_calls += 1;
+                        stats.instances += draw.draw_count;
+                        stats.triangles += draw.triangles;
+                    }
+                    continue;
+                }
+
+                for model in self.material_meshes.get(material_handle).unwrap_or(&Vec::new()).iter().filter(|model| pipeline.draws_model(model)){
+                    let mesh = resource_manager.get_mesh(model.get_mesh()).unwrap();
+
+                    let vertex_buffers = resource_manager.get_mesh_vertex_buffers(model.get_mesh()).unwrap();
+                    let index_buffers = resource_manager.get_mesh_index_buffers(model.get_mesh()).unwrap();
+
+                    // Shaders still reading a `transform` uniform get the model's copy of it
+                    if let Some(transform_uniform_handle) = model.get_transform_uniform_handle(){
+                        if material.get_shader_bindings().is_some_and(|bindings| bindings.contains_key("transform")){
+                            let mut temp_update_material = resource_manager.get_material(material_handle).unwrap();
+                            temp_update_material.set_uniform("transform", transform_uniform_handle, resource_manager);
+                        }
+                    }
+
+                    debug_log!(Subsystem::Render, "Transform: {:?}", model.get_transform().get_position());
+
+                    material.bind_material(render_pass, objects_version);
+                    stats.bind_group_sets += material.get_bind_group_count() as u32;
+
+
+                    for (idx, submesh) in mesh.get_sub_meshes().iter().enumerate(){
+                        vertex_buffers[idx].bind_vertex_buffer(0, render_pass);
+                        index_buffers[idx].bind_index_buffer(render_pass);
+                        submesh.render_object(render_pass, model.get_object_index());
+
+                        stats.draw_calls += 1;
+                        stats.instances += 1;
+                        stats.triangles += submesh.get_indices_count() as u64 / 3;
+                    }
+                }
+            }
+        }
+    }
+}
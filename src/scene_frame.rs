@@ -0,0 +1,120 @@
+use wgpu::StoreOp;
+use crate::camera_passes::CameraPasses;
+use crate::frame_context::{CustomDrawFn, FrameContext};
+use crate::managers::resource_manager::ResourceManager;
+use crate::post::blit::Blitter;
+use crate::post::post_stack::PostStack;
+use crate::scene_batches::SceneBatches;
+use crate::screen_attachments::{self, ScreenAttachments};
+use crate::settings::RenderSettings;
+use crate::stats::FrameStats;
+
+/// # Scene Frame
+///
+/// Encodes a frame of the scene into an output view: culling, point shadows, every camera and the
+/// default camera's pass, post effects and queued blits. Shared by the windowed and headless renderers,
+/// so both draw the same frame
+pub(crate) struct SceneFrame<'a>{
+    pub(crate) output: &'a wgpu::TextureView,
+    pub(crate) output_format: wgpu::TextureFormat,
+    // Size of the output, which the attachments match
+    pub(crate) size: [u32; 2],
+    pub(crate) attachments: &'a mut ScreenAttachments,
+    pub(crate) post_stack: &'a mut PostStack,
+    pub(crate) blitter: &'a mut Blitter,
+    pub(crate) custom_draw: &'a mut Option<CustomDrawFn>,
+    pub(crate) settings: &'a RenderSettings,
+}
+
+impl SceneFrame<'_>{
+    /// # Encode
+    ///
+    /// Encodes the frame into the output. `timestamp_writes` time the default camera's pass
+    pub(crate) fn encode(
+        &mut self,
+        resource_manager: &ResourceManager,
+        batches: &SceneBatches,
+        encoder: &mut wgpu::CommandEncoder,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites>,
+        stats: &mut FrameStats
+    ){
+        let output = self.output;
+        let depth = self.attachments.get_depth();
+        let msaa_color = self.attachments.get_msaa_color();
+
+        // The Hi-Z pyramid is built from each frame's depth, so there's no depth to build it from on the web,
+        // and it can't be built from a multisampled one
+        let occlusion_culling = self.settings.occlusion_culling && resource_manager.get_gpu_culling().is_some() && !cfg!(target_arch = "wasm32")
+            && self.attachments.get_sample_count() == 1;
+        batches.cull(resource_manager, encoder, self.attachments.get_hi_z().filter(|_| occlusion_culling));
+
+        // With post effects enabled, the scene is drawn into the stack's target and the effects write the output
+        let post_target = self.post_stack.begin_frame(self.settings);
+        let scene_target = post_target.as_ref().map_or(output, |target| target.get_texture_view());
+
+        // Shadows are sampled by every camera, so they're drawn before any of them
+        resource_manager.render_point_shadows();
+
+        let camera_passes = CameraPasses{
+            surface_target: scene_target,
+            surface_depth: &depth,
+            surface_msaa: msaa_color.as_deref(),
+            surface_size: self.size,
+            clear_color: self.settings.get_clear_color(),
+        };
+        let cameras_drew_surface = camera_passes.draw(resource_manager, batches, self.custom_draw, stats);
+
+        // Cameras drawing to the surface replace the default one
+        if !cameras_drew_surface{
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor{
+                    label: Some("Render Pass"),
+                    color_attachments: &[
+                        Some(screen_attachments::color_attachment(scene_target, msaa_color.as_deref(), wgpu::Operations{
+                            load: wgpu::LoadOp::Clear(self.settings.get_clear_color()),
+                            store: StoreOp::Store
+                        }))
+                    ],
+                    depth_stencil_attachment: if cfg!(target_arch = "wasm32"){
+                        None
+                    }else{
+                        Some(wgpu::RenderPassDepthStencilAttachment{
+                            view: depth.get_texture_view(),
+                            depth_ops: Some(wgpu::Operations{
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: StoreOp::Store
+                            }),
+                            stencil_ops: depth.get_format().has_stencil_aspect().then_some(wgpu::Operations{
+                                load: wgpu::LoadOp::Clear(0),
+                                store: StoreOp::Store
+                            })
+                        })
+                    },
+                    timestamp_writes,
+                    occlusion_query_set: None,
+                }
+            );
+
+            batches.draw(resource_manager, &mut render_pass, stats);
+
+            if let Some(custom_draw) = self.custom_draw.as_mut(){
+                custom_draw(&mut FrameContext::new(resource_manager, &mut render_pass, stats));
+            }
+        }
+
+        // Ready for the next frame's culling, which is only for the default camera
+        if let Some(hi_z) = self.attachments.get_hi_z_mut(){
+            if occlusion_culling && !cameras_drew_surface{
+                hi_z.build(encoder, &depth.create_depth_view());
+            }else{
+                hi_z.invalidate();
+            }
+        }
+
+        if post_target.is_some(){
+            self.post_stack.apply(encoder, self.settings, output);
+        }
+
+        self.blitter.flush(encoder, resource_manager, output, self.output_format, self.size);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use crate::hi_z::HiZPyramid;
+use crate::managers::sampler_cache::SamplerCache;
+use crate::scene_batches;
+use crate::types::texture::Texture;
+use crate::utils::handle::Handle;
+
+/// Default format of the renderer's depth buffer
+pub(crate) const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// # Screen Attachments
+///
+/// Screen-sized render targets owned by the renderer (depth, plus any extra
+/// targets such as normals), recreated whenever the surface is resized
+pub(crate) struct ScreenAttachments{
+    depth: Handle<Texture>,
+    // Multisampled colour target the scene is drawn into and resolved from, when multisampling is on
+    msaa_color: Option<Handle<Texture>>,
+    samples: u32,
+    // Additional named colour attachments
+    color: HashMap<String, Handle<Texture>>,
+    // Depth pyramid for occlusion culling, when the device can cull on the GPU
+    hi_z: Option<HiZPyramid>,
+
+    width: u32,
+    height: u32,
+
+    samplers: Handle<SamplerCache>,
+    _device: Handle<wgpu::Device>
+}
+
+impl ScreenAttachments{
+    pub(crate) fn new(device: Handle<wgpu::Device>, samplers: Handle<SamplerCache>, width: u32, height: u32) -> Self{
+        // Textures can't be zero sized (e.g a minimised window)
+        let (width, height) = (width.max(1), height.max(1));
+
+        let depth = Texture::create_screen_texture(&device, &samplers, width, height, DEPTH_FORMAT, "Depth Texture");
+        let hi_z = device.features().contains(scene_batches::INDIRECT_FEATURES)
+            .then(|| HiZPyramid::new(device.clone(), width, height));
+
+        Self{
+            depth: Handle::new(depth),
+            msaa_color: None,
+            samples: 1,
+            color: HashMap::new(),
+            hi_z,
+
+            width,
+            height,
+
+            samplers,
+            _device: device
+        }
+    }
+
+    /// Recreates every attachment at the new size. Zero sizes are ignored
+    pub(crate) fn resize(&mut self, width: u32, height: u32){
+        if width == 0 || height == 0 || (width == self.width && height == self.height){
+            return;
+        }
+
+        self.width = width;
+        self.height = height;
+
+        self.depth.resize(&self._device, width, height);
+        if let Some(msaa_color) = self.msaa_color.as_mut(){
+            msaa_color.resize(&self._device, width, height);
+        }
+        for attachment in self.color.values_mut(){
+            attachment.resize(&self._device, width, height);
+        }
+        if let Some(hi_z) = self.hi_z.as_mut(){
+            hi_z.resize(width, height);
+        }
+    }
+
+    /// Recreates the depth buffer in a new format
+    pub(crate) fn set_depth_format(&mut self, format: wgpu::TextureFormat){
+        if self.depth.get_format() == format{
+            return;
+        }
+
+        self.depth = Handle::new(self.create_depth(format, self.samples));
+        if let Some(hi_z) = self.hi_z.as_mut(){
+            hi_z.invalidate();
+        }
+    }
+
+    /// # Set Multisampling
+    ///
+    /// Sets the samples per pixel the scene is drawn with, recreating the depth buffer to match. With more
+    /// than one, the scene is drawn into a multisampled colour target of the given format, which is resolved
+    /// into the frame's target
+    pub(crate) fn set_multisampling(&mut self, samples: u32, color_format: wgpu::TextureFormat){
+        if samples != self.samples{
+            self.samples = samples;
+            self.depth = Handle::new(self.create_depth(self.depth.get_format(), samples));
+        }
+
+        self.msaa_color = match self.msaa_color.take(){
+            Some(msaa_color) if samples > 1 && msaa_color.get_format() == color_format && msaa_color.get_raw_texture().sample_count() == samples => Some(msaa_color),
+            _ => (samples > 1).then(|| Handle::new(Texture::create_multisampled_texture(&self._device, &self.samplers, self.width, self.height, color_format, samples, "Multisampled Color Texture"))),
+        };
+    }
+
+    fn create_depth(&self, format: wgpu::TextureFormat, samples: u32) -> Texture{
+        if samples > 1{
+            Texture::create_multisampled_texture(&self._device, &self.samplers, self.width, self.height, format, samples, "Depth Texture")
+        }else{
+            Texture::create_screen_texture(&self._device, &self.samplers, self.width, self.height, format, "Depth Texture")
+        }
+    }
+
+    pub(crate) fn get_sample_count(&self) -> u32{
+        self.samples
+    }
+
+    pub(crate) fn get_msaa_color(&self) -> Option<Handle<Texture>>{
+        self.msaa_color.clone()
+    }
+
+    pub(crate) fn get_depth(&self) -> Handle<Texture>{
+        self.depth.clone()
+    }
+
+    /// Adds a named colour attachment (e.g normals), replacing any with the same name
+    pub(crate) fn add_color_attachment(&mut self, name: &str, format: wgpu::TextureFormat) -> Handle<Texture>{
+        let texture = Handle::new(Texture::create_screen_texture(&self._device, &self.samplers, self.width, self.height, format, name));
+        self.color.insert(name.to_string(), texture.clone());
+
+        texture
+    }
+
+    pub(crate) fn get_color_attachment(&self, name: &str) -> Option<Handle<Texture>>{
+        self.color.get(name).cloned()
+    }
+
+    pub(crate) fn get_hi_z(&self) -> Option<&HiZPyramid>{
+        self.hi_z.as_ref()
+    }
+
+    pub(crate) fn get_hi_z_mut(&mut self) -> Option<&mut HiZPyramid>{
+        self.hi_z.as_mut()
+    }
+}
+
+/// A colour attachment drawing into `target`, or into `msaa_color` and resolving into `target` when multisampling
+pub(crate) fn color_attachment<'a>(target: &'a wgpu::TextureView, msaa_color: Option<&'a Texture>, ops: wgpu::Operations<wgpu::Color>) -> wgpu::RenderPassColorAttachment<'a>{
+    match msaa_color{
+        Some(msaa_color) => wgpu::RenderPassColorAttachment{
+            view: msaa_color.get_texture_view(),
+            resolve_target: Some(target),
+            ops,
+        },
+        None => wgpu::RenderPassColorAttachment{
+            view: target,
+            resolve_target: None,
+            ops,
+        },
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+/// # Render Settings
+///
+/// Runtime-tweakable renderer settings. These can be loaded from a TOML file,
+/// which the renderer watches and reloads whenever it changes, e.g:
+///
+/// ```toml
+/// clear_color = [0.1, 0.1, 0.1, 1.0]
+/// depth_format = "Depth24PlusStencil8"
+/// output_encoding = "ShaderSrgb"
+/// msaa_samples = 4
+/// shadow_resolution = 2048
+/// anisotropy = 8
+///
+/// [post_effects]
+/// bloom = true
+/// vignette = true
+/// color_grading = true
+///
+/// [bloom]
+/// threshold = 1.0
+/// intensity = 0.5
+///
+/// [color_grading]
+/// lut = "luts/warm.cube"
+/// intensity = 0.8
+///
+/// [vignette]
+/// intensity = 0.4
+///
+/// [shadows]
+/// depth_bias = 0.05
+/// pcf_size = 3
+/// ```
+///
+/// Any field missing from the file keeps its default value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderSettings{
+    /// Colour the frame is cleared to before rendering (linear RGBA)
+    pub clear_color: [f64; 4],
+    /// Format of the depth buffer. Falls back to `Depth32Float` if the adapter can't use the chosen one
+    pub depth_format: DepthFormat,
+    /// Number of samples per pixel for multisampling, 1 to turn it off. Usually 2, 4 or 8, falling back to
+    /// the highest count the adapter supports below it. Occlusion culling is skipped while it's on
+    pub msaa_samples: u32,
+    /// Width and height of shadow maps, in pixels. Point lights have six of them, one per cube face
+    pub shadow_resolution: u32,
+    /// How shadows are biased and filtered
+    pub shadows: ShadowSettings,
+    /// Anisotropic filtering level for textures (1 disables it, up to 16)
+    pub anisotropy: u16,
+    /// How colours are encoded for display. The linear modes draw the scene into a float target
+    pub output_encoding: OutputEncoding,
+    /// Post effects by name, and whether they're enabled
+    pub post_effects: HashMap<String, bool>,
+    /// Parameters of the `bloom` post effect
+    pub bloom: BloomSettings,
+    /// Parameters of the `color_grading` post effect
+    pub color_grading: ColorGradingSettings,
+    /// Parameters of the `vignette` post effect
+    pub vignette: VignetteSettings,
+    /// Parameters of the `film_grain` post effect
+    pub film_grain: FilmGrainSettings,
+    /// Parameters of the `chromatic_aberration` post effect
+    pub chromatic_aberration: ChromaticAberrationSettings,
+    /// Whether GPU culling also culls models hidden behind others in the previous frame's depth.
+    /// Only used when GPU culling is on, see `ResourceManager::set_cull_view_projection`
+    pub occlusion_culling: bool,
+}
+
+impl Default for RenderSettings{
+    fn default() -> Self{
+        Self{
+            clear_color: [1.0, 1.0, 1.0, 1.0],
+            depth_format: DepthFormat::Depth32Float,
+            msaa_samples: 1,
+            shadow_resolution: 2048,
+            shadows: ShadowSettings::default(),
+            anisotropy: 1,
+            output_encoding: OutputEncoding::HardwareSrgb,
+            post_effects: HashMap::new(),
+            bloom: BloomSettings::default(),
+            color_grading: ColorGradingSettings::default(),
+            vignette: VignetteSettings::default(),
+            film_grain: FilmGrainSettings::default(),
+            chromatic_aberration: ChromaticAberrationSettings::default(),
+            occlusion_culling: true,
+        }
+    }
+}
+
+impl RenderSettings{
+    /// # Load From File
+    ///
+    /// Loads settings from a TOML file
+    pub fn load_from_file<T: AsRef<Path>>(path: T) -> anyhow::Result<Self>{
+        let source = std::fs::read_to_string(path.as_ref())?;
+        Self::from_toml(&source)
+    }
+
+    pub fn from_toml(source: &str) -> anyhow::Result<Self>{
+        Ok(toml::from_str(source)?)
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String>{
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn get_clear_color(&self) -> wgpu::Color{
+        wgpu::Color{
+            r: self.clear_color[0],
+            g: self.clear_color[1],
+            b: self.clear_color[2],
+            a: self.clear_color[3],
+        }
+    }
+
+    /// Whether the named post effect is enabled. Effects not in the settings are disabled
+    pub fn is_post_effect_enabled(&self, name: &str) -> bool{
+        self.post_effects.get(name).copied().unwrap_or(false)
+    }
+
+    pub fn set_post_effect_enabled(&mut self, name: &str, enabled: bool){
+        self.post_effects.insert(name.to_string(), enabled);
+    }
+
+    /// The sample count to draw with on an adapter, the highest up to `msaa_samples` that every target format
+    /// supports. Only 1 and 4 are supported everywhere, other counts need adapter specific format features
+    pub(crate) fn resolve_msaa_samples(&self, adapter: &wgpu::Adapter, formats: &[wgpu::TextureFormat]) -> u32{
+        let adapter_specific = adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        let supported = |samples: u32| samples == 1 || ((samples == 4 || adapter_specific)
+            && formats.iter().all(|format| adapter.get_texture_format_features(*format).flags.sample_count_supported(samples)));
+
+        let samples = [16, 8, 4, 2, 1].into_iter()
+            .find(|samples| *samples <= self.msaa_samples && supported(*samples))
+            .unwrap_or(1);
+        if samples != self.msaa_samples{
+            warn!("{}x multisampling isn't supported by the adapter, falling back to {}x", self.msaa_samples, samples);
+        }
+        samples
+    }
+}
+
+/// # Color Grading Settings
+///
+/// Parameters of the `color_grading` post effect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorGradingSettings{
+    /// `.cube` file or PNG strip to load the LUT from (see `ColorGradingLut::load`). Without one,
+    /// the LUT set through `Renderer::set_color_grading_lut` is used, or an identity LUT
+    pub lut: Option<PathBuf>,
+    /// How much of the graded colour is used, from 0 (none) to 1
+    pub intensity: f32,
+}
+
+impl Default for ColorGradingSettings{
+    fn default() -> Self{
+        Self{
+            lut: None,
+            intensity: 1.0,
+        }
+    }
+}
+
+/// # Shadow Settings
+///
+/// Biasing and filtering of shadows. Too little bias makes surfaces shadow themselves in stripes
+/// (shadow acne), while too much detaches shadows from the objects casting them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShadowSettings{
+    /// Distance surfaces are moved towards the light before being compared with the shadow map, in world units
+    pub depth_bias: f32,
+    /// Extra bias for surfaces at grazing angles to the light, scaled by the tangent of the angle
+    pub slope_bias: f32,
+    /// Distance surfaces are pushed along their normal before sampling, in world units.
+    /// Scales down as the surface faces the light
+    pub normal_offset: f32,
+    /// Width of the percentage-closer filtering kernel, in texels. 1 takes a single sample,
+    /// larger kernels soften the shadow edges at the cost of a sample per texel
+    pub pcf_size: u32,
+}
+
+impl Default for ShadowSettings{
+    fn default() -> Self{
+        Self{
+            depth_bias: 0.05,
+            slope_bias: 0.05,
+            normal_offset: 0.02,
+            pcf_size: 1,
+        }
+    }
+}
+
+impl ShadowSettings{
+    /// The largest `pcf_size` shaders sample, which larger sizes are clamped to
+    pub const MAX_PCF_SIZE: u32 = 7;
+}
+
+/// # Output Encoding
+///
+/// How the frame's colours are encoded for display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OutputEncoding{
+    /// Draw into the sRGB swapchain, which encodes colours as they're written. Shaders output linear colours
+    HardwareSrgb,
+    /// Draw into a linear target, and apply the sRGB curve in a final pass. Shaders output linear colours
+    ShaderSrgb,
+    /// Draw into a linear target, and write it out unchanged. For shaders that output display (gamma encoded) colours themselves
+    Passthrough,
+}
+
+impl OutputEncoding{
+    /// Whether the scene is drawn into a linear target, rather than straight into the sRGB output
+    pub fn is_linear(&self) -> bool{
+        *self != OutputEncoding::HardwareSrgb
+    }
+}
+
+/// # Bloom Settings
+///
+/// Parameters of the `bloom` post effect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BloomSettings{
+    /// Brightness a colour channel has to pass to glow. Lit surfaces rarely go past 1,
+    /// so the default only picks out emissive materials and bright highlights
+    pub threshold: f32,
+    /// Range below the threshold over which the glow fades in, so it has no hard edge
+    pub knee: f32,
+    /// How much of the glow is added back on top of the frame
+    pub intensity: f32,
+    /// How far the glow spreads, scaling the blur
+    pub radius: f32,
+}
+
+impl Default for BloomSettings{
+    fn default() -> Self{
+        Self{
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 0.5,
+            radius: 1.0,
+        }
+    }
+}
+
+/// # Vignette Settings
+///
+/// Parameters of the `vignette` post effect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VignetteSettings{
+    /// How dark the corners get, from 0 (not at all) to 1 (black)
+    pub intensity: f32,
+    /// Distance from the centre darkening starts at, where the corners are at 1
+    pub radius: f32,
+    /// Distance over which it reaches full intensity
+    pub smoothness: f32,
+}
+
+impl Default for VignetteSettings{
+    fn default() -> Self{
+        Self{
+            intensity: 0.5,
+            radius: 0.5,
+            smoothness: 0.5,
+        }
+    }
+}
+
+/// # Film Grain Settings
+///
+/// Parameters of the `film_grain` post effect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilmGrainSettings{
+    /// Strength of the noise added to each channel
+    pub intensity: f32,
+    /// Size of each grain, in pixels
+    pub size: f32,
+}
+
+impl Default for FilmGrainSettings{
+    fn default() -> Self{
+        Self{
+            intensity: 0.05,
+            size: 1.5,
+        }
+    }
+}
+
+/// # Chromatic Aberration Settings
+///
+/// Parameters of the `chromatic_aberration` post effect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChromaticAberrationSettings{
+    /// How far the red and blue channels are shifted at the corners, as a fraction of the frame
+    pub intensity: f32,
+}
+
+impl Default for ChromaticAberrationSettings{
+    fn default() -> Self{
+        Self{
+            intensity: 0.005,
+        }
+    }
+}
+
+/// # Depth Format
+///
+/// The formats the depth buffer can use. Formats with a stencil aspect allow pipelines to use stencil tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DepthFormat{
+    /// 32 bit float depth, the most precise
+    Depth32Float,
+    /// At least 24 bits of depth, with an 8 bit stencil
+    Depth24PlusStencil8,
+    /// 16 bit depth, using the least memory
+    Depth16Unorm,
+}
+
+impl DepthFormat{
+    pub fn get_format(&self) -> wgpu::TextureFormat{
+        match self{
+            DepthFormat::Depth32Float => wgpu::TextureFormat::Depth32Float,
+            DepthFormat::Depth24PlusStencil8 => wgpu::TextureFormat::Depth24PlusStencil8,
+            DepthFormat::Depth16Unorm => wgpu::TextureFormat::Depth16Unorm,
+        }
+    }
+
+    pub fn has_stencil(&self) -> bool{
+        self.get_format().has_stencil_aspect()
+    }
+
+    /// The texture format to use on an adapter. The depth buffer is rendered to and sampled
+    /// (e.g for occlusion culling), so formats the adapter can't do both with fall back to `Depth32Float`
+    pub(crate) fn resolve(&self, adapter: &wgpu::Adapter) -> wgpu::TextureFormat{
+        let required = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+        let format = self.get_format();
+
+        if adapter.get_texture_format_features(format).allowed_usages.contains(required){
+            format
+        }else{
+            warn!("Depth format {:?} isn't supported by the adapter, falling back to Depth32Float", self);
+            wgpu::TextureFormat::Depth32Float
+        }
+    }
+}
+
+/// # Settings Watcher
+///
+/// Watches a settings file's modification time, and reloads it when it changes
+pub(crate) struct SettingsWatcher{
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+}
+
+impl SettingsWatcher{
+    pub(crate) fn new<T: AsRef<Path>>(path: T) -> Self{
+        let path = path.as_ref().to_path_buf();
+        let last_modified = Self::modified_time(&path);
+
+        Self{
+            path,
+            last_modified,
+        }
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime>{
+        std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Returns the new settings if the file has changed since it was last loaded.
+    /// A file that fails to parse is reported and ignored, so the current settings stay in place
+    pub(crate) fn poll(&mut self) -> Option<RenderSettings>{
+        let modified = Self::modified_time(&self.path);
+        if modified.is_none() || modified == self.last_modified{
+            return None;
+        }
+
+        self.last_modified = modified;
+
+        match RenderSettings::load_from_file(&self.path){
+            Ok(settings) => {
+                info!("Reloaded render settings from {:?}", self.path);
+                Some(settings)
+            },
+            Err(e) => {
+                error!("Failed to reload render settings from {:?}: {}", self.path, e);
+                None
+            }
+        }
+    }
+}
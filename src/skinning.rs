@@ -0,0 +1,141 @@
+use crate::managers::resource_handle::ResourceHandle;
+use crate::types::mesh::Mesh;
+use crate::types::vertex::SkinnedVertex;
+use crate::utils::buffer::{Buffer, BufferType};
+use crate::utils::handle::Handle;
+
+const SKIN_SHADER: &str = include_str!("../assets/shaders/skin.wgsl");
+const WORKGROUP_SIZE: u32 = 64;
+
+/// # GPU Skin
+///
+/// The `SkinnedVertex` data of a mesh skinned by `GpuSkinning`, and the `SkinUniform` it follows.
+/// The skinned vertices are written into the vertex buffers of another mesh
+pub(crate) struct GpuSkin{
+    // One per sub mesh
+    source_buffers: Vec<Buffer>,
+    skin_uniform: ResourceHandle,
+}
+
+impl GpuSkin{
+    pub(crate) fn new(device: &wgpu::Device, skinned_mesh: &Mesh, skin_uniform: ResourceHandle) -> Self{
+        let source_buffers = skinned_mesh.get_sub_meshes().iter()
+            .map(|sub_mesh| Buffer::create_buffer_from_bytes(device, sub_mesh.get_vertex_bytes(), BufferType::Storage))
+            .collect();
+
+        Self{
+            source_buffers,
+            skin_uniform,
+        }
+    }
+
+    pub(crate) fn get_skin_uniform(&self) -> &ResourceHandle{
+        &self.skin_uniform
+    }
+
+    pub(crate) fn get_source_buffers(&self) -> &[Buffer]{
+        &self.source_buffers
+    }
+
+    pub(crate) fn get_memory_size(&self) -> u64{
+        self.source_buffers.iter().map(|buffer| buffer.get_size() as u64).sum()
+    }
+}
+
+/// # GPU Skinning
+///
+/// A compute pass skinning `SkinnedVertex` meshes into standard `Vertex` buffers each frame,
+/// so skinned meshes can be drawn by shaders and passes that know nothing about joints
+pub(crate) struct GpuSkinning{
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+
+    _device: Handle<wgpu::Device>,
+}
+
+impl GpuSkinning{
+    /// Whether the device can run compute shaders with storage buffers, which WebGL can't
+    pub(crate) fn is_supported(device: &wgpu::Device) -> bool{
+        let limits = device.limits();
+        limits.max_compute_workgroups_per_dimension > 0 && limits.max_storage_buffers_per_shader_stage >= 2
+    }
+
+    pub(crate) fn new(device: Handle<wgpu::Device>) -> Self{
+        let buffer_entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry{
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer{
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None
+            },
+            count: None
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor{
+            label: Some("Skinning Bind Group Layout"),
+            entries: &[
+                buffer_entry(0, wgpu::BufferBindingType::Uniform),
+                buffer_entry(1, wgpu::BufferBindingType::Storage{ read_only: true }),
+                buffer_entry(2, wgpu::BufferBindingType::Storage{ read_only: false }),
+            ]
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Skinning Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor{
+            label: Some("Skinning Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(SKIN_SHADER.into())
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor{
+            label: Some("Skinning Pipeline"),
+            layout: Some(&layout),
+            module: &shader_module,
+            entry_point: "skin_main",
+        });
+
+        Self{
+            pipeline,
+            bind_group_layout,
+
+            _device: device,
+        }
+    }
+
+    /// Records the skinning of each `(skin uniform, source vertices, output vertices)` sub mesh into a
+    /// single compute pass. Bind groups are made each time, so replaced uniform buffers are picked up
+    pub(crate) fn dispatch<'a>(&self, encoder: &mut wgpu::CommandEncoder, sub_meshes: impl Iterator<Item = (&'a wgpu::Buffer, &'a Buffer, &'a Buffer)>){
+        let bind_groups: Vec<(wgpu::BindGroup, u32)> = sub_meshes
+            .filter(|(_, source, _)| source.get_size() > 0)
+            .map(|(skin, source, output)| {
+                let bind_group = self._device.create_bind_group(&wgpu::BindGroupDescriptor{
+                    label: Some("Skinning Bind Group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry{ binding: 0, resource: skin.as_entire_binding() },
+                        wgpu::BindGroupEntry{ binding: 1, resource: source.buffer.as_entire_binding() },
+                        wgpu::BindGroupEntry{ binding: 2, resource: output.buffer.as_entire_binding() },
+                    ]
+                });
+                let vertex_count = (source.get_size() / std::mem::size_of::<SkinnedVertex>()) as u32;
+                (bind_group, vertex_count)
+            })
+            .collect();
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
+            label: Some("Skinning Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+
+        for (bind_group, vertex_count) in bind_groups.iter(){
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            compute_pass.dispatch_workgroups(vertex_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceManager;
+use crate::settings::RenderSettings;
+
+/// # State Dump
+///
+/// A snapshot of the renderer's setup and resources, from `Renderer::dump_state`. Attach `to_toml`'s
+/// output to bug reports, or diff it between a working and a broken configuration. Resources show their
+/// name where they have one (see `ResourceManager::set_resource_name`), otherwise their type and id
+#[derive(Debug, Clone, Serialize)]
+pub struct StateDump{
+    pub adapter: AdapterState,
+    /// `None` for the headless renderer, which has no surface
+    pub surface: Option<SurfaceState>,
+    pub settings: RenderSettings,
+    pub resources: ResourceState,
+}
+
+impl StateDump{
+    pub(crate) fn capture(adapter: &wgpu::Adapter, surface: Option<&wgpu::SurfaceConfiguration>,
+                          settings: &RenderSettings, resource_manager: &ResourceManager) -> Self{
+        Self{
+            adapter: AdapterState::capture(adapter),
+            surface: surface.map(SurfaceState::capture),
+            settings: settings.clone(),
+            resources: ResourceState::capture(resource_manager),
+        }
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String>{
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+/// The GPU and driver the renderer runs on
+#[derive(Debug, Clone, Serialize)]
+pub struct AdapterState{
+    pub name: String,
+    pub backend: String,
+    pub device_type: String,
+    pub vendor: u32,
+    pub device: u32,
+    pub driver: String,
+    pub driver_info: String,
+    /// Optional features the adapter supports, sorted
+    pub features: Vec<String>,
+}
+
+impl AdapterState{
+    fn capture(adapter: &wgpu::Adapter) -> Self{
+        let info = adapter.get_info();
+        let mut features: Vec<String> = adapter.features().iter_names().map(|(name, _)| name.to_string()).collect();
+        features.sort();
+
+        Self{
+            name: info.name,
+            backend: format!("{:?}", info.backend),
+            device_type: format!("{:?}", info.device_type),
+            vendor: info.vendor,
+            device: info.device,
+            driver: info.driver,
+            driver_info: info.driver_info,
+            features,
+        }
+    }
+}
+
+/// How the window's surface is configured
+#[derive(Debug, Clone, Serialize)]
+pub struct SurfaceState{
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+    pub present_mode: String,
+    pub alpha_mode: String,
+    pub usage: String,
+}
+
+impl SurfaceState{
+    fn capture(config: &wgpu::SurfaceConfiguration) -> Self{
+        Self{
+            width: config.width,
+            height: config.height,
+            format: format!("{:?}", config.format),
+            present_mode: format!("{:?}", config.present_mode),
+            alpha_mode: format!("{:?}", config.alpha_mode),
+            usage: format!("{:?}", config.usage),
+        }
+    }
+}
+
+/// The resource manager's contents
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceState{
+    /// Formats the pipelines are built for
+    pub color_format: String,
+    pub depth_format: String,
+    pub counts: ResourceCounts,
+    /// Sorted by shader, then hash
+    pub pipelines: Vec<PipelineState>,
+    /// Sorted by name
+    pub materials: Vec<MaterialState>,
+}
+
+impl ResourceState{
+    fn capture(resource_manager: &ResourceManager) -> Self{
+        let memory = resource_manager.get_memory_usage();
+        let counts = ResourceCounts{
+            meshes: resource_manager.get_all_mesh_handles().len(),
+            textures: resource_manager.get_all_texture_handles().len(),
+            materials: resource_manager.get_all_material_handles().len(),
+            models: resource_manager.get_all_model_handles().len(),
+            shaders: resource_manager.get_all_shader_handles().len(),
+            pipelines: resource_manager.get_all_pipeline_handles().len(),
+            uniforms: resource_manager.get_all_uniform_handles().len(),
+            lights: resource_manager.get_light_handles().len(),
+            cameras: resource_manager.get_camera_handles().len(),
+            mesh_bytes: memory.mesh_bytes,
+            texture_bytes: memory.texture_bytes,
+            uniform_bytes: memory.uniform_bytes,
+        };
+
+        let mut pipelines: Vec<PipelineState> = resource_manager.get_all_pipeline_handles().iter()
+            .filter_map(|handle| PipelineState::capture(resource_manager, handle))
+            .collect();
+        pipelines.sort_by(|a, b| (&a.shader, &a.hash).cmp(&(&b.shader, &b.hash)));
+
+        let mut materials: Vec<MaterialState> = resource_manager.get_all_material_handles().iter()
+            .filter_map(|handle| MaterialState::capture(resource_manager, handle))
+            .collect();
+        materials.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self{
+            color_format: format!("{:?}", resource_manager.get_color_format()),
+            depth_format: format!("{:?}", resource_manager.get_depth_format()),
+            counts,
+            pipelines,
+            materials,
+        }
+    }
+}
+
+/// How many of each resource are loaded, and the GPU memory they take up in bytes
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ResourceCounts{
+    pub meshes: usize,
+    pub textures: usize,
+    pub materials: usize,
+    pub models: usize,
+    pub shaders: usize,
+    pub pipelines: usize,
+    pub uniforms: usize,
+    pub lights: usize,
+    pub cameras: usize,
+    pub mesh_bytes: u64,
+    pub texture_bytes: u64,
+    pub uniform_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineState{
+    pub handle: String,
+    /// Hash of everything the pipeline was built from, pipelines with the same one are shared
+    pub hash: String,
+    pub shader: String,
+    pub topology: Option<String>,
+    /// Whether back faces are drawn, for double sided models
+    pub double_sided: bool,
+    /// Stride of each vertex buffer, in bytes
+    pub vertex_strides: Vec<u64>,
+}
+
+impl PipelineState{
+    fn capture(resource_manager: &ResourceManager, handle: &ResourceHandle) -> Option<Self>{
+        let pipeline = resource_manager.get_pipeline(handle)?;
+        let mesh_layout = resource_manager.get_pipeline_mesh_layout(handle);
+
+        Some(Self{
+            handle: describe_resource(resource_manager, handle, "Pipeline"),
+            hash: format!("{:016x}", pipeline.get_uuid()),
+            shader: describe_resource(resource_manager, &pipeline.get_shader(), "Shader"),
+            topology: mesh_layout.map(|layout| format!("{:?}", layout.get_topology())),
+            double_sided: pipeline.is_double_sided(),
+            vertex_strides: mesh_layout.map(|layout| layout.get_vertex_buffer_layouts().iter().map(|layout| layout.array_stride).collect()).unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaterialState{
+    pub name: String,
+    pub shader: String,
+    /// The material this is an instance of, if any
+    pub template: Option<String>,
+    /// Texture bound to each binding name
+    pub textures: BTreeMap<String, String>,
+    /// Uniform bound to each binding name
+    pub uniforms: BTreeMap<String, String>,
+    /// Problems `ResourceManager::validate_material` finds with the bindings
+    pub diagnostics: Vec<String>,
+}
+
+impl MaterialState{
+    fn capture(resource_manager: &ResourceManager, handle: &ResourceHandle) -> Option<Self>{
+        let material = resource_manager.get_material(handle)?;
+        let describe_all = |bindings: &std::collections::HashMap<String, ResourceHandle>, kind: &str| -> BTreeMap<String, String>{
+            bindings.iter().map(|(name, handle)| (name.clone(), describe_resource(resource_manager, handle, kind))).collect()
+        };
+
+        Some(Self{
+            name: describe_resource(resource_manager, handle, "Material"),
+            shader: describe_resource(resource_manager, &material.get_shader(), "Shader"),
+            template: material.get_template().map(|template| describe_resource(resource_manager, template, "Material")),
+            textures: describe_all(material.get_textures(), "Texture"),
+            uniforms: describe_all(material.get_uniforms(), "Uniform"),
+            diagnostics: material.validate(resource_manager).iter().map(|diagnostic| diagnostic.to_string()).collect(),
+        })
+    }
+}
+
+// A resource's name, or its kind and id if it doesn't have one. The kind is passed in, as
+// uniform buffers share their handle type with materials
+fn describe_resource(resource_manager: &ResourceManager, handle: &ResourceHandle, kind: &str) -> String{
+    match resource_manager.get_resource_name(handle){
+        Some(name) => name.to_string(),
+        None => format!("{} {:016x}", kind, handle.get_uuid()),
+    }
+}
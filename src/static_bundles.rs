@@ -0,0 +1,228 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use crate::debug::{debug_log, Subsystem};
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::resource_manager::ResourceManager;
+use crate::stats::FrameStats;
+use crate::types::model::Model;
+use crate::utils::handle::Handle;
+
+// Pipeline - the materials drawn with it, and the static models drawn with each material
+type StaticBatches = Vec<(ResourceHandle, Vec<(ResourceHandle, Vec<Handle<Model>>)>)>;
+
+/// # Static Bundles
+///
+/// Render bundles holding the draws of every static model (see `Model::set_static`), so they're
+/// encoded once and replayed every frame. There's a bundle per copy of the `objects` buffer,
+/// as each one binds a different copy
+pub(crate) struct StaticBundles{
+    // Hash of everything the bundles were recorded from, so they're only re-recorded when it changes
+    signature: Option<u64>,
+    bundles: Vec<wgpu::RenderBundle>,
+
+    // Totals of the recorded draws, added to the frame stats each time they're replayed
+    draw_calls: u32,
+    instances: u32,
+    triangles: u64,
+}
+
+impl StaticBundles{
+    pub(crate) fn new() -> Self{
+        Self{
+            signature: None,
+            bundles: Vec::new(),
+
+            draw_calls: 0,
+            instances: 0,
+            triangles: 0,
+        }
+    }
+
+    /// Whether the model is drawn from the bundles, rather than encoded every frame.
+    /// Shaders reading a `transform` uniform need it changed between draws, so can't be bundled
+    pub(crate) fn is_bundled(resource_manager: &ResourceManager, model: &Model) -> bool{
+        model.is_static()
+            && resource_manager.is_mesh_uploaded(model.get_mesh())
+            && !resource_manager.borrow_material(model.get_draw_material()).get_shader_bindings()
+                .is_some_and(|bindings| bindings.contains_key("transform"))
+    }
+
+    // Groups the static models the same way `SceneBatches` does, in a stable order so
+    // the signature doesn't change from frame to frame
+    fn collect_batches(resource_manager: &ResourceManager) -> StaticBatches{
+        let mut models: Vec<Handle<Model>> = resource_manager.get_all_models().into_iter()
+            .filter(|model| Self::is_bundled(resource_manager, model))
+            .collect();
+        if models.is_empty(){
+            return Vec::new();
+        }
+        models.sort_by_key(|model| model.get_object_index());
+
+        let mut material_handles: Vec<ResourceHandle> = Vec::new();
+        for model in models.iter(){
+            if !material_handles.contains(model.get_draw_material()){
+                material_handles.push(model.get_draw_material().clone());
+            }
+        }
+        material_handles.sort_by_key(|handle| handle.get_uuid());
+
+        let mut pipeline_handles = resource_manager.get_all_pipeline_handles();
+        pipeline_handles.sort_by_key(|handle| handle.get_uuid());
+
+        pipeline_handles.into_iter().filter_map(|pipeline_handle|{
+            let pipeline = resource_manager.get_pipeline(&pipeline_handle).unwrap();
+            let shader = pipeline.get_shader();
+            let materials: Vec<(ResourceHandle, Vec<Handle<Model>>)> = material_handles.iter()
+                .filter(|material_handle| resource_manager.borrow_material(material_handle).get_shader() == shader)
+                .map(|material_handle|{
+                    let material_models: Vec<Handle<Model>> = models.iter()
+                        .filter(|model| model.get_draw_material() == material_handle && pipeline.draws_model(model))
+                        .cloned()
+                        .collect();
+                    (material_handle.clone(), material_models)
+                })
+                .filter(|(_, material_models)| !material_models.is_empty())
+                .collect();
+
+            (!materials.is_empty()).then_some((pipeline_handle, materials))
+        }).collect()
+    }
+
+    // Everything recorded into the bundles: the attachment formats, pipelines, material bind groups,
+    // and each model's object slot and mesh buffers
+    fn compute_signature(resource_manager: &ResourceManager, batches: &StaticBatches) -> u64{
+        let mut hasher = DefaultHasher::new();
+        resource_manager.get_color_format().hash(&mut hasher);
+        resource_manager.get_depth_format().hash(&mut hasher);
+        resource_manager.get_sample_count().hash(&mut hasher);
+        resource_manager.get_objects_buffer().get_buffers().len().hash(&mut hasher);
+
+        for (pipeline_handle, materials) in batches.iter(){
+            pipeline_handle.get_uuid().hash(&mut hasher);
+            resource_manager.get_pipeline(pipeline_handle).unwrap().get_uuid().hash(&mut hasher);
+
+            for (material_handle, models) in materials.iter(){
+                material_handle.get_uuid().hash(&mut hasher);
+                let material = resource_manager.borrow_material(material_handle);
+                material.get_generation().hash(&mut hasher);
+                // Recorded into the bundle when the material is bound
+                let mut dynamic_offsets: Vec<(&u32, &Vec<u32>)> = material.get_dynamic_offsets().iter().collect();
+                dynamic_offsets.sort();
+                dynamic_offsets.hash(&mut hasher);
+
+                for model in models.iter(){
+                    model.get_object_index().hash(&mut hasher);
+                    model.get_mesh().get_uuid().hash(&mut hasher);
+
+                    let mesh = resource_manager.get_mesh(model.get_mesh()).unwrap();
+                    let vertex_buffers = resource_manager.get_mesh_vertex_buffers(model.get_mesh()).unwrap();
+                    let index_buffers = resource_manager.get_mesh_index_buffers(model.get_mesh()).unwrap();
+                    for (idx, submesh) in mesh.get_sub_meshes().iter().enumerate(){
+                        submesh.get_indices_count().hash(&mut hasher);
+                        vertex_buffers[idx].get_buffer().global_id().hash(&mut hasher);
+                        index_buffers[idx].get_buffer().global_id().hash(&mut hasher);
+                    }
+                }
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// # Update
+    ///
+    /// Re-records the bundles if the static models, or anything the bundles hold, changed since they were recorded
+    pub(crate) fn update(&mut self, resource_manager: &ResourceManager){
+        let batches = Self::collect_batches(resource_manager);
+
+        // The bundles hold the materials' bind groups, so those have to be up-to-date first
+        for (_, materials) in batches.iter(){
+            for (material_handle, _) in materials.iter(){
+                resource_manager.get_material(material_handle).unwrap().generate_bind_groups(resource_manager);
+            }
+        }
+
+        let signature = Self::compute_signature(resource_manager, &batches);
+        if self.signature == Some(signature){
+            return;
+        }
+        self.signature = Some(signature);
+
+        self.draw_calls = 0;
+        self.instances = 0;
+        self.triangles = 0;
+        if batches.is_empty(){
+            self.bundles.clear();
+            return;
+        }
+
+        let device = resource_manager.get_device();
+        let color_format = resource_manager.get_color_format();
+        let depth_format = resource_manager.get_depth_format();
+
+        let versions = resource_manager.get_objects_buffer().get_buffers().len();
+        self.bundles = (0..versions).map(|version|{
+            let mut encoder = device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor{
+                label: Some("Static Render Bundle Encoder"),
+                color_formats: &[Some(color_format)],
+                depth_stencil: Some(wgpu::RenderBundleDepthStencil{
+                    format: depth_format,
+                    depth_read_only: false,
+                    stencil_read_only: false,
+                }),
+                sample_count: resource_manager.get_sample_count(),
+                multiview: None,
+            });
+
+            for (pipeline_handle, materials) in batches.iter(){
+                let pipeline = resource_manager.get_pipeline(pipeline_handle).unwrap();
+                encoder.set_pipeline(pipeline.get_render_pipeline());
+
+                for (material_handle, models) in materials.iter(){
+                    resource_manager.borrow_material(material_handle).bind_material(&mut encoder, version);
+
+                    for model in models.iter(){
+                        let mesh = resource_manager.get_mesh(model.get_mesh()).unwrap();
+                        let vertex_buffers = resource_manager.get_mesh_vertex_buffers(model.get_mesh()).unwrap();
+                        let index_buffers = resource_manager.get_mesh_index_buffers(model.get_mesh()).unwrap();
+
+                        for (idx, submesh) in mesh.get_sub_meshes().iter().enumerate(){
+                            vertex_buffers[idx].bind_vertex_buffer(0, &mut encoder);
+                            index_buffers[idx].bind_index_buffer(&mut encoder);
+                            submesh.render_object(&mut encoder, model.get_object_index());
+
+                            // Every version holds the same draws, so only count them once
+                            if version == 0{
+                                self.draw_calls += 1;
+                                self.instances += 1;
+                                self.triangles += submesh.get_indices_count() as u64 / 3;
+                            }
+                        }
+                    }
+                }
+            }
+
+            encoder.finish(&wgpu::RenderBundleDescriptor{
+                label: Some("Static Render Bundle"),
+            })
+        }).collect();
+
+        debug_log!(Subsystem::Render, "Recorded {} static render bundles with {} draws", self.bundles.len(), self.draw_calls);
+    }
+
+    /// # Draw
+    ///
+    /// Replays the bundle reading the given version of the `objects` buffer. Bundles reset the
+    /// pass' pipeline and bind groups afterwards, so anything drawn next has to set its own
+    pub(crate) fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, objects_version: usize, stats: &mut FrameStats){
+        if self.bundles.is_empty(){
+            return;
+        }
+
+        render_pass.execute_bundles(std::iter::once(&self.bundles[objects_version % self.bundles.len()]));
+
+        stats.draw_calls += self.draw_calls;
+        stats.instances += self.instances;
+        stats.triangles += self.triangles;
+    }
+}
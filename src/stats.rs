@@ -0,0 +1,296 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use log::error;
+
+/// # Frame Stats
+///
+/// Statistics for the most recently rendered frame, updated by `Renderer::render`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats{
+    /// Number of draw calls issued
+    pub draw_calls: u32,
+    /// Number of instances drawn across all draw calls
+    pub instances: u32,
+    /// Number of triangles drawn across all draw calls
+    pub triangles: u64,
+    /// Number of times the active pipeline was changed
+    pub pipeline_switches: u32,
+    /// Number of bind groups set
+    pub bind_group_sets: u32,
+
+    /// CPU time spent recording and submitting the frame, in milliseconds
+    pub cpu_time_ms: f32,
+    /// Breakdown of `cpu_time_ms` by stage
+    pub cpu_timings: CpuTimings,
+    /// Time between the start of this frame and the start of the previous frame, in milliseconds
+    pub frame_time_ms: f32,
+    /// Frames per second, derived from `frame_time_ms`
+    pub fps: f32,
+    /// GPU time spent in the main render pass, in milliseconds
+    ///
+    /// `None` if the adapter doesn't support timestamp queries. This lags a frame
+    /// or two behind, as the results are read back without stalling
+    pub gpu_time_ms: Option<f32>,
+}
+
+/// # CPU Timings
+///
+/// CPU time spent in each stage of a frame, in milliseconds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuTimings{
+    /// Updating transforms, lights and uniforms, and rebuilding any bind groups that changed
+    pub bind_group_update_ms: f32,
+    /// Gathering the visible models into batches
+    pub extract_ms: f32,
+    /// Recording the frame's passes into the command encoder
+    pub encode_ms: f32,
+    /// Submitting the recorded commands to the queue
+    pub submit_ms: f32,
+    /// Acquiring and presenting the surface texture, which is where waiting on vsync shows up
+    pub present_ms: f32,
+}
+
+impl CpuTimings{
+    pub fn total_ms(&self) -> f32{
+        self.bind_group_update_ms + self.extract_ms + self.encode_ms + self.submit_ms + self.present_ms
+    }
+
+    /// The per-stage average of `timings`, or all zeros if there are none
+    pub fn average<'a>(timings: impl Iterator<Item = &'a CpuTimings>) -> CpuTimings{
+        let mut sum = CpuTimings::default();
+        let mut count = 0;
+        for timing in timings{
+            sum.bind_group_update_ms += timing.bind_group_update_ms;
+            sum.extract_ms += timing.extract_ms;
+            sum.encode_ms += timing.encode_ms;
+            sum.submit_ms += timing.submit_ms;
+            sum.present_ms += timing.present_ms;
+            count += 1;
+        }
+
+        if count == 0{
+            return sum;
+        }
+
+        let count = count as f32;
+        CpuTimings{
+            bind_group_update_ms: sum.bind_group_update_ms / count,
+            extract_ms: sum.extract_ms / count,
+            encode_ms: sum.encode_ms / count,
+            submit_ms: sum.submit_ms / count,
+            present_ms: sum.present_ms / count,
+        }
+    }
+}
+
+// Times consecutive stages of a frame, each lap returning the milliseconds since the last one
+pub(crate) struct ScopeTimer{
+    last: Instant,
+}
+
+impl ScopeTimer{
+    pub(crate) fn start() -> Self{
+        Self{
+            last: Instant::now(),
+        }
+    }
+
+    pub(crate) fn lap(&mut self) -> f32{
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f32() * 1000.0;
+        self.last = now;
+        elapsed
+    }
+}
+
+/// # Stats History
+///
+/// The stats of the last few frames, oldest first
+pub(crate) struct StatsHistory{
+    frames: VecDeque<FrameStats>,
+    capacity: usize,
+}
+
+impl StatsHistory{
+    pub(crate) const DEFAULT_CAPACITY: usize = 120;
+
+    pub(crate) fn new(capacity: usize) -> Self{
+        Self{
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub(crate) fn push(&mut self, stats: FrameStats){
+        if self.capacity == 0{
+            return;
+        }
+
+        while self.frames.len() >= self.capacity{
+            self.frames.pop_front();
+        }
+        self.frames.push_back(stats);
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize){
+        self.capacity = capacity;
+        while self.frames.len() > capacity{
+            self.frames.pop_front();
+        }
+    }
+
+    pub(crate) fn get_capacity(&self) -> usize{
+        self.capacity
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &FrameStats> + '_{
+        self.frames.iter()
+    }
+}
+
+/// # Memory Usage
+///
+/// Approximate GPU memory used by the resource manager's resources, in bytes, per resource type
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryUsage{
+    /// Vertex and index buffers for all meshes
+    pub mesh_bytes: u64,
+    /// All textures, including every mip level
+    pub texture_bytes: u64,
+    /// User-created uniform buffers
+    pub uniform_bytes: u64,
+}
+
+impl MemoryUsage{
+    pub fn total_bytes(&self) -> u64{
+        self.mesh_bytes + self.texture_bytes + self.uniform_bytes
+    }
+}
+
+/// # GPU Timer
+///
+/// Measures render pass GPU time with timestamp queries, reading
+/// the results back asynchronously so the CPU never waits on the GPU
+pub(crate) struct GpuTimer{
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    // Nanoseconds per timestamp tick
+    period: f32,
+
+    // Set by the map_async callback once the readback buffer can be read
+    mapped: Arc<AtomicBool>,
+    // Whether the readback buffer is mapped (or being mapped). While it is,
+    // we can't copy new results into it, so timing is skipped for that frame
+    pending: bool,
+}
+
+impl GpuTimer{
+    const QUERY_COUNT: u32 = 2;
+    const BUFFER_SIZE: wgpu::BufferAddress = Self::QUERY_COUNT as wgpu::BufferAddress * std::mem::size_of::<u64>() as wgpu::BufferAddress;
+
+    /// Creates a timer, or returns `None` if the device doesn't support timestamp queries
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self>{
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY){
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor{
+            label: Some("GPU Timer Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: Self::QUERY_COUNT,
+        });
+
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("GPU Timer Resolve Buffer"),
+            size: Self::BUFFER_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("GPU Timer Readback Buffer"),
+            size: Self::BUFFER_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(Self{
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+
+            mapped: Arc::new(AtomicBool::new(false)),
+            pending: false,
+        })
+    }
+
+    /// The timestamp writes for the render pass, or `None` if the previous results haven't been read yet
+    pub(crate) fn timestamp_writes(&self) -> Option<wgpu::RenderPassTimestampWrites<'_>>{
+        if self.pending{
+            return None;
+        }
+
+        Some(wgpu::RenderPassTimestampWrites{
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        })
+    }
+
+    /// Resolves the queries into the readback buffer. Must be called after the timed pass has ended
+    pub(crate) fn resolve(&self, encoder: &mut wgpu::CommandEncoder){
+        if self.pending{
+            return;
+        }
+
+        encoder.resolve_query_set(&self.query_set, 0..Self::QUERY_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.readback_buffer, 0, Self::BUFFER_SIZE);
+    }
+
+    /// Starts mapping the readback buffer. Must be called after the frame has been submitted
+    pub(crate) fn begin_readback(&mut self){
+        if self.pending{
+            return;
+        }
+
+        let mapped = self.mapped.clone();
+        self.readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result|{
+            match result{
+                Ok(_) => mapped.store(true, Ordering::Release),
+                Err(e) => error!("Failed to map GPU timer buffer: {}", e)
+            }
+        });
+
+        self.pending = true;
+    }
+
+    /// Returns the GPU time in milliseconds if a result is ready, without blocking
+    pub(crate) fn try_read(&mut self, device: &wgpu::Device) -> Option<f32>{
+        if !self.pending{
+            return None;
+        }
+
+        device.poll(wgpu::Maintain::Poll);
+
+        if !self.mapped.swap(false, Ordering::Acquire){
+            return None;
+        }
+
+        let time_ms = {
+            let data = self.readback_buffer.slice(..).get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&data);
+            let ticks = timestamps[1].saturating_sub(timestamps[0]);
+
+            ticks as f32 * self.period / 1_000_000.0
+        };
+
+        self.readback_buffer.unmap();
+        self.pending = false;
+
+        Some(time_ms)
+    }
+}
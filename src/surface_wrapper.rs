@@ -1,14 +1,17 @@
 use std::ops::Deref;
-use log::{error, info};
+use log::{error, info, warn};
 use winit::raw_window_handle::{HasDisplayHandle, HasRawWindowHandle};
 use crate::device_handle::DeviceHandle;
 use crate::utils::{handle::Handle, mut_handle::MutHandle};
 use crate::instance_handle::InstanceHandle;
+use crate::uniform::storage_buffer::FRAMES_IN_FLIGHT;
 
 pub struct SurfaceWrapper{
     // wgpu
     _surface: Handle<wgpu::Surface<'static>>,
-    _surface_configuration: MutHandle<wgpu::SurfaceConfiguration>
+    _surface_configuration: MutHandle<wgpu::SurfaceConfiguration>,
+    // Usages the surface's textures can be configured with on this adapter
+    supported_usages: wgpu::TextureUsages,
 }
 
 impl SurfaceWrapper{
@@ -29,6 +32,9 @@ impl SurfaceWrapper{
                 surface_caps.formats[0]
             });
 
+        // The surface has to support the usage, and so does its format (e.g sRGB formats can't be storage bound)
+        let supported_usages = surface_caps.usages & adapter.get_texture_format_features(surface_format).allowed_usages;
+
         let surface_configuration = MutHandle::new(wgpu::SurfaceConfiguration{
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
@@ -36,7 +42,7 @@ impl SurfaceWrapper{
             height: window.inner_size().height,
             present_mode: wgpu::PresentMode::Fifo,
             alpha_mode: surface_caps.alpha_modes[0],
-            desired_maximum_frame_latency: 3,
+            desired_maximum_frame_latency: FRAMES_IN_FLIGHT as u32,
             view_formats: vec![]
         });
 
@@ -47,7 +53,8 @@ impl SurfaceWrapper{
         let surface = Handle::new(surface);
         Self{
             _surface: surface,
-            _surface_configuration: surface_configuration
+            _surface_configuration: surface_configuration,
+            supported_usages,
         }
     }
 
@@ -67,6 +74,30 @@ impl SurfaceWrapper{
         }
     }
 
+    /// Usages the surface can be configured with, see `set_usage`
+    pub fn get_supported_usages(&self) -> wgpu::TextureUsages{
+        self.supported_usages
+    }
+
+    /// # Set Usage
+    ///
+    /// Reconfigures the surface's textures with the given usages on top of `RENDER_ATTACHMENT`,
+    /// e.g `COPY_SRC` to read frames back or `STORAGE_BINDING` to write to them from compute.
+    /// Usages the adapter doesn't support are skipped with a warning. Returns the usages set
+    pub fn set_usage(&mut self, device: &wgpu::Device, usage: wgpu::TextureUsages) -> wgpu::TextureUsages{
+        let usage = usage | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        let unsupported = usage - self.supported_usages;
+        if !unsupported.is_empty(){
+            warn!("Surface usages {:?} aren't supported by the adapter with format {:?}, skipping them", unsupported, self._surface_configuration.get().format);
+        }
+
+        let usage = usage & self.supported_usages;
+        self._surface_configuration.get().usage = usage;
+        self._surface.configure(device, &self._surface_configuration.get());
+
+        usage
+    }
+
     pub fn resize_surface(&mut self, device: &wgpu::Device, size: winit::dpi::PhysicalSize<u32>){
         self._surface_configuration.get().width = size.width;
         self._surface_configuration.get().height = size.height;
@@ -0,0 +1,451 @@
+//! # Testing
+//!
+//! Utilities for golden-image regression tests. A [`HeadlessRenderer`] renders the
+//! scene in its resource manager to an offscreen target (no window required), and
+//! [`assert_matches_golden`] compares the result against a reference image on disk.
+//!
+//! ```ignore
+//! let mut renderer = HeadlessRenderer::new()?;
+//! // ... load meshes, materials and models through renderer.get_resource_manager()
+//! let image = renderer.render_to_image(256, 256)?;
+//! assert_matches_golden(&image, "tests/golden/cube.png", 2);
+//! ```
+//!
+//! Set `MINIRENDERER_UPDATE_GOLDEN=1` to write (or overwrite) the reference images.
+
+use std::path::Path;
+use image::RgbaImage;
+use crate::managers::resource_manager::ResourceManager;
+use crate::scene_batches::{self, SceneBatches};
+use crate::post::color_grading::ColorGradingLut;
+use crate::post::post_stack::PostStack;
+use crate::post::blit::{Blitter, FULL_RECT};
+use crate::types::camera::CameraTarget;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::settings::RenderSettings;
+use crate::stats::FrameStats;
+use crate::state_dump::StateDump;
+use crate::types::bindless;
+use crate::utils::handle::Handle;
+use crate::utils::mut_handle::MutHandle;
+use crate::frame_context::CustomDrawFn;
+use crate::scene_frame::SceneFrame;
+use crate::screen_attachments::ScreenAttachments;
+use crate::debug::GpuValidation;
+
+// The pipelines render to this format, so the offscreen target has to match
+const TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+/// # Headless Renderer
+///
+/// Renders the scene to an offscreen texture and reads it back, without a window or surface
+pub struct HeadlessRenderer{
+    adapter: wgpu::Adapter,
+    device: Handle<wgpu::Device>,
+    queue: Handle<wgpu::Queue>,
+
+    resource_manager: MutHandle<ResourceManager>,
+    settings: RenderSettings,
+    // Depth, multisampled colour and Hi-Z pyramid, resized to each image before it's rendered
+    screen_attachments: ScreenAttachments,
+    post_stack: PostStack,
+    blitter: Blitter,
+    custom_draw: Option<CustomDrawFn>,
+    // Seconds each rendered image advances animations by
+    frame_time: f32,
+    // Time in the scene uniform, advanced by `frame_time` each render
+    time: f32,
+    // Whether to capture the next render with an attached graphics debugger
+    capture_next_frame: bool,
+}
+
+impl HeadlessRenderer{
+    /// Creates a headless renderer, or returns an error if no GPU adapter is available
+    /// (so tests can be skipped on machines without one)
+    pub fn new() -> anyhow::Result<Self>{
+        Self::with_gpu_validation(GpuValidation::default())
+    }
+
+    /// Same as `new`, with the given graphics API debugging
+    pub fn with_gpu_validation(gpu_validation: GpuValidation) -> anyhow::Result<Self>{
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor{
+            backends: wgpu::util::backend_bits_from_env().unwrap_or_default(),
+            flags: gpu_validation.get_instance_flags(),
+            ..Default::default()
+        });
+
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions{
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false
+        })).ok_or_else(|| anyhow::anyhow!("No suitable GPU adapter found"))?;
+
+        let (mut required_features, required_limits) = bindless::device_requirements(&adapter)
+            .unwrap_or((wgpu::Features::empty(), wgpu::Limits::default()));
+        if adapter.features().contains(scene_batches::INDIRECT_FEATURES){
+            required_features |= adapter.features() & (scene_batches::INDIRECT_FEATURES | wgpu::Features::MULTI_DRAW_INDIRECT_COUNT);
+        }
+        required_features |= adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor{
+                label: Some("Headless Device"),
+                required_features,
+                required_limits
+            },
+            None
+        ))?;
+
+        gpu_validation.apply_to_device(&device);
+
+        let device = Handle::new(device);
+        let queue = Handle::new(queue);
+
+        let resource_manager = MutHandle::new(ResourceManager::new(device.clone(), queue.clone()));
+        let mut screen_attachments = ScreenAttachments::new(device.clone(), resource_manager.get().get_sampler_cache(), 1, 1);
+        screen_attachments.set_depth_format(resource_manager.get().get_depth_format());
+        // Resized to each image before it's rendered
+        let post_stack = PostStack::new(device.clone(), queue.clone(), resource_manager.get().get_sampler_cache(), TARGET_FORMAT, 1, 1);
+        let blitter = Blitter::new(device.clone());
+
+        Ok(Self{
+            adapter,
+            device,
+            queue,
+
+            resource_manager,
+            settings: RenderSettings::default(),
+            screen_attachments,
+            post_stack,
+            blitter,
+            custom_draw: None,
+            frame_time: 1.0 / 60.0,
+            time: 0.0,
+            capture_next_frame: false,
+        })
+    }
+
+    pub fn get_resource_manager(&self) -> MutHandle<ResourceManager>{
+        self.resource_manager.clone()
+    }
+
+    pub fn set_render_settings(&mut self, settings: RenderSettings){
+        if settings.anisotropy != self.settings.anisotropy{
+            self.resource_manager.get().set_default_anisotropy(settings.anisotropy);
+        }
+        if settings.shadow_resolution != self.settings.shadow_resolution{
+            self.resource_manager.get().set_shadow_resolution(settings.shadow_resolution);
+        }
+        if settings.shadows != self.settings.shadows{
+            self.resource_manager.get().set_shadow_settings(settings.shadows.clone());
+        }
+        if settings.depth_format != self.settings.depth_format{
+            let format = settings.depth_format.resolve(&self.adapter);
+            self.screen_attachments.set_depth_format(format);
+            self.resource_manager.get().set_depth_format(format);
+        }
+        self.post_stack.apply_settings(&self.settings, &settings);
+        if settings.output_encoding != self.settings.output_encoding{
+            let format = if settings.output_encoding.is_linear() { self.post_stack.get_scene_format() } else { TARGET_FORMAT };
+            self.resource_manager.get().set_color_format(format);
+        }
+        if settings.msaa_samples != self.settings.msaa_samples || settings.depth_format != self.settings.depth_format
+            || settings.output_encoding != self.settings.output_encoding{
+            let mut rm = self.resource_manager.get();
+            let color_format = rm.get_color_format();
+            let samples = settings.resolve_msaa_samples(&self.adapter, &[color_format, rm.get_depth_format()]);
+            self.screen_attachments.set_multisampling(samples, color_format);
+            rm.set_sample_count(samples);
+        }
+        self.settings = settings;
+    }
+
+    /// Same as `Renderer::dump_state`, without a surface
+    pub fn dump_state(&self) -> StateDump{
+        StateDump::capture(&self.adapter, None, &self.settings, &self.resource_manager.get())
+    }
+
+    /// Same as `Renderer::trigger_gpu_capture`, capturing the next `render_to_image`
+    pub fn trigger_gpu_capture(&mut self){
+        self.capture_next_frame = true;
+    }
+
+    /// Same as `Renderer::blit`, drawn into the next `render_to_image`
+    pub fn blit(&mut self, source: &ResourceHandle, target: CameraTarget, filter: wgpu::FilterMode){
+        self.blitter.queue(source, FULL_RECT, target, FULL_RECT, filter);
+    }
+
+    /// Same as `Renderer::blit_region`
+    pub fn blit_region(&mut self, source: &ResourceHandle, source_rect: [f32; 4], target: CameraTarget, target_rect: [f32; 4], filter: wgpu::FilterMode){
+        self.blitter.queue(source, source_rect, target, target_rect, filter);
+    }
+
+    /// Same as `Renderer::set_custom_draw`
+    pub fn set_custom_draw(&mut self, custom_draw: Option<CustomDrawFn>){
+        self.custom_draw = custom_draw;
+    }
+
+    /// Sets how many seconds each rendered image advances animations (see `ResourceManager::animate_transform`) by,
+    /// which is a fixed step so renders are reproducible. Defaults to 1/60th of a second
+    pub fn set_frame_time(&mut self, frame_time: f32){
+        self.frame_time = frame_time;
+    }
+
+    pub fn set_color_grading_lut(&mut self, lut: &ColorGradingLut){
+        self.post_stack.set_color_grading_lut(lut);
+    }
+
+    /// # Render To Image
+    ///
+    /// Renders the scene at the given size and reads the result back as an RGBA image
+    pub fn render_to_image(&mut self, width: u32, height: u32) -> anyhow::Result<RgbaImage>{
+        let size = wgpu::Extent3d{
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let target = self.device.create_texture(&wgpu::TextureDescriptor{
+            label: Some("Headless Render Target"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TARGET_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        self.screen_attachments.resize(width, height);
+        self.post_stack.resize(width, height);
+
+        // Rows in a texture to buffer copy must be aligned to 256 bytes
+        let unpadded_bytes_per_row = width * 4;
+        let bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("Headless Readback Buffer"),
+            size: (bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Same per-frame updates the windowed renderer does before drawing
+        {
+            let mut rm = self.resource_manager.get();
+            rm.process_queued_resources();
+            rm.update_frame(self.frame_time, self.time, [width, height]);
+        }
+
+        self.time += self.frame_time;
+
+        let capturing = std::mem::take(&mut self.capture_next_frame);
+        if capturing{
+            self.device.start_capture();
+        }
+
+        let rm = self.resource_manager.get();
+        let batches = SceneBatches::prepare(&rm);
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor{
+            label: Some("Headless Render Encoder")
+        });
+
+        let mut stats = FrameStats::default();
+        let mut scene_frame = SceneFrame{
+            output: &view,
+            output_format: TARGET_FORMAT,
+            size: [width, height],
+            attachments: &mut self.screen_attachments,
+            post_stack: &mut self.post_stack,
+            blitter: &mut self.blitter,
+            custom_draw: &mut self.custom_draw,
+            settings: &self.settings,
+        };
+        scene_frame.encode(&rm, &batches, &mut encoder, None, &mut stats);
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture{
+                texture: &target,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer{
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout{
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            size
+        );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        if capturing{
+            self.device.stop_capture();
+        }
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result|{
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let data = readback_buffer.slice(..).get_mapped_range();
+            for row in data.chunks(bytes_per_row as usize){
+                // BGRA -> RGBA
+                for pixel in row[..unpadded_bytes_per_row as usize].chunks(4){
+                    pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                }
+            }
+        }
+        readback_buffer.unmap();
+
+        RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("Read back image data doesn't match the target size"))
+    }
+}
+
+/// # Image Comparison
+///
+/// The result of comparing two images
+#[derive(Debug, Clone, Copy)]
+pub struct ImageComparison{
+    /// Largest difference of any single channel, across all pixels
+    pub max_channel_difference: u8,
+    /// Number of pixels where any channel differs by more than the tolerance
+    pub mismatched_pixels: usize,
+    pub total_pixels: usize,
+}
+
+impl ImageComparison{
+    pub fn passed(&self) -> bool{
+        self.mismatched_pixels == 0
+    }
+}
+
+/// # Compare Images
+///
+/// Compares two images channel by channel. A pixel mismatches if any of its channels
+/// differ by more than `tolerance`. Returns an error if the images are different sizes
+pub fn compare_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> anyhow::Result<ImageComparison>{
+    if actual.dimensions() != expected.dimensions(){
+        anyhow::bail!(
+            "Image size mismatch: got {:?}, expected {:?}",
+            actual.dimensions(), expected.dimensions()
+        );
+    }
+
+    let mut comparison = ImageComparison{
+        max_channel_difference: 0,
+        mismatched_pixels: 0,
+        total_pixels: (actual.width() * actual.height()) as usize,
+    };
+
+    for (actual, expected) in actual.pixels().zip(expected.pixels()){
+        let difference = actual.0.iter().zip(expected.0.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0);
+
+        comparison.max_channel_difference = comparison.max_channel_difference.max(difference);
+        if difference > tolerance{
+            comparison.mismatched_pixels += 1;
+        }
+    }
+
+    Ok(comparison)
+}
+
+/// # Assert Matches Golden
+///
+/// Compares an image against the reference image at `path`, panicking if they differ by more
+/// than `tolerance` per channel. On failure the actual image is written next to the reference
+/// (as `<name>.actual.png`) for inspection.
+///
+/// If the reference doesn't exist, or `MINIRENDERER_UPDATE_GOLDEN` is set, the image is
+/// written as the new reference instead
+pub fn assert_matches_golden<T: AsRef<Path>>(actual: &RgbaImage, path: T, tolerance: u8){
+    let path = path.as_ref();
+
+    if std::env::var_os("MINIRENDERER_UPDATE_GOLDEN").is_some() || !path.exists(){
+        if let Some(parent) = path.parent(){
+            std::fs::create_dir_all(parent).unwrap_or_else(|e| panic!("Failed to create {:?}: {}", parent, e));
+        }
+        actual.save(path).unwrap_or_else(|e| panic!("Failed to write golden image {:?}: {}", path, e));
+        return;
+    }
+
+    let expected = image::open(path)
+        .unwrap_or_else(|e| panic!("Failed to load golden image {:?}: {}", path, e))
+        .to_rgba8();
+
+    let comparison = compare_images(actual, &expected, tolerance)
+        .unwrap_or_else(|e| panic!("Golden image {:?}: {}", path, e));
+
+    if !comparison.passed(){
+        let actual_path = path.with_extension("actual.png");
+        let _ = actual.save(&actual_path);
+
+        panic!(
+            "Image doesn't match golden image {:?}: {} of {} pixels differ by more than {} (max difference {}). Actual image written to {:?}",
+            path, comparison.mismatched_pixels, comparison.total_pixels, tolerance,
+            comparison.max_channel_difference, actual_path
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn identical_images_match(){
+        let image = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        let comparison = compare_images(&image, &image.clone(), 0).unwrap();
+
+        assert!(comparison.passed());
+        assert_eq!(comparison.max_channel_difference, 0);
+        assert_eq!(comparison.total_pixels, 16);
+    }
+
+    #[test]
+    fn differences_within_tolerance_pass(){
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(1, 2, Rgba([102, 99, 100, 255]));
+
+        let comparison = compare_images(&actual, &expected, 2).unwrap();
+        assert!(comparison.passed());
+        assert_eq!(comparison.max_channel_difference, 2);
+    }
+
+    #[test]
+    fn differences_over_tolerance_count_each_pixel_once(){
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut actual = expected.clone();
+        // Every channel differs, but it's still one pixel
+        actual.put_pixel(0, 0, Rgba([110, 90, 120, 200]));
+        actual.put_pixel(3, 3, Rgba([100, 104, 100, 255]));
+
+        let comparison = compare_images(&actual, &expected, 3).unwrap();
+        assert!(!comparison.passed());
+        assert_eq!(comparison.mismatched_pixels, 2);
+        assert_eq!(comparison.max_channel_difference, 55);
+    }
+
+    #[test]
+    fn different_sizes_are_an_error(){
+        let actual = RgbaImage::new(4, 4);
+        let expected = RgbaImage::new(4, 8);
+
+        assert!(compare_images(&actual, &expected, 255).is_err());
+    }
+}
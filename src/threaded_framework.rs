@@ -0,0 +1,173 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use log::error;
+use crate::renderer::Renderer;
+
+struct SharedSnapshot<E>{
+    snapshot: E,
+    // Increased on every publish, so the reader knows when there's a newer snapshot
+    version: u64,
+}
+
+/// # Snapshot Buffer
+///
+/// Creates the two ends of a buffered snapshot, handing the latest snapshot from one thread to another
+/// without either waiting on the other for more than a swap. Three snapshots rotate between the
+/// writer, the reader and the shared slot, so neither end ever sees one the other is using
+pub fn snapshot_buffer<E: Default>() -> (SnapshotWriter<E>, SnapshotReader<E>){
+    let shared = Arc::new(Mutex::new(SharedSnapshot{
+        snapshot: E::default(),
+        version: 0,
+    }));
+
+    (
+        SnapshotWriter{ shared: shared.clone(), back: E::default() },
+        SnapshotReader{ shared, front: E::default(), version: 0 },
+    )
+}
+
+/// # Snapshot Writer
+///
+/// The update thread's end of a snapshot buffer
+pub struct SnapshotWriter<E>{
+    shared: Arc<Mutex<SharedSnapshot<E>>>,
+    back: E,
+}
+
+impl<E> SnapshotWriter<E>{
+    /// The snapshot being written. After a publish it holds an older snapshot, so should be overwritten rather than added to
+    pub fn get_mut(&mut self) -> &mut E{
+        &mut self.back
+    }
+
+    /// Makes the written snapshot the latest, for the reader to pick up
+    pub fn publish(&mut self){
+        let mut shared = self.shared.lock().unwrap();
+        std::mem::swap(&mut shared.snapshot, &mut self.back);
+        shared.version += 1;
+    }
+}
+
+/// # Snapshot Reader
+///
+/// The render thread's end of a snapshot buffer
+pub struct SnapshotReader<E>{
+    shared: Arc<Mutex<SharedSnapshot<E>>>,
+    front: E,
+    version: u64,
+}
+
+impl<E> SnapshotReader<E>{
+    /// Takes the latest snapshot if one was published since the last call. Returns whether it did
+    pub fn update(&mut self) -> bool{
+        let mut shared = self.shared.lock().unwrap();
+        if shared.version == self.version{
+            return false;
+        }
+
+        std::mem::swap(&mut shared.snapshot, &mut self.front);
+        self.version = shared.version;
+        true
+    }
+
+    /// The most recently taken snapshot
+    pub fn get(&self) -> &E{
+        &self.front
+    }
+}
+
+/// State the render thread keeps between frames
+struct RenderThreadState<E>{
+    reader: SnapshotReader<E>,
+    apply: fn(&E, &mut Renderer) -> (),
+}
+
+/// # Threaded Render Framework
+///
+/// Like `RenderFramework`, but the user's update runs on its own thread so heavy logic doesn't hold up presenting.
+/// Each update fills in a snapshot of the scene (`E`), e.g the transforms of the models that moved, and
+/// before each frame the render thread applies the latest snapshot to the renderer.
+///
+/// `init` runs on the render thread before the update thread starts, so it can load resources
+pub struct ThreadedRenderFramework<S, E>{
+    state: S, // Persistent state, owned by the update thread once running
+    init: fn(&mut S, &mut Renderer) -> (),
+    update: fn(&mut S, &mut E, f32) -> (),
+    apply: fn(&E, &mut Renderer) -> (),
+    // Updates per second, or None to update as fast as possible
+    tick_rate: Option<f32>,
+    renderer: Renderer
+}
+
+impl<S: Send + 'static, E: Default + Send + 'static> ThreadedRenderFramework<S, E>{
+    /// `update` is given the state, the snapshot to fill in, and the seconds since the last update.
+    /// `apply` is given the latest snapshot each frame, which can be the same as the frame before
+    pub fn new(
+        state: S,
+        renderer: Renderer,
+        init: fn(&mut S, &mut Renderer) -> (),
+        update: fn(&mut S, &mut E, f32) -> (),
+        apply: fn(&E, &mut Renderer) -> (),
+    ) -> Self{
+        Self{
+            state,
+            init,
+            update,
+            apply,
+            tick_rate: Some(60.0),
+            renderer
+        }
+    }
+
+    /// Sets how many times per second the update runs, or None to run it as fast as possible. 60 by default
+    pub fn with_tick_rate(mut self, tick_rate: Option<f32>) -> Self{
+        self.tick_rate = tick_rate.filter(|rate| *rate > 0.0);
+        self
+    }
+
+    pub fn run(mut self){
+        (self.init)(&mut self.state, &mut self.renderer);
+
+        let (mut writer, reader) = snapshot_buffer::<E>();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let update_thread = {
+            let running = running.clone();
+            let (mut state, update) = (self.state, self.update);
+            let tick = self.tick_rate.map(|rate| Duration::from_secs_f32(1.0 / rate));
+
+            std::thread::Builder::new().name("update".to_string()).spawn(move ||{
+                let mut last_tick = Instant::now();
+                while running.load(Ordering::Relaxed){
+                    let tick_start = Instant::now();
+                    let delta = tick_start.duration_since(last_tick).as_secs_f32();
+                    last_tick = tick_start;
+
+                    update(&mut state, writer.get_mut(), delta);
+                    writer.publish();
+
+                    if let Some(remaining) = tick.and_then(|tick| tick.checked_sub(tick_start.elapsed())){
+                        std::thread::sleep(remaining);
+                    }
+                }
+            }).unwrap_or_else(|e| {
+                error!("Failed to spawn update thread: {}", e);
+                panic!("Failed to spawn update thread: {}", e)
+            })
+        };
+
+        let render_state = RenderThreadState{ reader, apply: self.apply };
+        self.renderer.run(render_state, Self::render_frame);
+
+        running.store(false, Ordering::Relaxed);
+        if update_thread.join().is_err(){
+            error!("Update thread panicked");
+        }
+    }
+
+    fn render_frame(state: &mut RenderThreadState<E>, renderer: &mut Renderer){
+        state.reader.update();
+        (state.apply)(state.reader.get(), renderer);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::sampler_cache::{SamplerCache, SamplerKey};
+use crate::types::texture::{ColorSpace, Texture};
+use crate::utils::handle::Handle;
+
+/// The most textures the bindless texture array holds
+pub const MAX_BINDLESS_TEXTURES: u32 = 256;
+
+/// The name of the global texture array. Shaders declare it as
+/// `var bindless_textures: binding_array<texture_2d<f32>>;` with a single `bindless_textures_sampler`,
+/// and index it with the indices from `ResourceManager::get_bindless_texture_index`
+pub const BINDLESS_TEXTURES_BINDING: &str = "bindless_textures";
+
+/// Shader that samples each model's texture from the bindless texture array, using the first
+/// index set with `ResourceManager::set_model_bindless_textures`. Every model can share one
+/// material, as it only needs `objects` and a `camera` uniform in group 0 and the array in group 1
+pub const BINDLESS_SHADER: &str = include_str!("../../assets/shaders/bindless.wgsl");
+
+// Each model picks its own texture, so the index isn't uniform across a draw's invocations
+pub(crate) const BINDLESS_FEATURES: wgpu::Features = wgpu::Features::TEXTURE_BINDING_ARRAY
+    .union(wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING);
+
+/// The features and limits to request for bindless textures, or None if the adapter can't do them
+pub(crate) fn device_requirements(adapter: &wgpu::Adapter) -> Option<(wgpu::Features, wgpu::Limits)> {
+    // Leave room for the regular textures a shader binds alongside the array
+    let max_sampled_textures = adapter.limits().max_sampled_textures_per_shader_stage;
+    if !adapter.features().contains(BINDLESS_FEATURES) || max_sampled_textures < MAX_BINDLESS_TEXTURES + 16 {
+        return None;
+    }
+
+    let limits = wgpu::Limits {
+        max_sampled_textures_per_shader_stage: max_sampled_textures,
+        ..wgpu::Limits::default()
+    };
+    Some((BINDLESS_FEATURES, limits))
+}
+
+/// # Bindless Textures
+///
+/// The textures in the global texture array, in index order. Unused slots are filled with
+/// a white placeholder when binding, as every element of the array has to be bound
+pub(crate) struct BindlessTextures {
+    textures: Vec<ResourceHandle>,
+    indices: HashMap<ResourceHandle, u32>,
+    placeholder: Texture,
+    // Shared by every texture in the array
+    sampler: Handle<wgpu::Sampler>,
+}
+
+impl BindlessTextures {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, samplers: &SamplerCache) -> Self {
+        let placeholder = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+        let placeholder = Texture::from_image(device, queue, samplers, &placeholder, ColorSpace::Linear, "Bindless Placeholder Texture");
+
+        let sampler = samplers.get(SamplerKey {
+            address_mode: wgpu::AddressMode::Repeat,
+            ..SamplerKey::linear(1)
+        });
+
+        Self {
+            textures: Vec::new(),
+            indices: HashMap::new(),
+            placeholder,
+            sampler,
+        }
+    }
+
+    /// Adds a texture to the array if it isn't already in it, returning its index and whether it was added.
+    /// Returns None when the array is full
+    pub fn register(&mut self, texture_handle: &ResourceHandle) -> Option<(u32, bool)> {
+        if let Some(index) = self.indices.get(texture_handle) {
+            return Some((*index, false));
+        }
+        if self.textures.len() as u32 >= MAX_BINDLESS_TEXTURES {
+            return None;
+        }
+
+        let index = self.textures.len() as u32;
+        self.textures.push(texture_handle.clone());
+        self.indices.insert(texture_handle.clone(), index);
+        Some((index, true))
+    }
+
+    pub fn get_index(&self, texture_handle: &ResourceHandle) -> Option<u32> {
+        self.indices.get(texture_handle).copied()
+    }
+
+    pub fn get_textures(&self) -> &[ResourceHandle] {
+        &self.textures
+    }
+
+    pub fn get_placeholder(&self) -> &Texture {
+        &self.placeholder
+    }
+
+    pub fn get_sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+}
@@ -0,0 +1,34 @@
+use glam::Vec3;
+
+/// # Bounding Sphere
+///
+/// A sphere enclosing a set of points, in the space of those points (e.g a submesh's local space)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Centred on the points' bounding box, so not the tightest fit, but cheap to build.
+    /// None if there are no points
+    pub fn from_points(points: impl Iterator<Item = Vec3> + Clone) -> Option<Self> {
+        let (min, max) = points.clone().fold(None, |bounds: Option<(Vec3, Vec3)>, point| match bounds {
+            Some((min, max)) => Some((min.min(point), max.max(point))),
+            None => Some((point, point)),
+        })?;
+
+        let center = (min + max) * 0.5;
+        let radius = points.map(|point| point.distance_squared(center)).fold(0.0, f32::max).sqrt();
+        Some(Self { center, radius })
+    }
+
+    /// Center in xyz and radius in w, as the GPU reads it
+    pub fn to_array(&self) -> [f32; 4] {
+        [self.center.x, self.center.y, self.center.z, self.radius]
+    }
+}
@@ -0,0 +1,117 @@
+use crate::types::pbr_material::PBR_SHADER;
+
+/// Shader that draws the `base_color` texture multiplied by the `material` colour, without lighting.
+/// Reads `objects` and the renderer's `scene` uniform in group 0
+pub const UNLIT_SHADER: &str = include_str!("../../assets/shaders/unlit.wgsl");
+
+/// Shader that shades the `base_color` texture multiplied by the `material` colour with Blinn-Phong.
+/// Like `UNLIT_SHADER`, plus the `lights` uniform from `ResourceManager::get_lights_uniform_handle`
+pub const LIT_SHADER: &str = include_str!("../../assets/shaders/lit.wgsl");
+
+/// `LIT_SHADER` for `SkinnedVertex` meshes, blending each vertex between the joints of a `SkinUniform`
+/// named `skin`
+pub const SKINNED_SHADER: &str = include_str!("../../assets/shaders/skinned.wgsl");
+
+/// Shader that draws models in flat magenta, used in place of shaders that fail to compile
+/// and unknown built-in shaders
+pub const ERROR_SHADER: &str = include_str!("../../assets/shaders/error.wgsl");
+
+/// The names `ResourceManager::get_builtin_shader` accepts
+pub const BUILTIN_SHADER_NAMES: [&str; 4] = ["unlit", "lit", "pbr", "skinned"];
+
+/// The name of the joint matrices uniform of `SKINNED_SHADER`
+pub const SKIN_BINDING: &str = "skin";
+
+/// The most joints a `SkinUniform` holds
+pub const MAX_JOINTS: usize = 64;
+
+/// # Builtin Shader Source
+///
+/// The WGSL of a built-in shader, by one of the `BUILTIN_SHADER_NAMES`
+pub fn builtin_shader_source(name: &str) -> Option<&'static str>{
+    match name{
+        "unlit" => Some(UNLIT_SHADER),
+        "lit" => Some(LIT_SHADER),
+        "pbr" => Some(PBR_SHADER),
+        "skinned" => Some(SKINNED_SHADER),
+        _ => None
+    }
+}
+
+/// # Basic Material Uniform
+///
+/// The `material` uniform of `UNLIT_SHADER`, `LIT_SHADER` and `SKINNED_SHADER`. The colour
+/// multiplies the `base_color` texture, and the specular is ignored when unlit. The emissive
+/// colour is added on top of the lighting, masked by the texture, so a texture's bright parts glow
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct BasicMaterialUniform{
+    /// Linear RGBA
+    pub color: [f32; 4],
+    /// Specular strength in x, shininess in y
+    pub specular: [f32; 4],
+    /// Linear RGB, already multiplied by its strength. Values above the bloom threshold glow
+    /// when the `bloom` post effect is on
+    pub emissive: [f32; 4],
+}
+
+impl BasicMaterialUniform{
+    pub fn new(color: [f32; 4]) -> Self{
+        Self{
+            color,
+            specular: [0.5, 32.0, 0.0, 0.0],
+            emissive: [0.0; 4],
+        }
+    }
+
+    pub fn with_specular(mut self, strength: f32, shininess: f32) -> Self{
+        self.specular = [strength, shininess, 0.0, 0.0];
+        self
+    }
+
+    /// Makes the material glow in the linear colour, scaled by the strength. Strengths above 1
+    /// push it past the bloom threshold, e.g for neon signs
+    pub fn with_emissive(mut self, color: [f32; 3], strength: f32) -> Self{
+        self.emissive = [color[0] * strength, color[1] * strength, color[2] * strength, 0.0];
+        self
+    }
+}
+
+impl Default for BasicMaterialUniform{
+    fn default() -> Self{
+        Self::new([1.0; 4])
+    }
+}
+
+crate::impl_as_bytes!(BasicMaterialUniform);
+
+/// # Skin Uniform
+///
+/// The `skin` uniform of `SKINNED_SHADER`: each joint's world transform multiplied by its
+/// inverse bind matrix, relative to the model. Unused joints are left as the identity
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkinUniform{
+    pub joints: [[[f32; 4]; 4]; MAX_JOINTS],
+}
+
+impl SkinUniform{
+    /// Joints past `MAX_JOINTS` are ignored
+    pub fn new(joint_matrices: &[glam::Mat4]) -> Self{
+        let mut uniform = Self::default();
+        for (joint, matrix) in uniform.joints.iter_mut().zip(joint_matrices){
+            *joint = matrix.to_cols_array_2d();
+        }
+        uniform
+    }
+}
+
+impl Default for SkinUniform{
+    fn default() -> Self{
+        Self{
+            joints: [glam::Mat4::IDENTITY.to_cols_array_2d(); MAX_JOINTS],
+        }
+    }
+}
+
+crate::impl_as_bytes!(SkinUniform);
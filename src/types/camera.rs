@@ -0,0 +1,76 @@
+use crate::managers::resource_handle::ResourceHandle;
+
+/// A layer mask with every render layer, which cameras draw by default
+pub const ALL_RENDER_LAYERS: u32 = u32::MAX;
+
+/// # Camera Target
+///
+/// Where a camera draws to
+#[derive(Debug, Clone, PartialEq)]
+pub enum CameraTarget{
+    /// The window (or the headless renderer's image), before post effects and overlays
+    Surface,
+    /// A texture from `ResourceManager::create_render_texture`, which materials can then sample
+    Texture(ResourceHandle),
+}
+
+/// # Camera
+///
+/// A view of the scene drawn each frame, in addition to (or instead of) the default camera
+/// set with `ResourceManager::set_camera`. Added with `ResourceManager::add_camera`.
+///
+/// Cameras are drawn in order of priority, lowest first, so cameras drawing into textures
+/// should have a lower priority than the cameras showing those textures. Once any active camera
+/// draws to the surface, the default camera isn't drawn
+#[derive(Debug, Clone)]
+pub struct Camera{
+    pub view: glam::Mat4,
+    pub projection: glam::Mat4,
+    pub target: CameraTarget,
+    /// The area of the target drawn to, as x, y, width and height from 0 to 1. `None` covers all of it
+    pub viewport: Option<[f32; 4]>,
+    pub priority: i32,
+    /// The colour the target is cleared to, or `None` for the render settings' clear colour.
+    /// Only the first camera drawing to a target clears it, as clears cover the whole target
+    pub clear_color: Option<wgpu::Color>,
+    /// Inactive cameras aren't drawn
+    pub active: bool,
+    /// Bitmask of the render layers drawn, see `Model::set_render_layers`
+    pub layer_mask: u32,
+}
+
+impl Camera{
+    pub fn new(view: glam::Mat4, projection: glam::Mat4, target: CameraTarget) -> Self{
+        Self{
+            view,
+            projection,
+            target,
+            viewport: None,
+            priority: 0,
+            clear_color: None,
+            active: true,
+            layer_mask: ALL_RENDER_LAYERS,
+        }
+    }
+
+    pub fn with_viewport(mut self, x: f32, y: f32, width: f32, height: f32) -> Self{
+        self.viewport = Some([x, y, width, height]);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self{
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_clear_color(mut self, clear_color: wgpu::Color) -> Self{
+        self.clear_color = Some(clear_color);
+        self
+    }
+
+    /// Only draws the models on at least one of the render layers in the mask
+    pub fn with_layer_mask(mut self, layer_mask: u32) -> Self{
+        self.layer_mask = layer_mask;
+        self
+    }
+}
@@ -0,0 +1,100 @@
+use crate::types::vertex::Vertex;
+use crate::utils::buffer::{AsBytes, Buffer, BufferType};
+use crate::debug::{debug_log, Subsystem};
+
+// Number of buffer sets we rotate between, so we never write into one the GPU may still be reading
+const BUFFER_COUNT: usize = 2;
+
+/// # Dynamic Mesh
+///
+/// A mesh whose vertices and indices are rewritten often (cloth, trails, soft bodies).
+///
+/// Instead of recreating buffers, data is written into preallocated buffers which only grow
+/// when the data no longer fits. Two sets of buffers are used in turn, so each update writes
+/// into the set that the previous frame wasn't drawn with
+pub struct DynamicMesh{
+    vertex_buffers: Vec<Vec<Buffer>>,
+    index_buffers: Vec<Vec<Buffer>>,
+    current: usize,
+
+    vertex_capacity: usize,
+    index_capacity: usize,
+}
+
+impl DynamicMesh{
+    pub(crate) fn new(device: &wgpu::Device, vertex_capacity: usize, index_capacity: usize) -> Self{
+        let mut mesh = Self{
+            vertex_buffers: Vec::new(),
+            index_buffers: Vec::new(),
+            current: 0,
+
+            vertex_capacity: 0,
+            index_capacity: 0,
+        };
+        mesh.allocate(device, vertex_capacity.max(1), index_capacity.max(1));
+
+        mesh
+    }
+
+    fn allocate(&mut self, device: &wgpu::Device, vertex_capacity: usize, index_capacity: usize){
+        debug_log!(Subsystem::Resources, "Allocating dynamic mesh buffers for {} vertices and {} indices", vertex_capacity, index_capacity);
+
+        let vertex_bytes = vec![0u8; vertex_capacity * std::mem::size_of::<Vertex>()];
+        let index_bytes = vec![0u8; index_capacity * std::mem::size_of::<u32>()];
+
+        self.vertex_buffers = (0..BUFFER_COUNT)
+            .map(|_| vec![Buffer::create_buffer_from_bytes(device, &vertex_bytes, BufferType::Vertex)])
+            .collect();
+        self.index_buffers = (0..BUFFER_COUNT)
+            .map(|_| vec![Buffer::create_buffer_from_bytes(device, &index_bytes, BufferType::Index)])
+            .collect();
+
+        self.vertex_capacity = vertex_capacity;
+        self.index_capacity = index_capacity;
+    }
+
+    /// Writes new data into the next set of buffers and makes it the one that's drawn,
+    /// growing the buffers if the data doesn't fit
+    pub(crate) fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, vertices: &[Vertex], indices: &[u32]){
+        if vertices.len() > self.vertex_capacity || indices.len() > self.index_capacity{
+            self.allocate(
+                device,
+                vertices.len().max(self.vertex_capacity).next_power_of_two(),
+                indices.len().max(self.index_capacity).next_power_of_two(),
+            );
+        }
+
+        self.current = (self.current + 1) % BUFFER_COUNT;
+        if !vertices.is_empty(){
+            self.vertex_buffers[self.current][0].update(queue, vertices.as_bytes());
+        }
+        if !indices.is_empty(){
+            self.index_buffers[self.current][0].update(queue, indices.as_bytes());
+        }
+    }
+
+    pub(crate) fn get_vertex_buffers(&self) -> &Vec<Buffer>{
+        &self.vertex_buffers[self.current]
+    }
+
+    pub(crate) fn get_index_buffers(&self) -> &Vec<Buffer>{
+        &self.index_buffers[self.current]
+    }
+
+    pub(crate) fn get_memory_size(&self) -> u64{
+        self.vertex_buffers.iter().chain(self.index_buffers.iter())
+            .flatten()
+            .map(|buffer| buffer.get_size() as u64)
+            .sum()
+    }
+
+    /// Number of vertices that fit before the buffers need to grow
+    pub fn get_vertex_capacity(&self) -> usize{
+        self.vertex_capacity
+    }
+
+    /// Number of indices that fit before the buffers need to grow
+    pub fn get_index_capacity(&self) -> usize{
+        self.index_capacity
+    }
+}
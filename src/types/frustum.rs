@@ -0,0 +1,60 @@
+use glam::{Mat4, Vec3, Vec4};
+use crate::types::bounds::BoundingSphere;
+
+/// # Frustum
+///
+/// The volume a camera sees, as six planes in the space the view-projection matrix
+/// was built from (usually world space). Works with standard, reverse-Z and infinite
+/// far projections
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far, with the normal in xyz pointing inwards and
+    /// the distance in w. Normals are normalized, so plane tests give true distances
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// # From View Projection
+    ///
+    /// Extracts the planes bounding clip space (x and y in -w..w, z in 0..w) from a view-projection matrix
+    pub fn from_view_projection(view_projection: Mat4) -> Self {
+        let (row_x, row_y, row_z, row_w) = (view_projection.row(0), view_projection.row(1), view_projection.row(2), view_projection.row(3));
+
+        let planes = [row_w + row_x, row_w - row_x, row_w + row_y, row_w - row_y, row_z, row_w - row_z].map(|plane| {
+            let length = plane.truncate().length();
+            plane / length.max(f32::EPSILON)
+        });
+
+        Self { planes }
+    }
+
+    /// Signed distance from a plane to a point, positive on the inside
+    fn distance(plane: Vec4, point: Vec3) -> f32 {
+        plane.truncate().dot(point) + plane.w
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes.iter().all(|plane| Self::distance(*plane, point) >= 0.0)
+    }
+
+    /// Whether any of the sphere may be inside. Spheres near the frustum's corners can pass
+    /// without being visible, which is fine for culling
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().all(|plane| Self::distance(*plane, sphere.center) >= -sphere.radius)
+    }
+
+    /// Whether any of the axis-aligned box between `min` and `max` may be inside, with the same caveat as `intersects_sphere`
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the plane's normal
+            let normal = plane.truncate();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), max, min);
+            Self::distance(*plane, corner) >= 0.0
+        })
+    }
+
+    /// The planes as the GPU reads them
+    pub(crate) fn to_arrays(self) -> [[f32; 4]; 6] {
+        self.planes.map(|plane| plane.to_array())
+    }
+}
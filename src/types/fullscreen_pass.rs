@@ -0,0 +1,37 @@
+use crate::managers::resource_handle::ResourceHandle;
+
+/// The full-screen vertex shader `ResourceManager::create_fullscreen_pass` prepends to fragment shaders, shared
+/// with the post effects. It declares `PostVertexOutput`, with the clip position and a `uv` from 0 to 1 across the target
+pub const FULLSCREEN_TRIANGLE_WGSL: &str = include_str!("../../assets/shaders/post_fullscreen.wgsl");
+
+/// # Fullscreen Pass
+///
+/// A fragment shader drawn over a single triangle covering its target, e.g for custom post effects,
+/// LUT previews or procedural backgrounds. The shader's textures and uniforms are assigned to
+/// `get_material` like any other material, and the `scene` and `globals` uniforms are bound automatically.
+///
+/// Draw it into a render texture with `ResourceManager::draw_fullscreen_pass`, or behind the scene
+/// with `FrameContext::draw_fullscreen`
+#[derive(Debug, Clone)]
+pub struct FullscreenPass {
+    material: ResourceHandle,
+    pipeline: ResourceHandle,
+}
+
+impl FullscreenPass {
+    pub(crate) fn new(material: ResourceHandle, pipeline: ResourceHandle) -> Self {
+        Self {
+            material,
+            pipeline,
+        }
+    }
+
+    /// The material holding the shader's inputs
+    pub fn get_material(&self) -> &ResourceHandle {
+        &self.material
+    }
+
+    pub fn get_pipeline(&self) -> &ResourceHandle {
+        &self.pipeline
+    }
+}
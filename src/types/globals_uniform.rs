@@ -0,0 +1,48 @@
+/// The name of the uniform the renderer fills with the frame's timing and resolution, see `GlobalsUniform`.
+/// Any material whose shader declares it has it bound automatically, unless the material assigns its own
+pub const GLOBALS_BINDING: &str = "globals";
+
+/// WGSL declaration of `GlobalsUniform`. Bind it at any free group and binding, e.g
+/// `@group(1) @binding(0) var<uniform> globals: Globals;`
+pub const GLOBALS_UNIFORM_WGSL: &str = r#"
+struct Globals {
+    // The default camera's position, in world space
+    camera_position: vec3<f32>,
+    // Seconds since the renderer started
+    time: f32,
+    // Size of the frame being drawn, in pixels
+    resolution: vec2<f32>,
+    // Seconds since the previous frame
+    delta: f32,
+    // Frames drawn before this one
+    frame: u32,
+};
+"#;
+
+/// # Globals Uniform
+///
+/// Timing and resolution data for animated shader effects, kept up to date by the renderer
+/// every frame. Unlike `SceneUniform`, it's the same for every camera
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GlobalsUniform{
+    pub camera_position: [f32; 3],
+    pub time: f32,
+    pub resolution: [f32; 2],
+    pub delta: f32,
+    pub frame: u32,
+}
+
+impl GlobalsUniform{
+    pub fn new(camera_position: glam::Vec3, time: f32, delta: f32, resolution: [u32; 2], frame: u32) -> Self{
+        Self{
+            camera_position: camera_position.to_array(),
+            time,
+            resolution: [resolution[0] as f32, resolution[1] as f32],
+            delta,
+            frame,
+        }
+    }
+}
+
+crate::impl_as_bytes!(GlobalsUniform);
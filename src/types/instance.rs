@@ -1,5 +1,3 @@
-use crate::utils::buffer::AsBytes;
-
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Instance{
@@ -37,26 +35,4 @@ impl Instance {
     }
 }
 
-// Impl to &[u8] for Instance
-impl AsBytes for Instance {
-    fn as_bytes(&self) -> &[u8] {
-        unsafe {
-            std::slice::from_raw_parts(
-                (self as *const Instance) as *const u8,
-                std::mem::size_of::<Instance>(),
-            )
-        }
-    }
-}
-
-// Impl to &[u8] for Vec<Instance>
-impl AsBytes for Vec<Instance> {
-    fn as_bytes(&self) -> &[u8] {
-        unsafe {
-            std::slice::from_raw_parts(
-                self.as_ptr() as *const u8,
-                self.len() * std::mem::size_of::<Instance>(),
-            )
-        }
-    }
-}
+crate::impl_as_bytes!(Instance);
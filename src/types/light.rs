@@ -0,0 +1,241 @@
+use glam::Vec3;
+use crate::settings::ShadowSettings;
+use crate::types::transform::Transform;
+
+/// The most lights `LightsUniform` holds. Lights past this are ignored when shading
+pub const MAX_LIGHTS: usize = 16;
+
+/// The most point lights casting shadows at once. Shadow casters past this are shaded unshadowed
+pub const MAX_POINT_SHADOWS: usize = 4;
+
+/// The name of the point shadow map binding. Shaders declare it as `texture_depth_2d_array`, with a
+/// `sampler_comparison` named `point_shadows_sampler`, and the renderer binds them itself.
+/// Each shadowed light has six layers, one per cube face, holding the distance to the light over its far plane
+pub const POINT_SHADOWS_BINDING: &str = "point_shadows";
+
+/// Far plane of the shadows of point lights without a range
+pub const DEFAULT_POINT_SHADOW_FAR: f32 = 50.0;
+
+/// # Light Type
+///
+/// The shape of a light, following `KHR_lights_punctual`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightType {
+    /// Infinitely far away, lighting everything from the transform's -Z direction
+    Directional,
+    /// Emits in every direction from the transform's position
+    Point,
+    /// Emits a cone along the transform's -Z direction. Angles are in radians from the centre of the cone
+    Spot { inner_cone_angle: f32, outer_cone_angle: f32 },
+}
+
+/// # Light
+///
+/// A punctual light. Intensity is in lux for directional lights and candela for the others
+#[derive(Debug, Clone)]
+pub struct Light {
+    light_type: LightType,
+    color: Vec3,
+    intensity: f32,
+    // None means the light reaches infinitely far
+    range: Option<f32>,
+    transform: Transform,
+    cast_shadows: bool,
+}
+
+impl Light {
+    pub fn new(light_type: LightType, color: Vec3, intensity: f32) -> Self {
+        Self {
+            light_type,
+            color,
+            intensity,
+            range: None,
+            transform: Transform::new(),
+            cast_shadows: false,
+        }
+    }
+
+    pub fn directional(color: Vec3, intensity: f32) -> Self {
+        Self::new(LightType::Directional, color, intensity)
+    }
+
+    pub fn point(color: Vec3, intensity: f32) -> Self {
+        Self::new(LightType::Point, color, intensity)
+    }
+
+    pub fn spot(color: Vec3, intensity: f32, inner_cone_angle: f32, outer_cone_angle: f32) -> Self {
+        Self::new(LightType::Spot { inner_cone_angle, outer_cone_angle }, color, intensity)
+    }
+
+    /// Distance after which the light has no effect, smoothly fading towards it
+    pub fn with_range(mut self, range: f32) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Casts shadows. Only point lights are shadowed for now, see `MAX_POINT_SHADOWS`
+    pub fn with_shadows(mut self) -> Self {
+        self.cast_shadows = true;
+        self
+    }
+
+    pub fn get_light_type(&self) -> LightType {
+        self.light_type
+    }
+
+    pub fn get_color(&self) -> Vec3 {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Vec3) {
+        self.color = color;
+    }
+
+    pub fn get_intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+    }
+
+    pub fn get_range(&self) -> Option<f32> {
+        self.range
+    }
+
+    pub fn set_range(&mut self, range: Option<f32>) {
+        self.range = range;
+    }
+
+    pub fn is_casting_shadows(&self) -> bool {
+        self.cast_shadows
+    }
+
+    pub fn set_cast_shadows(&mut self, cast_shadows: bool) {
+        self.cast_shadows = cast_shadows;
+    }
+
+    /// Distance the light's shadows reach, which is its range if it has one
+    pub fn get_shadow_far(&self) -> f32 {
+        self.range.unwrap_or(DEFAULT_POINT_SHADOW_FAR)
+    }
+
+    pub(crate) fn has_point_shadow(&self) -> bool {
+        self.cast_shadows && self.light_type == LightType::Point
+    }
+
+    pub fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    pub fn get_position(&self) -> Vec3 {
+        self.transform.get_position()
+    }
+
+    /// The direction light travels in, which is the transform's -Z axis
+    pub fn get_direction(&self) -> Vec3 {
+        self.transform.get_rotation() * Vec3::NEG_Z
+    }
+}
+
+/// # Light Uniform
+///
+/// A single light, as laid out in `LightsUniform`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    /// Position, and range (0 for infinite)
+    pub position_range: [f32; 4],
+    /// Direction, and type (0 directional, 1 point, 2 spot)
+    pub direction_type: [f32; 4],
+    /// Linear colour, and intensity
+    pub color_intensity: [f32; 4],
+    /// Spot cone attenuation scale and offset, applied to the cosine of the angle to the light
+    pub spot_scale_offset: [f32; 4],
+    /// First layer of the light's faces in the point shadow map (negative without a shadow), and its far plane
+    pub shadow: [f32; 4],
+}
+
+impl LightUniform {
+    pub fn new(light: &Light) -> Self {
+        let (light_type, spot_scale_offset) = match light.light_type {
+            LightType::Directional => (0.0, [0.0; 4]),
+            LightType::Point => (1.0, [0.0; 4]),
+            LightType::Spot { inner_cone_angle, outer_cone_angle } => {
+                // Maps cos(outer) to 0 and cos(inner) to 1, as in the KHR_lights_punctual reference
+                let scale = 1.0 / (inner_cone_angle.cos() - outer_cone_angle.cos()).max(0.001);
+                (2.0, [scale, -outer_cone_angle.cos() * scale, 0.0, 0.0])
+            }
+        };
+
+        let position = light.get_position();
+        let direction = light.get_direction();
+        Self {
+            position_range: [position.x, position.y, position.z, light.range.unwrap_or(0.0)],
+            direction_type: [direction.x, direction.y, direction.z, light_type],
+            color_intensity: [light.color.x, light.color.y, light.color.z, light.intensity],
+            spot_scale_offset,
+            shadow: [-1.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+/// # Lights Uniform
+///
+/// Every light in the scene, as bound to the `lights` uniform of `PBR_SHADER`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightsUniform {
+    /// The number of lights in use, in x
+    pub count: [u32; 4],
+    /// Depth bias, slope bias, normal offset and PCF kernel size, from `ShadowSettings`
+    pub shadow: [f32; 4],
+    pub lights: [LightUniform; MAX_LIGHTS],
+}
+
+impl LightsUniform {
+    pub fn new<'a>(lights: impl Iterator<Item = &'a Light>) -> Self {
+        let mut uniform: Self = bytemuck::Zeroable::zeroed();
+        uniform.set_shadow_settings(&ShadowSettings::default());
+        let mut shadow_count = 0;
+        for (slot, light) in uniform.lights.iter_mut().zip(lights) {
+            *slot = LightUniform::new(light);
+            uniform.count[0] += 1;
+
+            // Same order as `point_shadow_casters`, which the shadow map is drawn from
+            if light.has_point_shadow() && shadow_count < MAX_POINT_SHADOWS {
+                slot.shadow = [(shadow_count * 6) as f32, light.get_shadow_far(), 0.0, 0.0];
+                shadow_count += 1;
+            }
+        }
+        uniform
+    }
+
+    pub fn with_shadow_settings(mut self, settings: &ShadowSettings) -> Self {
+        self.set_shadow_settings(settings);
+        self
+    }
+
+    fn set_shadow_settings(&mut self, settings: &ShadowSettings) {
+        let pcf_size = settings.pcf_size.clamp(1, ShadowSettings::MAX_PCF_SIZE);
+        self.shadow = [settings.depth_bias, settings.slope_bias, settings.normal_offset, pcf_size as f32];
+    }
+}
+
+/// The lights given a point shadow, in shadow map order
+pub(crate) fn point_shadow_casters<'a>(lights: impl Iterator<Item = &'a Light>) -> impl Iterator<Item = &'a Light> {
+    lights.take(MAX_LIGHTS)
+        .filter(|light| light.has_point_shadow())
+        .take(MAX_POINT_SHADOWS)
+}
+
+crate::impl_as_bytes!(LightsUniform);
@@ -1,12 +1,95 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
-use log::{error, info};
+use log::{error, warn};
+use wgpu::util::RenderEncoder;
 use crate::utils::handle::Handle;
 use crate::managers::resource_handle::ResourceHandle;
 use crate::managers::resource_manager::{ResourceManager, ResourceType};
+use crate::managers::sampler_cache::SamplerKey;
 use crate::types::texture::Texture;
-use crate::utils::buffer::{Buffer, BufferType};
+use crate::types::object_data::OBJECTS_BINDING;
+use crate::types::scene_uniform::SCENE_BINDING;
+use crate::types::globals_uniform::GLOBALS_BINDING;
+use crate::types::light::POINT_SHADOWS_BINDING;
+use crate::types::bindless::{BINDLESS_TEXTURES_BINDING, MAX_BINDLESS_TEXTURES};
+use crate::uniform::uniform_buffer::UniformBuffer;
 use crate::utils::shader_reflect::{Binding, BindingType};
+use crate::debug::{debug_log, Subsystem};
+
+/// # Material Diagnostic
+///
+/// A problem found when cross-checking a material against its shader's reflected bindings
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaterialDiagnostic{
+    /// The material has no shader assigned
+    NoShader,
+    /// The shader declares a texture the material doesn't provide
+    MissingTexture(String),
+    /// The shader declares a uniform the material doesn't provide
+    MissingUniform(String),
+    /// The material provides a texture the shader doesn't declare
+    ExtraTexture(String),
+    /// The material provides a uniform the shader doesn't declare
+    ExtraUniform(String),
+    /// The material provides a resource under the binding's name, but of the wrong kind
+    MistypedBinding{ name: String, expected: &'static str, found: &'static str },
+    /// The material references a resource handle the resource manager doesn't know about
+    UnknownResource{ name: String, kind: &'static str },
+    /// A sampler binding that doesn't follow the `<texture>_sampler` naming convention
+    MisnamedSampler(String),
+    /// The uniform data doesn't match the size or member offsets of the shader's struct
+    UniformLayoutMismatch{ name: String, message: String },
+    /// The shader uses a binding kind materials can't provide yet
+    UnsupportedBinding{ name: String, kind: &'static str }
+}
+
+impl MaterialDiagnostic{
+    /// Whether this diagnostic stops the material from rendering.
+    /// Extra resources are harmless, so they are only reported as warnings
+    pub fn is_error(&self) -> bool{
+        !matches!(self, MaterialDiagnostic::ExtraTexture(_) | MaterialDiagnostic::ExtraUniform(_))
+    }
+
+    /// Whether the renderer can stand in for the binding instead of refusing to render. Missing
+    /// 2D textures get a white texture, textures that don't exist a magenta checker, and
+    /// uniforms a zeroed buffer of the size the shader expects
+    pub fn has_fallback(&self) -> bool{
+        matches!(self,
+            MaterialDiagnostic::MissingTexture(_) |
+            MaterialDiagnostic::MissingUniform(_) |
+            MaterialDiagnostic::MistypedBinding{ .. } |
+            MaterialDiagnostic::UnknownResource{ .. } |
+            MaterialDiagnostic::UniformLayoutMismatch{ .. }
+        )
+    }
+}
+
+impl std::fmt::Display for MaterialDiagnostic{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        match self{
+            MaterialDiagnostic::NoShader =>
+                write!(f, "Material has no shader. Call `assign_shader_to_material` before rendering"),
+            MaterialDiagnostic::MissingTexture(name) =>
+                write!(f, "Shader expects texture `{}`. Call `assign_texture_to_material(.., \"{}\")`", name, name),
+            MaterialDiagnostic::MissingUniform(name) =>
+                write!(f, "Shader expects uniform `{}`. Call `assign_uniform_to_material(.., \"{}\")`", name, name),
+            MaterialDiagnostic::ExtraTexture(name) =>
+                write!(f, "Texture `{}` is assigned but the shader has no binding with that name", name),
+            MaterialDiagnostic::ExtraUniform(name) =>
+                write!(f, "Uniform `{}` is assigned but the shader has no binding with that name", name),
+            MaterialDiagnostic::MistypedBinding{ name, expected, found } =>
+                write!(f, "Binding `{}` is a {} in the shader, but a {} was assigned", name, expected, found),
+            MaterialDiagnostic::UnknownResource{ name, kind } =>
+                write!(f, "The {} assigned to `{}` doesn't exist in the resource manager", kind, name),
+            MaterialDiagnostic::MisnamedSampler(name) =>
+                write!(f, "Sampler `{}` must be named `<texture>_sampler` so it can be matched to a texture", name),
+            MaterialDiagnostic::UniformLayoutMismatch{ message, .. } =>
+                write!(f, "{}", message),
+            MaterialDiagnostic::UnsupportedBinding{ name, kind } =>
+                write!(f, "Binding `{}` is a {} binding, which materials don't support yet", name, kind),
+        }
+    }
+}
 
 pub struct Material{
     // Textures
@@ -17,11 +100,21 @@ pub struct Material{
     // Entries are separate, and are generated from the bind group layouts
     // closer to the time of rendering
     bind_groups: HashMap<u32, Handle<wgpu::BindGroup>>,
-    // The buffer to the binding (for Uniforms only)
-    bind_group_buffers: HashMap<String, Handle<Buffer>>,
+    // Groups reading the versioned `objects` buffer, with a bind group per version of it
+    versioned_bind_groups: HashMap<u32, Vec<Handle<wgpu::BindGroup>>>,
+    // Bind groups replaced by `set_uniform` while drawing. A render pass may still be using them,
+    // so they're kept alive until the next time the bind groups are generated
+    retired_bind_groups: Vec<Handle<wgpu::BindGroup>>,
+
+    // Zeroed uniforms bound in place of missing or mis-sized ones, by binding name
+    fallback_uniforms: HashMap<String, Handle<UniformBuffer>>,
+    // Group - offsets its buffers with dynamic offsets are bound at, see `set_dynamic_offsets`
+    dynamic_offsets: HashMap<u32, Vec<u32>>,
 
     // Flag to check if the bind groups need to be regenerated
     needs_regen: bool,
+    // Groups to rebuild on their own, while the rest of the bind groups stay valid, see `swap_texture`
+    stale_groups: HashSet<u32>,
 
 
     // Shader
@@ -31,6 +124,17 @@ pub struct Material{
     // Acceptable pipelines
     pipelines: Vec<ResourceHandle>,
 
+    // Template material this material is an instance of (if any). Instances share the
+    // template's shader and only own bind groups for the groups they override
+    template: Option<ResourceHandle>,
+    // Bumped every time the bind groups are regenerated, so instances know when the
+    // template's shared bind groups have gone stale
+    generation: u64,
+    template_generation: u64,
+
+    // What models created with this material start with, see `Model::set_double_sided`
+    double_sided: bool,
+
     // A reference to the device
     _device: Handle<wgpu::Device>,
     _queue: Handle<wgpu::Queue>
@@ -43,31 +147,94 @@ impl Material{
             uniforms: HashMap::new(),
 
             bind_groups: HashMap::new(),
-            bind_group_buffers: HashMap::new(),
+            versioned_bind_groups: HashMap::new(),
+            retired_bind_groups: Vec::new(),
+            fallback_uniforms: HashMap::new(),
+            dynamic_offsets: HashMap::new(),
             needs_regen: true,
+            stale_groups: HashSet::new(),
             
             shader_handle: None, // Just a dummy handle for now
             shader_bindings: None, // we assign when we assign the shader
             pipelines: Vec::new(),
 
+            template: None,
+            generation: 0,
+            template_generation: 0,
+
+            double_sided: false,
+
             _device: device,
             _queue: queue
         }
     }
 
-    pub(crate) fn update(&mut self, resource_manager: &ResourceManager){
-        // Update the buffers
+    /// # New Instance
+    ///
+    /// Creates a lightweight instance of a template material. The instance shares the
+    /// template's shader (and therefore its pipeline and bind group layouts), and any
+    /// texture or uniform that isn't overridden is read from the template.
+    ///
+    /// Bind groups that contain no overridden bindings are shared with the template
+    /// instead of being duplicated.
+    pub fn new_instance(template_handle: ResourceHandle, template: &Material) -> Self{
+        Self{
+            textures: HashMap::new(),
+            uniforms: HashMap::new(),
 
-        // Go through each uniform and each buffer, and check the string name are the same
+            bind_groups: HashMap::new(),
+            versioned_bind_groups: HashMap::new(),
+            retired_bind_groups: Vec::new(),
+            fallback_uniforms: HashMap::new(),
+            dynamic_offsets: template.dynamic_offsets.clone(),
+            needs_regen: true,
+            stale_groups: HashSet::new(),
 
-        // If they are, update the buffer with the new data
-        for (buffer_name, buffer_handle) in self.bind_group_buffers.iter(){
-            for (uniform_name, uniform_handle) in self.uniforms.iter(){
-                if buffer_name == uniform_name{
-                    let uniform = resource_manager.get_uniform_buffer(uniform_handle).unwrap();
-                    buffer_handle.copy_buffer(&self._device, &self._queue, uniform.get_buffer());
-                }
-            }
+            shader_handle: template.shader_handle.clone(),
+            shader_bindings: template.shader_bindings.clone(),
+            pipelines: template.pipelines.clone(),
+
+            template: Some(template_handle),
+            generation: 0,
+            template_generation: 0,
+
+            double_sided: template.double_sided,
+
+            _device: template._device.clone(),
+            _queue: template._queue.clone()
+        }
+    }
+
+    /// # Duplicate
+    ///
+    /// Creates a material with the same shader, textures and uniforms, and the same template
+    /// if this is an instance. The assignments are copied, but the resources they point to are shared.
+    /// Bind groups are created afresh the first time it's drawn
+    pub fn duplicate(&self) -> Self{
+        Self{
+            textures: self.textures.clone(),
+            uniforms: self.uniforms.clone(),
+
+            bind_groups: HashMap::new(),
+            versioned_bind_groups: HashMap::new(),
+            retired_bind_groups: Vec::new(),
+            fallback_uniforms: HashMap::new(),
+            dynamic_offsets: self.dynamic_offsets.clone(),
+            needs_regen: true,
+            stale_groups: HashSet::new(),
+
+            shader_handle: self.shader_handle.clone(),
+            shader_bindings: self.shader_bindings.clone(),
+            pipelines: self.pipelines.clone(),
+
+            template: self.template.clone(),
+            generation: 0,
+            template_generation: 0,
+
+            double_sided: self.double_sided,
+
+            _device: self._device.clone(),
+            _queue: self._queue.clone()
         }
     }
 
@@ -78,6 +245,25 @@ impl Material{
         self.needs_regen = true;
     }
 
+    pub fn uses_texture(&self, texture_handle: &ResourceHandle) -> bool{
+        self.textures.values().any(|handle| handle == texture_handle)
+    }
+
+    /// # Set Dynamic Offsets
+    ///
+    /// Sets the byte offsets a group's buffers with dynamic offsets are bound at, in binding order, e.g to
+    /// pick an element of a buffer holding one struct per object. Offsets must be multiples of the device's
+    /// `min_uniform_buffer_offset_alignment` (usually 256). Takes effect the next time the material is bound,
+    /// without rebuilding its bind groups. Groups without any set are bound at 0
+    pub fn set_dynamic_offsets(&mut self, group: u32, offsets: Vec<u32>){
+        self.dynamic_offsets.insert(group, offsets);
+    }
+
+    /// Forces the bind groups to be rebuilt, e.g when a texture's sampler was replaced
+    pub(crate) fn mark_needs_regen(&mut self){
+        self.needs_regen = true;
+    }
+
     pub fn add_uniform(&mut self, name: &str, uniform_handle: ResourceHandle){
         self.uniforms.insert(name.to_string(), uniform_handle);
 
@@ -85,18 +271,83 @@ impl Material{
         self.needs_regen = true;
     }
 
-    /// Set Material
+    /// # Set Uniform
     ///
     /// To be used when the parameter is being set during render time
-    /// for switching between params for each model for example
+    /// for switching between params for each model for example.
+    /// The material binds the uniform's buffer directly, so the bind groups are rebuilt
+    /// whenever the uniform differs from the one currently bound
     pub fn set_uniform(&mut self, name: &str, uniform_handle: ResourceHandle, resource_manager: &ResourceManager){
-        //  This will only be used when the material is being set during render time,
-        // so we assume the bind groups are already generated
-        // Update the buffer
-        let buffer_handle = self.bind_group_buffers.get(name).unwrap();
-        let uniform = resource_manager.get_uniform_buffer(&uniform_handle).unwrap();
-        
-        buffer_handle.copy_buffer(&self._device, &self._queue, uniform.get_buffer());
+        if self.uniforms.get(name) == Some(&uniform_handle){
+            return;
+        }
+
+        // The current bind groups may already be recorded in a render pass
+        let mut retired = std::mem::take(&mut self.retired_bind_groups);
+        retired.extend(self.bind_groups.values().cloned());
+        retired.extend(self.versioned_bind_groups.values().flatten().cloned());
+
+        self.add_uniform(name, uniform_handle);
+        self.generate_bind_groups(resource_manager);
+        self.retired_bind_groups = retired;
+    }
+
+    /// # Swap Texture
+    ///
+    /// Replaces the texture bound to a binding while drawing, e.g to switch skins. Only the
+    /// bind group holding the texture and its sampler is rebuilt, the rest are left as they are
+    pub fn swap_texture(&mut self, name: &str, texture_handle: ResourceHandle, resource_manager: &ResourceManager){
+        if self.textures.get(name) == Some(&texture_handle){
+            return;
+        }
+
+        let sampler_name = format!("{}_sampler", name);
+        let groups: HashSet<u32> = self.shader_bindings.iter().flatten()
+            .filter(|(binding_name, _)| *binding_name == name || **binding_name == sampler_name)
+            .map(|(_, binding)| binding.get_group())
+            .collect();
+
+        self.textures.insert(name.to_string(), texture_handle);
+
+        // Nothing's been generated yet, or it'll all be generated anyway
+        if self.needs_regen || groups.is_empty(){
+            self.needs_regen = true;
+            return;
+        }
+
+        // The current bind groups may already be recorded in a render pass
+        let mut retired = std::mem::take(&mut self.retired_bind_groups);
+        for group in groups.iter(){
+            retired.extend(self.bind_groups.get(group).cloned());
+            retired.extend(self.versioned_bind_groups.get(group).into_iter().flatten().cloned());
+        }
+
+        self.stale_groups.extend(groups);
+        self.generate_bind_groups(resource_manager);
+        self.retired_bind_groups = retired;
+    }
+
+    /// Returns the template this material is an instance of, if any
+    pub fn get_template(&self) -> Option<&ResourceHandle>{
+        self.template.as_ref()
+    }
+
+    pub fn is_instance(&self) -> bool{
+        self.template.is_some()
+    }
+
+    /// Whether the bind groups are out of date, e.g after `add_uniform`, `add_texture` or `swap_texture`
+    pub(crate) fn needs_bind_groups(&self) -> bool{
+        self.needs_regen || !self.stale_groups.is_empty()
+    }
+
+    /// Whether models created with this material are double sided, e.g from a glTF material's `doubleSided`
+    pub fn set_double_sided(&mut self, double_sided: bool){
+        self.double_sided = double_sided;
+    }
+
+    pub fn is_double_sided(&self) -> bool{
+        self.double_sided
     }
 
     pub fn get_texture(&self, name: &str) -> Option<&ResourceHandle>{
@@ -107,6 +358,14 @@ impl Material{
         self.uniforms.get(name)
     }
 
+    pub fn get_uniforms(&self) -> &HashMap<String, ResourceHandle>{
+        &self.uniforms
+    }
+
+    pub fn get_textures(&self) -> &HashMap<String, ResourceHandle>{
+        &self.textures
+    }
+
     pub fn set_shader(&mut self, shader: ResourceHandle, bindings: HashMap<String, Binding>){
         self.shader_handle = Some(shader);
         self.shader_bindings = Some(bindings);
@@ -116,6 +375,10 @@ impl Material{
     pub fn get_shader(&self) -> ResourceHandle{
         self.shader_handle.as_ref().unwrap().clone()
     }
+
+    pub fn get_shader_bindings(&self) -> Option<&HashMap<String, Binding>>{
+        self.shader_bindings.as_ref()
+    }
     
 
     pub fn add_pipeline(&mut self, pipeline: ResourceHandle){
@@ -124,11 +387,39 @@ impl Material{
 
     
     pub fn generate_bind_groups(&mut self, resource_manager: &ResourceManager){
+        // Bind groups retired while drawing the last frame are no longer in use
+        self.retired_bind_groups.clear();
+
+        // Instances share bind groups with their template, which is brought up-to-date before
+        // anything draws (see `ResourceManager::update_template_bind_groups`), so regenerate if it changed
+        let template = self.get_template_material(resource_manager);
+
+        if let Some(template) = template.as_ref(){
+            if template.generation != self.template_generation{
+                self.needs_regen = true;
+            }
+        }
+
         // Check if we need to regenerate the bind groups
-        if !self.needs_regen{
+        if !self.needs_regen && self.stale_groups.is_empty(){
             return;
         }
 
+        // Report every problem up-front, rather than panicking on the first bad binding.
+        // Bindings with a fallback are substituted below, so only the rest stop the material rendering
+        let (fallbacks, errors): (Vec<MaterialDiagnostic>, Vec<MaterialDiagnostic>) = self.validate(resource_manager).into_iter()
+            .filter(|diagnostic| diagnostic.is_error())
+            .partition(|diagnostic| diagnostic.has_fallback());
+        for diagnostic in fallbacks.iter(){
+            warn!("Material validation failed, using a fallback: {}", diagnostic);
+        }
+        if !errors.is_empty(){
+            for diagnostic in errors.iter(){
+                error!("Material validation failed: {}", diagnostic);
+            }
+            panic!("Material is misconfigured ({} errors), see the log for details", errors.len());
+        }
+
         // We generate bind groups for each binding the shader has, using
         // the textures and uniforms we have. We check the string name of the
         // binding against the textures and uniforms we have (saved as keys), and generate the
@@ -136,59 +427,100 @@ impl Material{
         // so we can reuse them, and only regenerate them if the textures or uniforms change
         let shader_bindings = self.shader_bindings.as_ref().unwrap();
 
-        error!("Generating bind groups");
+        // The groups this material creates itself. A regular material owns every group,
+        // an instance only owns the groups where it overrides at least one binding
+        let owned_groups: HashSet<u32> = shader_bindings.iter()
+            .filter(|(name, binding)| template.is_none() || self.overrides_binding(name, binding))
+            .map(|(_, binding)| binding.get_group())
+            .collect();
+        // Either all of them, or only the stale ones when nothing else changed
+        let regen_groups: HashSet<u32> = owned_groups.iter()
+            .filter(|group| self.needs_regen || self.stale_groups.contains(group))
+            .copied()
+            .collect();
 
-        // Initial pass to generate the buffers for the uniforms and storage
-        for (name, binding) in shader_bindings.iter(){
-            match binding.get_binding_type(){
-                BindingType::Uniform => {
-                    let uniform_handle = self.uniforms.get(name).unwrap_or_else(||{
-                        error!("Failed to bind uniform: {}", name);
-                        error!("Please ensure the shader and material are correctly configured");
-                        panic!();
-                    });
+        debug_log!(Subsystem::Materials, "Generating bind groups");
 
-                    let uniform = resource_manager.get_uniform_buffer(uniform_handle).unwrap_or_else(||{
-                        error!("Failed to bind uniform: {}", name);
-                        error!("Please ensure the shader and material are correctly configured");
-                        panic!();
-                    });
+        let shader = resource_manager.get_shader(self.shader_handle.as_ref().unwrap());
 
-                    // Create another buffer for the bind group
-                    let buffer = Buffer::create_buffer_from_type(
-                        &self._device,
-                        &uniform.get_data().as_bytes(),
-                        BufferType::Uniform
-                    );
-
-                    let buffer_handle = Handle::new(buffer);
+        // Initial pass to find the uniforms, whose buffers are bound directly
+        let mut uniform_buffers: HashMap<&str, Handle<UniformBuffer>> = HashMap::new();
+        for (name, binding) in shader_bindings.iter(){
+            if !regen_groups.contains(&binding.get_group()) || !matches!(binding.get_binding_type(), BindingType::Uniform){
+                continue;
+            }
 
-                    info!("Created buffer for uniform: {}", name);
+            let expected_size = shader.and_then(|shader| shader.get_uniform_layout(name)).map(|layout| layout.size as usize);
+            let uniform = self.uniforms.get(name)
+                .or_else(|| template.as_ref().and_then(|template| template.get_uniform(name)))
+                .or_else(|| (name == SCENE_BINDING).then(|| resource_manager.get_scene_uniform_ref()))
+                .or_else(|| (name == GLOBALS_BINDING).then(|| resource_manager.get_globals_uniform_ref()))
+                .and_then(|uniform_handle| resource_manager.get_uniform_buffer(uniform_handle))
+                .filter(|uniform| shader.is_none_or(|shader| shader.validate_uniform_layout(name, uniform).is_ok()));
+
+            let uniform = match (uniform, expected_size){
+                (Some(uniform), _) => uniform,
+                (None, Some(size)) => self.fallback_uniforms.entry(name.clone())
+                    .or_insert_with(|| Handle::new(UniformBuffer::new(self._device.clone(), vec![0u8; size], "Fallback Uniform")))
+                    .clone(),
+                (None, None) => {
+                    error!("Failed to bind uniform: {}", name);
+                    error!("Please ensure the shader and material are correctly configured");
+                    panic!();
+                }
+            };
 
-                    self.bind_group_buffers.insert(name.to_string(), buffer_handle.clone());
-                },
-                BindingType::Storage => {
-                    todo!()
-                },
-                _ => {}
-            }
+            uniform_buffers.insert(name.as_str(), uniform);
         }
 
         // Now we have the bindings, figure out which textures and uniforms we need
         // Group -> Entry, so we can generate the bind groups correctly
+        // Every element of the bindless texture array has to be bound, so unused slots get a placeholder
+        let bindless_views: Vec<&wgpu::TextureView> = match resource_manager.get_bindless_textures(){
+            Some(bindless) if shader_bindings.contains_key(BINDLESS_TEXTURES_BINDING) => {
+                let mut views: Vec<&wgpu::TextureView> = bindless.get_textures().iter()
+                    .map(|texture_handle| resource_manager.borrow_texture(texture_handle).get_texture_view())
+                    .collect();
+                views.resize(MAX_BINDLESS_TEXTURES as usize, bindless.get_placeholder().get_texture_view());
+                views
+            }
+            _ => Vec::new()
+        };
+
+        // Samplers the layout declares as non-filtering can't be given the textures' own, filtering ones
+        let non_filtering_sampler = shader_bindings.values()
+            .any(|binding| shader.and_then(|shader| shader.get_layout_entry(binding.get_group(), binding.get_binding()))
+                .is_some_and(|entry| entry.ty == wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering)))
+            .then(|| resource_manager.get_sampler_cache().get(SamplerKey::nearest()));
+
         let mut entries: HashMap<u32, Vec<wgpu::BindGroupEntry>> = HashMap::new();
 
         for (name, binding) in shader_bindings.iter(){
-            info!("Binding: {}", name);
+            if !regen_groups.contains(&binding.get_group()){
+                continue;
+            }
+
+            debug_log!(Subsystem::Materials, "Binding: {}", name);
             match binding.get_binding_type(){
-                BindingType::Texture => {
-                    info!("Type: Texture");
+                BindingType::Texture if name == BINDLESS_TEXTURES_BINDING => {
+                    debug_log!(Subsystem::Materials, "Type: Bindless Texture Array");
 
-                    let texture_handle = self.textures.get(name).unwrap_or_else(||{
-                        error!("Failed to bind texture: {}", name);
-                        error!("Please ensure the shader and material are correctly configured");
-                        panic!();
-                    });
+                    let entry = wgpu::BindGroupEntry{
+                        binding: binding.get_binding(),
+                        resource: wgpu::BindingResource::TextureViewArray(&bindless_views),
+                    };
+                    let entries = entries.entry(binding.get_group()).or_default();
+                    entries.push(entry);
+                },
+                BindingType::Texture => {
+                    debug_log!(Subsystem::Materials, "Type: Texture");
+
+                    let texture_handle = self.find_texture_or_fallback(name, binding, template, resource_manager)
+                        .unwrap_or_else(||{
+                            error!("Failed to bind texture: {}", name);
+                            error!("Please ensure the shader and material are correctly configured");
+                            panic!();
+                        });
                     let texture = resource_manager.borrow_texture(texture_handle);
                     let texture_view = texture.get_texture_view();
                     let entry = wgpu::BindGroupEntry{
@@ -199,36 +531,56 @@ impl Material{
                     entries.push(entry);
                 },
                 BindingType::TextureSampler => {
-                    info!("Type: Texture Sampler");
+                    debug_log!(Subsystem::Materials, "Type: Texture Sampler");
                     // The name will be *texture_name*_sampler,
                     // so we need to strip the _sampler part
                     let sampler_texture_name = &name[..name.len() - 8];
-                    let texture_handle = self.textures.get(sampler_texture_name).unwrap_or_else(||{
-                        error!("Failed to bind texture sampler: {}", name);
-                        error!("Please ensure the shader and material are correctly configured");
-                        panic!();
-                    });
+                    if sampler_texture_name == BINDLESS_TEXTURES_BINDING{
+                        let entry = wgpu::BindGroupEntry{
+                            binding: binding.get_binding(),
+                            resource: wgpu::BindingResource::Sampler(resource_manager.get_bindless_textures().unwrap().get_sampler()),
+                        };
+                        let entries = entries.entry(binding.get_group()).or_default();
+                        entries.push(entry);
+                        continue;
+                    }
+                    let texture_handle = self.find_texture_or_fallback(sampler_texture_name, binding, template, resource_manager)
+                        .unwrap_or_else(||{
+                            error!("Failed to bind texture sampler: {}", name);
+                            error!("Please ensure the shader and material are correctly configured");
+                            panic!();
+                        });
                     let texture = resource_manager.borrow_texture(texture_handle);
-                    let texture_sampler = texture.get_texture_sampler();
+                    let non_filtering = shader.and_then(|shader| shader.get_layout_entry(binding.get_group(), binding.get_binding()))
+                        .is_some_and(|entry| entry.ty == wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering));
+                    let texture_sampler = match non_filtering_sampler.as_ref(){
+                        Some(sampler) if non_filtering => sampler,
+                        _ => texture.get_texture_sampler(),
+                    };
                     let entry = wgpu::BindGroupEntry{
                         binding: binding.get_binding(),
-                        resource: wgpu::BindingResource::Sampler(&texture_sampler),
+                        resource: wgpu::BindingResource::Sampler(texture_sampler),
                     };
                     let entries = entries.entry(binding.get_group()).or_insert_with(Vec::new);
                     entries.push(entry);
                 },
                 BindingType::Uniform => {
-                    info!("Type: Uniform");
-                    // We already generated the buffer for this, so we just need to get it
-                    let buffer_handle = self.bind_group_buffers.get(name).unwrap();
+                    debug_log!(Subsystem::Materials, "Type: Uniform");
+                    // We already found the uniform for this, so we just need to get it
+                    let uniform = uniform_buffers.get(name.as_str()).unwrap();
+
+                    // With a dynamic offset, the binding only covers the struct the offset points at
+                    let size = shader.filter(|shader| shader.has_dynamic_offset(name))
+                        .and_then(|shader| shader.get_uniform_layout(name))
+                        .and_then(|layout| wgpu::BufferSize::new(layout.size as u64));
 
                     // Create the entry
                     let entry = wgpu::BindGroupEntry{
                         binding: binding.get_binding(),
                         resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding{
-                            buffer: &buffer_handle.get_buffer(),
+                            buffer: uniform.get_buffer(),
                             offset: 0,
-                            size: None
+                            size
                         })
                     };
 
@@ -236,33 +588,243 @@ impl Material{
                     entries.push(entry);
                 },
                 BindingType::Storage => {
-                    info!("Type: Storage");
-                    todo!()
+                    debug_log!(Subsystem::Materials, "Type: Storage");
+                    if name != OBJECTS_BINDING{
+                        error!("Failed to bind storage buffer: {}", name);
+                        error!("Only the `{}` storage buffer is supported", OBJECTS_BINDING);
+                        panic!();
+                    }
+
+                    let entry = wgpu::BindGroupEntry{
+                        binding: binding.get_binding(),
+                        resource: resource_manager.get_objects_buffer().get_buffer().as_entire_binding()
+                    };
+
+                    let entries = entries.entry(binding.get_group()).or_default();
+                    entries.push(entry);
                 }
             }
         }
 
-        let shader = resource_manager.get_shader(&self.shader_handle.as_ref().unwrap()).unwrap();
+        let shader = shader.unwrap();
+        let objects_binding = shader_bindings.get(OBJECTS_BINDING);
+
+        // Every buffer with a dynamic offset needs one when the group is bound, so any not set are bound at 0
+        for group in shader_bindings.values().map(|binding| binding.get_group()).collect::<HashSet<u32>>(){
+            let count = shader.get_dynamic_offset_count(group);
+            if count > 0{
+                self.dynamic_offsets.entry(group).or_default().resize(count, 0);
+            }
+        }
 
         // For each group, generate the bind group layout
         for (group, entries) in entries.iter(){
             let layout = shader.get_bind_group_layout(*group);
             if let Some(layout) = layout {
-                let bind_group = self._device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    layout: &layout,
-                    entries,
-                    label: None
-                });
-                self.bind_groups.insert(*group, Handle::new(bind_group));
+                match objects_binding.filter(|binding| binding.get_group() == *group){
+                    // The objects buffer is rewritten every frame into its next copy, so the group gets a bind group per copy
+                    Some(objects_binding) => {
+                        let bind_groups = resource_manager.get_objects_buffer().get_buffers().iter().map(|buffer|{
+                            let mut entries = entries.clone();
+                            for entry in entries.iter_mut().filter(|entry| entry.binding == objects_binding.get_binding()){
+                                entry.resource = buffer.as_entire_binding();
+                            }
+
+                            Handle::new(self._device.create_bind_group(&wgpu::BindGroupDescriptor {
+                                layout,
+                                entries: &entries,
+                                label: None
+                            }))
+                        }).collect();
+                        self.bind_groups.remove(group);
+                        self.versioned_bind_groups.insert(*group, bind_groups);
+                    }
+                    None => {
+                        let bind_group = self._device.create_bind_group(&wgpu::BindGroupDescriptor {
+                            layout: &layout,
+                            entries,
+                            label: None
+                        });
+                        self.versioned_bind_groups.remove(group);
+                        self.bind_groups.insert(*group, Handle::new(bind_group));
+                    }
+                }
+            }
+        }
+
+        // Any group we don't own is shared with the template as-is
+        if let Some(template) = template.as_ref(){
+            for (group, bind_group) in template.bind_groups.iter(){
+                if !owned_groups.contains(group){
+                    self.bind_groups.insert(*group, bind_group.clone());
+                }
+            }
+            for (group, bind_groups) in template.versioned_bind_groups.iter(){
+                if !owned_groups.contains(group){
+                    self.versioned_bind_groups.insert(*group, bind_groups.clone());
+                }
             }
+
+            self.template_generation = template.generation;
         }
 
+        self.generation += 1;
         self.needs_regen = false;
+        self.stale_groups.clear();
+    }
+
+    /// # Validate
+    ///
+    /// Cross-checks the material's textures and uniforms against the shader's reflected
+    /// bindings, returning every missing, extra or mistyped binding found
+    pub fn validate(&self, resource_manager: &ResourceManager) -> Vec<MaterialDiagnostic>{
+        let mut diagnostics = Vec::new();
+
+        let shader_bindings = match self.shader_bindings.as_ref(){
+            Some(shader_bindings) => shader_bindings,
+            None => {
+                diagnostics.push(MaterialDiagnostic::NoShader);
+                return diagnostics;
+            }
+        };
+
+        let shader = self.shader_handle.as_ref().and_then(|handle| resource_manager.get_shader(handle));
+        let template = self.get_template_material(resource_manager);
+
+        let find_texture = |name: &str| self.textures.get(name)
+            .or_else(|| template.as_ref().and_then(|template| template.get_texture(name)));
+        let find_uniform = |name: &str| self.uniforms.get(name)
+            .or_else(|| template.as_ref().and_then(|template| template.get_uniform(name)));
+
+        for (name, binding) in shader_bindings.iter(){
+            match binding.get_binding_type(){
+                // The renderer provides the bindless texture array itself
+                BindingType::Texture if name == BINDLESS_TEXTURES_BINDING => {
+                    if resource_manager.get_bindless_textures().is_none(){
+                        diagnostics.push(MaterialDiagnostic::UnsupportedBinding{ name: name.clone(), kind: "bindless texture" });
+                    }
+                },
+                BindingType::Texture => match find_texture(name){
+                    Some(texture_handle) => {
+                        if resource_manager.get_texture(texture_handle).is_none(){
+                            diagnostics.push(MaterialDiagnostic::UnknownResource{ name: name.clone(), kind: "texture" });
+                        }
+                    },
+                    None if find_uniform(name).is_some() => {
+                        diagnostics.push(MaterialDiagnostic::MistypedBinding{ name: name.clone(), expected: "texture", found: "uniform" });
+                    },
+                    // The renderer provides the point shadow map itself
+                    None if name == POINT_SHADOWS_BINDING => {},
+                    None => diagnostics.push(MaterialDiagnostic::MissingTexture(name.clone()))
+                },
+                BindingType::TextureSampler => match name.strip_suffix("_sampler"){
+                    // If the shader declares the texture, the texture binding reports it instead
+                    Some(texture_name) => {
+                        if !shader_bindings.contains_key(texture_name) && find_texture(texture_name).is_none(){
+                            diagnostics.push(MaterialDiagnostic::MissingTexture(texture_name.to_string()));
+                        }
+                    },
+                    None => diagnostics.push(MaterialDiagnostic::MisnamedSampler(name.clone()))
+                },
+                BindingType::Uniform => match find_uniform(name){
+                    Some(uniform_handle) => match resource_manager.get_uniform_buffer(uniform_handle){
+                        Some(uniform) => {
+                            if let Some(Err(e)) = shader.map(|shader| shader.validate_uniform_layout(name, &uniform)){
+                                diagnostics.push(MaterialDiagnostic::UniformLayoutMismatch{ name: name.clone(), message: e.to_string() });
+                            }
+                        },
+                        None => diagnostics.push(MaterialDiagnostic::UnknownResource{ name: name.clone(), kind: "uniform" })
+                    },
+                    None if find_texture(name).is_some() => {
+                        diagnostics.push(MaterialDiagnostic::MistypedBinding{ name: name.clone(), expected: "uniform", found: "texture" });
+                    },
+                    // The renderer provides the scene and globals uniforms itself
+                    None if name == SCENE_BINDING || name == GLOBALS_BINDING => {},
+                    None => diagnostics.push(MaterialDiagnostic::MissingUniform(name.clone()))
+                },
+                BindingType::Storage => {
+                    if name != OBJECTS_BINDING{
+                        diagnostics.push(MaterialDiagnostic::UnsupportedBinding{ name: name.clone(), kind: "storage" });
+                    }
+                }
+            }
+        }
+
+        // Anything the material provides that the shader never asks for. Names the shader does
+        // declare (under a different kind) are already reported as mistyped above
+        for name in self.textures.keys(){
+            if !shader_bindings.contains_key(name){
+                diagnostics.push(MaterialDiagnostic::ExtraTexture(name.clone()));
+            }
+        }
+
+        for name in self.uniforms.keys(){
+            if !shader_bindings.contains_key(name){
+                diagnostics.push(MaterialDiagnostic::ExtraUniform(name.clone()));
+            }
+        }
+
+        diagnostics
+    }
+
+    // Whether this material provides its own value for a binding, rather than
+    // inheriting it from the template
+    fn overrides_binding(&self, name: &str, binding: &Binding) -> bool{
+        match binding.get_binding_type(){
+            BindingType::Texture => self.textures.contains_key(name),
+            BindingType::TextureSampler => self.textures.contains_key(&name[..name.len() - 8]),
+            BindingType::Uniform | BindingType::Storage => self.uniforms.contains_key(name),
+        }
+    }
+
+    // The texture bound to a texture or sampler binding. Filterable, single sampled 2D bindings fall back to the white
+    // texture when the material has none, and to the missing texture when it names one that doesn't exist
+    fn find_texture_or_fallback<'a>(&'a self, name: &str, binding: &Binding, template: Option<&'a Material>, resource_manager: &'a ResourceManager) -> Option<&'a ResourceHandle>{
+        let texture_handle = self.textures.get(name)
+            .or_else(|| template.and_then(|template| template.get_texture(name)))
+            .or_else(|| (name == POINT_SHADOWS_BINDING).then(|| resource_manager.get_point_shadow_texture_ref()));
+
+        let has_fallback = !binding.is_depth() && !binding.is_multisampled() && binding.get_view_dimension() == wgpu::TextureViewDimension::D2;
+        match texture_handle{
+            Some(texture_handle) if resource_manager.get_texture(texture_handle).is_some() => Some(texture_handle),
+            Some(_) if has_fallback => Some(resource_manager.get_missing_texture_ref()),
+            None if has_fallback => Some(resource_manager.get_white_texture_ref()),
+            _ => None
+        }
+    }
+
+    fn get_template_material<'a>(&self, resource_manager: &'a ResourceManager) -> Option<&'a Material>{
+        self.template.as_ref().map(|handle|{
+            if resource_manager.get_material(handle).is_none(){
+                error!("Template material not found for material instance");
+                panic!("Template material not found for material instance");
+            }
+            resource_manager.borrow_material(handle)
+        })
+    }
+
+    /// Bumped every time the bind groups are regenerated, so anything recorded with them knows it's stale
+    pub(crate) fn get_generation(&self) -> u64{
+        self.generation
     }
 
-    pub fn bind_material<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>){
+    pub(crate) fn get_dynamic_offsets(&self) -> &HashMap<u32, Vec<u32>>{
+        &self.dynamic_offsets
+    }
+
+    pub fn get_bind_group_count(&self) -> usize{
+        self.bind_groups.len() + self.versioned_bind_groups.len()
+    }
+
+    /// Sets every bind group of the material, reading the given version of the `objects` buffer
+    /// (see `StorageBuffer::get_version`)
+    pub fn bind_material<'a>(&'a self, render_pass: &mut impl RenderEncoder<'a>, objects_version: usize){
+        let offsets = |group: &u32| self.dynamic_offsets.get(group).map_or(&[][..], |offsets| offsets.as_slice());
         for (group, bind_group) in self.bind_groups.iter(){
-            render_pass.set_bind_group(*group, bind_group, &[]);
+            render_pass.set_bind_group(*group, bind_group, offsets(group));
+        }
+        for (group, bind_groups) in self.versioned_bind_groups.iter(){
+            render_pass.set_bind_group(*group, &bind_groups[objects_version % bind_groups.len()], offsets(group));
         }
     }
 }
@@ -1,13 +1,24 @@
+use std::collections::HashMap;
 use std::fs::File;
 use log::{error, info};
 use wgpu::RenderPass;
-use crate::types::{instance::Instance, vertex::Vertex};
+use wgpu::util::RenderEncoder;
+use crate::types::{instance::Instance, vertex::{ColoredVertex, MultiUvVertex, QuantizedVertex, SkinnedVertex, Vertex, MAX_UV_SETS}};
+use crate::types::quantization::{self, QuantizationUniform};
+use crate::types::point_cloud::{Point, PointStyle, PointVertex};
 use crate::types::renderable::Renderable;
+use crate::types::bounds::BoundingSphere;
+use crate::debug::{debug_log, Subsystem};
+use crate::utils::{mesh_normals, mesh_optimize, mesh_simplify, ply::PlyData, stl};
 
 #[derive(Debug, Clone)]
 pub struct SubMesh{
     vertices: Vec<Vertex>,
     indices: Vec<u32>,
+
+    // Raw vertex data for meshes that don't use the standard `Vertex` (e.g point clouds).
+    // The layout is described by the mesh's `MeshLayout`
+    custom_vertices: Option<Vec<u8>>,
 }
 
 impl SubMesh{
@@ -15,28 +26,141 @@ impl SubMesh{
         Self{
             vertices,
             indices,
+
+            custom_vertices: None,
         }
     }
 
+    /// # From Custom Vertices
+    ///
+    /// Creates a submesh with a non-standard vertex type. The mesh it's added to needs
+    /// a `MeshLayout` describing the type, see `Mesh::with_layout`
+    pub fn from_custom_vertices<T: bytemuck::Pod>(vertices: &[T], indices: Vec<u32>) -> Self{
+        Self{
+            vertices: Vec::new(),
+            indices,
+
+            custom_vertices: Some(bytemuck::cast_slice(vertices).to_vec()),
+        }
+    }
+
+    /// Standard vertices. Empty if the submesh uses a custom vertex type
     pub fn get_vertices(&self) -> &Vec<Vertex> {
         &self.vertices
     }
 
+    /// The vertex data as uploaded to the GPU
+    pub fn get_vertex_bytes(&self) -> &[u8] {
+        match &self.custom_vertices {
+            Some(bytes) => bytes,
+            None => bytemuck::cast_slice(&self.vertices),
+        }
+    }
+
+    pub fn has_custom_vertices(&self) -> bool {
+        self.custom_vertices.is_some()
+    }
+
     pub fn get_indices(&self) -> &Vec<u32> {
         &self.indices
     }
 
+    /// Mutable access to the vertices. Call `ResourceManager::update_mesh` afterwards to upload the changes
+    pub fn get_vertices_mut(&mut self) -> &mut Vec<Vertex> {
+        &mut self.vertices
+    }
+
+    /// Mutable access to the indices. Call `ResourceManager::update_mesh` afterwards to upload the changes
+    pub fn get_indices_mut(&mut self) -> &mut Vec<u32> {
+        &mut self.indices
+    }
+
     pub fn get_indices_count(&self) -> usize {
         self.indices.len()
     }
+
+    /// # Compute Bounds
+    ///
+    /// A sphere around the submesh's vertices. Custom vertices are read through the
+    /// `Float32x3` position at shader location 0 of the layout's first vertex buffer,
+    /// and None is returned if there isn't one
+    pub fn compute_bounds(&self, layout: &MeshLayout) -> Option<BoundingSphere> {
+        BoundingSphere::from_points(self.read_positions(layout)?.into_iter())
+    }
+
+    // Vertex positions, read from custom vertices the same way as `compute_bounds`
+    fn read_positions(&self, layout: &MeshLayout) -> Option<Vec<glam::Vec3>> {
+        let bytes = match &self.custom_vertices {
+            Some(bytes) => bytes,
+            None => return Some(self.vertices.iter().map(|vertex| glam::Vec3::from(vertex.position)).collect()),
+        };
+
+        let buffer_layout = layout.vertex_buffer_layouts.first()?;
+        let position = buffer_layout.attributes.iter()
+            .find(|attribute| attribute.shader_location == 0 && attribute.format == wgpu::VertexFormat::Float32x3)?;
+
+        let stride = buffer_layout.array_stride as usize;
+        let offset = position.offset as usize;
+        Some(bytes.chunks_exact(stride).map(|vertex| {
+            glam::Vec3::from(bytemuck::pod_read_unaligned::<[f32; 3]>(&vertex[offset..offset + 12]))
+        }).collect())
+    }
+
+    /// # Optimize
+    ///
+    /// Returns a copy with its triangles reordered for the GPU's vertex cache, then for less overdraw,
+    /// and its vertices reordered in the order they're used. What's drawn is unchanged. Custom vertices
+    /// without a `Float32x3` position at location 0 only get the vertex cache reordering
+    pub fn optimize(&self, layout: &MeshLayout) -> SubMesh {
+        let positions = self.read_positions(layout);
+        let vertex_count = match &positions {
+            Some(positions) => positions.len(),
+            None => self.vertices.len().max(self.indices.iter().max().map_or(0, |max| *max as usize + 1)),
+        };
+
+        let mut indices = mesh_optimize::optimize_vertex_cache(&self.indices, vertex_count);
+        let Some(positions) = positions else {
+            return SubMesh { indices, ..self.clone() };
+        };
+        indices = mesh_optimize::optimize_overdraw(&indices, &positions);
+
+        let order = mesh_optimize::optimize_vertex_fetch(&mut indices, vertex_count);
+        match &self.custom_vertices {
+            Some(bytes) => {
+                let stride = bytes.len() / vertex_count;
+                let custom_vertices = order.iter()
+                    .flat_map(|&old| bytes[old as usize * stride..(old as usize + 1) * stride].iter().copied())
+                    .collect();
+                SubMesh { vertices: Vec::new(), indices, custom_vertices: Some(custom_vertices) }
+            }
+            None => SubMesh::new(order.iter().map(|&old| self.vertices[old as usize]).collect(), indices),
+        }
+    }
+
+    /// # Simplify
+    ///
+    /// Returns a copy with roughly `ratio` (0 to 1) of the triangles, stopping early
+    /// if the surface would move by more than `max_error` (in mesh units)
+    pub fn simplify(&self, ratio: f32, max_error: f32) -> SubMesh {
+        // We only know where the positions are in standard vertices
+        if self.has_custom_vertices() {
+            return self.clone();
+        }
+
+        let target_index_count = (self.indices.len() as f32 * ratio.clamp(0.0, 1.0)) as usize;
+        let (vertices, indices) = mesh_simplify::simplify(&self.vertices, &self.indices, target_index_count, max_error);
+
+        SubMesh::new(vertices, indices)
+    }
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MeshLayout{
     pub vertex_buffer_layouts: Vec<wgpu::VertexBufferLayout<'static>>, // Vertex buffer layouts -
                                             // multiple vertex buffers can be used in a single mesh
     pub index_format: wgpu::IndexFormat,
+    pub topology: wgpu::PrimitiveTopology,
 }
 
 impl MeshLayout{
@@ -44,9 +168,19 @@ impl MeshLayout{
         Self{
             vertex_buffer_layouts,
             index_format,
+            topology: wgpu::PrimitiveTopology::TriangleList,
         }
     }
 
+    pub fn with_topology(mut self, topology: wgpu::PrimitiveTopology) -> Self{
+        self.topology = topology;
+        self
+    }
+
+    pub fn get_topology(&self) -> wgpu::PrimitiveTopology{
+        self.topology
+    }
+
     pub fn get_vertex_buffer_layouts(&self) -> &Vec<wgpu::VertexBufferLayout<'static>>{
         &self.vertex_buffer_layouts
     }
@@ -62,6 +196,146 @@ pub struct Mesh{
 }
 
 impl Mesh{
+    /// Creates a mesh from submeshes using the standard `Vertex` layout
+    pub fn new(sub_meshes: Vec<SubMesh>) -> Self{
+        Self{
+            sub_meshes,
+            instances: Vec::new(),
+            layout: MeshLayout::new(vec![Vertex::desc()], wgpu::IndexFormat::Uint32),
+        }
+    }
+
+    /// # With Layout
+    ///
+    /// Creates a mesh from submeshes with a custom vertex layout or topology,
+    /// e.g submeshes created with `SubMesh::from_custom_vertices`
+    pub fn with_layout(sub_meshes: Vec<SubMesh>, layout: MeshLayout) -> Self{
+        Self{
+            sub_meshes,
+            instances: Vec::new(),
+            layout,
+        }
+    }
+
+    /// # From Points
+    ///
+    /// Creates a point cloud mesh, drawn either as single pixels or as sprites.
+    /// It uses the `PointVertex` layout, so needs a point cloud shader such as `POINT_CLOUD_SHADER`
+    pub fn from_points(points: &[Point], style: PointStyle) -> Self{
+        let (vertices, indices, topology) = match style{
+            PointStyle::Pixels => {
+                let vertices: Vec<PointVertex> = points.iter().map(|point| PointVertex{
+                    position: point.position,
+                    color: point.color,
+                    corner: [0.0, 0.0],
+                }).collect();
+                let indices = (0..vertices.len() as u32).collect();
+
+                (vertices, indices, wgpu::PrimitiveTopology::PointList)
+            }
+            PointStyle::Sprites => {
+                // Each point becomes a quad, which the shader expands to the point size
+                const CORNERS: [[f32; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+                let mut vertices = Vec::with_capacity(points.len() * 4);
+                let mut indices = Vec::with_capacity(points.len() * 6);
+                for point in points{
+                    let base = vertices.len() as u32;
+                    vertices.extend(CORNERS.iter().map(|&corner| PointVertex{
+                        position: point.position,
+                        color: point.color,
+                        corner,
+                    }));
+                    indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+                }
+
+                (vertices, indices, wgpu::PrimitiveTopology::TriangleList)
+            }
+        };
+
+        let layout = MeshLayout::new(vec![PointVertex::desc()], wgpu::IndexFormat::Uint32)
+            .with_topology(topology);
+
+        Self::with_layout(vec![SubMesh::from_custom_vertices(&vertices, indices)], layout)
+    }
+
+    /// # From Skinned Vertices
+    ///
+    /// Creates a mesh whose vertices follow up to four joints each, using the `SkinnedVertex`
+    /// layout, so needs a skinning shader such as `SKINNED_SHADER`
+    pub fn from_skinned_vertices(vertices: &[SkinnedVertex], indices: Vec<u32>) -> Self{
+        let layout = MeshLayout::new(vec![SkinnedVertex::desc()], wgpu::IndexFormat::Uint32);
+        Self::with_layout(vec![SubMesh::from_custom_vertices(vertices, indices)], layout)
+    }
+
+    /// Whether the mesh can be merged with `Mesh::merge`. Only meshes with the standard `Vertex` layout
+    /// and a list topology can be, as strips can't be joined without connecting their ends
+    pub fn is_mergeable(&self) -> bool{
+        let topology = self.layout.get_topology();
+        !self.sub_meshes.iter().any(|sub_mesh| sub_mesh.has_custom_vertices())
+            && matches!(topology, wgpu::PrimitiveTopology::TriangleList | wgpu::PrimitiveTopology::LineList | wgpu::PrimitiveTopology::PointList)
+    }
+
+    /// # Merge
+    ///
+    /// Bakes each mesh's transform into its vertices and combines every submesh into a single one,
+    /// so the result can be drawn in one call. Every mesh must be mergeable (see `is_mergeable`)
+    /// and share a topology, which the merged mesh keeps
+    pub fn merge(parts: &[(&Mesh, glam::Mat4)]) -> Mesh{
+        let topology = parts.first().map_or(wgpu::PrimitiveTopology::TriangleList, |(mesh, _)| mesh.layout.get_topology());
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for (mesh, matrix) in parts{
+            if !mesh.is_mergeable() || mesh.layout.get_topology() != topology{
+                error!("Skipping mesh with a custom vertex type or different topology while merging meshes");
+                continue;
+            }
+            // Normals need the inverse transpose, so non-uniform scales don't skew them
+            let normal_matrix = glam::Mat3::from_mat4(*matrix).inverse().transpose();
+
+            for sub_mesh in mesh.get_sub_meshes(){
+                let base = vertices.len() as u32;
+
+                vertices.extend(sub_mesh.get_vertices().iter().map(|vertex| Vertex{
+                    position: matrix.transform_point3(vertex.position.into()).into(),
+                    normal: (normal_matrix * glam::Vec3::from(vertex.normal)).normalize_or_zero().into(),
+                    tex_coords: vertex.tex_coords,
+                }));
+                indices.extend(sub_mesh.get_indices().iter().map(|index| index + base));
+            }
+        }
+
+        let layout = MeshLayout::new(vec![Vertex::desc()], wgpu::IndexFormat::Uint32).with_topology(topology);
+        Mesh::with_layout(vec![SubMesh::new(vertices, indices)], layout)
+    }
+
+    /// Loads a mesh from a file, picking the loader from its extension
+    pub(crate) fn load(path: &str) -> Self{
+        // Check if the path is an obj or fbx
+        if path.ends_with(".obj"){
+            Mesh::load_obj(path)
+        }else if path.ends_with(".gltf") || path.ends_with(".glb"){
+            Mesh::load_gltf(path)
+        }else if path.to_lowercase().ends_with(".stl"){
+            Mesh::load_stl(path)
+        }else if path.ends_with(".ply"){
+            Mesh::load_ply(path)
+        }else if path.ends_with(".usda") || path.ends_with(".usdz") || path.ends_with(".usd"){
+            #[cfg(feature = "usd")]
+            {
+                Mesh::load_usd(path)
+            }
+            #[cfg(not(feature = "usd"))]
+            {
+                error!("Loading USD files requires the `usd` feature");
+                panic!("Loading USD files requires the `usd` feature")
+            }
+        }else{
+            error!("Unsupported mesh format");
+            panic!("Unsupported mesh format")
+        }
+    }
 
     pub(crate) fn load_obj<T: AsRef<std::path::Path>>(path: T) -> Self{
         let load_options = tobj::LoadOptions {
@@ -138,8 +412,25 @@ impl Mesh{
 
         let mut sub_meshes = Vec::new();
 
+        // Files with a second UV set (e.g for lightmaps) use the extended vertex layout for every primitive
+        let uv_sets = document.meshes()
+            .flat_map(|mesh| mesh.primitives())
+            .map(|primitive| primitive.attributes().filter(|(semantic, _)| matches!(semantic, gltf::Semantic::TexCoords(_))).count())
+            .max()
+            .unwrap_or(0);
+        let multi_uv = uv_sets > 1;
+        if uv_sets > MAX_UV_SETS {
+            debug_log!(Subsystem::Resources, "glTF has {} UV sets, only the first {} are imported", uv_sets, MAX_UV_SETS);
+        }
+
         for mesh in document.meshes() {
             for primitive in mesh.primitives() {
+                if primitive.extension_value("KHR_draco_mesh_compression").is_some() {
+                    let sub_mesh = Self::load_draco_primitive(&document, &primitive, &buffers);
+                    sub_meshes.push(if multi_uv { Self::to_multi_uv(sub_mesh) } else { sub_mesh });
+                    continue;
+                }
+
                 let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
 
                 let positions: Vec<[f32; 3]> = reader
@@ -163,7 +454,7 @@ impl Mesh{
                     .collect();
 
                 // Get the tex coord type
-                info!("{:?}", tex_coords);
+                debug_log!(Subsystem::Resources, "{:?}", tex_coords);
 
                 let indices: Vec<u32> = if let Some(iter) = reader.read_indices() {
                     iter.into_u32().collect()
@@ -171,6 +462,32 @@ impl Mesh{
                     Vec::new()
                 };
 
+                if multi_uv {
+                    // Sets the primitive doesn't have fall back to the first one
+                    let extra_sets: Vec<Option<Vec<[f32; 2]>>> = (1..MAX_UV_SETS as u32)
+                        .map(|set| reader.read_tex_coords(set).map(|iter| iter.into_f32().collect()))
+                        .collect();
+
+                    let vertices: Vec<MultiUvVertex> = positions.iter().zip(normals.iter()).zip(tex_coords.iter()).enumerate()
+                        .map(|(i, ((pos, norm), tex))| {
+                            let mut vertex_tex_coords = [*tex; MAX_UV_SETS];
+                            for (set, tex_coords) in extra_sets.iter().enumerate() {
+                                if let Some(tex_coord) = tex_coords.as_ref().and_then(|tex_coords| tex_coords.get(i)) {
+                                    vertex_tex_coords[set + 1] = *tex_coord;
+                                }
+                            }
+                            MultiUvVertex {
+                                position: *pos,
+                                normal: *norm,
+                                tex_coords: vertex_tex_coords,
+                            }
+                        })
+                        .collect();
+
+                    sub_meshes.push(SubMesh::from_custom_vertices(&vertices, indices));
+                    continue;
+                }
+
                 let vertices = positions.iter().zip(normals.iter()).zip(tex_coords.iter())
                     .map(|((pos, norm), tex)| Vertex {
                         position: *pos,
@@ -184,7 +501,11 @@ impl Mesh{
             }
         }
 
-        let vertex_buffer_layouts = vec![Vertex::desc()];  // Assuming Vertex::desc() is properly defined elsewhere
+        let vertex_buffer_layouts = if multi_uv {
+            vec![MultiUvVertex::desc()]
+        } else {
+            vec![Vertex::desc()]
+        };
         let mesh_layout = MeshLayout::new(vertex_buffer_layouts, wgpu::IndexFormat::Uint32);
 
         Mesh {
@@ -194,10 +515,171 @@ impl Mesh{
         }
     }
 
+    // Copies the first UV set into every set, for primitives that only have one
+    fn to_multi_uv(sub_mesh: SubMesh) -> SubMesh {
+        let vertices: Vec<MultiUvVertex> = sub_mesh.get_vertices().iter().map(|vertex| MultiUvVertex {
+            position: vertex.position,
+            normal: vertex.normal,
+            tex_coords: [vertex.tex_coords; MAX_UV_SETS],
+        }).collect();
+
+        SubMesh::from_custom_vertices(&vertices, sub_mesh.get_indices().clone())
+    }
+
+    // Compressed primitives have no readable accessors, the data is decoded from the Draco bitstream
+    #[cfg(feature = "draco")]
+    fn load_draco_primitive(document: &gltf::Document, primitive: &gltf::Primitive, buffers: &[gltf::buffer::Data]) -> SubMesh {
+        let decoded = crate::utils::draco::decode_primitive(document, primitive, buffers).unwrap_or_else(|e| {
+            error!("Failed to decode Draco primitive: {}", e);
+            panic!("Failed to decode Draco primitive: {}", e);
+        });
+        debug_log!(Subsystem::Resources, "Decoded Draco primitive with {} vertices, {} indices", decoded.positions.len(), decoded.indices.len());
+
+        let normals = decoded.normals.unwrap_or_else(|| mesh_normals::smooth_normals(&decoded.positions, &decoded.indices));
+        let vertices = decoded.positions.iter().enumerate().map(|(i, position)| Vertex{
+            position: *position,
+            normal: normals[i],
+            tex_coords: decoded.tex_coords.as_ref().map(|tex_coords| tex_coords[i]).unwrap_or([0.0, 0.0]),
+        }).collect();
+
+        SubMesh::new(vertices, decoded.indices)
+    }
+
+    #[cfg(not(feature = "draco"))]
+    fn load_draco_primitive(_document: &gltf::Document, _primitive: &gltf::Primitive, _buffers: &[gltf::buffer::Data]) -> SubMesh {
+        error!("Loading Draco compressed glTF files requires the `draco` feature, or decompress them with `gltf-transform decompress`");
+        panic!("Loading Draco compressed glTF files requires the `draco` feature, or decompress them with `gltf-transform decompress`")
+    }
+
+    /// # Load STL
+    ///
+    /// Loads a binary or ASCII STL file. STL has no normals worth trusting, so they're generated,
+    /// keeping edges sharper than 30 degrees hard. Duplicate corners are welded afterwards
+    pub(crate) fn load_stl<T: AsRef<std::path::Path>>(path: T) -> Self{
+        let positions = stl::read_stl(path.as_ref()).unwrap_or_else(|e| {
+            error!("Failed to load stl file: {}", e);
+            panic!("Failed to load stl file: {}", e);
+        });
+        let normals = mesh_normals::creased_normals(&positions, 30f32.to_radians());
+
+        // Weld corners with the same position and normal
+        let mut vertices = Vec::new();
+        let mut indices = Vec::with_capacity(positions.len());
+        let mut welded: HashMap<[u32; 6], u32> = HashMap::new();
+        for (position, normal) in positions.iter().zip(normals.iter()){
+            let mut key = [0u32; 6];
+            key[..3].copy_from_slice(&position.map(f32::to_bits));
+            key[3..].copy_from_slice(&normal.map(f32::to_bits));
+
+            let index = *welded.entry(key).or_insert_with(|| {
+                vertices.push(Vertex{
+                    position: *position,
+                    normal: *normal,
+                    tex_coords: [0.0, 0.0],
+                });
+                vertices.len() as u32 - 1
+            });
+            indices.push(index);
+        }
+
+        info!("Loaded mesh from file: {:?}", path.as_ref());
+        debug_log!(Subsystem::Resources, "STL has {} triangles, {} vertices after welding", positions.len() / 3, vertices.len());
+
+        Self::new(vec![SubMesh::new(vertices, indices)])
+    }
+
+    /// # Load PLY
+    ///
+    /// Loads an ASCII or binary PLY mesh. Normals are generated if the file has none,
+    /// and if the file has vertex colours the mesh uses the `ColoredVertex` layout
+    pub(crate) fn load_ply<T: AsRef<std::path::Path>>(path: T) -> Self{
+        let ply = PlyData::load(path.as_ref()).unwrap_or_else(|e| {
+            error!("Failed to load ply file: {}", e);
+            panic!("Failed to load ply file: {}", e);
+        });
+        let positions = ply.get_positions().unwrap_or_else(|e| {
+            error!("Failed to load ply file: {}", e);
+            panic!("Failed to load ply file: {}", e);
+        });
+
+        // Triangulate polygons as fans
+        let mut indices = Vec::new();
+        for face in ply.faces.iter(){
+            for i in 1..face.len().saturating_sub(1){
+                indices.extend([face[0], face[i], face[i + 1]]);
+            }
+        }
+
+        let normals = ply.get_normals().unwrap_or_else(|| mesh_normals::smooth_normals(&positions, &indices));
+        let tex_coords = ply.get_tex_coords().unwrap_or_else(|| vec![[0.0, 0.0]; positions.len()]);
+
+        info!("Loaded mesh from file: {:?}", path.as_ref());
+
+        match ply.get_colors(){
+            Some(colors) => {
+                let vertices: Vec<ColoredVertex> = (0..positions.len()).map(|i| ColoredVertex{
+                    position: positions[i],
+                    normal: normals[i],
+                    tex_coords: tex_coords[i],
+                    color: colors[i],
+                }).collect();
+
+                Self::with_layout(
+                    vec![SubMesh::from_custom_vertices(&vertices, indices)],
+                    MeshLayout::new(vec![ColoredVertex::desc()], wgpu::IndexFormat::Uint32),
+                )
+            }
+            None => {
+                let vertices = (0..positions.len()).map(|i| Vertex{
+                    position: positions[i],
+                    normal: normals[i],
+                    tex_coords: tex_coords[i],
+                }).collect();
+
+                Self::new(vec![SubMesh::new(vertices, indices)])
+            }
+        }
+    }
+
+    /// # Load USD
+    ///
+    /// Loads every mesh in a `.usda` file, or a `.usdz` package whose root layer is a `.usda`, with their
+    /// transforms baked in. Each USD mesh becomes a submesh. Binary `.usdc` layers aren't supported
+    #[cfg(feature = "usd")]
+    pub(crate) fn load_usd<T: AsRef<std::path::Path>>(path: T) -> Self{
+        let usd_meshes = crate::utils::usd::load_usd(path.as_ref()).unwrap_or_else(|e| {
+            error!("Failed to load usd file: {}", e);
+            panic!("Failed to load usd file: {}", e);
+        });
+
+        let sub_meshes = usd_meshes.into_iter().map(|usd_mesh| {
+            debug_log!(Subsystem::Resources, "USD mesh `{}` has {} triangles", usd_mesh.name, usd_mesh.positions.len() / 3);
+
+            let normals = usd_mesh.normals.unwrap_or_else(|| mesh_normals::creased_normals(&usd_mesh.positions, 30f32.to_radians()));
+            let vertices = usd_mesh.positions.iter().enumerate().map(|(i, position)| Vertex{
+                position: *position,
+                normal: normals[i],
+                tex_coords: usd_mesh.tex_coords.as_ref().map(|tex_coords| tex_coords[i]).unwrap_or([0.0, 0.0]),
+            }).collect();
+            let indices = (0..usd_mesh.positions.len() as u32).collect();
+
+            SubMesh::new(vertices, indices)
+        }).collect();
+
+        info!("Loaded mesh from file: {:?}", path.as_ref());
+
+        Self::new(sub_meshes)
+    }
+
     pub fn get_sub_meshes(&self) -> &Vec<SubMesh>{
         &self.sub_meshes
     }
 
+    /// Mutable access to the submeshes. Call `ResourceManager::update_mesh` afterwards to upload the changes
+    pub fn get_sub_meshes_mut(&mut self) -> &mut Vec<SubMesh>{
+        &mut self.sub_meshes
+    }
+
     pub fn get_instances(&self) -> &Vec<Instance>{
         &self.instances
     }
@@ -205,6 +687,152 @@ impl Mesh{
     pub fn get_layout(&self) -> &MeshLayout{
         &self.layout
     }
+
+    /// The bounds of each submesh, see `SubMesh::compute_bounds`
+    pub fn compute_bounds(&self) -> Vec<Option<BoundingSphere>>{
+        self.sub_meshes.iter().map(|sub_mesh| sub_mesh.compute_bounds(&self.layout)).collect()
+    }
+
+    pub fn get_triangle_count(&self) -> usize{
+        self.sub_meshes.iter().map(|sub_mesh| sub_mesh.get_indices_count() / 3).sum()
+    }
+
+    /// # Simplify
+    ///
+    /// Returns a copy of the mesh with roughly `ratio` (0 to 1) of its triangles, e.g `0.5` halves them.
+    /// Open borders and seams are preserved, so very low ratios may not be reached
+    pub fn simplify(&self, ratio: f32) -> Mesh{
+        self.simplify_with_error(ratio, f32::MAX)
+    }
+
+    /// # Simplify With Error
+    ///
+    /// Like `simplify`, but stops once simplifying further would move the surface
+    /// by more than `max_error` (in mesh units), whichever comes first
+    pub fn simplify_with_error(&self, ratio: f32, max_error: f32) -> Mesh{
+        let sub_meshes: Vec<SubMesh> = self.sub_meshes.iter()
+            .map(|sub_mesh| sub_mesh.simplify(ratio, max_error))
+            .collect();
+
+        debug_log!(Subsystem::Resources, "Simplified mesh from {} to {} triangles", self.get_triangle_count(),
+            sub_meshes.iter().map(|sub_mesh| sub_mesh.get_indices_count() / 3).sum::<usize>());
+
+        Mesh{
+            sub_meshes,
+            instances: self.instances.clone(),
+            layout: self.layout.clone(),
+        }
+    }
+
+    /// # Optimize
+    ///
+    /// Reorders each submesh's triangles and vertices to raise GPU throughput on large meshes,
+    /// see `SubMesh::optimize`. Only triangle lists are reordered
+    pub fn optimize(&self) -> Mesh{
+        if self.layout.get_topology() != wgpu::PrimitiveTopology::TriangleList{
+            return self.clone();
+        }
+
+        let sub_meshes: Vec<SubMesh> = self.sub_meshes.iter()
+            .map(|sub_mesh| sub_mesh.optimize(&self.layout))
+            .collect();
+
+        let stride = self.layout.vertex_buffer_layouts.first().map_or(1, |layout| layout.array_stride.max(1) as usize);
+        for (before, after) in self.sub_meshes.iter().zip(sub_meshes.iter()){
+            debug_log!(Subsystem::Resources, "Optimized submesh, average cache miss ratio {:.3} -> {:.3}",
+                mesh_optimize::average_cache_miss_ratio(before.get_indices(), before.get_vertex_bytes().len() / stride),
+                mesh_optimize::average_cache_miss_ratio(after.get_indices(), after.get_vertex_bytes().len() / stride));
+        }
+
+        Mesh{
+            sub_meshes,
+            instances: self.instances.clone(),
+            layout: self.layout.clone(),
+        }
+    }
+
+    /// # To Bind Pose
+    ///
+    /// Drops the joints and weights of a `SkinnedVertex` mesh, giving a standard mesh in its bind pose.
+    /// Meshes with any other vertices give None
+    pub fn to_bind_pose(&self) -> Option<Mesh>{
+        if self.layout.vertex_buffer_layouts != [SkinnedVertex::desc()]{
+            return None;
+        }
+
+        let sub_meshes = self.sub_meshes.iter().map(|sub_mesh| {
+            let skinned: Vec<SkinnedVertex> = bytemuck::pod_collect_to_vec(sub_mesh.get_vertex_bytes());
+            let vertices = skinned.iter().map(|vertex| Vertex{
+                position: vertex.position,
+                normal: vertex.normal,
+                tex_coords: vertex.tex_coords,
+            }).collect();
+            SubMesh::new(vertices, sub_mesh.get_indices().clone())
+        }).collect();
+
+        Some(Mesh{
+            sub_meshes,
+            instances: self.instances.clone(),
+            layout: MeshLayout::new(vec![Vertex::desc()], self.layout.index_format)
+                .with_topology(self.layout.get_topology()),
+        })
+    }
+
+    /// # Quantize
+    ///
+    /// Converts a mesh to 16-bit positions, octahedral normals and half float UVs, halving its vertex
+    /// bandwidth. Standard vertices become `QuantizedVertex`; the `MultiUvVertex` and `ColoredVertex` data the
+    /// importers produce is packed the same way, with colours as `Unorm8x4`. Returns the quantized mesh and
+    /// the `QuantizationUniform` its shader decodes positions with, shared by every submesh.
+    /// Meshes with any other custom vertices give None
+    pub fn quantize(&self) -> Option<(Mesh, QuantizationUniform)>{
+        let custom_layout = self.sub_meshes.iter().any(|sub_mesh| sub_mesh.has_custom_vertices())
+            .then_some(self.layout.vertex_buffer_layouts.as_slice());
+        let vertex_layout = match custom_layout{
+            None => QuantizedVertex::desc(),
+            Some([layout]) if *layout == MultiUvVertex::desc() => quantization::quantized_multi_uv_layout(),
+            Some([layout]) if *layout == ColoredVertex::desc() => quantization::quantized_colored_layout(),
+            Some(_) => return None,
+        };
+
+        let mut min = glam::Vec3::splat(f32::MAX);
+        let mut max = glam::Vec3::splat(f32::MIN);
+        for position in self.sub_meshes.iter().filter_map(|sub_mesh| sub_mesh.read_positions(&self.layout)).flatten(){
+            min = min.min(position);
+            max = max.max(position);
+        }
+        let quantization = if min.cmpgt(max).any(){ QuantizationUniform::default() }else{ QuantizationUniform::from_bounds(min, max) };
+
+        let sub_meshes = self.sub_meshes.iter().map(|sub_mesh| {
+            let indices = sub_mesh.get_indices().clone();
+            if !sub_mesh.has_custom_vertices(){
+                return SubMesh::from_custom_vertices(&quantization::quantize_vertices(sub_mesh.get_vertices(), &quantization), indices);
+            }
+
+            let bytes = sub_mesh.get_vertex_bytes();
+            let packed = if vertex_layout == quantization::quantized_multi_uv_layout(){
+                quantization::quantize_multi_uv_vertices(&bytemuck::pod_collect_to_vec(bytes), &quantization)
+            }else{
+                quantization::quantize_colored_vertices(&bytemuck::pod_collect_to_vec(bytes), &quantization)
+            };
+            SubMesh::from_custom_vertices(&packed, indices)
+        }).collect();
+        let layout = MeshLayout::new(vec![vertex_layout], self.layout.index_format)
+            .with_topology(self.layout.get_topology());
+
+        Some((Mesh{
+            sub_meshes,
+            instances: self.instances.clone(),
+            layout,
+        }, quantization))
+    }
+
+    /// # Generate LODs
+    ///
+    /// Builds a chain of simplified meshes, one per ratio (e.g `[0.5, 0.25, 0.1]`)
+    pub fn generate_lods(&self, ratios: &[f32]) -> Vec<Mesh>{
+        ratios.iter().map(|&ratio| self.simplify(ratio)).collect()
+    }
 }
 
 impl<'a> Renderable<'a> for Mesh{
@@ -216,6 +844,14 @@ impl<'a> Renderable<'a> for Mesh{
     }
 }
 
+impl SubMesh{
+    /// Draws the submesh with `instance_index` set to the model's slot in the `objects` buffer
+    pub(crate) fn render_object<'a>(&'a self, render_pass: &mut impl RenderEncoder<'a>, object_index: u32){
+        let indices_count = self.get_indices_count();
+        render_pass.draw_indexed(0..indices_count as u32, 0, object_index..object_index + 1);
+    }
+}
+
 impl<'a> Renderable<'a> for SubMesh{
     fn render<'b>(&'b self, render_pass: &'a mut RenderPass<'b>) {
         let indices_count = self.get_indices_count();
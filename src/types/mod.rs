@@ -2,9 +2,27 @@ pub mod instance;
 pub mod material;
 pub mod sprite;
 pub mod transform;
+pub mod tween;
 pub mod vertex;
+pub mod quantization;
 pub mod mesh;
+pub mod dynamic_mesh;
+pub mod point_cloud;
+pub mod pbr_material;
+pub mod builtin_shaders;
+pub mod light;
 pub mod texture;
+pub mod texture_atlas;
+pub mod fullscreen_pass;
+pub mod bindless;
+pub mod bounds;
+pub mod frustum;
 pub mod model;
+pub mod object_data;
+pub mod scene_uniform;
+pub mod globals_uniform;
+pub mod camera;
+pub mod planar_reflection;
+pub mod property_block;
 pub mod renderable;
 pub mod shader;
@@ -1,22 +1,63 @@
+use std::collections::HashMap;
 use crate::managers::resource_handle::ResourceHandle;
 use crate::Transform;
-use crate::utils::handle::Handle;
+use crate::types::object_data::OBJECT_RECEIVES_SHADOWS;
+use crate::types::property_block::PropertyBlock;
+
+/// The render layers models are on until set otherwise, see `Model::set_render_layers`
+pub const DEFAULT_RENDER_LAYERS: u32 = 1;
 
 pub struct Model{
     mesh: ResourceHandle,
     material: ResourceHandle,
 
-    transform: Handle<Transform>,
-    transform_uniform_handle: ResourceHandle
+    transform: Transform,
+    // The model's slot in the `objects` storage buffer
+    object_index: u32,
+    // Indices into the bindless texture array, written to the model's `ObjectData`
+    texture_indices: [u32; 4],
+    // Only created for shaders that read the transform from a `transform` uniform
+    transform_uniform_handle: Option<ResourceHandle>,
+    // Created the first time a lightmap region is set
+    lightmap_uniform_handle: Option<ResourceHandle>,
+    // Drawn from the cached static render bundles instead of being encoded every frame
+    is_static: bool,
+    // Drawn into the point light shadow maps
+    casts_shadows: bool,
+    // Darkened by the point light shadow maps, written to the model's `ObjectData`
+    receives_shadows: bool,
+    // Drawn with a pipeline variant that doesn't cull back faces
+    double_sided: bool,
+    // Bitmask of the layers the model is on, drawn by cameras whose layer mask shares one
+    render_layers: u32,
+
+    properties: PropertyBlock,
+    // Instance of the material holding the uniforms the properties override,
+    // created the first time the properties are applied
+    property_material: Option<ResourceHandle>,
+    // Uniform name - the model's copy of the material's uniform, with the properties written over it
+    property_uniforms: HashMap<String, ResourceHandle>
 }
 
 impl Model{
-    pub fn new(mesh: ResourceHandle, material: ResourceHandle, transform: Transform, transform_uniform_handle: ResourceHandle) -> Self{
+    pub fn new(mesh: ResourceHandle, material: ResourceHandle, transform: Transform, object_index: u32) -> Self{
         Self{
             mesh,
             material,
-            transform: Handle::new(transform),
-            transform_uniform_handle
+            transform,
+            object_index,
+            texture_indices: [0; 4],
+            transform_uniform_handle: None,
+            lightmap_uniform_handle: None,
+            is_static: false,
+            casts_shadows: true,
+            receives_shadows: true,
+            double_sided: false,
+            render_layers: DEFAULT_RENDER_LAYERS,
+
+            properties: PropertyBlock::new(),
+            property_material: None,
+            property_uniforms: HashMap::new()
         }
     }
 
@@ -28,12 +69,174 @@ impl Model{
         &self.material
     }
 
-    pub fn get_transform(&self) -> Handle<Transform>{
-        self.transform.clone()
+    /// The material the model is drawn with, which is an instance of its material
+    /// when it has properties set
+    pub fn get_draw_material(&self) -> &ResourceHandle{
+        self.property_material.as_ref().unwrap_or(&self.material)
+    }
+
+    /// # Set Property
+    ///
+    /// Overrides a uniform member of this model's material, e.g `set_property("tint", [1.0, 0.0, 0.0, 1.0])`,
+    /// without affecting other models sharing the material
+    pub fn set_property<T: bytemuck::Pod>(&mut self, name: &str, value: T){
+        self.properties.set(name, value);
+    }
+
+    pub fn remove_property(&mut self, name: &str) -> bool{
+        self.properties.remove(name)
+    }
+
+    pub fn get_properties(&self) -> &PropertyBlock{
+        &self.properties
+    }
+
+    pub(crate) fn get_property_material(&self) -> Option<&ResourceHandle>{
+        self.property_material.as_ref()
+    }
+
+    pub(crate) fn set_property_material(&mut self, property_material: ResourceHandle){
+        self.property_material = Some(property_material);
+    }
+
+    pub(crate) fn get_property_uniforms(&self) -> &HashMap<String, ResourceHandle>{
+        &self.property_uniforms
     }
 
-    pub fn get_transform_uniform_handle(&self) -> ResourceHandle{
+    pub(crate) fn add_property_uniform(&mut self, name: &str, uniform_handle: ResourceHandle){
+        self.property_uniforms.insert(name.to_string(), uniform_handle);
+    }
+
+    /// # Set Static
+    ///
+    /// Static models are recorded into render bundles once and replayed every frame, instead
+    /// of being encoded each frame. The bundles are only re-recorded when the set of static
+    /// models changes. They can still move, as transforms are read from the `objects` buffer,
+    /// but they skip GPU culling
+    pub fn set_static(&mut self, is_static: bool){
+        self.is_static = is_static;
+    }
+
+    pub fn is_static(&self) -> bool{
+        self.is_static
+    }
+
+    /// # Set Casts Shadows
+    ///
+    /// Whether the model is drawn into the point light shadow maps. On by default
+    pub fn set_casts_shadows(&mut self, casts_shadows: bool){
+        self.casts_shadows = casts_shadows;
+    }
+
+    pub fn casts_shadows(&self) -> bool{
+        self.casts_shadows
+    }
+
+    /// # Set Receives Shadows
+    ///
+    /// Whether shaders darken the model where it's in shadow. Only the PBR shader samples shadows,
+    /// others can read `OBJECT_RECEIVES_SHADOWS` from the model's `ObjectData`. On by default
+    pub fn set_receives_shadows(&mut self, receives_shadows: bool){
+        self.receives_shadows = receives_shadows;
+    }
+
+    pub fn receives_shadows(&self) -> bool{
+        self.receives_shadows
+    }
+
+    /// # Set Double Sided
+    ///
+    /// Double sided models have their back faces drawn too, for thin surfaces like leaves and cloth.
+    /// They're drawn with a variant of the pipeline that doesn't cull, made at the start of the next frame.
+    /// Models start with their material's setting, see `ResourceManager::set_material_double_sided`
+    pub fn set_double_sided(&mut self, double_sided: bool){
+        self.double_sided = double_sided;
+    }
+
+    pub fn is_double_sided(&self) -> bool{
+        self.double_sided
+    }
+
+    /// # Set Render Layers
+    ///
+    /// Sets the layers the model is on, as a bitmask. Cameras only draw models on at least one of the
+    /// layers in their mask (see `Camera::with_layer_mask`), e.g to draw one set of models into a render
+    /// texture and another to the surface. Models start on layer 0 (`DEFAULT_RENDER_LAYERS`).
+    /// The default camera draws every layer
+    pub fn set_render_layers(&mut self, render_layers: u32){
+        self.render_layers = render_layers;
+    }
+
+    pub fn get_render_layers(&self) -> u32{
+        self.render_layers
+    }
+
+    /// The flags written to the model's `ObjectData`
+    pub(crate) fn get_object_flags(&self) -> u32{
+        if self.receives_shadows { OBJECT_RECEIVES_SHADOWS } else { 0 }
+    }
+
+    pub fn get_transform(&self) -> &Transform{
+        &self.transform
+    }
+
+    /// # Set Transform
+    ///
+    /// Models changed through `ResourceManager::get_model_mut` have their objects data rewritten
+    /// at the start of the next frame. Use `ResourceManager::set_model_transform` to also stop any animation
+    pub fn set_transform(&mut self, transform: Transform){
+        self.transform = transform;
+    }
+
+    pub fn get_object_index(&self) -> u32{
+        self.object_index
+    }
+
+    pub fn get_texture_indices(&self) -> [u32; 4]{
+        self.texture_indices
+    }
+
+    pub(crate) fn set_texture_indices(&mut self, texture_indices: [u32; 4]){
+        self.texture_indices = texture_indices;
+    }
+
+    pub fn get_transform_uniform_handle(&self) -> Option<ResourceHandle>{
         self.transform_uniform_handle.clone()
     }
+
+    pub(crate) fn set_transform_uniform_handle(&mut self, transform_uniform_handle: ResourceHandle){
+        self.transform_uniform_handle = Some(transform_uniform_handle);
+    }
+
+    pub fn get_lightmap_uniform_handle(&self) -> Option<ResourceHandle>{
+        self.lightmap_uniform_handle.clone()
+    }
+
+    pub(crate) fn set_lightmap_uniform_handle(&mut self, lightmap_uniform_handle: ResourceHandle){
+        self.lightmap_uniform_handle = Some(lightmap_uniform_handle);
+    }
+
+    /// Whether the two models draw the same way apart from their mesh and transform, so they can be merged
+    pub(crate) fn draws_like(&self, other: &Model) -> bool{
+        self.material == other.material
+            && self.texture_indices == other.texture_indices
+            && self.is_static == other.is_static
+            && self.casts_shadows == other.casts_shadows
+            && self.receives_shadows == other.receives_shadows
+            && self.double_sided == other.double_sided
+            && self.render_layers == other.render_layers
+            && self.properties == other.properties
+    }
+
+    /// Copies everything `draws_like` compares, other than the material, to a model drawn in place of this one
+    pub(crate) fn copy_draw_settings(&self, target: &mut Model){
+        target.texture_indices = self.texture_indices;
+        target.is_static = self.is_static;
+        target.casts_shadows = self.casts_shadows;
+        target.receives_shadows = self.receives_shadows;
+        target.double_sided = self.double_sided;
+        target.render_layers = self.render_layers;
+        target.properties = self.properties.clone();
+    }
 }
 
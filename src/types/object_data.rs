@@ -0,0 +1,47 @@
+use crate::types::transform::Transform;
+
+/// The name of the storage binding the renderer fills with every model's `ObjectData`.
+/// Shaders declare it as `var<storage, read> objects: array<ObjectData>` and index it with
+/// `@builtin(instance_index)`, which is set to the model's slot when drawing
+pub const OBJECTS_BINDING: &str = "objects";
+
+/// Set in `ObjectData::flags` when the model receives shadows, see `Model::set_receives_shadows`
+pub const OBJECT_RECEIVES_SHADOWS: u32 = 1;
+
+/// # Object Data
+///
+/// The per-model data in the `objects` storage buffer
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ObjectData {
+    pub model: [[f32; 4]; 4],
+    /// Indices into the bindless texture array, see `ResourceManager::set_model_bindless_textures`
+    pub texture_indices: [u32; 4],
+    /// `OBJECT_RECEIVES_SHADOWS` and friends in x, the rest is padding
+    pub flags: [u32; 4],
+}
+
+impl ObjectData {
+    pub fn new(transform: &Transform, texture_indices: [u32; 4]) -> Self {
+        Self {
+            model: transform.get_matrix().to_cols_array_2d(),
+            texture_indices,
+            flags: [OBJECT_RECEIVES_SHADOWS, 0, 0, 0],
+        }
+    }
+
+    pub fn with_flags(mut self, flags: u32) -> Self {
+        self.flags[0] = flags;
+        self
+    }
+}
+
+impl Default for ObjectData {
+    fn default() -> Self {
+        Self {
+            model: glam::Mat4::IDENTITY.to_cols_array_2d(),
+            texture_indices: [0; 4],
+            flags: [OBJECT_RECEIVES_SHADOWS, 0, 0, 0],
+        }
+    }
+}
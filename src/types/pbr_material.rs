@@ -0,0 +1,339 @@
+use glam::{Mat3, Vec2};
+use log::warn;
+use crate::types::texture::ColorSpace;
+
+/// Physically based shader for glTF materials, reading `objects` and a `camera` uniform in group 0
+/// and a `PbrMaterialUniform` named `material` plus the `PBR_TEXTURE_SLOTS` textures in group 1.
+/// Point lights casting shadows are shadowed through the renderer-bound `point_shadows` map
+pub const PBR_SHADER: &str = include_str!("../../assets/shaders/pbr.wgsl");
+
+/// The texture names `PBR_SHADER` samples, in the order of `PbrMaterialUniform::texture_transforms`
+pub const PBR_TEXTURE_SLOTS: [&str; 8] = [
+    "base_color",
+    "metallic_roughness",
+    "emissive",
+    "clearcoat",
+    "clearcoat_roughness",
+    "transmission",
+    "occlusion",
+    "normal",
+];
+
+/// The texture name of `PBR_SHADER`'s lightmap, which is always sampled with the second UV set
+pub const PBR_LIGHTMAP_SLOT: &str = "lightmap_texture";
+
+/// # Texture Transform
+///
+/// An offset, rotation and scale applied to texture coordinates, as described by
+/// `KHR_texture_transform`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureTransform {
+    pub offset: [f32; 2],
+    /// Rotation in radians, counter-clockwise
+    pub rotation: f32,
+    pub scale: [f32; 2],
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+        }
+    }
+}
+
+impl TextureTransform {
+    /// The transform as a matrix, in the order the extension specifies (scale, then rotate, then translate)
+    pub fn to_matrix(&self) -> Mat3 {
+        Mat3::from_translation(Vec2::from(self.offset))
+            * Mat3::from_angle(-self.rotation)
+            * Mat3::from_scale(Vec2::from(self.scale))
+    }
+
+    // A WGSL mat3x3 is three columns padded to 16 bytes each
+    fn to_uniform(self) -> [[f32; 4]; 3] {
+        let matrix = self.to_matrix();
+        [matrix.x_axis, matrix.y_axis, matrix.z_axis].map(|column| [column.x, column.y, column.z, 0.0])
+    }
+
+    fn from_json(value: Option<&gltf::json::Value>) -> Self {
+        let Some(value) = value else {
+            return Self::default();
+        };
+        let pair = |name: &str, default: [f32; 2]| value.get(name)
+            .and_then(|pair| pair.as_array())
+            .and_then(|pair| Some([pair.first()?.as_f64()? as f32, pair.get(1)?.as_f64()? as f32]))
+            .unwrap_or(default);
+
+        Self {
+            offset: pair("offset", [0.0, 0.0]),
+            rotation: value.get("rotation").and_then(|rotation| rotation.as_f64()).unwrap_or(0.0) as f32,
+            scale: pair("scale", [1.0, 1.0]),
+        }
+    }
+}
+
+/// # PBR Material Uniform
+///
+/// The `material` uniform of `PBR_SHADER`. Texture samples are multiplied by the factors,
+/// so a factor of 1 with a white texture uses the factor alone
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PbrMaterialUniform {
+    pub base_color_factor: [f32; 4],
+    /// The emissive colour multiplied by its strength, in rgb
+    pub emissive: [f32; 4],
+    /// Metallic, roughness, clearcoat and clearcoat roughness factors
+    pub metallic_roughness_clearcoat: [f32; 4],
+    /// Transmission factor, alpha cutoff (negative when not masked), and 1 when alpha blended
+    pub transmission_alpha: [f32; 4],
+    /// Texture coordinate transforms, in the order of `PBR_TEXTURE_SLOTS`
+    pub texture_transforms: [[[f32; 4]; 3]; 8],
+    /// The UV set each texture samples (0 or 1), in the order of `PBR_TEXTURE_SLOTS`, packed four to a row
+    pub texture_uv_sets: [[u32; 4]; 2],
+    /// How much the `occlusion` texture's red channel darkens indirect light, from 0 (not at all) to 1, in x.
+    /// The `normal` texture's scale in y, where 0 leaves the normals unchanged. 1 in z when occlusion is
+    /// packed into `metallic_roughness`, and 1 in w when `normal` only holds X and Y, see `set_packed_orm`
+    /// and `set_two_channel_normals`
+    pub occlusion_normal: [f32; 4],
+}
+
+impl Default for PbrMaterialUniform {
+    fn default() -> Self {
+        Self {
+            base_color_factor: [1.0; 4],
+            emissive: [0.0; 4],
+            metallic_roughness_clearcoat: [1.0, 1.0, 0.0, 0.0],
+            transmission_alpha: [0.0, -1.0, 0.0, 0.0],
+            texture_transforms: [TextureTransform::default().to_uniform(); 8],
+            texture_uv_sets: [[0; 4]; 2],
+            occlusion_normal: [1.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl PbrMaterialUniform {
+    pub fn set_texture_transform(&mut self, slot: &str, texture_transform: TextureTransform) {
+        if let Some(index) = PBR_TEXTURE_SLOTS.iter().position(|name| *name == slot) {
+            self.texture_transforms[index] = texture_transform.to_uniform();
+        }
+    }
+
+    /// Picks the UV set a texture slot samples. `PBR_SHADER` reads the first two sets,
+    /// and the second only exists on meshes with a `MultiUvVertex` layout
+    pub fn set_texture_uv_set(&mut self, slot: &str, uv_set: u32) {
+        if uv_set > 1 {
+            warn!("PBR shader only samples UV sets 0 and 1, {} uses set {}", slot, uv_set);
+        }
+        if let Some(index) = PBR_TEXTURE_SLOTS.iter().position(|name| *name == slot) {
+            self.texture_uv_sets[index / 4][index % 4] = uv_set.min(1);
+        }
+    }
+
+    /// Reads occlusion from the red channel of `metallic_roughness` instead of the `occlusion` texture,
+    /// for ORM textures packing occlusion, roughness and metallic into one. The `occlusion` slot is then unused
+    pub fn set_packed_orm(&mut self, packed: bool) {
+        self.occlusion_normal[2] = if packed { 1.0 } else { 0.0 };
+    }
+
+    pub fn is_packed_orm(&self) -> bool {
+        self.occlusion_normal[2] > 0.0
+    }
+
+    /// Reads only the red and green channels of `normal`, reconstructing Z as the normal is unit length.
+    /// Two-channel normal maps (e.g BC5 or RG8) take half the memory of RGBA ones
+    pub fn set_two_channel_normals(&mut self, two_channel: bool) {
+        self.occlusion_normal[3] = if two_channel { 1.0 } else { 0.0 };
+    }
+
+    pub fn is_two_channel_normals(&self) -> bool {
+        self.occlusion_normal[3] > 0.0
+    }
+}
+
+crate::impl_as_bytes!(PbrMaterialUniform);
+
+/// # Lightmap Uniform
+///
+/// The per-model `lightmap` uniform of `PBR_SHADER`. Models sharing an atlas use the scale and
+/// offset to pick their region of it, and an intensity of 0 turns the lightmap off
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightmapUniform {
+    /// Scale in xy and offset in zw, applied to the second UV set
+    pub scale_offset: [f32; 4],
+    /// Multiplier of the baked lighting, in x
+    pub intensity: [f32; 4],
+}
+
+impl Default for LightmapUniform {
+    fn default() -> Self {
+        Self {
+            scale_offset: [1.0, 1.0, 0.0, 0.0],
+            intensity: [0.0; 4],
+        }
+    }
+}
+
+impl LightmapUniform {
+    pub fn new(scale: Vec2, offset: Vec2, intensity: f32) -> Self {
+        Self {
+            scale_offset: [scale.x, scale.y, offset.x, offset.y],
+            intensity: [intensity, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+crate::impl_as_bytes!(LightmapUniform);
+
+/// # Gltf Pbr Material
+///
+/// A glTF material read into `PBR_SHADER`'s uniform, plus the glTF image each slot samples
+pub(crate) struct GltfPbrMaterial {
+    pub name: Option<String>,
+    pub uniform: PbrMaterialUniform,
+    /// Slot name, image index and the colour space the slot needs
+    pub textures: Vec<(&'static str, usize, ColorSpace)>,
+    pub double_sided: bool,
+}
+
+impl GltfPbrMaterial {
+    pub fn from_gltf(document: &gltf::Document, material: &gltf::Material) -> Self {
+        let pbr = material.pbr_metallic_roughness();
+        let mut uniform = PbrMaterialUniform::default();
+        let mut textures = Vec::new();
+
+        // Record the image and KHR_texture_transform of a typed texture slot
+        let mut add_texture = |uniform: &mut PbrMaterialUniform, slot: &'static str, info: Option<gltf::texture::Info>| {
+            if let Some(info) = info {
+                let texture_transform = info.extension_value("KHR_texture_transform");
+                uniform.set_texture_transform(slot, TextureTransform::from_json(texture_transform));
+                uniform.set_texture_uv_set(slot, uv_set_of(info.tex_coord(), texture_transform));
+                textures.push((slot, info.texture().source().index(), ColorSpace::from_gltf_slot(slot)));
+            }
+        };
+
+        uniform.base_color_factor = pbr.base_color_factor();
+        add_texture(&mut uniform, "base_color", pbr.base_color_texture());
+        add_texture(&mut uniform, "metallic_roughness", pbr.metallic_roughness_texture());
+
+        let emissive_strength = material.emissive_strength().unwrap_or(1.0);
+        let [red, green, blue] = material.emissive_factor().map(|channel| channel * emissive_strength);
+        uniform.emissive = [red, green, blue, 1.0];
+        add_texture(&mut uniform, "emissive", material.emissive_texture());
+
+        if let Some(transmission) = material.transmission() {
+            uniform.transmission_alpha[0] = transmission.transmission_factor();
+            add_texture(&mut uniform, "transmission", transmission.transmission_texture());
+        }
+
+        // Occlusion and normal textures have their own types for the strength and scale,
+        // but are otherwise typed slots
+        if let Some(occlusion) = material.occlusion_texture() {
+            let texture_transform = occlusion.extension_value("KHR_texture_transform");
+            let image = occlusion.texture().source().index();
+            uniform.occlusion_normal[0] = occlusion.strength();
+
+            // Exporters often write one ORM image for both slots, which then only needs sampling once
+            let metallic_roughness = pbr.metallic_roughness_texture();
+            let packed = metallic_roughness.as_ref().is_some_and(|info| {
+                let metallic_roughness_transform = info.extension_value("KHR_texture_transform");
+                info.texture().source().index() == image
+                    && uv_set_of(info.tex_coord(), metallic_roughness_transform) == uv_set_of(occlusion.tex_coord(), texture_transform)
+                    && TextureTransform::from_json(metallic_roughness_transform) == TextureTransform::from_json(texture_transform)
+            });
+
+            if packed {
+                uniform.set_packed_orm(true);
+            } else {
+                uniform.set_texture_transform("occlusion", TextureTransform::from_json(texture_transform));
+                uniform.set_texture_uv_set("occlusion", uv_set_of(occlusion.tex_coord(), texture_transform));
+                textures.push(("occlusion", image, ColorSpace::from_gltf_slot("occlusion")));
+            }
+        }
+
+        // Without a normal texture the scale stays 0, so the white placeholder leaves the normals alone
+        if let Some(normal) = material.normal_texture() {
+            let texture_transform = normal.extension_value("KHR_texture_transform");
+            uniform.set_texture_transform("normal", TextureTransform::from_json(texture_transform));
+            uniform.set_texture_uv_set("normal", uv_set_of(normal.tex_coord(), texture_transform));
+            uniform.occlusion_normal[1] = normal.scale();
+            textures.push(("normal", normal.texture().source().index(), ColorSpace::from_gltf_slot("normal")));
+        }
+
+        uniform.metallic_roughness_clearcoat[0] = pbr.metallic_factor();
+        uniform.metallic_roughness_clearcoat[1] = pbr.roughness_factor();
+
+        // The gltf crate doesn't know about clearcoat, so it's read from the raw extension
+        if let Some(clearcoat) = material.extension_value("KHR_materials_clearcoat") {
+            let factor = |name: &str| clearcoat.get(name).and_then(|factor| factor.as_f64()).unwrap_or(0.0) as f32;
+            uniform.metallic_roughness_clearcoat[2] = factor("clearcoatFactor");
+            uniform.metallic_roughness_clearcoat[3] = factor("clearcoatRoughnessFactor");
+
+            for (slot, key) in [("clearcoat", "clearcoatTexture"), ("clearcoat_roughness", "clearcoatRoughnessTexture")] {
+                let Some(texture_json) = clearcoat.get(key) else { continue };
+                let image = texture_json.get("index")
+                    .and_then(|index| index.as_u64())
+                    .and_then(|index| document.textures().nth(index as usize))
+                    .map(|texture| texture.source().index());
+                if let Some(image) = image {
+                    let texture_transform = texture_json.get("extensions").and_then(|extensions| extensions.get("KHR_texture_transform"));
+                    let tex_coord = texture_json.get("texCoord").and_then(|set| set.as_u64()).unwrap_or(0) as u32;
+                    uniform.set_texture_transform(slot, TextureTransform::from_json(texture_transform));
+                    uniform.set_texture_uv_set(slot, uv_set_of(tex_coord, texture_transform));
+                    textures.push((slot, image, ColorSpace::from_gltf_slot(slot)));
+                }
+            }
+        }
+
+        match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => {}
+            gltf::material::AlphaMode::Mask => uniform.transmission_alpha[1] = material.alpha_cutoff().unwrap_or(0.5),
+            gltf::material::AlphaMode::Blend => uniform.transmission_alpha[2] = 1.0,
+        }
+
+        Self {
+            name: material.name().map(str::to_string),
+            uniform,
+            textures,
+            double_sided: material.double_sided(),
+        }
+    }
+}
+
+// KHR_texture_transform can override the texture's UV set with its own texCoord
+fn uv_set_of(tex_coord: u32, texture_transform: Option<&gltf::json::Value>) -> u32 {
+    texture_transform
+        .and_then(|texture_transform| texture_transform.get("texCoord"))
+        .and_then(|set| set.as_u64())
+        .map(|set| set as u32)
+        .unwrap_or(tex_coord)
+}
+
+/// Converts a decoded glTF image to 8-bit RGBA
+pub(crate) fn gltf_image_to_rgba(data: &gltf::image::Data) -> anyhow::Result<image::RgbaImage> {
+    use gltf::image::Format;
+    use image::{DynamicImage, ImageBuffer};
+
+    let (width, height) = (data.width, data.height);
+    let wide = || data.pixels.chunks_exact(2).map(|value| u16::from_le_bytes([value[0], value[1]])).collect::<Vec<u16>>();
+    let float = || data.pixels.chunks_exact(4).map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]])).collect::<Vec<f32>>();
+
+    let image = match data.format {
+        Format::R8 => ImageBuffer::from_raw(width, height, data.pixels.clone()).map(DynamicImage::ImageLuma8),
+        Format::R8G8 => ImageBuffer::from_raw(width, height, data.pixels.clone()).map(DynamicImage::ImageLumaA8),
+        Format::R8G8B8 => ImageBuffer::from_raw(width, height, data.pixels.clone()).map(DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => ImageBuffer::from_raw(width, height, data.pixels.clone()).map(DynamicImage::ImageRgba8),
+        Format::R16 => ImageBuffer::from_raw(width, height, wide()).map(DynamicImage::ImageLuma16),
+        Format::R16G16 => ImageBuffer::from_raw(width, height, wide()).map(DynamicImage::ImageLumaA16),
+        Format::R16G16B16 => ImageBuffer::from_raw(width, height, wide()).map(DynamicImage::ImageRgb16),
+        Format::R16G16B16A16 => ImageBuffer::from_raw(width, height, wide()).map(DynamicImage::ImageRgba16),
+        Format::R32G32B32FLOAT => ImageBuffer::from_raw(width, height, float()).map(DynamicImage::ImageRgb32F),
+        Format::R32G32B32A32FLOAT => ImageBuffer::from_raw(width, height, float()).map(DynamicImage::ImageRgba32F),
+    };
+
+    image.map(|image| image.to_rgba8())
+        .ok_or_else(|| anyhow::anyhow!("glTF image data doesn't match its {}x{} size", width, height))
+}
@@ -0,0 +1,85 @@
+use glam::{Mat4, Vec3, Vec4};
+use crate::managers::resource_handle::ResourceHandle;
+
+/// WGSL helper for sampling a planar reflection. Pass the vertex's clip position to the fragment
+/// shader in a separate location (the `@builtin(position)` input is in pixels), and sample the
+/// reflection texture at `planar_reflection_uv(clip_position)`
+pub const PLANAR_REFLECTION_WGSL: &str = r#"
+fn planar_reflection_uv(clip_position: vec4<f32>) -> vec2<f32> {
+    let ndc = clip_position.xy / clip_position.w;
+    // The reflection is drawn mirrored horizontally, see `PlanarReflection`
+    return vec2<f32>(0.5 - ndc.x * 0.5, 0.5 - ndc.y * 0.5);
+}
+"#;
+
+/// # Planar Reflection
+///
+/// A camera following the default camera (see `ResourceManager::set_camera`) mirrored across a plane,
+/// drawing into a render texture that mirror and water materials sample with `PLANAR_REFLECTION_WGSL`.
+/// Created with `ResourceManager::create_planar_reflection`.
+///
+/// Anything behind the plane is clipped with an oblique near plane, so it doesn't show up in the
+/// reflection. The reflection is drawn mirrored horizontally, which keeps the triangles' winding,
+/// so back-face culling still works
+#[derive(Debug, Clone)]
+pub struct PlanarReflection{
+    camera: ResourceHandle,
+    texture: ResourceHandle,
+}
+
+impl PlanarReflection{
+    pub(crate) fn new(camera: ResourceHandle, texture: ResourceHandle) -> Self{
+        Self{ camera, texture }
+    }
+
+    /// The camera drawing the reflection, e.g to change its priority
+    pub fn get_camera(&self) -> &ResourceHandle{
+        &self.camera
+    }
+
+    /// The render texture holding the reflection, to assign to materials
+    pub fn get_texture(&self) -> &ResourceHandle{
+        &self.texture
+    }
+}
+
+/// A plane through the point, as the normal in xyz and the distance in w
+pub(crate) fn plane_from_point_normal(point: Vec3, normal: Vec3) -> Vec4{
+    let normal = normal.normalize();
+    normal.extend(-normal.dot(point))
+}
+
+/// # Reflect Camera
+///
+/// Mirrors a camera's view and projection across a plane (normal in xyz, distance in w),
+/// with the near plane moved onto the mirror plane. Expects a projection with a 0..1 depth range
+pub(crate) fn reflect_camera(view: Mat4, projection: Mat4, plane: Vec4) -> (Mat4, Mat4){
+    // Keep the camera on the positive side, so the reflection camera is on the negative one
+    let camera_position = view.inverse().w_axis;
+    let plane = if plane.dot(camera_position) < 0.0 { -plane } else { plane };
+
+    let n = plane.truncate();
+    let reflection = Mat4::from_cols(
+        Vec4::new(1.0 - 2.0 * n.x * n.x, -2.0 * n.x * n.y, -2.0 * n.x * n.z, 0.0),
+        Vec4::new(-2.0 * n.y * n.x, 1.0 - 2.0 * n.y * n.y, -2.0 * n.y * n.z, 0.0),
+        Vec4::new(-2.0 * n.z * n.x, -2.0 * n.z * n.y, 1.0 - 2.0 * n.z * n.z, 0.0),
+        Vec4::new(-2.0 * plane.w * n.x, -2.0 * plane.w * n.y, -2.0 * plane.w * n.z, 1.0),
+    );
+    let reflected_view = view * reflection;
+
+    // Oblique near plane (Lengyel), replacing the near plane with the mirror plane in view space
+    let clip_plane = reflected_view.inverse().transpose() * plane;
+    let corner = projection.inverse() * Vec4::new(clip_plane.x.signum(), clip_plane.y.signum(), 1.0, 1.0);
+    let scale = clip_plane.dot(corner);
+    let mut oblique = projection;
+    if scale.abs() > f32::EPSILON{
+        let row = clip_plane / scale;
+        oblique.x_axis.z = row.x;
+        oblique.y_axis.z = row.y;
+        oblique.z_axis.z = row.z;
+        oblique.w_axis.z = row.w;
+    }
+
+    // Reflecting flips the winding, and flipping x flips it back
+    (reflected_view, Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0)) * oblique)
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+use anyhow::{bail, Context};
+use crate::utils::ply::PlyData;
+
+/// Shader that draws point cloud meshes, reading `objects` and a `camera` uniform in group 0
+/// and a `PointCloudUniform` named `point_cloud` in group 1
+pub const POINT_CLOUD_SHADER: &str = include_str!("../../assets/shaders/point_cloud.wgsl");
+
+/// # Point
+///
+/// A single point in a point cloud
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Point {
+    pub position: [f32; 3],
+    /// Linear RGBA, 0 to 1
+    pub color: [f32; 4],
+}
+
+/// # Point Style
+///
+/// How points are rasterized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointStyle {
+    /// One pixel per point, using the point list topology. Cheapest, but size can't be changed
+    Pixels,
+    /// A round sprite per point, sized in pixels by the `point_cloud` uniform
+    Sprites,
+}
+
+/// # Point Vertex
+///
+/// The vertex format of point cloud meshes. `corner` is which corner of the sprite
+/// the vertex is (-1 to 1), and is zero for pixel points
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PointVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+    pub corner: [f32; 2],
+}
+
+impl PointVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<PointVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ],
+        }
+    }
+}
+
+/// # Point Cloud Uniform
+///
+/// Settings for `POINT_CLOUD_SHADER`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PointCloudUniform {
+    /// Size of the render target in pixels
+    pub viewport: [f32; 2],
+    /// Diameter of sprite points in pixels
+    pub size: f32,
+    _padding: f32,
+}
+
+impl PointCloudUniform {
+    pub fn new(size: f32, viewport: [f32; 2]) -> Self {
+        Self {
+            viewport,
+            size,
+            _padding: 0.0,
+        }
+    }
+}
+
+crate::impl_as_bytes!(PointCloudUniform);
+
+/// # Load Points
+///
+/// Loads points from a `.ply` or `.xyz` file. Points without colours are white
+pub fn load_points<T: AsRef<Path>>(path: T) -> anyhow::Result<Vec<Point>> {
+    let path = path.as_ref();
+    match path.extension().and_then(|extension| extension.to_str()).map(|extension| extension.to_lowercase()).as_deref() {
+        Some("ply") => load_ply_points(path),
+        Some("xyz") | Some("txt") => load_xyz_points(path),
+        _ => bail!("Unsupported point cloud format: {}", path.display()),
+    }
+}
+
+fn load_ply_points(path: &Path) -> anyhow::Result<Vec<Point>> {
+    let ply = PlyData::load(path)?;
+    let positions = ply.get_positions()?;
+    let colors = ply.get_colors();
+
+    Ok(positions.into_iter().enumerate().map(|(i, position)| Point {
+        position,
+        color: colors.as_ref().map(|colors| colors[i]).unwrap_or([1.0; 4]),
+    }).collect())
+}
+
+// Each line is `x y z` optionally followed by `r g b`, either 0 to 1 or 0 to 255
+fn load_xyz_points(path: &Path) -> anyhow::Result<Vec<Point>> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut rows = Vec::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        let values = line.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|value| !value.is_empty())
+            .map(|value| value.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .with_context(|| format!("Invalid point on line {}", line_number + 1))?;
+        if values.len() < 3 {
+            bail!("Expected at least 3 values on line {}", line_number + 1);
+        }
+
+        rows.push(values);
+    }
+
+    // Colours are either all 0 to 1, or all 0 to 255
+    let color_scale = if rows.iter().any(|row| row.len() >= 6 && row[3..6].iter().any(|&c| c > 1.0)) { 255.0 } else { 1.0 };
+
+    Ok(rows.into_iter().map(|row| Point {
+        position: [row[0], row[1], row[2]],
+        color: if row.len() >= 6 {
+            [row[3] / color_scale, row[4] / color_scale, row[5] / color_scale, 1.0]
+        } else {
+            [1.0; 4]
+        },
+    }).collect())
+}
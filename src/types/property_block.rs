@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use log::warn;
+use crate::utils::shader_reflect::UniformLayout;
+
+/// # Property Block
+///
+/// Per-model overrides of individual uniform members of a material, e.g a tint colour or
+/// emissive strength. Each property is matched by name against the members of the material
+/// shader's uniform structs, and the rest of the uniform is read from the material
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropertyBlock{
+    properties: HashMap<String, Vec<u8>>,
+}
+
+impl PropertyBlock{
+    pub fn new() -> Self{
+        Self::default()
+    }
+
+    /// Sets a property. The value must have the size of the shader member it overrides,
+    /// e.g `[f32; 4]` for a `vec4<f32>`
+    pub fn set<T: bytemuck::Pod>(&mut self, name: &str, value: T){
+        self.properties.insert(name.to_string(), bytemuck::bytes_of(&value).to_vec());
+    }
+
+    /// Removes a property, so the material's value is used again
+    pub fn remove(&mut self, name: &str) -> bool{
+        self.properties.remove(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[u8]>{
+        self.properties.get(name).map(|value| value.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.properties.is_empty()
+    }
+
+    /// Whether any property overrides a member of the uniform
+    pub(crate) fn overrides(&self, layout: &UniformLayout) -> bool{
+        layout.members.iter().any(|member| self.properties.contains_key(&member.name))
+    }
+
+    /// Writes the properties over the matching members of a uniform's data
+    pub(crate) fn apply(&self, layout: &UniformLayout, data: &mut [u8]){
+        for member in layout.members.iter(){
+            let Some(value) = self.properties.get(&member.name) else { continue };
+
+            let range = member.offset as usize..(member.offset + member.size) as usize;
+            if value.len() != member.size as usize || range.end > data.len(){
+                warn!("Property `{}` is {} bytes, but `{}.{}` is {} bytes. Ignoring it",
+                    member.name, value.len(), layout.type_name, member.name, member.size);
+                continue;
+            }
+
+            data[range].copy_from_slice(value);
+        }
+    }
+}
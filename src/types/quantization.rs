@@ -0,0 +1,155 @@
+use crate::types::vertex::{uv_set_location, ColoredVertex, MultiUvVertex, QuantizedVertex, Vertex, VertexLayoutBuilder, COLOR_LOCATION, MAX_UV_SETS};
+
+/// The name shaders give the `QuantizationUniform` of a quantized mesh
+pub const QUANTIZATION_BINDING: &str = "quantization";
+
+/// WGSL helpers for reading a `QuantizedVertex`. Declare the inputs as `@location(0) position: vec4<f32>`,
+/// `@location(1) normal: vec2<f32>` and `@location(2) tex_coords: vec2<f32>`, then decode them with
+/// `decode_position(position, quantization)` and `decode_octahedral(normal)`
+pub const QUANTIZED_VERTEX_WGSL: &str = r#"
+struct Quantization {
+    offset: vec4<f32>,
+    scale: vec4<f32>,
+};
+
+fn decode_position(position: vec4<f32>, quantization: Quantization) -> vec3<f32> {
+    return quantization.offset.xyz + position.xyz * quantization.scale.xyz;
+}
+
+fn decode_octahedral(encoded: vec2<f32>) -> vec3<f32> {
+    var normal = vec3<f32>(encoded.x, encoded.y, 1.0 - abs(encoded.x) - abs(encoded.y));
+    // The lower hemisphere is folded over the diagonals
+    let fold = max(-normal.z, 0.0);
+    normal.x += select(fold, -fold, normal.x >= 0.0);
+    normal.y += select(fold, -fold, normal.y >= 0.0);
+    return normalize(normal);
+}
+"#;
+
+/// # Quantization Uniform
+///
+/// Maps a quantized mesh's 16-bit positions (0 to 1) back to mesh space, as `offset + position * scale`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct QuantizationUniform{
+    pub offset: [f32; 4],
+    pub scale: [f32; 4],
+}
+
+impl QuantizationUniform{
+    /// Covers the box from `min` to `max`. Flat axes get a scale of 1 so nothing divides by zero
+    pub fn from_bounds(min: glam::Vec3, max: glam::Vec3) -> Self{
+        let extent = (max - min).to_array().map(|axis| if axis > 0.0{ axis }else{ 1.0 });
+        Self{
+            offset: min.extend(0.0).to_array(),
+            scale: [extent[0], extent[1], extent[2], 0.0],
+        }
+    }
+
+    pub fn quantize_position(&self, position: [f32; 3]) -> [u16; 4]{
+        let mut quantized = [0u16; 4];
+        for axis in 0..3{
+            let normalized = (position[axis] - self.offset[axis]) / self.scale[axis];
+            quantized[axis] = (normalized.clamp(0.0, 1.0) * 65535.0).round() as u16;
+        }
+        quantized
+    }
+
+    pub fn dequantize_position(&self, position: [u16; 4]) -> [f32; 3]{
+        [0, 1, 2].map(|axis| self.offset[axis] + position[axis] as f32 / 65535.0 * self.scale[axis])
+    }
+}
+
+impl Default for QuantizationUniform{
+    fn default() -> Self{
+        Self::from_bounds(glam::Vec3::ZERO, glam::Vec3::ONE)
+    }
+}
+
+crate::impl_as_bytes!(QuantizationUniform);
+
+/// Packs a unit vector into two signed 16-bit values by projecting it onto an octahedron
+pub fn encode_octahedral(normal: [f32; 3]) -> [i16; 2]{
+    let normal = glam::Vec3::from(normal);
+    let length = normal.x.abs() + normal.y.abs() + normal.z.abs();
+    if length == 0.0{
+        return [0, 0];
+    }
+    let normal = normal / length;
+
+    let (x, y) = if normal.z >= 0.0{
+        (normal.x, normal.y)
+    }else{
+        // Fold the lower hemisphere over the diagonals
+        ((1.0 - normal.y.abs()) * normal.x.signum(), (1.0 - normal.x.abs()) * normal.y.signum())
+    };
+
+    [x, y].map(|value| (value.clamp(-1.0, 1.0) * 32767.0).round() as i16)
+}
+
+pub fn decode_octahedral(encoded: [i16; 2]) -> [f32; 3]{
+    let (x, y) = ((encoded[0] as f32 / 32767.0).max(-1.0), (encoded[1] as f32 / 32767.0).max(-1.0));
+    let mut normal = glam::Vec3::new(x, y, 1.0 - x.abs() - y.abs());
+    let fold = (-normal.z).max(0.0);
+    normal.x += if normal.x >= 0.0{ -fold }else{ fold };
+    normal.y += if normal.y >= 0.0{ -fold }else{ fold };
+    normal.normalize_or_zero().to_array()
+}
+
+fn encode_half2(value: [f32; 2]) -> [u16; 2]{
+    value.map(|component| half::f16::from_f32(component).to_bits())
+}
+
+/// Quantizes standard vertices against `quantization`, halving their size
+pub(crate) fn quantize_vertices(vertices: &[Vertex], quantization: &QuantizationUniform) -> Vec<QuantizedVertex>{
+    vertices.iter().map(|vertex| QuantizedVertex{
+        position: quantization.quantize_position(vertex.position),
+        normal: encode_octahedral(vertex.normal),
+        tex_coords: encode_half2(vertex.tex_coords),
+    }).collect()
+}
+
+/// The layout of quantized `MultiUvVertex` data: a `QuantizedVertex` with every UV set as `Float16x2`
+pub(crate) fn quantized_multi_uv_layout() -> wgpu::VertexBufferLayout<'static>{
+    let mut builder = VertexLayoutBuilder::new()
+        .with_attribute(0, wgpu::VertexFormat::Unorm16x4)
+        .with_attribute(1, wgpu::VertexFormat::Snorm16x2);
+    for set in 0..MAX_UV_SETS as u32{
+        builder = builder.with_attribute(uv_set_location(set), wgpu::VertexFormat::Float16x2);
+    }
+    builder.build()
+}
+
+pub(crate) fn quantize_multi_uv_vertices(vertices: &[MultiUvVertex], quantization: &QuantizationUniform) -> Vec<u8>{
+    let mut bytes = Vec::with_capacity(vertices.len() * quantized_multi_uv_layout().array_stride as usize);
+    for vertex in vertices.iter(){
+        bytes.extend_from_slice(bytemuck::bytes_of(&quantization.quantize_position(vertex.position)));
+        bytes.extend_from_slice(bytemuck::bytes_of(&encode_octahedral(vertex.normal)));
+        for tex_coords in vertex.tex_coords.iter(){
+            bytes.extend_from_slice(bytemuck::bytes_of(&encode_half2(*tex_coords)));
+        }
+    }
+    bytes
+}
+
+/// The layout of quantized `ColoredVertex` data: a `QuantizedVertex` with the colour as `Unorm8x4`
+pub(crate) fn quantized_colored_layout() -> wgpu::VertexBufferLayout<'static>{
+    VertexLayoutBuilder::new()
+        .with_attribute(0, wgpu::VertexFormat::Unorm16x4)
+        .with_attribute(1, wgpu::VertexFormat::Snorm16x2)
+        .with_attribute(uv_set_location(0), wgpu::VertexFormat::Float16x2)
+        .with_alias(uv_set_location(1), uv_set_location(0))
+        .with_attribute(COLOR_LOCATION, wgpu::VertexFormat::Unorm8x4)
+        .build()
+}
+
+pub(crate) fn quantize_colored_vertices(vertices: &[ColoredVertex], quantization: &QuantizationUniform) -> Vec<u8>{
+    let mut bytes = Vec::with_capacity(vertices.len() * quantized_colored_layout().array_stride as usize);
+    for vertex in vertices.iter(){
+        bytes.extend_from_slice(bytemuck::bytes_of(&quantization.quantize_position(vertex.position)));
+        bytes.extend_from_slice(bytemuck::bytes_of(&encode_octahedral(vertex.normal)));
+        bytes.extend_from_slice(bytemuck::bytes_of(&encode_half2(vertex.tex_coords)));
+        bytes.extend(vertex.color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8));
+    }
+    bytes
+}
@@ -0,0 +1,51 @@
+/// The name of the uniform the renderer fills with the camera and frame data, see `SceneUniform`.
+/// Any material whose shader declares it has it bound automatically, unless the material assigns its own
+pub const SCENE_BINDING: &str = "scene";
+
+/// WGSL declaration of `SceneUniform`. By convention it's bound at `@group(0) @binding(1)`,
+/// after the `objects` buffer, though any group and binding works
+pub const SCENE_UNIFORM_WGSL: &str = r#"
+struct Scene {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    // Seconds since the renderer started
+    time: f32,
+};
+"#;
+
+/// # Scene Uniform
+///
+/// The camera and frame data shared by every material, kept up to date by the renderer.
+/// The camera is set with `ResourceManager::set_camera`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SceneUniform{
+    pub view: [[f32; 4]; 4],
+    pub projection: [[f32; 4]; 4],
+    pub view_projection: [[f32; 4]; 4],
+    pub camera_position: [f32; 3],
+    pub time: f32,
+}
+
+impl SceneUniform{
+    pub fn new(view: glam::Mat4, projection: glam::Mat4, time: f32) -> Self{
+        Self{
+            view: view.to_cols_array_2d(),
+            projection: projection.to_cols_array_2d(),
+            view_projection: (projection * view).to_cols_array_2d(),
+            // The camera sits at the origin of view space
+            camera_position: view.inverse().w_axis.truncate().to_array(),
+            time,
+        }
+    }
+}
+
+impl Default for SceneUniform{
+    fn default() -> Self{
+        Self::new(glam::Mat4::IDENTITY, glam::Mat4::IDENTITY, 0.0)
+    }
+}
+
+crate::impl_as_bytes!(SceneUniform);
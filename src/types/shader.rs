@@ -1,13 +1,21 @@
 use std::collections::HashMap;
-use log::info;
+use std::num::NonZeroU32;
+use log::{error, warn};
 use crate::utils::handle::Handle;
-use crate::utils::shader_reflect::{Binding, BindingType, ShaderReflect};
+use crate::utils::shader_reflect::{Binding, BindingType, ShaderReflect, UniformLayout, VertexInput};
+use crate::types::vertex::uv_set_location;
+use crate::types::bindless::MAX_BINDLESS_TEXTURES;
+use crate::uniform::uniform_buffer::UniformBuffer;
+use crate::debug::{debug_log, Subsystem};
 
 pub struct Shader{
-    source: String,
     binds: ShaderReflect,
     // group name, bind group layout
     bind_group_layouts: HashMap<u32, Handle<wgpu::BindGroupLayout>>,
+    // The entries each layout was created from, whether reflected or set with `set_bind_group_layout`
+    layout_entries: HashMap<u32, Vec<wgpu::BindGroupLayoutEntry>>,
+    // Compiled once, and shared by every pipeline built from the shader
+    module: wgpu::ShaderModule,
 
     _device: Handle<wgpu::Device>
 }
@@ -15,10 +23,18 @@ pub struct Shader{
 impl Shader{
     pub fn new<T: Into<String>>(device: Handle<wgpu::Device>, source: T) -> Self{
         let source = source.into();
+        let module = device.create_shader_module(
+            wgpu::ShaderModuleDescriptor{
+                label: Some("Shader Module"),
+                source: wgpu::ShaderSource::Wgsl(source.as_str().into())
+            }
+        );
+
         Self{
-            source: source.clone(),
             binds: ShaderReflect::new(source),
             bind_group_layouts: HashMap::new(),
+            layout_entries: HashMap::new(),
+            module,
             _device: device
         }
     }
@@ -35,17 +51,28 @@ impl Shader{
             let group = binding.get_group();
             let bind = binding.get_binding();
 
+            // Texture arrays are only used for bindless textures, which need device support
+            if binding.is_array() && !self._device.features().contains(wgpu::Features::TEXTURE_BINDING_ARRAY){
+                error!("Shader uses the texture array `{}`, but the device doesn't support bindless textures", binding.get_name());
+                panic!("Shader uses the texture array `{}`, but the device doesn't support bindless textures. Check `is_bindless_supported` first", binding.get_name());
+            }
+
             let entry = match binding.get_binding_type(){
                 BindingType::Texture => {
                     wgpu::BindGroupLayoutEntry{
                         binding: bind,
                         visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Texture {
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            multisampled: false
+                            // Multisampled textures can't be filtered, so multisampled float ones aren't filterable
+                            sample_type: if binding.is_depth(){
+                                wgpu::TextureSampleType::Depth
+                            }else{
+                                wgpu::TextureSampleType::Float { filterable: !binding.is_multisampled() }
+                            },
+                            view_dimension: binding.get_view_dimension(),
+                            multisampled: binding.is_multisampled()
                         },
-                        count: None
+                        count: if binding.is_array(){ NonZeroU32::new(MAX_BINDLESS_TEXTURES) }else{ None }
                     }
                 },
                 BindingType::TextureSampler => {
@@ -53,29 +80,38 @@ impl Shader{
                         binding: bind,
                         visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Sampler(
-                            wgpu::SamplerBindingType::Filtering
+                            if binding.is_depth(){ wgpu::SamplerBindingType::Comparison }else{ wgpu::SamplerBindingType::Filtering }
                         ),
                         count: None
                     }
                 },
                 BindingType::Uniform => {
+                    // Lets wgpu check bound buffers are big enough when the bind group is made, rather than on every draw
+                    let min_binding_size = self.binds.get_uniform_layout(&binding.get_name())
+                        .and_then(|layout| wgpu::BufferSize::new(layout.size as u64));
                     wgpu::BindGroupLayoutEntry{
                         binding: bind,
                         visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Buffer {
                             ty: wgpu::BufferBindingType::Uniform,
                             has_dynamic_offset: false,
-                            min_binding_size: None
+                            min_binding_size
                         },
                         count: None
                     }
                 },
                 BindingType::Storage => {
+                    // Writable storage isn't allowed in vertex shaders
+                    let visibility = if binding.is_read_only(){
+                        wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE
+                    }else{
+                        wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE
+                    };
                     wgpu::BindGroupLayoutEntry{
                         binding: bind,
-                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                        visibility,
                         ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            ty: wgpu::BufferBindingType::Storage { read_only: binding.is_read_only() },
                             has_dynamic_offset: false,
                             min_binding_size: None
                         },
@@ -102,26 +138,90 @@ impl Shader{
                 }
             );
 
-            println!("Created bind group layout for group {}", group);
-            println!("{:?}", entries);
+            debug_log!(Subsystem::Shaders, "Created bind group layout for group {}", group);
+            debug_log!(Subsystem::Shaders, "{:?}", entries);
 
             self.bind_group_layouts.insert(group, Handle::new(layout));
+            self.layout_entries.insert(group, entries);
+        }
+    }
+
+    /// # Set Bind Group Layout
+    ///
+    /// Replaces the layout reflected for a group with an explicit one, for what reflection can't infer,
+    /// e.g uniforms with dynamic offsets or non-filtering samplers. It has to list the same bindings as
+    /// the shader declares in the group, and be set before any pipeline or material uses the shader
+    pub fn set_bind_group_layout(&mut self, group: u32, descriptor: &wgpu::BindGroupLayoutDescriptor){
+        let declared: Vec<(String, u32)> = self.binds.get_bindings().into_values()
+            .filter(|binding| binding.get_group() == group)
+            .map(|binding| (binding.get_name(), binding.get_binding()))
+            .collect();
+        for (name, binding) in declared.iter(){
+            if !descriptor.entries.iter().any(|entry| entry.binding == *binding){
+                warn!("Bind group layout for group {} has no entry for `{}` at binding {}", group, name, binding);
+            }
+        }
+        for entry in descriptor.entries.iter(){
+            if !declared.iter().any(|(_, binding)| *binding == entry.binding){
+                warn!("Bind group layout for group {} has an entry at binding {}, which the shader doesn't declare", group, entry.binding);
+            }
+        }
+
+        let layout = self._device.create_bind_group_layout(descriptor);
+        debug_log!(Subsystem::Shaders, "Set bind group layout for group {}: {:?}", group, descriptor.entries);
+
+        self.bind_group_layouts.insert(group, Handle::new(layout));
+        self.layout_entries.insert(group, descriptor.entries.to_vec());
+    }
+
+    /// The layout entry of a binding, as reflected or set with `set_bind_group_layout`
+    pub fn get_layout_entry(&self, group: u32, binding: u32) -> Option<&wgpu::BindGroupLayoutEntry>{
+        self.layout_entries.get(&group)?.iter().find(|entry| entry.binding == binding)
+    }
+
+    /// Whether the named binding is a buffer bound with a dynamic offset
+    pub fn has_dynamic_offset(&self, name: &str) -> bool{
+        self.binds.get_binding(name)
+            .and_then(|binding| self.get_layout_entry(binding.get_group(), binding.get_binding()))
+            .is_some_and(|entry| matches!(entry.ty, wgpu::BindingType::Buffer{ has_dynamic_offset: true, .. }))
+    }
+
+    /// How many dynamic offsets binding the group takes, one per buffer with a dynamic offset
+    pub fn get_dynamic_offset_count(&self, group: u32) -> usize{
+        self.layout_entries.get(&group).map_or(0, |entries| entries.iter()
+            .filter(|entry| matches!(entry.ty, wgpu::BindingType::Buffer{ has_dynamic_offset: true, .. }))
+            .count())
+    }
+
+    /// # Validate Uniform Layout
+    ///
+    /// Checks a uniform's data against the struct the shader declares for it, see `UniformLayout::validate_layout`.
+    /// Uniforms bound with a dynamic offset only see one struct at a time, so their data only has to hold at least one
+    pub fn validate_uniform_layout(&self, name: &str, uniform: &UniformBuffer) -> anyhow::Result<()>{
+        let Some(layout) = self.get_uniform_layout(name) else { return Ok(()) };
+        if self.has_dynamic_offset(name) && uniform.get_size() >= layout.size as usize{
+            return Ok(());
         }
+
+        layout.validate_layout(name, uniform.get_size(), uniform.get_data().get_member_offsets())
     }
 
     pub fn get_bindings(&self) -> HashMap<String, Binding>{
         self.binds.get_bindings()
     }
     
-    pub fn get_binding_by_name(&self, name: &str) -> Option<u32>{
-        // Search each binding for the name. If we find it, return the group and binding
-        for (_, binding) in self.binds.get_bindings(){
-            if binding.get_name() == name{
-                return Some(binding.get_binding());
-            }
-        }
-        
-        None
+    pub fn get_uniform_layout(&self, name: &str) -> Option<&UniformLayout>{
+        self.binds.get_uniform_layout(name)
+    }
+
+    /// The locations the vertex shader reads, to check against a mesh's vertex layout
+    pub fn get_vertex_inputs(&self) -> &[VertexInput]{
+        self.binds.get_vertex_inputs()
+    }
+
+    /// Whether the vertex shader reads UV set `set`, at `uv_set_location(set)`
+    pub fn uses_uv_set(&self, set: u32) -> bool{
+        self.get_vertex_inputs().iter().any(|input| input.location == uv_set_location(set))
     }
 
     pub fn get_bind_group_layout(&self, group: u32) -> Option<&Handle<wgpu::BindGroupLayout>>{
@@ -143,12 +243,8 @@ impl Shader{
         layouts.iter().map(|(_, layout)| layout.clone()).collect()
     }
 
-    pub fn compile(&self, device: &wgpu::Device) -> wgpu::ShaderModule{
-        device.create_shader_module(
-            wgpu::ShaderModuleDescriptor{
-                label: Some("Shader Module"),
-                source: wgpu::ShaderSource::Wgsl(self.source.clone().into())
-            }
-        )
+    /// The compiled shader module
+    pub fn get_module(&self) -> &wgpu::ShaderModule{
+        &self.module
     }
 }
\ No newline at end of file
@@ -1,7 +1,45 @@
 use std::collections::HashMap;
-use log::{error, info};
+use log::{info, warn};
 use crate::managers::resource_handle::ResourceHandle;
+use crate::managers::sampler_cache::{SamplerCache, SamplerKey};
 use crate::utils::{handle::Handle, mut_handle::MutHandle};
+use crate::debug::{debug_log, Subsystem};
+
+/// # Color Space
+///
+/// How a texture's data should be interpreted when sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Colour data (albedo, emissive). Converted from sRGB to linear when sampled
+    Srgb,
+    /// Non-colour data (normals, roughness, metalness, occlusion). Sampled as-is
+    Linear,
+}
+
+impl ColorSpace {
+    /// # From glTF Slot
+    ///
+    /// Returns the colour space the glTF spec requires for a material texture slot
+    /// (e.g `baseColorTexture`, `normal_texture`). Unknown slots are assumed to be colour
+    pub fn from_gltf_slot(slot: &str) -> Self {
+        // Normalise `normalTexture`, `normal_texture` and `normal` to the same name
+        let slot = slot.to_lowercase().replace('_', "");
+        let slot = slot.strip_suffix("texture").unwrap_or(&slot);
+
+        match slot {
+            "normal" | "metallicroughness" | "metallic" | "roughness" | "occlusion"
+            | "clearcoat" | "clearcoatnormal" | "clearcoatroughness" | "transmission" | "thickness" => ColorSpace::Linear,
+            _ => ColorSpace::Srgb,
+        }
+    }
+
+    pub fn get_format(&self) -> wgpu::TextureFormat {
+        match self {
+            ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+}
 
 pub struct Texture {
     texture: wgpu::Texture,
@@ -9,6 +47,7 @@ pub struct Texture {
     sampler: Handle<wgpu::Sampler>,
 
     size: wgpu::Extent3d,
+    anisotropy: u16,
 
     // All bind groups for this texture for all shaders
     //
@@ -22,22 +61,138 @@ impl Texture {
         &self.view
     }
 
+    /// A view of only the depth of a depth texture, as reading from combined depth-stencil formats needs
+    pub fn create_depth_view(&self) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Depth Only View"),
+            aspect: wgpu::TextureAspect::DepthOnly,
+            ..Default::default()
+        })
+    }
+
     pub fn get_texture_sampler(&self) -> &wgpu::Sampler {
         &self.sampler
     }
 
+    pub(crate) fn get_raw_texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
     pub fn get_texture_size(&self) -> wgpu::Extent3d {
         self.size
     }
 
+    pub fn get_mip_level_count(&self) -> u32 {
+        self.texture.mip_level_count()
+    }
+
+    /// Approximate GPU memory used by the texture in bytes, including all mip levels
+    pub fn get_memory_size(&self) -> u64 {
+        let format = self.texture.format();
+        let (block_width, block_height) = format.block_dimensions();
+        // Formats without a fixed copy size (e.g depth24plus) are assumed to be 4 bytes
+        let block_size = format.block_copy_size(None).unwrap_or(4) as u64;
+
+        let mut total = 0;
+        for mip in 0..self.texture.mip_level_count() {
+            let width = (self.size.width >> mip).max(1);
+            let height = (self.size.height >> mip).max(1);
+            total += width.div_ceil(block_width) as u64 * height.div_ceil(block_height) as u64 * block_size;
+        }
+
+        total * self.size.depth_or_array_layers as u64 * self.texture.sample_count() as u64
+    }
+
+    pub fn get_color_space(&self) -> ColorSpace {
+        if self.texture.format().is_srgb() {
+            ColorSpace::Srgb
+        } else {
+            ColorSpace::Linear
+        }
+    }
+
+    /// Loads a colour (sRGB) texture from a file
     pub fn load_from_file<T: AsRef<std::path::Path>>(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
+        samplers: &SamplerCache,
         path: T,
+    ) -> Self {
+        Self::load_from_file_with_color_space(device, queue, samplers, path, ColorSpace::Srgb)
+    }
+
+    /// Loads a texture from a file, interpreting its data in the given colour space.
+    /// Files that can't be read are replaced by `create_missing`'s checker, with a warning
+    pub fn load_from_file_with_color_space<T: AsRef<std::path::Path>>(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        samplers: &SamplerCache,
+        path: T,
+        color_space: ColorSpace,
     ) -> Self {
         info!("Loading texture from file: {:?}", path.as_ref());
-        let img = image::open(path).unwrap().to_rgba8();
-        let dimensions = img.dimensions();
+        match image::open(path.as_ref()) {
+            Ok(img) => Self::from_image(device, queue, samplers, &img.to_rgba8(), color_space, "Texture"),
+            Err(e) => {
+                warn!("Failed to load texture {:?}, using the missing texture instead: {}", path.as_ref(), e);
+                Self::create_missing(device, queue, samplers)
+            }
+        }
+    }
+
+    /// # Create Missing
+    ///
+    /// A magenta and black checker, standing in for textures that failed to load
+    /// or were never found, so they stand out without stopping the renderer
+    pub fn create_missing(device: &wgpu::Device, queue: &wgpu::Queue, samplers: &SamplerCache) -> Self {
+        let img = image::RgbaImage::from_fn(8, 8, |x, y| {
+            if (x / 2 + y / 2) % 2 == 0 {
+                image::Rgba([255, 0, 255, 255])
+            } else {
+                image::Rgba([0, 0, 0, 255])
+            }
+        });
+
+        Self::from_image(device, queue, samplers, &img, ColorSpace::Srgb, "Missing Texture")
+    }
+
+    /// # Create White
+    ///
+    /// A 1x1 white texture, bound in place of textures a material doesn't provide so
+    /// any colour factors are used alone
+    pub fn create_white(device: &wgpu::Device, queue: &wgpu::Queue, samplers: &SamplerCache) -> Self {
+        let img = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+
+        Self::from_image(device, queue, samplers, &img, ColorSpace::Linear, "White Texture")
+    }
+
+    /// # From Image
+    ///
+    /// Uploads an RGBA image that's already in memory, e.g one generated at runtime
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        samplers: &SamplerCache,
+        img: &image::RgbaImage,
+        color_space: ColorSpace,
+        label: &str,
+    ) -> Self {
+        Self::from_mips(device, queue, samplers, std::slice::from_ref(img), color_space, label)
+    }
+
+    /// # From Mips
+    ///
+    /// Uploads a full mip chain, largest first. Each level must be half the size of the
+    /// previous one (rounded down, minimum 1), as produced by `generate_mip_chain`
+    pub fn from_mips(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        samplers: &SamplerCache,
+        mips: &[image::RgbaImage],
+        color_space: ColorSpace,
+        label: &str,
+    ) -> Self {
+        let dimensions = mips[0].dimensions();
         let size = wgpu::Extent3d {
             width: dimensions.0,
             height: dimensions.1,
@@ -46,59 +201,73 @@ impl Texture {
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             size,
-            mip_level_count: 1,
+            mip_level_count: mips.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
-            label: Some("Texture"),
+            format: color_space.get_format(),
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::TEXTURE_BINDING,
+            label: Some(label),
             view_formats: &[],
         });
 
-        queue.write_texture(
-            // Tells wgpu where to copy the pixel data
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            // The actual pixel data
-            img.as_raw(),
-            // The layout of the texture
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            size,
-        );
+        for (level, mip) in mips.iter().enumerate() {
+            queue.write_texture(
+                // Tells wgpu where to copy the pixel data
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                // The actual pixel data
+                mip.as_raw(),
+                // The layout of the texture
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * mip.width()),
+                    rows_per_image: Some(mip.height()),
+                },
+                wgpu::Extent3d {
+                    width: mip.width(),
+                    height: mip.height(),
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
-            label: Some("Texture Sampler"),
-            ..Default::default()
-        });
-        
 
         Self {
             texture,
             view: Handle::new(view),
-            sampler: Handle::new(sampler),
+            sampler: samplers.get(SamplerKey::linear(1)),
 
             size,
+            anisotropy: 1,
             
             bind_groups: HashMap::new()
         }
     }
 
-    pub fn create_depth_texture(device: &wgpu::Device, sc_desc: MutHandle<wgpu::SurfaceConfiguration>) -> Self {
+    /// # Generate Mip Chain
+    ///
+    /// Downsamples an image on the CPU down to 1x1, returning every level largest first
+    pub fn generate_mip_chain(img: &image::RgbaImage) -> Vec<image::RgbaImage> {
+        let mut mips = vec![img.clone()];
+
+        let (mut width, mut height) = img.dimensions();
+        while width > 1 || height > 1 {
+            width = (width / 2).max(1);
+            height = (height / 2).max(1);
+
+            let previous = mips.last().unwrap();
+            mips.push(image::imageops::resize(previous, width, height, image::imageops::FilterType::Triangle));
+        }
+
+        mips
+    }
+
+    pub fn create_depth_texture(device: &wgpu::Device, samplers: &SamplerCache, sc_desc: MutHandle<wgpu::SurfaceConfiguration>) -> Self {
         let sc_desc = sc_desc.get();
 
         let size = wgpu::Extent3d {
@@ -119,20 +288,7 @@ impl Texture {
         });
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
-            lod_min_clamp: 0.0,
-            lod_max_clamp: 100.0,
-            compare: None,
-            anisotropy_clamp: 1,
-            border_color: None,
-            label: Some("Depth Texture Sampler"),
-        });
+        let sampler = samplers.get(SamplerKey::linear(1));
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
@@ -174,9 +330,10 @@ impl Texture {
         Self{
             texture,
             view: Handle::new(view),
-            sampler: Handle::new(sampler),
+            sampler,
 
             size,
+            anisotropy: 1,
             
             bind_groups: HashMap::new()
         }
@@ -189,7 +346,7 @@ impl Texture {
     // It is assumed screen textures do not store any data
     // and are instead written to.
     pub fn resize_screen_texture(&mut self, device: &wgpu::Device, sc_desc: MutHandle<wgpu::SurfaceConfiguration>) {
-        error!("Resizing screen texture");
+        debug_log!(Subsystem::Resources, "Resizing screen texture");
         let sc_desc = sc_desc.get();
 
         self.size = wgpu::Extent3d {
@@ -198,17 +355,171 @@ impl Texture {
             depth_or_array_layers: 1,
         };
 
-        self.texture = device.create_texture(&wgpu::TextureDescriptor {
-            size: self.size,
+        self.resize(device, sc_desc.width, sc_desc.height);
+    }
+
+    /// # Create Screen Texture
+    ///
+    /// Creates an empty texture to be rendered into, such as a depth or normal buffer
+    pub fn create_screen_texture(device: &wgpu::Device, samplers: &SamplerCache, width: u32, height: u32, format: wgpu::TextureFormat, label: &str) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
+            label: Some(label),
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view: Handle::new(view),
+            sampler: samplers.get(SamplerKey::linear(1)),
+
+            size,
+            anisotropy: 1,
+
+            bind_groups: HashMap::new()
+        }
+    }
+
+    /// # Create Multisampled Texture
+    ///
+    /// Creates a render target with several samples per pixel, which passes draw into and
+    /// resolve to a single sampled texture. Multisampled textures can't be copied, so it's only
+    /// usable as an attachment
+    pub fn create_multisampled_texture(device: &wgpu::Device, samplers: &SamplerCache, width: u32, height: u32, format: wgpu::TextureFormat, samples: u32, label: &str) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            sample_count: samples,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some(label),
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view: Handle::new(view),
+            sampler: samplers.get(SamplerKey::linear(1)),
+
+            size,
+            anisotropy: 1,
+
+            bind_groups: HashMap::new()
+        }
+    }
+
+    /// # Create Shadow Map
+    ///
+    /// Creates a square depth texture array to draw shadows into. It's viewed as a whole as a
+    /// `texture_depth_2d_array`, and sampled with a comparison sampler that filters the result
+    pub fn create_shadow_map(device: &wgpu::Device, samplers: &SamplerCache, resolution: u32, layers: u32, label: &str) -> Self {
+        let size = wgpu::Extent3d {
+            width: resolution,
+            height: resolution,
+            depth_or_array_layers: layers,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size,
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Depth32Float,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            label: Some("Depth Texture"),
+            label: Some(label),
+            view_formats: &[],
+        });
+
+        // A single layer would otherwise be viewed as a plain 2D texture
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view: Handle::new(view),
+            sampler: samplers.get(SamplerKey::comparison()),
+
+            size,
+            anisotropy: 1,
+
+            bind_groups: HashMap::new()
+        }
+    }
+
+    /// A view of a single layer of a texture array, to render into
+    pub(crate) fn create_layer_view(&self, layer: u32) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Texture Layer View"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..Default::default()
+        })
+    }
+
+    /// # Resize
+    ///
+    /// Recreates a screen texture at a new size, keeping its format and usage.
+    /// The contents are discarded
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        self.texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: self.size,
+            mip_level_count: self.texture.mip_level_count(),
+            sample_count: self.texture.sample_count(),
+            dimension: self.texture.dimension(),
+            format: self.texture.format(),
+            usage: self.texture.usage(),
+            label: Some("Screen Texture"),
             view_formats: &[],
         });
 
         self.view = Handle::new(self.texture.create_view(&wgpu::TextureViewDescriptor::default()));
     }
+
+    pub fn get_format(&self) -> wgpu::TextureFormat {
+        self.texture.format()
+    }
+
+    pub fn get_anisotropy(&self) -> u16 {
+        self.anisotropy
+    }
+
+    /// # Set Anisotropy
+    ///
+    /// Switches to the shared sampler with the given anisotropic filtering level (1 disables it).
+    /// Levels are clamped to 1..=16, which is the range wgpu supports
+    pub fn set_anisotropy(&mut self, samplers: &SamplerCache, anisotropy: u16) {
+        self.anisotropy = anisotropy.clamp(1, 16);
+        self.sampler = samplers.get(SamplerKey::linear(self.anisotropy));
+    }
 }
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::path::Path;
+use image::RgbaImage;
+use crate::managers::resource_handle::ResourceHandle;
+
+/// # Atlas Rect
+///
+/// Where an image ended up inside an atlas, both in pixels and in UV space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+
+    /// Top-left UV of the image
+    pub uv_min: [f32; 2],
+    /// Bottom-right UV of the image
+    pub uv_max: [f32; 2],
+}
+
+/// # Texture Atlas Builder
+///
+/// Collects many small images (sprites, glyphs, imposters) and packs them into a
+/// single texture, so they can all be drawn with one bind group.
+/// Pass the builder to `ResourceManager::create_texture_atlas` to upload it
+pub struct TextureAtlasBuilder {
+    images: Vec<(String, RgbaImage)>,
+
+    // Empty pixels between images, to stop filtering bleeding between neighbours
+    padding: u32,
+    max_size: u32,
+}
+
+impl Default for TextureAtlasBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextureAtlasBuilder {
+    pub fn new() -> Self {
+        Self {
+            images: Vec::new(),
+            padding: 1,
+            max_size: 8192,
+        }
+    }
+
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Largest width/height the atlas may grow to
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Adds an image under the given name. Adding the same name twice replaces the image
+    pub fn add_image(&mut self, name: &str, image: RgbaImage) {
+        self.images.retain(|(existing, _)| existing != name);
+        self.images.push((name.to_string(), image));
+    }
+
+    pub fn add_image_from_file<T: AsRef<Path>>(&mut self, name: &str, path: T) -> anyhow::Result<()> {
+        let image = image::open(path.as_ref())?.to_rgba8();
+        self.add_image(name, image);
+        Ok(())
+    }
+
+    pub fn get_image_count(&self) -> usize {
+        self.images.len()
+    }
+
+    /// # Build
+    ///
+    /// Packs every image into one RGBA image, returning it alongside where each image was placed.
+    /// Images are packed into rows (shelves), tallest first, and the atlas grows in powers of two
+    pub fn build(&self) -> anyhow::Result<(RgbaImage, HashMap<String, AtlasRect>)> {
+        if self.images.is_empty() {
+            anyhow::bail!("Cannot build a texture atlas with no images");
+        }
+
+        // Tallest first keeps the shelves tightly packed
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.images[i].1.height()));
+
+        let widest = self.images.iter().map(|(_, image)| image.width()).max().unwrap() + self.padding * 2;
+        let mut size = widest.next_power_of_two().max(64);
+
+        let placements = loop {
+            if size > self.max_size {
+                anyhow::bail!("Images don't fit in a {}x{} texture atlas", self.max_size, self.max_size);
+            }
+
+            if let Some(placements) = self.pack(&order, size) {
+                break placements;
+            }
+            size *= 2;
+        };
+
+        let mut atlas = RgbaImage::new(size, size);
+        let mut rects = HashMap::new();
+        for (index, (x, y)) in placements {
+            let (name, image) = &self.images[index];
+            image::imageops::replace(&mut atlas, image, x as i64, y as i64);
+
+            rects.insert(name.clone(), AtlasRect {
+                x,
+                y,
+                width: image.width(),
+                height: image.height(),
+                uv_min: [x as f32 / size as f32, y as f32 / size as f32],
+                uv_max: [(x + image.width()) as f32 / size as f32, (y + image.height()) as f32 / size as f32],
+            });
+        }
+
+        Ok((atlas, rects))
+    }
+
+    // Tries to fit every image into a square of the given size, returning each image's position
+    fn pack(&self, order: &[usize], size: u32) -> Option<Vec<(usize, (u32, u32))>> {
+        let mut placements = Vec::with_capacity(order.len());
+
+        let (mut shelf_x, mut shelf_y, mut shelf_height) = (0, 0, 0);
+        for &index in order {
+            let image = &self.images[index].1;
+            let width = image.width() + self.padding * 2;
+            let height = image.height() + self.padding * 2;
+
+            // Start a new shelf when this row is full
+            if shelf_x + width > size {
+                shelf_y += shelf_height;
+                shelf_x = 0;
+                shelf_height = 0;
+            }
+            if shelf_y + height > size {
+                return None;
+            }
+
+            placements.push((index, (shelf_x + self.padding, shelf_y + self.padding)));
+            shelf_x += width;
+            shelf_height = shelf_height.max(height);
+        }
+
+        Some(placements)
+    }
+}
+
+/// # Texture Atlas
+///
+/// A packed texture owned by the resource manager, plus the location of each image inside it
+pub struct TextureAtlas {
+    texture: ResourceHandle,
+    rects: HashMap<String, AtlasRect>,
+    size: u32,
+}
+
+impl TextureAtlas {
+    pub(crate) fn new(texture: ResourceHandle, rects: HashMap<String, AtlasRect>, size: u32) -> Self {
+        Self {
+            texture,
+            rects,
+            size,
+        }
+    }
+
+    /// Handle to the atlas texture, to be assigned to materials like any other texture
+    pub fn get_texture(&self) -> &ResourceHandle {
+        &self.texture
+    }
+
+    pub fn get_rect(&self, name: &str) -> Option<AtlasRect> {
+        self.rects.get(name).copied()
+    }
+
+    pub fn get_rects(&self) -> &HashMap<String, AtlasRect> {
+        &self.rects
+    }
+
+    /// Width and height of the atlas texture, in pixels
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+}
@@ -1,6 +1,4 @@
-use crate::AsBytes;
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Transform{
     pub position: glam::Vec3,
     pub rotation: glam::Quat,
@@ -43,6 +41,62 @@ impl Transform{
     pub fn set_scale(&mut self, scale: glam::Vec3) {
         self.scale = scale;
     }
+
+    /// The direction the transform faces, -Z rotated by the transform (the same as a right-handed camera)
+    pub fn get_forward(&self) -> glam::Vec3 {
+        self.rotation * glam::Vec3::NEG_Z
+    }
+
+    pub fn get_right(&self) -> glam::Vec3 {
+        self.rotation * glam::Vec3::X
+    }
+
+    pub fn get_up(&self) -> glam::Vec3 {
+        self.rotation * glam::Vec3::Y
+    }
+
+    /// # Look At
+    ///
+    /// Rotates the transform so it faces the target (see `get_forward`), keeping its top towards `up`.
+    /// Does nothing if the target is at the transform's position, or straight along `up`
+    pub fn look_at(&mut self, target: glam::Vec3, up: glam::Vec3) {
+        let forward = (target - self.position).normalize_or_zero();
+        let right = forward.cross(up).normalize_or_zero();
+        if forward == glam::Vec3::ZERO || right == glam::Vec3::ZERO {
+            return;
+        }
+
+        let up = right.cross(forward);
+        self.rotation = glam::Quat::from_mat3(&glam::Mat3::from_cols(right, up, -forward));
+    }
+
+    /// Moves the transform by an offset in world space
+    pub fn translate(&mut self, offset: glam::Vec3) {
+        self.position += offset;
+    }
+
+    /// Moves the transform by an offset relative to its rotation, e.g `Vec3::NEG_Z` moves it forward
+    pub fn translate_local(&mut self, offset: glam::Vec3) {
+        self.position += self.rotation * offset;
+    }
+
+    /// Rotates the transform around world space axes
+    pub fn rotate(&mut self, rotation: glam::Quat) {
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+
+    /// Rotates the transform around its own axes
+    pub fn rotate_local(&mut self, rotation: glam::Quat) {
+        self.rotation = (self.rotation * rotation).normalize();
+    }
+
+    /// # Rotate Around
+    ///
+    /// Rotates the transform around a pivot point in world space, moving it as well as turning it
+    pub fn rotate_around(&mut self, pivot: glam::Vec3, rotation: glam::Quat) {
+        self.position = pivot + rotation * (self.position - pivot);
+        self.rotate(rotation);
+    }
 }
 
 impl Into<TransformUniform> for Transform{
@@ -51,6 +105,8 @@ impl Into<TransformUniform> for Transform{
     }
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct TransformUniform{
     pub transform: [[f32; 4]; 4]
 }
@@ -63,14 +119,4 @@ impl TransformUniform{
     }
 }
 
-impl AsBytes for TransformUniform{
-    fn as_bytes(&self) -> &[u8] {
-        unsafe{
-            std::slice::from_raw_parts(
-                (self as *const Self) as *const u8,
-                std::mem::size_of::<Self>()
-            )
-
-        }
-    }
-}
\ No newline at end of file
+crate::impl_as_bytes!(TransformUniform);
@@ -0,0 +1,75 @@
+use crate::types::transform::Transform;
+
+/// # Easing
+///
+/// How a tween moves between its start and target over its duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing{
+    #[default]
+    Linear,
+    // Quadratic
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    // Cubic, for a more pronounced start and stop
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+}
+
+impl Easing{
+    /// Maps the linear progress `t`, from 0 to 1, to the eased progress
+    pub fn apply(&self, t: f32) -> f32{
+        let t = t.clamp(0.0, 1.0);
+        match self{
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 },
+        }
+    }
+}
+
+/// # Transform Tween
+///
+/// Moves a transform from where it was when the tween started to a target,
+/// see `ResourceManager::animate_transform`
+#[derive(Debug, Clone)]
+pub(crate) struct TransformTween{
+    start: Transform,
+    target: Transform,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl TransformTween{
+    pub(crate) fn new(start: Transform, target: Transform, duration: f32, easing: Easing) -> Self{
+        Self{
+            start,
+            target,
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    /// Advances the tween by `delta` seconds, returning the transform at that point
+    pub(crate) fn advance(&mut self, delta: f32) -> Transform{
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        let t = if self.duration > 0.0 { self.easing.apply(self.elapsed / self.duration) } else { 1.0 };
+
+        Transform{
+            position: self.start.position.lerp(self.target.position, t),
+            rotation: self.start.rotation.slerp(self.target.rotation, t),
+            scale: self.start.scale.lerp(self.target.scale, t),
+        }
+    }
+
+    pub(crate) fn is_finished(&self) -> bool{
+        self.elapsed >= self.duration
+    }
+}
@@ -1,7 +1,85 @@
-use crate::utils::buffer::AsBytes;
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+use log::error;
+
+/// The most UV sets a `MultiUvVertex` holds
+pub const MAX_UV_SETS: usize = 4;
+
+/// # UV Set Location
+///
+/// The shader location of a UV set. The first is at 2, and the rest start at 4,
+/// after the vertex colour at 3
+pub const fn uv_set_location(set: u32) -> u32 {
+    if set == 0 { 2 } else { set + 3 }
+}
+
+/// The shader location of a `ColoredVertex`'s colour
+pub const COLOR_LOCATION: u32 = 3;
+
+/// # Vertex Layout Builder
+///
+/// Builds a vertex buffer layout from attributes packed one after another, working out their offsets and
+/// the stride from each format's size (e.g 4 bytes for `Float16x2`, `Unorm8x4` and `Snorm16x2`, 8 for `Float16x4`).
+/// Useful for packed layouts that have no Rust vertex type
+#[derive(Debug, Clone, Default)]
+pub struct VertexLayoutBuilder {
+    attributes: Vec<wgpu::VertexAttribute>,
+    size: wgpu::BufferAddress,
+}
+
+impl VertexLayoutBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an attribute after the previous one
+    pub fn with_attribute(mut self, shader_location: u32, format: wgpu::VertexFormat) -> Self {
+        self.attributes.push(wgpu::VertexAttribute { offset: self.size, shader_location, format });
+        self.size += format.size();
+        self
+    }
+
+    /// Reads an existing attribute at another location too, e.g so the only UV set doubles as the second
+    pub fn with_alias(mut self, shader_location: u32, existing_location: u32) -> Self {
+        let Some(existing) = self.attributes.iter().find(|attribute| attribute.shader_location == existing_location).copied() else {
+            error!("Can't alias vertex location {}, as nothing is at it", existing_location);
+            panic!("Can't alias vertex location {}, as nothing is at it", existing_location)
+        };
+        self.attributes.push(wgpu::VertexAttribute { shader_location, ..existing });
+        self
+    }
+
+    /// The size of each vertex, padded to the 4 bytes vertex buffers need
+    pub fn get_stride(&self) -> wgpu::BufferAddress {
+        self.size.next_multiple_of(wgpu::VERTEX_STRIDE_ALIGNMENT)
+    }
+
+    pub fn build(self) -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.get_stride(),
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: intern_attributes(self.attributes),
+        }
+    }
+}
+
+// Vertex buffer layouts borrow their attributes for 'static, so each distinct
+// list of attributes is leaked once and shared by every layout using it
+fn intern_attributes(attributes: Vec<wgpu::VertexAttribute>) -> &'static [wgpu::VertexAttribute] {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static [wgpu::VertexAttribute]>>> = OnceLock::new();
+
+    let mut interned = INTERNED.get_or_init(|| Mutex::new(HashSet::new())).lock().unwrap();
+    if let Some(existing) = interned.get(attributes.as_slice()) {
+        return existing;
+    }
+
+    let leaked: &'static [wgpu::VertexAttribute] = Box::leak(attributes.into_boxed_slice());
+    interned.insert(leaked);
+    leaked
+}
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
     pub normal: [f32; 3],
@@ -30,31 +108,231 @@ impl Vertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                // The only UV set doubles as the second (`uv_set_location(1)`), so shaders sampling `uv1` still work
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
             ],
         }
     }
 }
 
 
-// Impl to &[u8] for Vertex
-impl AsBytes for Vertex {
-    fn as_bytes(&self) -> &[u8] {
-        unsafe {
-            std::slice::from_raw_parts(
-                (self as *const Vertex) as *const u8,
-                std::mem::size_of::<Vertex>(),
-            )
+crate::impl_as_bytes!(Vertex);
+
+/// # Colored Vertex
+///
+/// A standard vertex with an extra per-vertex colour at location 3, used by meshes
+/// imported with vertex colours (e.g PLY scans)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ColoredVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl ColoredVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ColoredVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // The only UV set doubles as the second (`uv_set_location(1)`), so shaders sampling `uv1` still work
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: COLOR_LOCATION,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
         }
     }
 }
-// Impl to &[u8] for Vec<Vertex>
-impl AsBytes for Vec<Vertex> {
-    fn as_bytes(&self) -> &[u8] {
-        unsafe {
-            std::slice::from_raw_parts(
-                self.as_ptr() as *const u8,
-                self.len() * std::mem::size_of::<Vertex>(),
-            )
+
+crate::impl_as_bytes!(ColoredVertex);
+
+/// # Multi UV Vertex
+///
+/// A standard vertex with up to `MAX_UV_SETS` UV sets, used by meshes imported with more
+/// than one (e.g lightmapped glTF). Set `n` is at `uv_set_location(n)`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MultiUvVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [[f32; 2]; MAX_UV_SETS],
+}
+
+impl MultiUvVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2 + MAX_UV_SETS] = [
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Float32x3,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                shader_location: 1,
+                format: wgpu::VertexFormat::Float32x3,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                shader_location: uv_set_location(0),
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                shader_location: uv_set_location(1),
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 10]>() as wgpu::BufferAddress,
+                shader_location: uv_set_location(2),
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                shader_location: uv_set_location(3),
+                format: wgpu::VertexFormat::Float32x2,
+            },
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<MultiUvVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
         }
     }
-}
\ No newline at end of file
+}
+
+crate::impl_as_bytes!(MultiUvVertex);
+
+/// The shader location of a `SkinnedVertex`'s joint indices, after the UV sets
+pub const JOINTS_LOCATION: u32 = 8;
+/// The shader location of a `SkinnedVertex`'s joint weights
+pub const WEIGHTS_LOCATION: u32 = 9;
+
+/// # Skinned Vertex
+///
+/// A standard vertex influenced by up to four joints, as drawn by `SKINNED_SHADER`. Weights
+/// are normalised by the shader, and a vertex with no weight follows the model alone
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkinnedVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
+}
+
+impl SkinnedVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SkinnedVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: JOINTS_LOCATION,
+                    format: wgpu::VertexFormat::Uint32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: WEIGHTS_LOCATION,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+crate::impl_as_bytes!(SkinnedVertex);
+
+/// # Quantized Vertex
+///
+/// A standard vertex in half the size: the position as 16-bit values across the mesh's bounds (decoded with a
+/// `QuantizationUniform`), the normal octahedral-encoded in two 16-bit values, and the UVs as half floats.
+/// See `QUANTIZED_VERTEX_WGSL` for reading it in a shader
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct QuantizedVertex {
+    pub position: [u16; 4],
+    pub normal: [i16; 2],
+    pub tex_coords: [u16; 2],
+}
+
+impl QuantizedVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = [
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Unorm16x4,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[u16; 4]>() as wgpu::BufferAddress,
+                shader_location: 1,
+                format: wgpu::VertexFormat::Snorm16x2,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[u16; 6]>() as wgpu::BufferAddress,
+                shader_location: 2,
+                format: wgpu::VertexFormat::Float16x2,
+            },
+            // As with `Vertex`, the only UV set doubles as the second
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[u16; 6]>() as wgpu::BufferAddress,
+                shader_location: uv_set_location(1),
+                format: wgpu::VertexFormat::Float16x2,
+            },
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<QuantizedVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+crate::impl_as_bytes!(QuantizedVertex);
@@ -1,2 +1,3 @@
 pub mod observable_data;
+pub mod storage_buffer;
 pub mod uniform_buffer;
\ No newline at end of file
@@ -0,0 +1,89 @@
+use crate::utils::buffer::AsBytes;
+use crate::utils::handle::Handle;
+
+/// Frames the CPU can get ahead of the GPU, and so the number of copies kept of data rewritten every frame
+pub(crate) const FRAMES_IN_FLIGHT: usize = 3;
+
+/// # Storage Buffer
+///
+/// A read-only storage buffer that grows to fit the data written to it. Growing replaces
+/// the underlying buffer, so bind groups referencing it have to be recreated.
+///
+/// A versioned buffer keeps a copy per frame in flight and writes each frame to the next one,
+/// so a write never lands in a buffer the GPU may still be reading
+pub struct StorageBuffer {
+    buffers: Vec<wgpu::Buffer>,
+    // The copy written most recently, which draws should read
+    current: usize,
+    // Size of each allocation in bytes
+    capacity: usize,
+    label: String,
+    device: Handle<wgpu::Device>,
+}
+
+impl StorageBuffer {
+    /// Creates a buffer with a copy per version, rotated through on every write. One version makes a plain buffer
+    pub(crate) fn new(device: Handle<wgpu::Device>, capacity: usize, label: &str, versions: usize) -> Self {
+        // Empty storage bindings aren't allowed, so there's always room for something
+        let capacity = capacity.max(256);
+        let buffers = Self::create_buffers(&device, capacity, label, versions.max(1));
+
+        Self {
+            buffers,
+            current: 0,
+            capacity,
+            label: label.to_string(),
+            device,
+        }
+    }
+
+    fn create_buffers(device: &wgpu::Device, capacity: usize, label: &str, versions: usize) -> Vec<wgpu::Buffer> {
+        (0..versions).map(|_| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: capacity as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })).collect()
+    }
+
+    /// # Write
+    ///
+    /// Writes the data to the start of the next copy, doubling the allocation until it fits.
+    /// Returns true if the buffers were replaced
+    pub(crate) fn write<T: AsBytes>(&mut self, queue: &wgpu::Queue, data: &T) -> bool {
+        let bytes = data.as_bytes();
+        let mut grown = false;
+        if bytes.len() > self.capacity {
+            while self.capacity < bytes.len() {
+                self.capacity *= 2;
+            }
+            self.buffers = Self::create_buffers(&self.device, self.capacity, &self.label, self.buffers.len());
+            grown = true;
+        }
+
+        if !bytes.is_empty() {
+            self.current = (self.current + 1) % self.buffers.len();
+            queue.write_buffer(&self.buffers[self.current], 0, bytes);
+        }
+        grown
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The copy written most recently
+    pub(crate) fn get_buffer(&self) -> &wgpu::Buffer {
+        &self.buffers[self.current]
+    }
+
+    /// Every copy, in version order, for building a bind group per version
+    pub(crate) fn get_buffers(&self) -> &[wgpu::Buffer] {
+        &self.buffers
+    }
+
+    /// Index of the copy written most recently, into `get_buffers`
+    pub(crate) fn get_version(&self) -> usize {
+        self.current
+    }
+}
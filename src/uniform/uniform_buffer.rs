@@ -5,6 +5,8 @@ use crate::utils::handle::Handle;
 
 pub struct UniformBuffer {
     buffer: wgpu::Buffer,
+    // Size of the allocation in bytes. Data written to the buffer must match this exactly
+    size: usize,
     data: ObservableData<Box<dyn AsBytes>>,
     device: Handle<wgpu::Device>,
 }
@@ -21,6 +23,7 @@ impl UniformBuffer {
 
         Self {
             buffer,
+            size: initial_data.as_bytes().len(),
             data: ObservableData::new(Box::new(initial_data)),
             device: device.clone(),
         }
@@ -33,14 +36,35 @@ impl UniformBuffer {
         }
     }
 
-    pub fn set_data<T: AsBytes + 'static>(&mut self, new_data: T) {
+    /// # Set Data
+    ///
+    /// Sets the data to be written to the buffer on the next update
+    ///
+    /// Returns an error if the data is a different size to the buffer's allocation,
+    /// as writing it would either overrun the buffer or leave stale bytes behind
+    pub fn set_data<T: AsBytes + 'static>(&mut self, new_data: T) -> anyhow::Result<()> {
+        let new_size = new_data.as_bytes().len();
+        if new_size != self.size {
+            anyhow::bail!(
+                "Uniform buffer size mismatch: buffer was created with {} bytes, but the new data is {} bytes. \
+                Create a new uniform buffer if the data layout has changed",
+                self.size, new_size
+            );
+        }
+
         self.data.set(Box::new(new_data));
+
+        Ok(())
     }
 
     pub(crate) fn get_data(&self) -> &Box<dyn AsBytes> {
         &self.data.get()
     }
 
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
     pub(crate) fn get_buffer(&self) -> &wgpu::Buffer {
         &self.buffer
     }
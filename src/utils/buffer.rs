@@ -1,98 +1,48 @@
-use log::info;
 // Helpful buffer utilities
-use wgpu::util::DeviceExt;
+use wgpu::util::{DeviceExt, RenderEncoder};
+use crate::debug::{debug_log, Subsystem};
 
 #[derive(Debug, Clone, Copy)]
 pub enum BufferType{
     Vertex,
     Index,
     Instance,
-    Uniform,
     Storage,
+    // A vertex buffer compute shaders write into, e.g by GPU skinning
+    StorageVertex,
 }
 
 pub struct Buffer{
     pub buffer: wgpu::Buffer,
     pub size: usize,
     pub buffer_type: BufferType,
-    
-    /* 
-     * Bind group layout and bind group
-     * Used for uniform buffers
-     */
-    bind_group_layout: Option<wgpu::BindGroupLayout>,
-    bind_group: Option<wgpu::BindGroup>,
 }
 
 impl Buffer{
     pub fn create_buffer_from_bytes(device: &wgpu::Device, data: &[u8], buffer_type: BufferType) -> Self{
-        info!("Creating buffer from bytes: {:?}", buffer_type);
-        info!("Data size: {:?}", data.len());
+        debug_log!(Subsystem::Resources, "Creating buffer from bytes: {:?}", buffer_type);
+        debug_log!(Subsystem::Resources, "Data size: {:?}", data.len());
 
         let buffer = device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor{
                 label: Some("Buffer"),
                 contents: data,
                 usage: match buffer_type{
-                    BufferType::Vertex => wgpu::BufferUsages::VERTEX,
-                    BufferType::Index => wgpu::BufferUsages::INDEX,
+                    BufferType::Vertex => wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                    BufferType::Index => wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
                     BufferType::Instance => wgpu::BufferUsages::VERTEX,
-                    BufferType::Uniform => wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
                     BufferType::Storage => wgpu::BufferUsages::STORAGE,
+                    BufferType::StorageVertex => wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
                 },
             }
         );
 
-        info!("Buffer created");
-        
-        let bind_group_layout = match buffer_type{
-            BufferType::Uniform => Some(device.create_bind_group_layout(
-                &wgpu::BindGroupLayoutDescriptor{
-                    label: Some("Uniform Buffer Bind Group Layout"),
-                    entries: &[
-                        wgpu::BindGroupLayoutEntry{
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }
-                    ],
-                }
-            )),
-            _ => None,
-        };
+        debug_log!(Subsystem::Resources, "Buffer created");
         
-        let bind_group = match buffer_type{
-            BufferType::Uniform => Some(device.create_bind_group(
-                &wgpu::BindGroupDescriptor{
-                    label: Some("Uniform Buffer Bind Group"),
-                    layout: bind_group_layout.as_ref().unwrap(),
-                    entries: &[
-                        wgpu::BindGroupEntry{
-                            binding: 0,
-                            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding{
-                                buffer: &buffer,
-                                offset: 0,
-                                size: None,
-                            }),
-                        }
-                    ],
-                }
-            )),
-            _ => None,
-        };
-
         Self{
             buffer,
             size: data.len(),
             buffer_type,
-            
-            bind_group_layout,
-            bind_group,
         }
     }
 
@@ -102,17 +52,13 @@ impl Buffer{
 }
 
 impl Buffer{
-    pub fn bind_vertex_buffer<'a>(&'a self, index: u32, render_pass: &mut wgpu::RenderPass<'a>) {
+    pub fn bind_vertex_buffer<'a>(&'a self, index: u32, render_pass: &mut impl RenderEncoder<'a>) {
         render_pass.set_vertex_buffer(index, self.buffer.slice(..));
     }
 
-    pub fn bind_index_buffer<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+    pub fn bind_index_buffer<'a>(&'a self, render_pass: &mut impl RenderEncoder<'a>) {
         render_pass.set_index_buffer(self.buffer.slice(..), wgpu::IndexFormat::Uint32);
     }
-    
-    pub fn bind_uniform_buffer<'a>(&'a self, index: u32, render_pass: &mut wgpu::RenderPass<'a>) {
-        render_pass.set_bind_group(index, self.bind_group.as_ref().unwrap(), &[]);
-    }
 }
 
 impl Buffer{
@@ -120,16 +66,6 @@ impl Buffer{
         queue.write_buffer(&self.buffer, 0, data);
     }
 
-    pub fn copy_buffer(&self, device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer){
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor{
-            label: Some("Buffer Copy Encoder"),
-        });
-
-        encoder.copy_buffer_to_buffer(&buffer, 0, &self.buffer, 0, self.size as wgpu::BufferAddress);
-
-        queue.submit(std::iter::once(encoder.finish()));
-    }
-
     pub fn update_from_type<T: AsBytes>(&self, queue: &wgpu::Queue, data: &T){
         self.update(queue, data.as_bytes());
     }
@@ -153,7 +89,7 @@ impl Buffer{
 
 impl Drop for Buffer{
     fn drop(&mut self){
-        info!("Dropping buffer");
+        debug_log!(Subsystem::Resources, "Dropping buffer");
     }
 }
 
@@ -161,24 +97,77 @@ impl Drop for Buffer{
 /// Trait for converting a type to a byte slice.
 ///
 /// Must be implemented for types that are used in buffers.
+///
+/// Prefer deriving `bytemuck::Pod` and using [`impl_as_bytes!`](crate::impl_as_bytes)
+/// over implementing this by hand. `Pod` statically checks the type is `#[repr(C)]`
+/// and has no padding, so the byte view is always safe and matches the declared layout.
 pub trait AsBytes {
     fn as_bytes(&self) -> &[u8];
+
+    /// The name and byte offset of each member, for types that list them with `impl_as_bytes!`.
+    /// Uniforms with them are checked member by member against the shader's struct, rather than just by size
+    fn get_member_offsets(&self) -> &'static [(&'static str, usize)]{
+        &[]
+    }
 }
 
-impl AsBytes for &[u8]{
+impl<T: bytemuck::Pod> AsBytes for &[T]{
     fn as_bytes(&self) -> &[u8]{
-        *self
+        bytemuck::cast_slice(self)
     }
 }
 
-impl AsBytes for &[u32] {
-    fn as_bytes(&self) -> &[u8] {
-        // Cast to a byte slice
-        unsafe {
-            std::slice::from_raw_parts(
-                self.as_ptr() as *const u8,
-                self.len() * std::mem::size_of::<u32>(),
-            )
-        }
+impl<T: bytemuck::Pod> AsBytes for Vec<T>{
+    fn as_bytes(&self) -> &[u8]{
+        bytemuck::cast_slice(self)
     }
-}
\ No newline at end of file
+}
+
+/// # Impl AsBytes
+///
+/// Implements [`AsBytes`] for one or more types that implement `bytemuck::Pod`. The derives can go through
+/// the re-exported bytemuck, so the user doesn't need to depend on it
+///
+/// ```ignore
+/// use minirenderer::bytemuck;
+///
+/// #[repr(C)]
+/// #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+/// #[bytemuck(crate = "minirenderer::bytemuck")]
+/// struct CameraUniform {
+///     view: [[f32; 4]; 4],
+///     projection: [[f32; 4]; 4],
+/// }
+///
+/// minirenderer::impl_as_bytes!(CameraUniform);
+/// ```
+///
+/// Listing the members the shader's struct declares, by the names it gives them, also checks each one sits
+/// at the offset the shader expects when the uniform is assigned to a material. Padding is left out
+///
+/// ```ignore
+/// minirenderer::impl_as_bytes!(CameraUniform { view, projection });
+/// ```
+#[macro_export]
+macro_rules! impl_as_bytes {
+    ($ty:ty { $($member:ident),+ $(,)? }) => {
+        impl $crate::AsBytes for $ty {
+            fn as_bytes(&self) -> &[u8] {
+                $crate::bytemuck::bytes_of(self)
+            }
+
+            fn get_member_offsets(&self) -> &'static [(&'static str, usize)] {
+                &[$((stringify!($member), ::core::mem::offset_of!($ty, $member))),+]
+            }
+        }
+    };
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl $crate::AsBytes for $ty {
+                fn as_bytes(&self) -> &[u8] {
+                    $crate::bytemuck::bytes_of(self)
+                }
+            }
+        )+
+    };
+}
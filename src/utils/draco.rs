@@ -0,0 +1,122 @@
+//! Decoding of glTF primitives compressed with `KHR_draco_mesh_compression`.
+//!
+//! The compressed bitstream lives in the buffer view named by the extension, and the
+//! extension's `attributes` map glTF semantics to Draco attribute ids. Decoding itself is
+//! done by the Draco C++ library through `draco_decoder`, which needs cmake and a C++
+//! compiler to build
+use anyhow::{anyhow, bail, Context};
+use draco_decoder::{AttributeDataType, MeshAttribute, MeshDecodeResult};
+
+pub(crate) const DRACO_EXTENSION: &str = "KHR_draco_mesh_compression";
+
+/// # Draco Primitive
+///
+/// The decoded attributes of one compressed primitive, attributes it doesn't have are `None`
+pub(crate) struct DracoPrimitive {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub tex_coords: Option<Vec<[f32; 2]>>,
+    pub indices: Vec<u32>,
+}
+
+/// Decodes a primitive carrying the Draco extension, using the already loaded glTF buffers
+pub(crate) fn decode_primitive(document: &gltf::Document, primitive: &gltf::Primitive, buffers: &[gltf::buffer::Data]) -> anyhow::Result<DracoPrimitive> {
+    let extension = primitive.extension_value(DRACO_EXTENSION)
+        .ok_or_else(|| anyhow!("Primitive has no {} extension", DRACO_EXTENSION))?;
+
+    let view_index = extension.get("bufferView").and_then(|view| view.as_u64())
+        .ok_or_else(|| anyhow!("{} extension has no bufferView", DRACO_EXTENSION))? as usize;
+    let view = document.views().nth(view_index)
+        .ok_or_else(|| anyhow!("Draco buffer view {} doesn't exist", view_index))?;
+    let buffer = buffers.get(view.buffer().index())
+        .ok_or_else(|| anyhow!("Draco buffer view points at a missing buffer"))?;
+    let bytes = buffer.get(view.offset()..view.offset() + view.length())
+        .ok_or_else(|| anyhow!("Draco buffer view is out of range"))?;
+
+    let decoded = draco_decoder::decode_mesh_with_config_sync(bytes)
+        .ok_or_else(|| anyhow!("Failed to decode Draco mesh"))?;
+
+    let attribute_id = |semantic: &str| extension.get("attributes")
+        .and_then(|attributes| attributes.get(semantic))
+        .and_then(|id| id.as_u64())
+        .map(|id| id as u32);
+
+    // The glTF accessor says whether integer attributes are normalised
+    let normalized = |semantic: &gltf::Semantic| primitive.get(semantic)
+        .map(|accessor| accessor.normalized())
+        .unwrap_or(false);
+
+    let positions = attribute_id("POSITION")
+        .map(|id| read_attribute::<3>(&decoded, id, normalized(&gltf::Semantic::Positions)))
+        .ok_or_else(|| anyhow!("Draco primitive has no POSITION attribute"))??;
+    let normals = attribute_id("NORMAL")
+        .map(|id| read_attribute::<3>(&decoded, id, normalized(&gltf::Semantic::Normals)))
+        .transpose()?;
+    let tex_coords = attribute_id("TEXCOORD_0")
+        .map(|id| read_attribute::<2>(&decoded, id, normalized(&gltf::Semantic::TexCoords(0))))
+        .transpose()?;
+
+    Ok(DracoPrimitive {
+        positions,
+        normals,
+        tex_coords,
+        indices: read_indices(&decoded)?,
+    })
+}
+
+// Indices sit at the front of the decoded buffer, as u16 when they fit
+fn read_indices(decoded: &MeshDecodeResult) -> anyhow::Result<Vec<u32>> {
+    let count = decoded.config.index_count() as usize;
+    let length = decoded.config.index_length() as usize;
+    let bytes = decoded.data.get(..length).context("Draco index data is truncated")?;
+
+    Ok(if length == count * 2 {
+        bytes.chunks_exact(2).map(|index| u16::from_le_bytes([index[0], index[1]]) as u32).collect()
+    } else {
+        bytes.chunks_exact(4).map(|index| u32::from_le_bytes(index.try_into().unwrap())).collect()
+    })
+}
+
+fn read_attribute<const N: usize>(decoded: &MeshDecodeResult, unique_id: u32, normalized: bool) -> anyhow::Result<Vec<[f32; N]>> {
+    let attribute: MeshAttribute = decoded.config.attributes().into_iter()
+        .find(|attribute| attribute.unique_id() == unique_id)
+        .ok_or_else(|| anyhow!("Draco mesh has no attribute with id {}", unique_id))?;
+
+    let dim = attribute.dim() as usize;
+    if dim < N {
+        bail!("Draco attribute {} has {} components, expected {}", unique_id, dim, N);
+    }
+
+    let start = attribute.offset() as usize;
+    let bytes = decoded.data.get(start..start + attribute.lenght() as usize)
+        .context("Draco attribute data is truncated")?;
+
+    let data_type = attribute.data_type();
+    let size = data_type.size_in_bytes();
+    let values: Vec<f32> = bytes.chunks_exact(size)
+        .map(|value| read_component(data_type, value, normalized))
+        .collect();
+
+    Ok(values.chunks_exact(dim)
+        .map(|components| std::array::from_fn(|i| components[i]))
+        .collect())
+}
+
+fn read_component(data_type: AttributeDataType, bytes: &[u8], normalized: bool) -> f32 {
+    // Normalised integers map onto 0 to 1, or -1 to 1 when signed
+    let (value, scale) = match data_type {
+        AttributeDataType::Int8 => (bytes[0] as i8 as f32, i8::MAX as f32),
+        AttributeDataType::UInt8 => (bytes[0] as f32, u8::MAX as f32),
+        AttributeDataType::Int16 => (i16::from_le_bytes([bytes[0], bytes[1]]) as f32, i16::MAX as f32),
+        AttributeDataType::UInt16 => (u16::from_le_bytes([bytes[0], bytes[1]]) as f32, u16::MAX as f32),
+        AttributeDataType::Int32 => (i32::from_le_bytes(bytes.try_into().unwrap()) as f32, i32::MAX as f32),
+        AttributeDataType::UInt32 => (u32::from_le_bytes(bytes.try_into().unwrap()) as f32, u32::MAX as f32),
+        AttributeDataType::Float32 => return f32::from_le_bytes(bytes.try_into().unwrap()),
+    };
+
+    if normalized {
+        (value / scale).max(-1.0)
+    } else {
+        value
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use glam::Vec3;
+
+/// # Smooth Normals
+///
+/// Generates per-vertex normals for an indexed triangle list by averaging the normals
+/// of the faces around each vertex, weighted by face area
+pub(crate) fn smooth_normals(positions: &[[f32; 3]], indices: &[u32]) -> Vec<[f32; 3]> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| Vec3::from(positions[i as usize]));
+        // Not normalised, so larger faces contribute more
+        let normal = (b - a).cross(c - a);
+
+        for &index in triangle {
+            normals[index as usize] += normal;
+        }
+    }
+
+    normals.into_iter().map(|normal| normal.normalize_or_zero().into()).collect()
+}
+
+/// # Creased Normals
+///
+/// Generates normals for an unindexed triangle soup (e.g STL), where every triangle has its own
+/// three corners. Corners sharing a position are smoothed together, unless the angle between
+/// their faces is larger than `crease_angle` (radians), which keeps hard edges on CAD models sharp
+pub(crate) fn creased_normals(positions: &[[f32; 3]], crease_angle: f32) -> Vec<[f32; 3]> {
+    let face_normals: Vec<Vec3> = positions.chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(Vec3::from);
+            (b - a).cross(c - a)
+        })
+        .collect();
+
+    // Find every corner that shares each position
+    let mut corners_at: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    for (corner, position) in positions.iter().enumerate() {
+        corners_at.entry(position.map(f32::to_bits)).or_default().push(corner);
+    }
+
+    let cos_crease = crease_angle.cos();
+    positions.iter().enumerate().map(|(corner, position)| {
+        let face_normal = face_normals[corner / 3].normalize_or_zero();
+
+        let normal: Vec3 = corners_at[&position.map(f32::to_bits)].iter()
+            .map(|&other| face_normals[other / 3])
+            .filter(|other_normal| other_normal.normalize_or_zero().dot(face_normal) >= cos_crease)
+            .sum();
+
+        normal.normalize_or(face_normal).into()
+    }).collect()
+}
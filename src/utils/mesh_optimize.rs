@@ -0,0 +1,229 @@
+use glam::Vec3;
+
+// Tom Forsyth's "Linear-Speed Vertex Cache Optimisation" scoring, tuned for a 32 entry LRU cache
+const CACHE_SIZE: usize = 32;
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+// The post-transform cache size overdraw clusters and the miss ratio are measured with,
+// roughly what current GPUs hold
+const FIFO_CACHE_SIZE: usize = 16;
+
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: u32) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+
+    let cache_score = match cache_position {
+        // The last triangle's vertices get a fixed score, so the next one doesn't just reuse its edge
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+        None => 0.0,
+    };
+
+    // Vertices with few triangles left are finished off first, so they don't linger
+    cache_score + VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+/// Reorders triangles so consecutive ones share vertices, letting the GPU reuse
+/// vertex shader results from its post-transform cache
+pub(crate) fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return indices.to_vec();
+    }
+
+    // The triangles using each vertex, packed as offsets into one list
+    let mut remaining = vec![0u32; vertex_count];
+    for &index in indices.iter().take(triangle_count * 3) {
+        remaining[index as usize] += 1;
+    }
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for vertex in 0..vertex_count {
+        offsets[vertex + 1] = offsets[vertex] + remaining[vertex] as usize;
+    }
+    let mut vertex_triangles = vec![0u32; offsets[vertex_count]];
+    let mut filled = offsets.clone();
+    for triangle in 0..triangle_count {
+        for corner in 0..3 {
+            let vertex = indices[triangle * 3 + corner] as usize;
+            vertex_triangles[filled[vertex]] = triangle as u32;
+            filled[vertex] += 1;
+        }
+    }
+
+    let mut cache_positions: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores: Vec<f32> = (0..vertex_count).map(|vertex| vertex_score(None, remaining[vertex])).collect();
+    let mut triangle_scores: Vec<f32> = (0..triangle_count)
+        .map(|triangle| (0..3).map(|corner| vertex_scores[indices[triangle * 3 + corner] as usize]).sum())
+        .collect();
+    let mut emitted = vec![false; triangle_count];
+
+    let mut output = Vec::with_capacity(triangle_count * 3);
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    // Where to resume looking for a triangle when nothing in the cache is left to draw
+    let mut scan_start = 0;
+
+    let mut best = (0..triangle_count).max_by(|a, b| triangle_scores[*a].total_cmp(&triangle_scores[*b]));
+    while let Some(triangle) = best {
+        emitted[triangle] = true;
+        let corners = [indices[triangle * 3], indices[triangle * 3 + 1], indices[triangle * 3 + 2]];
+        output.extend_from_slice(&corners);
+
+        for &vertex in corners.iter() {
+            remaining[vertex as usize] -= 1;
+        }
+
+        // The triangle's vertices move to the front of the cache, pushing the oldest out of the back
+        let mut new_cache: Vec<u32> = corners.to_vec();
+        new_cache.extend(cache.iter().filter(|vertex| !corners.contains(vertex)));
+        let evicted: Vec<u32> = if new_cache.len() > CACHE_SIZE { new_cache.split_off(CACHE_SIZE) } else { Vec::new() };
+        cache = new_cache;
+
+        for &vertex in evicted.iter() {
+            cache_positions[vertex as usize] = None;
+        }
+        for (position, &vertex) in cache.iter().enumerate() {
+            cache_positions[vertex as usize] = Some(position);
+        }
+
+        // Only triangles touching a vertex whose score changed need rescoring, and the best of
+        // those is the next one drawn
+        best = None;
+        let mut best_score = f32::MIN;
+        for &vertex in cache.iter().chain(evicted.iter()) {
+            let vertex = vertex as usize;
+            let score = vertex_score(cache_positions[vertex], remaining[vertex]);
+            let delta = score - vertex_scores[vertex];
+            vertex_scores[vertex] = score;
+
+            for &adjacent in vertex_triangles[offsets[vertex]..offsets[vertex + 1]].iter() {
+                let adjacent = adjacent as usize;
+                if emitted[adjacent] {
+                    continue;
+                }
+                triangle_scores[adjacent] += delta;
+                if cache_positions[vertex].is_some() && triangle_scores[adjacent] > best_score {
+                    best_score = triangle_scores[adjacent];
+                    best = Some(adjacent);
+                }
+            }
+        }
+
+        if best.is_none() {
+            while scan_start < triangle_count && emitted[scan_start] {
+                scan_start += 1;
+            }
+            best = (scan_start < triangle_count).then_some(scan_start);
+        }
+    }
+
+    // Any trailing indices that don't make up a whole triangle are kept as they were
+    output.extend_from_slice(&indices[triangle_count * 3..]);
+    output
+}
+
+// Simulates a FIFO post-transform cache, returning for each triangle how many of its vertices missed
+fn fifo_cache_misses(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let mut timestamps = vec![0usize; vertex_count];
+    let mut time = FIFO_CACHE_SIZE + 1;
+
+    indices.chunks_exact(3).map(|triangle| {
+        let mut misses = 0;
+        for &vertex in triangle.iter() {
+            let vertex = vertex as usize;
+            if time - timestamps[vertex] > FIFO_CACHE_SIZE {
+                timestamps[vertex] = time;
+                time += 1;
+                misses += 1;
+            }
+        }
+        misses
+    }).collect()
+}
+
+/// The average number of vertex shader runs per triangle, from 0.5 at best to 3 at worst
+pub(crate) fn average_cache_miss_ratio(indices: &[u32], vertex_count: usize) -> f32 {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return 0.0;
+    }
+
+    fifo_cache_misses(indices, vertex_count).iter().sum::<u32>() as f32 / triangle_count as f32
+}
+
+/// Reorders cache-optimised triangles so the ones facing away from the mesh's centre are drawn first,
+/// as they tend to hide the rest. Triangles are moved in clusters split wherever the vertex cache
+/// starts over, so the cache efficiency is kept
+pub(crate) fn optimize_overdraw(indices: &[u32], positions: &[Vec3]) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return indices.to_vec();
+    }
+
+    let misses = fifo_cache_misses(indices, positions.len());
+    let mut cluster_starts: Vec<usize> = (0..triangle_count).filter(|&triangle| triangle == 0 || misses[triangle] == 3).collect();
+    cluster_starts.push(triangle_count);
+
+    // Area-weighted centroid and normal of each cluster
+    let clusters: Vec<(std::ops::Range<usize>, Vec3, Vec3, f32)> = cluster_starts.windows(2).map(|range| {
+        let mut centroid = Vec3::ZERO;
+        let mut normal = Vec3::ZERO;
+        let mut area = 0.0;
+        for triangle in range[0]..range[1] {
+            let [a, b, c] = [0, 1, 2].map(|corner| positions[indices[triangle * 3 + corner] as usize]);
+            let cross = (b - a).cross(c - a);
+            let triangle_area = cross.length();
+            centroid += (a + b + c) / 3.0 * triangle_area;
+            normal += cross;
+            area += triangle_area;
+        }
+        if area > 0.0 {
+            centroid /= area;
+        }
+        (range[0]..range[1], centroid, normal.normalize_or_zero(), area)
+    }).collect();
+
+    let total_area: f32 = clusters.iter().map(|cluster| cluster.3).sum();
+    let mesh_centroid = if total_area > 0.0 {
+        clusters.iter().map(|cluster| cluster.1 * cluster.3).sum::<Vec3>() / total_area
+    } else {
+        Vec3::ZERO
+    };
+
+    let mut order: Vec<(usize, f32)> = clusters.iter().enumerate()
+        .map(|(cluster, (_, centroid, normal, _))| (cluster, (*centroid - mesh_centroid).dot(*normal)))
+        .collect();
+    order.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut output = Vec::with_capacity(indices.len());
+    for (cluster, _) in order {
+        let range = &clusters[cluster].0;
+        output.extend_from_slice(&indices[range.start * 3..range.end * 3]);
+    }
+    output.extend_from_slice(&indices[triangle_count * 3..]);
+    output
+}
+
+/// Renumbers vertices in the order the indices first use them, so vertex fetches walk
+/// through memory. Returns the old index of each new vertex; unused vertices are dropped
+pub(crate) fn optimize_vertex_fetch(indices: &mut [u32], vertex_count: usize) -> Vec<u32> {
+    let mut remap = vec![u32::MAX; vertex_count];
+    let mut order = Vec::with_capacity(vertex_count);
+
+    for index in indices.iter_mut() {
+        let old = *index as usize;
+        if remap[old] == u32::MAX {
+            remap[old] = order.len() as u32;
+            order.push(old as u32);
+        }
+        *index = remap[old];
+    }
+
+    order
+}
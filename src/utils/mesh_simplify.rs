@@ -0,0 +1,233 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use glam::DVec3;
+use crate::types::vertex::Vertex;
+
+// Symmetric 4x4 error quadric, stored as its 10 unique coefficients
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(normal: DVec3, d: f64) -> Self {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        Self([
+            a * a, a * b, a * c, a * d,
+            b * b, b * c, b * d,
+            c * c, c * d,
+            d * d,
+        ])
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a += b;
+        }
+    }
+
+    // Sum of squared distances from the point to every plane in the quadric
+    fn error(&self, p: DVec3) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (p.x, p.y, p.z);
+
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+// A candidate edge collapse, moving `from` onto `to`
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+    // Versions of both vertices when this was queued, so stale entries can be skipped
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // Reversed, so the binary heap pops the cheapest collapse first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// # Simplify
+///
+/// Reduces a triangle list towards `target_index_count` indices using quadric error metric
+/// edge collapses, stopping early if the next collapse would move the surface by more than
+/// `max_error` (in mesh units).
+///
+/// Vertices are collapsed onto their neighbours rather than to new positions, so normals and UVs
+/// stay valid. Vertices on open borders (including UV and normal seams, where vertices are split)
+/// are locked, to avoid tearing holes in the mesh
+pub(crate) fn simplify(vertices: &[Vertex], indices: &[u32], target_index_count: usize, max_error: f32) -> (Vec<Vertex>, Vec<u32>) {
+    let positions: Vec<DVec3> = vertices.iter().map(|v| DVec3::from_array(v.position.map(|p| p as f64))).collect();
+    let mut triangles: Vec<[u32; 3]> = indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+    let mut alive = vec![true; triangles.len()];
+
+    // Accumulate the planes of every triangle touching each vertex
+    let mut quadrics = vec![Quadric::default(); vertices.len()];
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+    for (index, triangle) in triangles.iter().enumerate() {
+        let [a, b, c] = triangle.map(|i| positions[i as usize]);
+        let normal = (b - a).cross(c - a);
+        let length = normal.length();
+        if length > 0.0 {
+            let normal = normal / length;
+            let quadric = Quadric::from_plane(normal, -normal.dot(a));
+
+            for &vertex in triangle {
+                quadrics[vertex as usize].add(&quadric);
+            }
+        }
+
+        for &vertex in triangle {
+            vertex_triangles[vertex as usize].push(index);
+        }
+    }
+
+    // Edges used by only one triangle are borders
+    let mut edge_counts: HashMap<(u32, u32), u32> = HashMap::new();
+    for triangle in triangles.iter() {
+        for edge in 0..3 {
+            let (a, b) = (triangle[edge], triangle[(edge + 1) % 3]);
+            *edge_counts.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+    let mut locked = vec![false; vertices.len()];
+    for (&(a, b), &count) in edge_counts.iter() {
+        if count == 1 {
+            locked[a as usize] = true;
+            locked[b as usize] = true;
+        }
+    }
+
+    let mut versions = vec![0u32; vertices.len()];
+    let mut heap = BinaryHeap::new();
+    let max_cost = (max_error as f64) * (max_error as f64);
+
+    let push_collapses = |heap: &mut BinaryHeap<Collapse>, quadrics: &[Quadric], versions: &[u32], a: u32, b: u32| {
+        let mut quadric = quadrics[a as usize];
+        quadric.add(&quadrics[b as usize]);
+
+        for (from, to) in [(a, b), (b, a)] {
+            if locked[from as usize] {
+                continue;
+            }
+            heap.push(Collapse {
+                cost: quadric.error(positions[to as usize]).max(0.0),
+                from,
+                to,
+                from_version: versions[from as usize],
+                to_version: versions[to as usize],
+            });
+        }
+    };
+
+    for &(a, b) in edge_counts.keys() {
+        push_collapses(&mut heap, &quadrics, &versions, a, b);
+    }
+
+    let mut triangle_count = triangles.len();
+    let target_triangles = target_index_count / 3;
+
+    while triangle_count > target_triangles {
+        let collapse = match heap.pop() {
+            Some(collapse) => collapse,
+            None => break,
+        };
+        if collapse.cost > max_cost {
+            break;
+        }
+
+        let (from, to) = (collapse.from as usize, collapse.to as usize);
+        if versions[from] != collapse.from_version || versions[to] != collapse.to_version {
+            continue;
+        }
+
+        // Reject collapses that would flip a surviving triangle
+        let flips = vertex_triangles[from].iter()
+            .filter(|&&t| alive[t] && !triangles[t].contains(&collapse.to))
+            .any(|&t| {
+                let before = triangles[t].map(|i| positions[i as usize]);
+                let after = triangles[t].map(|i| positions[if i == collapse.from { to } else { i as usize }]);
+
+                let normal_before = (before[1] - before[0]).cross(before[2] - before[0]);
+                let normal_after = (after[1] - after[0]).cross(after[2] - after[0]);
+                normal_before.dot(normal_after) <= 0.0
+            });
+        if flips {
+            continue;
+        }
+
+        // Move every triangle from `from` onto `to`, dropping the ones that become degenerate
+        let moved = std::mem::take(&mut vertex_triangles[from]);
+        for &t in moved.iter() {
+            if !alive[t] {
+                continue;
+            }
+            if triangles[t].contains(&collapse.to) {
+                alive[t] = false;
+                triangle_count -= 1;
+                continue;
+            }
+
+            for vertex in triangles[t].iter_mut() {
+                if *vertex == collapse.from {
+                    *vertex = collapse.to;
+                }
+            }
+            vertex_triangles[to].push(t);
+        }
+
+        let from_quadric = quadrics[from];
+        quadrics[to].add(&from_quadric);
+        versions[from] += 1;
+        versions[to] += 1;
+
+        // Requeue every edge around the merged vertex with its new cost
+        vertex_triangles[to].retain(|&t| alive[t]);
+        let mut neighbours: Vec<u32> = vertex_triangles[to].iter()
+            .flat_map(|&t| triangles[t])
+            .filter(|&v| v != collapse.to)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+
+        for neighbour in neighbours {
+            push_collapses(&mut heap, &quadrics, &versions, collapse.to, neighbour);
+        }
+    }
+
+    // Compact the vertices that are still referenced
+    let mut remap = vec![u32::MAX; vertices.len()];
+    let mut new_vertices = Vec::new();
+    let mut new_indices = Vec::with_capacity(triangle_count * 3);
+    for (triangle, _) in triangles.iter().zip(alive.iter()).filter(|(_, &alive)| alive) {
+        for &vertex in triangle {
+            if remap[vertex as usize] == u32::MAX {
+                remap[vertex as usize] = new_vertices.len() as u32;
+                new_vertices.push(vertices[vertex as usize]);
+            }
+            new_indices.push(remap[vertex as usize]);
+        }
+    }
+
+    (new_vertices, new_indices)
+}
@@ -3,3 +3,13 @@ mod renderable;
 pub mod handle;
 pub mod mut_handle;
 pub mod shader_reflect;
+pub(crate) mod mesh_simplify;
+pub(crate) mod mesh_optimize;
+pub(crate) mod ply;
+pub(crate) mod stl;
+pub(crate) mod mesh_normals;
+pub(crate) mod readback;
+#[cfg(feature = "usd")]
+pub(crate) mod usd;
+#[cfg(feature = "draco")]
+pub(crate) mod draco;
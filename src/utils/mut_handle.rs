@@ -30,7 +30,7 @@ impl<T> MutHandle<T>{
     /// # Returns
     ///
     /// A reference to the value stored in the MutHandle
-    pub fn get(&self) -> MutexGuard<T>{
+    pub fn get(&self) -> MutexGuard<'_, T>{
         self.inner.lock().unwrap()
     }
     
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use anyhow::{anyhow, bail, Context};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PlyType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        Ok(match name {
+            "char" | "int8" => PlyType::Int8,
+            "uchar" | "uint8" => PlyType::UInt8,
+            "short" | "int16" => PlyType::Int16,
+            "ushort" | "uint16" => PlyType::UInt16,
+            "int" | "int32" => PlyType::Int32,
+            "uint" | "uint32" => PlyType::UInt32,
+            "float" | "float32" => PlyType::Float32,
+            "double" | "float64" => PlyType::Float64,
+            _ => bail!("Unknown PLY property type `{}`", name),
+        })
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            PlyType::Int8 | PlyType::UInt8 => 1,
+            PlyType::Int16 | PlyType::UInt16 => 2,
+            PlyType::Int32 | PlyType::UInt32 | PlyType::Float32 => 4,
+            PlyType::Float64 => 8,
+        }
+    }
+
+    // The value integer colours are divided by to get 0 to 1
+    fn color_scale(&self) -> f64 {
+        match self {
+            PlyType::Int8 | PlyType::UInt8 => u8::MAX as f64,
+            PlyType::Int16 | PlyType::UInt16 => u16::MAX as f64,
+            PlyType::Int32 | PlyType::UInt32 => u32::MAX as f64,
+            PlyType::Float32 | PlyType::Float64 => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PlyProperty {
+    Scalar { name: String, ty: PlyType },
+    List { name: String, count_ty: PlyType, item_ty: PlyType },
+}
+
+#[derive(Debug, Clone)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+/// # Ply Data
+///
+/// The vertices and faces of a PLY file. Vertex properties are stored by name
+/// (`x`, `red`, `nx`...), faces as lists of vertex indices
+pub(crate) struct PlyData {
+    pub vertex_count: usize,
+    vertex_properties: HashMap<String, (PlyType, Vec<f64>)>,
+    pub faces: Vec<Vec<u32>>,
+}
+
+impl PlyData {
+    pub fn load<T: AsRef<std::path::Path>>(path: T) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path.as_ref())
+            .with_context(|| format!("Failed to read {}", path.as_ref().display()))?;
+        Self::parse(&bytes)
+    }
+
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        let header_end = bytes.windows(10).position(|window| window == b"end_header")
+            .ok_or_else(|| anyhow!("PLY file has no end_header"))?;
+        let header = std::str::from_utf8(&bytes[..header_end])?;
+
+        // The body starts after the end of the end_header line
+        let body_start = bytes[header_end..].iter().position(|&b| b == b'\n')
+            .map(|offset| header_end + offset + 1)
+            .unwrap_or(bytes.len());
+        let body = &bytes[body_start..];
+
+        let mut lines = header.lines();
+        if lines.next().map(|line| line.trim()) != Some("ply") {
+            bail!("Not a PLY file");
+        }
+
+        let mut format = None;
+        let mut elements: Vec<PlyElement> = Vec::new();
+        for line in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["format", "ascii", ..] => format = Some(PlyFormat::Ascii),
+                ["format", "binary_little_endian", ..] => format = Some(PlyFormat::BinaryLittleEndian),
+                ["format", "binary_big_endian", ..] => format = Some(PlyFormat::BinaryBigEndian),
+                ["element", name, count] => elements.push(PlyElement {
+                    name: name.to_string(),
+                    count: count.parse()?,
+                    properties: Vec::new(),
+                }),
+                ["property", "list", count_ty, item_ty, name] => {
+                    let element = elements.last_mut().ok_or_else(|| anyhow!("PLY property before any element"))?;
+                    element.properties.push(PlyProperty::List {
+                        name: name.to_string(),
+                        count_ty: PlyType::parse(count_ty)?,
+                        item_ty: PlyType::parse(item_ty)?,
+                    });
+                }
+                ["property", ty, name] => {
+                    let element = elements.last_mut().ok_or_else(|| anyhow!("PLY property before any element"))?;
+                    element.properties.push(PlyProperty::Scalar {
+                        name: name.to_string(),
+                        ty: PlyType::parse(ty)?,
+                    });
+                }
+                _ => {} // Comments, obj_info, etc
+            }
+        }
+        let format = format.ok_or_else(|| anyhow!("PLY file has no format"))?;
+
+        let mut reader = PlyReader { format, body, position: 0 };
+        let mut data = Self {
+            vertex_count: 0,
+            vertex_properties: HashMap::new(),
+            faces: Vec::new(),
+        };
+
+        for element in elements.iter() {
+            if element.name == "vertex" {
+                data.vertex_count = element.count;
+                for property in element.properties.iter() {
+                    if let PlyProperty::Scalar { name, ty } = property {
+                        data.vertex_properties.insert(name.clone(), (*ty, Vec::with_capacity(element.count)));
+                    }
+                }
+            }
+
+            for _ in 0..element.count {
+                for property in element.properties.iter() {
+                    match property {
+                        PlyProperty::Scalar { name, ty } => {
+                            let value = reader.read(*ty)?;
+                            if element.name == "vertex" {
+                                data.vertex_properties.get_mut(name).unwrap().1.push(value);
+                            }
+                        }
+                        PlyProperty::List { name, count_ty, item_ty } => {
+                            let count = reader.read(*count_ty)? as usize;
+                            let mut items = Vec::with_capacity(count);
+                            for _ in 0..count {
+                                items.push(reader.read(*item_ty)? as u32);
+                            }
+
+                            if element.name == "face" && (name == "vertex_indices" || name == "vertex_index") {
+                                data.faces.push(items);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(data)
+    }
+
+    pub fn get_property(&self, name: &str) -> Option<&[f64]> {
+        self.vertex_properties.get(name).map(|(_, values)| values.as_slice())
+    }
+
+    pub fn get_positions(&self) -> anyhow::Result<Vec<[f32; 3]>> {
+        match (self.get_property("x"), self.get_property("y"), self.get_property("z")) {
+            (Some(x), Some(y), Some(z)) => Ok((0..self.vertex_count)
+                .map(|i| [x[i] as f32, y[i] as f32, z[i] as f32])
+                .collect()),
+            _ => bail!("PLY file has no vertex positions"),
+        }
+    }
+
+    pub fn get_normals(&self) -> Option<Vec<[f32; 3]>> {
+        let (x, y, z) = (self.get_property("nx")?, self.get_property("ny")?, self.get_property("nz")?);
+        Some((0..self.vertex_count).map(|i| [x[i] as f32, y[i] as f32, z[i] as f32]).collect())
+    }
+
+    pub fn get_tex_coords(&self) -> Option<Vec<[f32; 2]>> {
+        let u = self.get_property("u").or_else(|| self.get_property("s")).or_else(|| self.get_property("texture_u"))?;
+        let v = self.get_property("v").or_else(|| self.get_property("t")).or_else(|| self.get_property("texture_v"))?;
+        Some((0..self.vertex_count).map(|i| [u[i] as f32, v[i] as f32]).collect())
+    }
+
+    /// Vertex colours normalised to 0 to 1, with alpha defaulting to opaque
+    pub fn get_colors(&self) -> Option<Vec<[f32; 4]>> {
+        let channel = |names: &[&str]| names.iter()
+            .find_map(|name| self.vertex_properties.get(*name))
+            .map(|(ty, values)| (ty.color_scale(), values));
+
+        let (red_scale, red) = channel(&["red", "r", "diffuse_red"])?;
+        let (green_scale, green) = channel(&["green", "g", "diffuse_green"])?;
+        let (blue_scale, blue) = channel(&["blue", "b", "diffuse_blue"])?;
+        let alpha = channel(&["alpha", "a", "diffuse_alpha"]);
+
+        Some((0..self.vertex_count).map(|i| [
+            (red[i] / red_scale) as f32,
+            (green[i] / green_scale) as f32,
+            (blue[i] / blue_scale) as f32,
+            alpha.map(|(scale, alpha)| (alpha[i] / scale) as f32).unwrap_or(1.0),
+        ]).collect())
+    }
+}
+
+struct PlyReader<'a> {
+    format: PlyFormat,
+    body: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PlyReader<'a> {
+    fn read(&mut self, ty: PlyType) -> anyhow::Result<f64> {
+        if self.format == PlyFormat::Ascii {
+            return self.read_ascii();
+        }
+
+        let size = ty.size();
+        let bytes = self.body.get(self.position..self.position + size)
+            .ok_or_else(|| anyhow!("PLY file ended unexpectedly"))?;
+        self.position += size;
+
+        let mut buffer = [0u8; 8];
+        buffer[..size].copy_from_slice(bytes);
+        if self.format == PlyFormat::BinaryBigEndian {
+            buffer[..size].reverse();
+        }
+
+        Ok(match ty {
+            PlyType::Int8 => buffer[0] as i8 as f64,
+            PlyType::UInt8 => buffer[0] as f64,
+            PlyType::Int16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            PlyType::UInt16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            PlyType::Int32 => i32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+            PlyType::UInt32 => u32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+            PlyType::Float32 => f32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+            PlyType::Float64 => f64::from_le_bytes(buffer),
+        })
+    }
+
+    // Reads the next whitespace separated number
+    fn read_ascii(&mut self) -> anyhow::Result<f64> {
+        while self.position < self.body.len() && self.body[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+        let start = self.position;
+        while self.position < self.body.len() && !self.body[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+        if start == self.position {
+            bail!("PLY file ended unexpectedly");
+        }
+
+        let word = std::str::from_utf8(&self.body[start..self.position])?;
+        word.parse::<f64>().with_context(|| format!("Invalid PLY value `{}`", word))
+    }
+}
@@ -0,0 +1,170 @@
+// Copying GPU buffers and textures back to the CPU
+use std::sync::mpsc;
+use image::RgbaImage;
+
+type Decode<T> = Box<dyn FnOnce(&[u8]) -> anyhow::Result<T> + Send>;
+
+/// # Pending Readback
+///
+/// Data being copied back from the GPU. The copy is submitted and the staging buffer mapped straight away,
+/// and the renderer polls the device every frame, so the data arrives a frame or two later without the CPU
+/// waiting on the GPU. Check for it with `try_take`, or block until it's ready with `wait`
+pub struct PendingReadback<T>{
+    staging_buffer: wgpu::Buffer,
+    // Sent by the map_async callback once the staging buffer can be read
+    receiver: mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    // Turns the mapped bytes into the data. Taken once the data has been read
+    decode: Option<Decode<T>>,
+}
+
+impl<T> PendingReadback<T>{
+    // Starts mapping the staging buffer. Must be called after the copy into it has been submitted
+    fn new(staging_buffer: wgpu::Buffer, decode: Decode<T>) -> Self{
+        let (sender, receiver) = mpsc::channel();
+        staging_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result|{
+            let _ = sender.send(result);
+        });
+
+        Self{
+            staging_buffer,
+            receiver,
+            decode: Some(decode),
+        }
+    }
+
+    /// Returns the data if the copy has finished, without blocking. `None` while it's still in flight,
+    /// and once the data has been taken
+    pub fn try_take(&mut self) -> Option<anyhow::Result<T>>{
+        self.decode.as_ref()?;
+        match self.receiver.try_recv(){
+            Ok(result) => Some(self.read(result)),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(self.read(Err(wgpu::BufferAsyncError))),
+        }
+    }
+
+    /// Waits for the copy to finish and returns the data, e.g in tests
+    pub fn wait(mut self, device: &wgpu::Device) -> anyhow::Result<T>{
+        if self.decode.is_none(){
+            anyhow::bail!("The read back data has already been taken");
+        }
+
+        device.poll(wgpu::Maintain::Wait);
+        let result = self.receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError));
+        self.read(result)
+    }
+
+    fn read(&mut self, result: Result<(), wgpu::BufferAsyncError>) -> anyhow::Result<T>{
+        let decode = self.decode.take().ok_or_else(|| anyhow::anyhow!("The read back data has already been taken"))?;
+        result?;
+
+        let data = decode(&self.staging_buffer.slice(..).get_mapped_range());
+        self.staging_buffer.unmap();
+        data
+    }
+}
+
+/// Reads the contents of the buffers back, one after the other. Each has to have `COPY_SRC` usage
+pub(crate) fn read_buffers(device: &wgpu::Device, queue: &wgpu::Queue, buffers: &[&wgpu::Buffer]) -> PendingReadback<Vec<u8>>{
+    let size: wgpu::BufferAddress = buffers.iter().map(|buffer| buffer.size()).sum();
+
+    // Empty buffers can't be mapped, so there's always something to map even with nothing to read
+    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor{
+        label: Some("Readback Staging Buffer"),
+        size: size.max(wgpu::COPY_BUFFER_ALIGNMENT),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor{
+        label: Some("Buffer Readback Encoder"),
+    });
+    let mut offset = 0;
+    for buffer in buffers{
+        encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, offset, buffer.size());
+        offset += buffer.size();
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+
+    PendingReadback::new(staging_buffer, Box::new(move |data| Ok(data[..size as usize].to_vec())))
+}
+
+/// Reads the first mip level of a colour texture back as an RGBA image. The texture has to have
+/// `COPY_SRC` usage. 8 bit formats are read as they're stored, so sRGB textures stay sRGB encoded.
+/// Float formats are clamped to 0-1
+pub(crate) fn read_texture(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> anyhow::Result<PendingReadback<RgbaImage>>{
+    let format = texture.format();
+    let texel_size = match format{
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb
+        | wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => 4,
+        wgpu::TextureFormat::Rgba16Float => 8,
+        wgpu::TextureFormat::Rgba32Float => 16,
+        _ => anyhow::bail!("Can't read back a texture with the format {:?}", format),
+    };
+
+    let width = texture.width();
+    let height = texture.height();
+
+    // Rows in a texture to buffer copy must be aligned to 256 bytes
+    let unpadded_bytes_per_row = width * texel_size;
+    let bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor{
+        label: Some("Readback Staging Buffer"),
+        size: (bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor{
+        label: Some("Texture Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture{
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer{
+            buffer: &staging_buffer,
+            layout: wgpu::ImageDataLayout{
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d{
+            width,
+            height,
+            depth_or_array_layers: 1,
+        }
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let to_byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let decode = move |data: &[u8]|{
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in data.chunks(bytes_per_row as usize){
+            for texel in row[..unpadded_bytes_per_row as usize].chunks(texel_size as usize){
+                match format{
+                    wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                        pixels.extend_from_slice(&[texel[2], texel[1], texel[0], texel[3]]);
+                    }
+                    wgpu::TextureFormat::Rgba16Float => {
+                        pixels.extend(texel.chunks(2).map(|value| to_byte(half::f16::from_le_bytes([value[0], value[1]]).to_f32())));
+                    }
+                    wgpu::TextureFormat::Rgba32Float => {
+                        pixels.extend(texel.chunks(4).map(|value| to_byte(f32::from_le_bytes([value[0], value[1], value[2], value[3]]))));
+                    }
+                    _ => pixels.extend_from_slice(texel),
+                }
+            }
+        }
+
+        RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("Read back image data doesn't match the texture size"))
+    };
+
+    Ok(PendingReadback::new(staging_buffer, Box::new(decode)))
+}
@@ -1,6 +1,7 @@
 use regex::Regex;
 use std::collections::HashMap;
 use log::{error, info};
+use crate::debug::{debug_log, Subsystem};
 
 #[derive(Debug, Clone)]
 pub enum BindingType{
@@ -15,7 +16,16 @@ pub struct Binding{
     group: u32,
     binding: u32,
     name: String,
-    binding_type: BindingType
+    binding_type: BindingType,
+    // Storage bindings declared with `read` access. Only these can be used in vertex shaders
+    read_only: bool,
+    // Declared as a `binding_array`
+    array: bool,
+    // Depth textures (`texture_depth_*`) and comparison samplers (`sampler_comparison`)
+    depth: bool,
+    // Multisampled textures (`texture_multisampled_2d`, `texture_depth_multisampled_2d`), which are only read with `textureLoad`
+    multisampled: bool,
+    view_dimension: wgpu::TextureViewDimension
 }
 
 impl Binding{
@@ -34,19 +44,169 @@ impl Binding{
     pub fn get_binding_type(&self) -> BindingType{
         self.binding_type.clone()
     }
+
+    pub fn is_read_only(&self) -> bool{
+        self.read_only
+    }
+
+    pub fn is_array(&self) -> bool{
+        self.array
+    }
+
+    pub fn is_depth(&self) -> bool{
+        self.depth
+    }
+
+    pub fn is_multisampled(&self) -> bool{
+        self.multisampled
+    }
+
+    /// The dimension of a texture binding, e.g `D2Array` for `texture_depth_2d_array`
+    pub fn get_view_dimension(&self) -> wgpu::TextureViewDimension{
+        self.view_dimension
+    }
 }
 
 
+/// # Uniform Member
+///
+/// A single member of a uniform struct, as laid out by the shader
+#[derive(Debug, Clone)]
+pub struct UniformMember{
+    pub name: String,
+    pub offset: u32,
+    pub size: u32
+}
+
+/// # Uniform Array
+///
+/// The length of a uniform declared as a fixed size array, e.g `array<mat4x4<f32>, 64>` for bone
+/// matrices, and the bytes from the start of one element to the next
+#[derive(Debug, Clone, Copy)]
+pub struct UniformArray{
+    pub length: u32,
+    pub stride: u32
+}
+
+/// # Uniform Layout
+///
+/// The memory layout the shader expects for a uniform binding, reflected with naga.
+/// For arrays, the members are those of each element, if it's a struct
+#[derive(Debug, Clone)]
+pub struct UniformLayout{
+    pub type_name: String,
+    pub size: u32,
+    pub members: Vec<UniformMember>,
+    pub array: Option<UniformArray>
+}
+
+impl UniformLayout{
+    /// # Validate Layout
+    ///
+    /// Checks the Rust-side uniform data against the struct the shader expects, returning a descriptive
+    /// error (including the expected member offsets) on mismatch. The size is always checked, and where each
+    /// member sits too if the data lists its member offsets (see `AsBytes::get_member_offsets`)
+    pub fn validate_layout(&self, binding_name: &str, data_size: usize, member_offsets: &[(&str, usize)]) -> anyhow::Result<()>{
+        if data_size == self.size as usize{
+            return self.validate_offsets(binding_name, member_offsets);
+        }
+
+        let mut message = match self.array{
+            Some(array) => {
+                let mut message = format!(
+                    "Uniform `{}` is {} bytes, but the shader's `{}` expects {} bytes ({} elements of {} bytes each).",
+                    binding_name, data_size, self.type_name, self.size, array.length, array.stride
+                );
+                if data_size.is_multiple_of(array.stride as usize){
+                    message.push_str(&format!(" The data holds {} elements, every element has to be written", data_size / array.stride as usize));
+                }
+                if !self.members.is_empty(){
+                    message.push_str(" Expected element layout:");
+                }
+                message
+            }
+            None => format!(
+                "Uniform `{}` is {} bytes, but the shader's `{}` struct expects {} bytes. Expected layout:",
+                binding_name, data_size, self.type_name, self.size
+            ),
+        };
+        self.push_members(&mut message);
+
+        // A whole number of array elements is a length problem, not a padding one
+        let whole_elements = self.array.is_some_and(|array| data_size.is_multiple_of(array.stride as usize));
+        if data_size < self.size as usize && !whole_elements{
+            message.push_str("\nCheck the Rust struct is #[repr(C)] and padded to WGSL alignment rules (e.g vec3 aligns to 16 bytes)");
+        }
+
+        anyhow::bail!(message)
+    }
+
+    // Compares the offsets the Rust type lists against the shader's members, by name. Arrays of structs
+    // are written from slices, which can't list the element's offsets, so only single structs are checked
+    fn validate_offsets(&self, binding_name: &str, member_offsets: &[(&str, usize)]) -> anyhow::Result<()>{
+        if member_offsets.is_empty() || self.array.is_some(){
+            return Ok(());
+        }
+
+        let mut problems = Vec::new();
+        for member in self.members.iter(){
+            match member_offsets.iter().find(|(name, _)| *name == member.name){
+                Some((_, offset)) if *offset != member.offset as usize =>
+                    problems.push(format!("`{}` is at offset {}, but the shader expects it at {}", member.name, offset, member.offset)),
+                Some(_) => {},
+                None => problems.push(format!("`{}` isn't listed by the Rust type", member.name)),
+            }
+        }
+        for (name, _) in member_offsets.iter(){
+            if !self.members.iter().any(|member| member.name == *name){
+                problems.push(format!("`{}` isn't a member of the shader's struct", name));
+            }
+        }
+
+        if problems.is_empty(){
+            return Ok(());
+        }
+
+        let mut message = format!(
+            "Uniform `{}` doesn't match the layout of the shader's `{}` struct: {}. Expected layout:",
+            binding_name, self.type_name, problems.join(", ")
+        );
+        self.push_members(&mut message);
+        message.push_str("\nCheck the Rust struct is #[repr(C)] and padded to WGSL alignment rules (e.g vec3 aligns to 16 bytes)");
+
+        anyhow::bail!(message)
+    }
+
+    fn push_members(&self, message: &mut String){
+        for member in self.members.iter(){
+            message.push_str(&format!("\n    {} @ offset {} ({} bytes)", member.name, member.offset, member.size));
+        }
+    }
+}
+
+/// # Vertex Input
+///
+/// An input of the shader's `vertex_main`, and the location it's read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexInput{
+    pub name: String,
+    pub location: u32
+}
+
 pub struct ShaderReflect{
     source: String,
-    bindings: HashMap<String, Binding>
+    bindings: HashMap<String, Binding>,
+    uniform_layouts: HashMap<String, UniformLayout>,
+    vertex_inputs: Vec<VertexInput>
 }
 
 impl ShaderReflect{
     pub fn new<T: Into<String>>(source: T) -> Self{
         Self{
             source: source.into(),
-            bindings: HashMap::new()
+            bindings: HashMap::new(),
+            uniform_layouts: HashMap::new(),
+            vertex_inputs: Vec::new()
         }
     }
 
@@ -57,31 +217,47 @@ impl ShaderReflect{
             let binding = capture[2].parse::<u32>().unwrap();
             let name = &capture[3];
             let tex_type = &capture[4];
+            // The element type is inside the brackets, e.g `binding_array<texture_2d<f32>>`
+            let array = tex_type.trim() == "binding_array";
+            let depth = tex_type.contains("depth") || tex_type.contains("comparison");
+            let multisampled = tex_type.contains("multisampled");
+            let view_dimension = Self::texture_view_dimension(tex_type.trim());
 
             if tex_type.contains("sampler") {
                 self.bindings.insert(name.to_string(), Binding {
                     group,
                     binding,
                     name: name.to_string(),
-                    binding_type: BindingType::TextureSampler
+                    binding_type: BindingType::TextureSampler,
+                    read_only: false,
+                    array,
+                    depth,
+                    multisampled,
+                    view_dimension
                 });
             } else {
                 self.bindings.insert(name.to_string(), Binding {
                     group,
                     binding,
                     name: name.to_string(),
-                    binding_type: BindingType::Texture
+                    binding_type: BindingType::Texture,
+                    read_only: false,
+                    array,
+                    depth,
+                    multisampled,
+                    view_dimension
                 });
             }
         }
 
-        // get wgsl uniform bindings
-        let re_binding_type = Regex::new(r"@group\(\s*(\d+)\s*\)\s*@binding\(\s*(\d+)\s*\)\s*var\s*\W(\w+)\W\s*(\w+)\s*:\s*(\w*)").unwrap();
+        // get wgsl uniform and storage bindings, with the optional access mode (e.g `var<storage, read>`)
+        let re_binding_type = Regex::new(r"@group\(\s*(\d+)\s*\)\s*@binding\(\s*(\d+)\s*\)\s*var\s*<\s*(\w+)\s*(?:,\s*(\w+)\s*)?>\s*(\w+)\s*:").unwrap();
         for capture in re_binding_type.captures_iter(&self.source){
             let group = capture[1].parse::<u32>().unwrap();
             let binding = capture[2].parse::<u32>().unwrap();
             let bind_type = &capture[3];
-            let name = &capture[4];
+            let read_only = capture.get(4).map(|access| access.as_str()).unwrap_or("read") == "read";
+            let name = &capture[5];
 
             let binding_type = match bind_type{
                 "uniform" => BindingType::Uniform,
@@ -96,14 +272,134 @@ impl ShaderReflect{
                 group,
                 binding,
                 name: name.to_string(),
-                binding_type
+                binding_type,
+                read_only,
+                array: false,
+                depth: false,
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2
             });
         }
 
-        println!("{:?}", self.bindings);
+        debug_log!(Subsystem::Shaders, "{:?}", self.bindings);
+
+        let module = match naga::front::wgsl::parse_str(&self.source){
+            Ok(module) => module,
+            Err(e) => {
+                error!("Failed to parse shader for reflection: {}", e);
+                return;
+            }
+        };
+
+        self.reflect_uniform_layouts(&module);
+        self.reflect_vertex_inputs(&module);
+    }
+
+    // Collects the locations `vertex_main` reads, whether passed directly or in a struct,
+    // so they can be checked against a mesh's vertex layout
+    fn reflect_vertex_inputs(&mut self, module: &naga::Module){
+        let entry_point = match module.entry_points.iter().find(|entry_point| entry_point.name == "vertex_main"){
+            Some(entry_point) => entry_point,
+            None => return
+        };
+
+        for argument in entry_point.function.arguments.iter(){
+            match (&argument.binding, &module.types[argument.ty].inner){
+                (Some(naga::Binding::Location{ location, .. }), _) => self.vertex_inputs.push(VertexInput{
+                    name: argument.name.clone().unwrap_or_default(),
+                    location: *location
+                }),
+                (None, naga::TypeInner::Struct{ members, .. }) => {
+                    for member in members.iter(){
+                        if let Some(naga::Binding::Location{ location, .. }) = member.binding{
+                            self.vertex_inputs.push(VertexInput{
+                                name: member.name.clone().unwrap_or_default(),
+                                location
+                            });
+                        }
+                    }
+                }
+                _ => {} // Built-ins such as the vertex index
+            }
+        }
+    }
+
+    // The view dimension named by a texture type, e.g `texture_depth_cube`. Samplers and
+    // anything unrecognised are 2D
+    fn texture_view_dimension(tex_type: &str) -> wgpu::TextureViewDimension{
+        if tex_type.ends_with("cube_array"){
+            wgpu::TextureViewDimension::CubeArray
+        }else if tex_type.ends_with("cube"){
+            wgpu::TextureViewDimension::Cube
+        }else if tex_type.ends_with("2d_array"){
+            wgpu::TextureViewDimension::D2Array
+        }else if tex_type.ends_with("3d"){
+            wgpu::TextureViewDimension::D3
+        }else if tex_type.ends_with("1d"){
+            wgpu::TextureViewDimension::D1
+        }else{
+            wgpu::TextureViewDimension::D2
+        }
+    }
+
+    // Uses naga to work out the size and member offsets of each uniform struct or array,
+    // so we can validate the Rust-side data against it
+    fn reflect_uniform_layouts(&mut self, module: &naga::Module){
+
+        let mut layouter = naga::proc::Layouter::default();
+        if let Err(e) = layouter.update(module.to_ctx()){
+            error!("Failed to compute uniform layouts: {}", e);
+            return;
+        }
+
+        for (_, variable) in module.global_variables.iter(){
+            if variable.space != naga::AddressSpace::Uniform{
+                continue;
+            }
+
+            let name = match variable.name.as_ref(){
+                Some(name) => name.clone(),
+                None => continue
+            };
+
+            // Arrays of structs (e.g lights) list the members of a single element
+            let (element_ty, array) = match module.types[variable.ty].inner{
+                naga::TypeInner::Array{ base, size: naga::ArraySize::Constant(length), stride } =>
+                    (base, Some(UniformArray{ length: length.get(), stride })),
+                _ => (variable.ty, None)
+            };
+
+            let members = match &module.types[element_ty].inner{
+                naga::TypeInner::Struct{ members, .. } => members.iter().map(|member| UniformMember{
+                    name: member.name.clone().unwrap_or_default(),
+                    offset: member.offset,
+                    size: layouter[member.ty].size
+                }).collect(),
+                _ => Vec::new()
+            };
+
+            self.uniform_layouts.insert(name, UniformLayout{
+                type_name: variable.ty.to_wgsl(&module.to_ctx()),
+                size: layouter[variable.ty].size,
+                members,
+                array
+            });
+        }
     }
 
     pub fn get_bindings(&self) -> HashMap<String, Binding>{
         self.bindings.clone()
     }
+
+    pub fn get_binding(&self, name: &str) -> Option<&Binding>{
+        self.bindings.get(name)
+    }
+
+    pub fn get_vertex_inputs(&self) -> &[VertexInput]{
+        &self.vertex_inputs
+    }
+
+    pub fn get_uniform_layout(&self, name: &str) -> Option<&UniformLayout>{
+        self.uniform_layouts.get(name)
+    }
 }
@@ -0,0 +1,76 @@
+use anyhow::{anyhow, bail, Context};
+
+/// # Read STL
+///
+/// Reads the triangle corners of a binary or ASCII STL file, three per triangle.
+/// The facet normals are ignored, as many exporters write them incorrectly
+pub(crate) fn read_stl<T: AsRef<std::path::Path>>(path: T) -> anyhow::Result<Vec<[f32; 3]>> {
+    let bytes = std::fs::read(path.as_ref())
+        .with_context(|| format!("Failed to read {}", path.as_ref().display()))?;
+
+    if is_binary(&bytes) {
+        read_binary(&bytes)
+    } else {
+        read_ascii(std::str::from_utf8(&bytes)?)
+    }
+}
+
+// ASCII files start with `solid`, but so do some binary ones, so check the size matches
+// the triangle count in the binary header too
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < 84 {
+        return false;
+    }
+
+    let triangle_count = u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize;
+    let expected_size = 84 + triangle_count * 50;
+
+    expected_size == bytes.len() || !bytes.starts_with(b"solid")
+}
+
+fn read_binary(bytes: &[u8]) -> anyhow::Result<Vec<[f32; 3]>> {
+    let triangle_count = u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize;
+    if bytes.len() < 84 + triangle_count * 50 {
+        bail!("STL file is truncated, expected {} triangles", triangle_count);
+    }
+
+    let read_f32 = |offset: usize| f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+    let mut positions = Vec::with_capacity(triangle_count * 3);
+    for triangle in 0..triangle_count {
+        // Each triangle is a normal, three corners and a 2 byte attribute count
+        let start = 84 + triangle * 50 + 12;
+        for corner in 0..3 {
+            let offset = start + corner * 12;
+            positions.push([read_f32(offset), read_f32(offset + 4), read_f32(offset + 8)]);
+        }
+    }
+
+    Ok(positions)
+}
+
+fn read_ascii(source: &str) -> anyhow::Result<Vec<[f32; 3]>> {
+    let mut positions = Vec::new();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("vertex") {
+            continue;
+        }
+
+        let mut position = [0.0; 3];
+        for value in position.iter_mut() {
+            *value = words.next()
+                .ok_or_else(|| anyhow!("Missing vertex coordinate on line {}", line_number + 1))?
+                .parse()
+                .with_context(|| format!("Invalid vertex on line {}", line_number + 1))?;
+        }
+        positions.push(position);
+    }
+
+    if positions.len() % 3 != 0 {
+        bail!("STL file has {} vertices, which isn't a whole number of triangles", positions.len());
+    }
+
+    Ok(positions)
+}
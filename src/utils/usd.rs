@@ -0,0 +1,851 @@
+//! A small reader for USD scenes, enough to pull out meshes, their transforms and their materials.
+//!
+//! Only ASCII layers are read: `.usda` files, and `.usdz` packages whose root layer is a `.usda`.
+//! Binary crate layers (`.usdc`), which most `.usdz` packages use, references, payloads and variants
+//! aren't supported, convert those with `usdcat --flatten scene.usdc -o scene.usda` first.
+//! Materials are read from their `UsdPreviewSurface` shader, see `load_usd_materials`
+use std::collections::HashMap;
+use std::path::PathBuf;
+use anyhow::{anyhow, bail, Context};
+use glam::{DMat4, DQuat, DVec3};
+use image::RgbaImage;
+use log::warn;
+use crate::types::pbr_material::PbrMaterialUniform;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Punct(char),
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Num(f64),
+    Str(String),
+    List(Vec<Value>),
+    Other,
+}
+
+impl Value {
+    // Every number in the value, with tuples and arrays flattened
+    fn flatten(&self, out: &mut Vec<f64>) {
+        match self {
+            Value::Num(value) => out.push(*value),
+            Value::List(values) => values.iter().for_each(|value| value.flatten(out)),
+            _ => {}
+        }
+    }
+
+    fn to_floats(&self) -> Vec<f64> {
+        let mut out = Vec::new();
+        self.flatten(&mut out);
+        out
+    }
+
+    fn to_strings(&self) -> Vec<String> {
+        match self {
+            Value::Str(value) => vec![value.clone()],
+            Value::List(values) => values.iter().flat_map(|value| value.to_strings()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Prim {
+    type_name: String,
+    name: String,
+    attributes: HashMap<String, Value>,
+    children: Vec<Prim>,
+}
+
+impl Prim {
+    fn get_str(&self, name: &str) -> Option<&str> {
+        match self.attributes.get(name) {
+            Some(Value::Str(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// # Usd Mesh
+///
+/// A mesh prim flattened into triangles, with one vertex per face corner,
+/// in world space (Y up, right handed)
+pub(crate) struct UsdMesh {
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub tex_coords: Option<Vec<[f32; 2]>>,
+}
+
+/// # Usd Material
+///
+/// A `UsdPreviewSurface` material read into `PBR_SHADER`'s uniform, plus the image each slot samples
+pub(crate) struct UsdMaterial {
+    pub name: String,
+    pub uniform: PbrMaterialUniform,
+    /// Slot name and the image it samples, already in the channel layout `PBR_SHADER` reads
+    pub textures: Vec<(&'static str, RgbaImage)>,
+}
+
+/// Loads every mesh in a `.usda` file, or a `.usdz` package with a `.usda` root layer
+pub(crate) fn load_usd<T: AsRef<std::path::Path>>(path: T) -> anyhow::Result<Vec<UsdMesh>> {
+    let package = Package::read(path.as_ref())?;
+    let layer = parse_layer(&package.root_layer)?;
+
+    let mut meshes = Vec::new();
+    for prim in layer.prims.iter() {
+        collect_meshes(prim, layer.root_transform(), &mut meshes);
+    }
+
+    Ok(meshes)
+}
+
+/// Loads every material with a `UsdPreviewSurface` shader, in the layer's order. Textures are read
+/// from the package, or relative to the layer for `.usda` files. A texture that can't be read
+/// is warned about and left out, so its slot falls back to the factor alone
+pub(crate) fn load_usd_materials<T: AsRef<std::path::Path>>(path: T) -> anyhow::Result<Vec<UsdMaterial>> {
+    let package = Package::read(path.as_ref())?;
+    let layer = parse_layer(&package.root_layer)?;
+
+    let mut material_prims = Vec::new();
+    for prim in layer.prims.iter() {
+        collect_prims(prim, &format!("/{}", prim.name), "Material", &mut material_prims);
+    }
+
+    let mut images = HashMap::new();
+    let materials = material_prims.into_iter().filter_map(|(path, material)| {
+        let surface = find_surface(&layer, &path, material)?;
+        Some(build_material(&layer, &package, &mut images, material, surface))
+    }).collect();
+
+    Ok(materials)
+}
+
+// The root layer of a file, and for `.usdz` packages the other files it holds
+struct Package {
+    root_layer: String,
+    files: HashMap<String, Vec<u8>>,
+    directory: PathBuf,
+}
+
+impl Package {
+    fn read(path: &std::path::Path) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let directory = path.parent().map(|parent| parent.to_path_buf()).unwrap_or_default();
+
+        if path.extension().map(|extension| extension.eq_ignore_ascii_case("usdz")).unwrap_or(false) {
+            let entries = read_usdz_entries(&bytes)?;
+            let (name, data) = entries.first().ok_or_else(|| anyhow!("USDZ package is empty"))?;
+            if data.starts_with(b"PXR-USDC") || name.ends_with(".usdc") {
+                bail!("USDZ root layer `{}` is a binary .usdc layer, which isn't supported. Repackage it with a .usda root layer", name);
+            }
+
+            let root_layer = String::from_utf8(data.to_vec())?;
+            let files = entries.into_iter().map(|(name, data)| (name, data.to_vec())).collect();
+            Ok(Self { root_layer, files, directory })
+        } else if bytes.starts_with(b"PXR-USDC") {
+            bail!("Binary .usdc layers aren't supported, convert to .usda with usdcat first");
+        } else {
+            Ok(Self { root_layer: String::from_utf8(bytes)?, files: HashMap::new(), directory })
+        }
+    }
+
+    // Asset paths are relative to the layer, which for packages is the root of the zip
+    fn read_asset(&self, asset: &str) -> anyhow::Result<Vec<u8>> {
+        let asset = asset.trim_start_matches("./");
+        if self.files.is_empty() {
+            let path = self.directory.join(asset);
+            std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))
+        } else {
+            self.files.get(asset).cloned().ok_or_else(|| anyhow!("USDZ package has no file `{}`", asset))
+        }
+    }
+}
+
+// USDZ packages are uncompressed zip files, where the first file is the root layer
+fn read_usdz_entries(bytes: &[u8]) -> anyhow::Result<Vec<(String, &[u8])>> {
+    let read_u16 = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) as usize;
+    let read_u32 = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+
+    if bytes.len() < 30 || read_u32(0) != 0x04034b50 {
+        bail!("Not a USDZ package");
+    }
+
+    // Walk the local file headers, which end where the central directory starts
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + 30 <= bytes.len() && read_u32(offset) == 0x04034b50 {
+        let compression = read_u16(offset + 8);
+        let size = read_u32(offset + 18);
+        let name_length = read_u16(offset + 26);
+        let extra_length = read_u16(offset + 28);
+
+        let name = bytes.get(offset + 30..offset + 30 + name_length).ok_or_else(|| anyhow!("USDZ package is truncated"))?;
+        let name = std::str::from_utf8(name)?.to_string();
+        let start = offset + 30 + name_length + extra_length;
+        let data = bytes.get(start..start + size).ok_or_else(|| anyhow!("USDZ package is truncated"))?;
+
+        if compression != 0 {
+            bail!("USDZ package is compressed, which the spec doesn't allow");
+        }
+        entries.push((name, data));
+        offset = start + size;
+    }
+
+    Ok(entries)
+}
+
+fn tokenize(source: &str) -> anyhow::Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '"' | '\'' => {
+                // Triple quoted strings are used for docs
+                let triple = chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c);
+                let quote_length = if triple { 3 } else { 1 };
+                i += quote_length;
+
+                let start = i;
+                loop {
+                    if i >= chars.len() {
+                        bail!("Unterminated string in USD layer");
+                    }
+                    if chars[i] == '\\' {
+                        i += 2;
+                        continue;
+                    }
+                    if chars[i] == c && (!triple || (chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c))) {
+                        break;
+                    }
+                    i += 1;
+                }
+                tokens.push(Token::Str(chars[start..i].iter().collect()));
+                i += quote_length;
+            }
+            // Asset paths and relationship targets, which we only need to skip
+            '@' | '<' => {
+                let end = if c == '@' { '@' } else { '>' };
+                let start = i + 1;
+                i += 1;
+                while i < chars.len() && chars[i] != end {
+                    i += 1;
+                }
+                tokens.push(Token::Str(chars[start..i.min(chars.len())].iter().collect()));
+                i += 1;
+            }
+            '(' | ')' | '[' | ']' | '{' | '}' | '=' | ',' | ';' => {
+                tokens.push(Token::Punct(c));
+                i += 1;
+            }
+            _ if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '.' | '-' | '+')) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match word.parse::<f64>() {
+                    Ok(value) => tokens.push(Token::Num(value)),
+                    Err(_) => tokens.push(Token::Ident(word)),
+                }
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !"()[]{}=,;\"'#@<".contains(chars[i]) {
+                    i += 1;
+                }
+                if start == i {
+                    // Anything we don't understand on its own
+                    tokens.push(Token::Punct(c));
+                    i += 1;
+                } else {
+                    tokens.push(Token::Ident(chars[start..i].iter().collect()));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn expect_punct(&mut self, c: char) -> anyhow::Result<()> {
+        match self.next() {
+            Some(Token::Punct(found)) if found == c => Ok(()),
+            found => bail!("Expected `{}` in USD layer, found {:?}", c, found),
+        }
+    }
+
+    // Skips a bracketed block, including everything nested inside it
+    fn skip_block(&mut self) -> anyhow::Result<()> {
+        let mut depth = 0;
+        loop {
+            match self.next() {
+                Some(Token::Punct('(' | '[' | '{')) => depth += 1,
+                Some(Token::Punct(')' | ']' | '}')) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                Some(_) => {}
+                None => bail!("Unbalanced brackets in USD layer"),
+            }
+        }
+    }
+
+    // Reads the metadata block after a prim or layer header, if there is one
+    fn parse_metadata(&mut self) -> anyhow::Result<HashMap<String, Value>> {
+        let mut metadata = HashMap::new();
+        if !self.is_punct('(') {
+            return Ok(metadata);
+        }
+        self.next();
+
+        while !self.is_punct(')') {
+            match self.next() {
+                Some(Token::Ident(name)) if self.is_punct('=') => {
+                    self.next();
+                    let value = self.parse_value()?;
+                    metadata.insert(name, value);
+                }
+                Some(Token::Punct('(' | '[' | '{')) => {
+                    self.position -= 1;
+                    self.skip_block()?;
+                }
+                Some(_) => {}
+                None => bail!("Unterminated metadata in USD layer"),
+            }
+        }
+        self.next();
+
+        Ok(metadata)
+    }
+
+    fn parse_value(&mut self) -> anyhow::Result<Value> {
+        match self.peek().cloned() {
+            Some(Token::Num(value)) => {
+                self.next();
+                Ok(Value::Num(value))
+            }
+            Some(Token::Str(value)) => {
+                self.next();
+                Ok(Value::Str(value))
+            }
+            Some(Token::Punct(open @ ('(' | '['))) => {
+                self.next();
+                let close = if open == '(' { ')' } else { ']' };
+
+                let mut values = Vec::new();
+                while !self.is_punct(close) {
+                    if self.is_punct(',') {
+                        self.next();
+                        continue;
+                    }
+                    values.push(self.parse_value()?);
+                }
+                self.next();
+
+                Ok(Value::List(values))
+            }
+            Some(Token::Punct('{')) => {
+                // Time samples and dictionaries
+                self.skip_block()?;
+                Ok(Value::Other)
+            }
+            Some(Token::Ident(_)) => {
+                self.next();
+                Ok(Value::Other)
+            }
+            found => bail!("Unexpected {:?} in USD value", found),
+        }
+    }
+
+    fn parse_prim(&mut self) -> anyhow::Result<Prim> {
+        // `def`, `over` or `class`, then an optional type and a name
+        self.next();
+        let mut prim = Prim::default();
+        if let Some(Token::Ident(type_name)) = self.peek().cloned() {
+            prim.type_name = type_name;
+            self.next();
+        }
+        match self.next() {
+            Some(Token::Str(name)) => prim.name = name,
+            found => bail!("Expected a prim name in USD layer, found {:?}", found),
+        }
+
+        self.parse_metadata()?;
+        self.expect_punct('{')?;
+
+        while !self.is_punct('}') {
+            match self.peek().cloned() {
+                Some(Token::Ident(keyword)) if matches!(keyword.as_str(), "def" | "over" | "class") => {
+                    prim.children.push(self.parse_prim()?);
+                }
+                Some(Token::Ident(keyword)) if keyword == "variantSet" => {
+                    // variantSet "name" = { ... }
+                    self.next();
+                    self.next();
+                    self.expect_punct('=')?;
+                    self.skip_block()?;
+                }
+                Some(Token::Ident(_)) => self.parse_attribute(&mut prim)?,
+                None => bail!("Unterminated prim `{}` in USD layer", prim.name),
+                // Leftovers from things we don't parse, such as reference targets
+                _ => {
+                    self.next();
+                }
+            }
+        }
+        self.next();
+
+        Ok(prim)
+    }
+
+    // [qualifiers] type[[]] name [= value] [(metadata)]
+    fn parse_attribute(&mut self, prim: &mut Prim) -> anyhow::Result<()> {
+        let mut words = Vec::new();
+        while let Some(Token::Ident(word)) = self.peek().cloned() {
+            self.next();
+            words.push(word);
+            if self.is_punct('[') && self.tokens.get(self.position + 1) == Some(&Token::Punct(']')) {
+                // Array type, e.g point3f[]
+                self.position += 2;
+            }
+        }
+
+        let name = words.last().cloned().ok_or_else(|| anyhow!("Expected an attribute name in USD layer"))?;
+        if self.is_punct('=') {
+            self.next();
+            let value = self.parse_value()?;
+            prim.attributes.insert(name, value);
+        }
+        self.parse_metadata()?;
+
+        Ok(())
+    }
+}
+
+struct Layer {
+    prims: Vec<Prim>,
+    z_up: bool,
+}
+
+impl Layer {
+    // Converts Z up scenes to our Y up
+    fn root_transform(&self) -> DMat4 {
+        if self.z_up {
+            DMat4::from_rotation_x(-std::f64::consts::FRAC_PI_2)
+        } else {
+            DMat4::IDENTITY
+        }
+    }
+
+    // Finds a prim by its absolute path, e.g `/Root/Material/Shader`
+    fn find_prim(&self, path: &str) -> Option<&Prim> {
+        let mut names = path.trim_start_matches('/').split('/');
+        let first = names.next()?;
+        let mut prim = self.prims.iter().find(|prim| prim.name == first)?;
+        for name in names {
+            prim = prim.children.iter().find(|child| child.name == name)?;
+        }
+        Some(prim)
+    }
+}
+
+fn parse_layer(source: &str) -> anyhow::Result<Layer> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+
+    let metadata = parser.parse_metadata()?;
+    let z_up = matches!(metadata.get("upAxis"), Some(Value::Str(axis)) if axis == "Z");
+
+    let mut prims = Vec::new();
+    while let Some(token) = parser.peek() {
+        match token {
+            Token::Ident(keyword) if matches!(keyword.as_str(), "def" | "over" | "class") => {
+                let is_class = keyword == "class";
+                let prim = parser.parse_prim()?;
+                // Classes are templates, and aren't drawn themselves
+                if !is_class {
+                    prims.push(prim);
+                }
+            }
+            _ => {
+                parser.next();
+            }
+        }
+    }
+
+    Ok(Layer { prims, z_up })
+}
+
+// Every prim of a type below `prim`, including itself, with its path
+fn collect_prims<'a>(prim: &'a Prim, path: &str, type_name: &str, out: &mut Vec<(String, &'a Prim)>) {
+    if prim.type_name == type_name {
+        out.push((path.to_string(), prim));
+    }
+    for child in prim.children.iter() {
+        collect_prims(child, &format!("{}/{}", path, child.name), type_name, out);
+    }
+}
+
+fn collect_meshes(prim: &Prim, parent: DMat4, meshes: &mut Vec<UsdMesh>) {
+    let world = parent * local_transform(prim);
+
+    if prim.type_name == "Mesh" {
+        if let Some(mesh) = build_mesh(prim, world) {
+            meshes.push(mesh);
+        }
+    }
+
+    for child in prim.children.iter() {
+        collect_meshes(child, world, meshes);
+    }
+}
+
+// Applies the ops in xformOpOrder, e.g [translate, rotateXYZ, scale]
+fn local_transform(prim: &Prim) -> DMat4 {
+    let order = match prim.attributes.get("xformOpOrder") {
+        Some(order) => order.to_strings(),
+        None => return DMat4::IDENTITY,
+    };
+
+    let mut matrix = DMat4::IDENTITY;
+    for op in order.iter() {
+        let values = match prim.attributes.get(op) {
+            Some(value) => value.to_floats(),
+            None => continue,
+        };
+        let vec3 = || DVec3::new(values[0], values[1], values[2]);
+        // Op names are `xformOp:<kind>` with an optional `:<suffix>`
+        let kind = op.split(':').nth(1).unwrap_or("");
+
+        let op_matrix = match (kind, values.len()) {
+            ("translate", 3) => DMat4::from_translation(vec3()),
+            ("scale", 3) => DMat4::from_scale(vec3()),
+            ("rotateX", 1) => DMat4::from_rotation_x(values[0].to_radians()),
+            ("rotateY", 1) => DMat4::from_rotation_y(values[0].to_radians()),
+            ("rotateZ", 1) => DMat4::from_rotation_z(values[0].to_radians()),
+            // rotateXYZ rotates around X first, then Y, then Z
+            ("rotateXYZ", 3) => DMat4::from_rotation_z(values[2].to_radians())
+                * DMat4::from_rotation_y(values[1].to_radians())
+                * DMat4::from_rotation_x(values[0].to_radians()),
+            ("rotateZYX", 3) => DMat4::from_rotation_x(values[0].to_radians())
+                * DMat4::from_rotation_y(values[1].to_radians())
+                * DMat4::from_rotation_z(values[2].to_radians()),
+            // Quaternions are written real part first
+            ("orient", 4) => DMat4::from_quat(DQuat::from_xyzw(values[1], values[2], values[3], values[0]).normalize()),
+            // USD matrices are row-major with row vectors, which is the same memory layout as glam
+            ("transform", 16) => DMat4::from_cols_slice(&values),
+            _ => DMat4::IDENTITY,
+        };
+        matrix *= op_matrix;
+    }
+
+    matrix
+}
+
+fn build_mesh(prim: &Prim, world: DMat4) -> Option<UsdMesh> {
+    let points = prim.attributes.get("points")?.to_floats();
+    let counts = prim.attributes.get("faceVertexCounts")?.to_floats();
+    let corner_indices = prim.attributes.get("faceVertexIndices")?.to_floats();
+
+    let left_handed = matches!(prim.attributes.get("orientation"), Some(Value::Str(orientation)) if orientation == "leftHanded");
+    let normal_matrix = glam::DMat3::from_mat4(world).inverse().transpose();
+
+    let normals = prim.attributes.get("normals").map(|normals| normals.to_floats());
+    let tex_coords = prim.attributes.get("primvars:st").map(|st| st.to_floats());
+    let tex_coord_indices = prim.attributes.get("primvars:st:indices").map(|indices| indices.to_floats());
+
+    let mut positions = Vec::new();
+    let mut out_normals = Vec::new();
+    let mut out_tex_coords = Vec::new();
+
+    // Per-vertex or per-corner data is chosen by length, as the interpolation metadata is dropped
+    let point_count = points.len() / 3;
+    let corner_count = corner_indices.len();
+    let normals_per_corner = normals.as_ref().map(|normals| normals.len() / 3 == corner_count && corner_count != point_count);
+    let has_normals = normals.as_ref().map(|normals| normals.len() / 3 == point_count || normals.len() / 3 == corner_count).unwrap_or(false);
+
+    let mut corner_start = 0;
+    for &count in counts.iter() {
+        let count = count as usize;
+        if corner_start + count > corner_count {
+            break;
+        }
+
+        // Fan triangulation, reversing the winding for left handed meshes
+        for i in 1..count.saturating_sub(1) {
+            let triangle = if left_handed { [0, i + 1, i] } else { [0, i, i + 1] };
+
+            for offset in triangle {
+                let corner = corner_start + offset;
+                let point = corner_indices[corner] as usize;
+
+                let position = world.transform_point3(DVec3::new(points[point * 3], points[point * 3 + 1], points[point * 3 + 2]));
+                positions.push(position.as_vec3().into());
+
+                if has_normals {
+                    let normals = normals.as_ref().unwrap();
+                    let index = if normals_per_corner == Some(true) { corner } else { point };
+                    let normal = normal_matrix * DVec3::new(normals[index * 3], normals[index * 3 + 1], normals[index * 3 + 2]);
+                    out_normals.push(normal.normalize_or_zero().as_vec3().into());
+                }
+
+                if let Some(tex_coords) = tex_coords.as_ref() {
+                    let index = match tex_coord_indices.as_ref() {
+                        Some(indices) => indices.get(corner).copied().unwrap_or(0.0) as usize,
+                        None if tex_coords.len() / 2 == corner_count => corner,
+                        None => point,
+                    };
+                    // USD UVs start at the bottom left, ours at the top left
+                    let uv = [tex_coords.get(index * 2).copied().unwrap_or(0.0), tex_coords.get(index * 2 + 1).copied().unwrap_or(0.0)];
+                    out_tex_coords.push([uv[0] as f32, 1.0 - uv[1] as f32]);
+                }
+            }
+        }
+
+        corner_start += count;
+    }
+
+    let has_tex_coords = !out_tex_coords.is_empty();
+    Some(UsdMesh {
+        name: prim.name.clone(),
+        positions,
+        normals: if has_normals { Some(out_normals) } else { None },
+        tex_coords: if has_tex_coords { Some(out_tex_coords) } else { None },
+    })
+}
+
+// What a shader input is set to, following connections
+enum Input<'a> {
+    Value(Vec<f64>),
+    // A `UsdUVTexture` shader, and the output read from it, e.g `rgb` or `r`
+    Texture(&'a Prim, &'a str),
+    Unset,
+}
+
+// Splits a connection target, e.g `/Material/Texture.outputs:rgb`, into the prim path and the property
+fn split_connection(target: &str) -> Option<(&str, &str)> {
+    let (path, property) = target.rsplit_once('.')?;
+    Some((path, property))
+}
+
+// Reads `inputs:<name>` of a shader. Connections to a material's interface inputs are followed,
+// up to a few levels deep so a cycle can't hang the loader
+fn get_input<'a>(layer: &'a Layer, prim: &'a Prim, name: &str, depth: usize) -> Input<'a> {
+    if let Some(target) = prim.get_str(&format!("{}.connect", name)) {
+        let Some((path, property)) = split_connection(target) else { return Input::Unset };
+        let Some(source) = layer.find_prim(path) else { return Input::Unset };
+
+        if let Some(output) = property.strip_prefix("outputs:") {
+            if source.get_str("info:id") == Some("UsdUVTexture") {
+                return Input::Texture(source, output);
+            }
+        } else if property.starts_with("inputs:") && depth < 8 {
+            return get_input(layer, source, property, depth + 1);
+        }
+        return Input::Unset;
+    }
+
+    match prim.attributes.get(name) {
+        Some(value) => Input::Value(value.to_floats()),
+        None => Input::Unset,
+    }
+}
+
+// The `UsdPreviewSurface` shader a material's surface output comes from
+fn find_surface<'a>(layer: &'a Layer, path: &str, material: &'a Prim) -> Option<&'a Prim> {
+    let is_preview_surface = |prim: &Prim| prim.get_str("info:id") == Some("UsdPreviewSurface");
+
+    let connected = material.get_str("outputs:surface.connect")
+        .and_then(split_connection)
+        .and_then(|(shader_path, _)| layer.find_prim(shader_path))
+        .filter(|shader| is_preview_surface(shader));
+    let surface = connected.or_else(|| material.children.iter().find(|child| is_preview_surface(child)));
+
+    if surface.is_none() {
+        warn!("USD material `{}` has no UsdPreviewSurface shader, skipping it", path);
+    }
+    surface
+}
+
+// The image of a `UsdUVTexture` shader and its scale, which multiplies the samples
+fn read_texture(package: &Package, images: &mut HashMap<String, Option<RgbaImage>>, texture: &Prim) -> Option<(RgbaImage, [f32; 4])> {
+    let file = texture.get_str("inputs:file")?;
+    let image = images.entry(file.to_string()).or_insert_with(|| {
+        let image = package.read_asset(file).and_then(|bytes| Ok(image::load_from_memory(&bytes)?.to_rgba8()));
+        image.map_err(|e| warn!("Failed to read USD texture `{}`: {}", file, e)).ok()
+    });
+
+    let scale = match texture.attributes.get("inputs:scale").map(|scale| scale.to_floats()) {
+        Some(scale) if scale.len() == 4 => [scale[0] as f32, scale[1] as f32, scale[2] as f32, scale[3] as f32],
+        _ => [1.0; 4],
+    };
+    image.clone().map(|image| (image, scale))
+}
+
+fn channel_index(output: &str) -> usize {
+    match output {
+        "g" => 1,
+        "b" => 2,
+        "a" => 3,
+        _ => 0,
+    }
+}
+
+// Packs channels of several images into one, as (target channel, image, source channel).
+// Channels nothing is packed into are white, so the slot's factor is used alone there
+fn pack_channels(sources: Vec<(usize, RgbaImage, usize)>) -> Option<RgbaImage> {
+    let (width, height) = sources.first().map(|(_, image, _)| image.dimensions())?;
+    let mut packed = RgbaImage::from_pixel(width, height, image::Rgba([255; 4]));
+
+    for (target, image, source) in sources {
+        let image = if image.dimensions() == (width, height) {
+            image
+        } else {
+            image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle)
+        };
+        for (packed_pixel, pixel) in packed.pixels_mut().zip(image.pixels()) {
+            packed_pixel[target] = pixel[source];
+        }
+    }
+
+    Some(packed)
+}
+
+fn build_material(layer: &Layer, package: &Package, images: &mut HashMap<String, Option<RgbaImage>>, material: &Prim, surface: &Prim) -> UsdMaterial {
+    let mut uniform = PbrMaterialUniform::default();
+    let mut textures = Vec::new();
+
+    // UsdPreviewSurface's own defaults, for inputs that aren't authored
+    uniform.base_color_factor = [0.18, 0.18, 0.18, 1.0];
+    uniform.metallic_roughness_clearcoat[0] = 0.0;
+    uniform.metallic_roughness_clearcoat[1] = 0.5;
+
+    // Colours are used as a whole, so their texture's rgb is sampled as is
+    let mut diffuse_file = None;
+    for (input, slot) in [("inputs:diffuseColor", "base_color"), ("inputs:emissiveColor", "emissive")] {
+        let color = match get_input(layer, surface, input, 0) {
+            Input::Value(value) if value.len() == 3 => [value[0] as f32, value[1] as f32, value[2] as f32],
+            Input::Texture(texture, _) => match read_texture(package, images, texture) {
+                Some((image, scale)) => {
+                    if slot == "base_color" {
+                        diffuse_file = texture.get_str("inputs:file");
+                    }
+                    textures.push((slot, image));
+                    [scale[0], scale[1], scale[2]]
+                }
+                None => [1.0; 3],
+            },
+            _ => continue,
+        };
+        let factor = if slot == "base_color" { &mut uniform.base_color_factor } else { &mut uniform.emissive };
+        factor[..3].copy_from_slice(&color);
+    }
+
+    // Scalars read one channel of their texture, which is packed into the channel `PBR_SHADER` reads
+    let mut scalar = |input: &str, factor: &mut f32| -> Option<(RgbaImage, usize)> {
+        match get_input(layer, surface, input, 0) {
+            Input::Value(value) if value.len() == 1 => {
+                *factor = value[0] as f32;
+                None
+            }
+            Input::Texture(texture, output) => {
+                let (image, scale) = read_texture(package, images, texture)?;
+                let channel = channel_index(output);
+                *factor = scale[channel];
+                Some((image, channel))
+            }
+            _ => None,
+        }
+    };
+
+    let [metallic, roughness, ..] = &mut uniform.metallic_roughness_clearcoat;
+    let metallic_texture = scalar("inputs:metallic", metallic);
+    let roughness_texture = scalar("inputs:roughness", roughness);
+    let mut occlusion = 1.0;
+    let occlusion_texture = scalar("inputs:occlusion", &mut occlusion);
+
+    // Metallic-roughness textures hold roughness in green and metallic in blue
+    let metallic_roughness = roughness_texture.map(|(image, channel)| (1, image, channel)).into_iter()
+        .chain(metallic_texture.map(|(image, channel)| (2, image, channel)));
+    if let Some(image) = pack_channels(metallic_roughness.collect()) {
+        textures.push(("metallic_roughness", image));
+    }
+    if let Some(image) = occlusion_texture.and_then(|(image, channel)| pack_channels(vec![(0, image, channel)])) {
+        textures.push(("occlusion", image));
+    }
+
+    // Without a normal texture the scale stays 0, so the white placeholder leaves the normals alone
+    if let Input::Texture(texture, _) = get_input(layer, surface, "inputs:normal", 0) {
+        if let Some((image, _)) = read_texture(package, images, texture) {
+            textures.push(("normal", image));
+            uniform.occlusion_normal[1] = 1.0;
+        }
+    }
+
+    // Opacity from a texture is only honoured as the alpha of the diffuse texture, which the base colour already samples
+    let mut blended = false;
+    match get_input(layer, surface, "inputs:opacity", 0) {
+        Input::Value(value) if value.len() == 1 => {
+            uniform.base_color_factor[3] = value[0] as f32;
+            blended = value[0] < 1.0;
+        }
+        Input::Texture(texture, output) => {
+            if diffuse_file.is_some() && texture.get_str("inputs:file") == diffuse_file && output == "a" {
+                blended = true;
+            } else {
+                warn!("USD material `{}` reads opacity from a texture other than its diffuse alpha, which isn't supported", material.name);
+            }
+        }
+        _ => {}
+    }
+    match get_input(layer, surface, "inputs:opacityThreshold", 0) {
+        Input::Value(value) if value.len() == 1 && value[0] > 0.0 => uniform.transmission_alpha[1] = value[0] as f32,
+        _ if blended => uniform.transmission_alpha[2] = 1.0,
+        _ => {}
+    }
+
+    UsdMaterial {
+        name: material.name.clone(),
+        uniform,
+        textures,
+    }
+}
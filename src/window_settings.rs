@@ -0,0 +1,101 @@
+use log::warn;
+use winit::window::{CursorGrabMode, CursorIcon, Icon, Window};
+use crate::debug::GpuValidation;
+
+/// # Window Settings
+///
+/// Options for the window the renderer creates, see `Renderer::with_window_settings`
+#[derive(Debug, Clone)]
+pub struct WindowSettings{
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    // Path to an image file used as the window icon
+    pub icon: Option<String>,
+    pub cursor_icon: CursorIcon,
+    pub cursor_mode: CursorMode,
+    // Graphics API debugging, which has to be chosen before the GPU is set up
+    pub gpu_validation: GpuValidation,
+}
+
+impl Default for WindowSettings{
+    fn default() -> Self{
+        Self{
+            title: "Renderer".to_string(),
+            width: 1600,
+            height: 1200,
+            icon: None,
+            cursor_icon: CursorIcon::Default,
+            cursor_mode: CursorMode::Normal,
+            gpu_validation: GpuValidation::default(),
+        }
+    }
+}
+
+impl WindowSettings{
+    pub fn with_title(mut self, title: &str) -> Self{
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self{
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn with_icon(mut self, path: &str) -> Self{
+        self.icon = Some(path.to_string());
+        self
+    }
+
+    pub fn with_cursor_icon(mut self, cursor_icon: CursorIcon) -> Self{
+        self.cursor_icon = cursor_icon;
+        self
+    }
+
+    pub fn with_cursor_mode(mut self, cursor_mode: CursorMode) -> Self{
+        self.cursor_mode = cursor_mode;
+        self
+    }
+
+    pub fn with_gpu_validation(mut self, gpu_validation: GpuValidation) -> Self{
+        self.gpu_validation = gpu_validation;
+        self
+    }
+}
+
+/// # Cursor Mode
+///
+/// How the cursor behaves over the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorMode{
+    #[default]
+    Normal,
+    /// Hidden while over the window, but free to leave it
+    Hidden,
+    /// Hidden and kept in place, for FPS style controls reading mouse motion.
+    /// Platforms that can't lock the cursor confine it to the window instead
+    Locked,
+}
+
+/// Loads an image file as a window icon
+pub(crate) fn load_window_icon(path: &str) -> anyhow::Result<Icon>{
+    let image = image::open(path)?.to_rgba8();
+    let (width, height) = image.dimensions();
+
+    Ok(Icon::from_rgba(image.into_raw(), width, height)?)
+}
+
+pub(crate) fn apply_cursor_mode(window: &Window, cursor_mode: CursorMode){
+    let grab_result = match cursor_mode{
+        CursorMode::Normal | CursorMode::Hidden => window.set_cursor_grab(CursorGrabMode::None),
+        CursorMode::Locked => window.set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined)),
+    };
+    if let Err(e) = grab_result{
+        warn!("Failed to set cursor mode {:?}: {}", cursor_mode, e);
+    }
+
+    window.set_cursor_visible(cursor_mode == CursorMode::Normal);
+}